@@ -0,0 +1,139 @@
+//! Benchmarks of the solvers over the standard puzzle collections. The
+//! collections aren't part of the repository; put them in `benches/data` as
+//! described in `benches/data/README.md`. Collections that are missing are
+//! replaced with generated puzzles, with a warning, so the benchmarks always
+//! run. Their groups are named after the generated puzzles, e.g.
+//! `generated-expert`, as the numbers aren't comparable with the collection's.
+//! With the `bench-download` feature, a missing collection is first fetched
+//! with `curl` from the URL in its environment variable, e.g.
+//! `SUDOKU_BENCH_TOP_1465_URL`, and kept in `benches/data` for later runs.
+
+use std::fs;
+use std::path::Path;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use sudoku::{
+    count_solutions, generate, parse_board, solve_logically, Backend, Board, Difficulty, Rng, SolveOptions, Solver,
+    ValueOrder,
+};
+
+/// The most puzzles benchmarked from a collection, so that the large ones
+/// finish in reasonable time.
+const MAX_PUZZLES: usize = 200;
+
+/// The collections, by file name, and the difficulty of the puzzles that
+/// stand in for them when they're missing.
+const COLLECTIONS: [(&str, Difficulty); 3] = [
+    ("easy-50k", Difficulty::Easy),
+    ("top-1465", Difficulty::Expert),
+    ("17-clue", Difficulty::Hard),
+];
+
+/// Returns the name of the benchmark group and the puzzles of the collection,
+/// or of its stand-in if it's missing.
+fn load(name: &str, fallback: Difficulty) -> (String, Vec<Board>) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("benches/data").join(format!("{}.txt", name));
+    #[cfg(feature = "bench-download")]
+    download(name, &path);
+    match fs::read_to_string(&path) {
+        Ok(text) => {
+            let puzzles = text
+                .lines()
+                .filter(|line| !line.starts_with('#'))
+                .filter_map(|line| line.split_whitespace().next().and_then(parse_board))
+                .take(MAX_PUZZLES)
+                .collect();
+            (String::from(name), puzzles)
+        }
+        Err(_) => {
+            eprintln!(
+                "warning: {} not found, benchmarking generated {} puzzles instead of {}",
+                path.display(),
+                fallback,
+                name
+            );
+            let mut rng = Rng::new(0);
+            (format!("generated-{}", fallback), (0..20).map(|_| generate(fallback, &mut rng)).collect())
+        }
+    }
+}
+
+/// Fetches the collection `name` to `path` unless it's there already, from
+/// the URL in `SUDOKU_BENCH_<NAME>_URL`. No URLs are built in, as the lists
+/// move around; failures only warn, and the benchmarks fall back as usual.
+#[cfg(feature = "bench-download")]
+fn download(name: &str, path: &Path) {
+    if path.exists() {
+        return;
+    }
+    let var = format!("SUDOKU_BENCH_{}_URL", name.to_uppercase().replace('-', "_"));
+    let url = match std::env::var(&var) {
+        Ok(url) => url,
+        Err(_) => {
+            eprintln!("warning: {} isn't set, so {} isn't downloaded", var, name);
+            return;
+        }
+    };
+    let status = std::process::Command::new("curl").args(["-fsSL", "-o"]).arg(path).arg(&url).status();
+    match status {
+        Ok(status) if status.success() => {}
+        Ok(status) => eprintln!("warning: downloading {} from {} failed: curl exited with {}", name, url, status),
+        Err(err) => eprintln!("warning: downloading {} from {} failed: can't run curl: {}", name, url, err),
+    }
+    if !path.exists() {
+        return;
+    }
+    // A page that isn't a list of puzzles is no better than a missing file.
+    let text = fs::read_to_string(path).unwrap_or_default();
+    if !text.lines().any(|line| line.split_whitespace().next().and_then(parse_board).is_some()) {
+        eprintln!("warning: {} from {} has no puzzles, removing it", name, url);
+        let _ = fs::remove_file(path);
+    }
+}
+
+fn bench_collections(c: &mut Criterion) {
+    for &(name, fallback) in COLLECTIONS.iter() {
+        let (name, puzzles) = load(name, fallback);
+        let mut group = c.benchmark_group(name);
+        group.throughput(Throughput::Elements(puzzles.len() as u64));
+        group.sample_size(10);
+        for &backend in Backend::ALL.iter() {
+            group.bench_with_input(BenchmarkId::new(backend.name(), puzzles.len()), &puzzles, |b, puzzles| {
+                let mut solver = backend.solver(SolveOptions::default());
+                let mut solution = Board::default();
+                b.iter(|| {
+                    for puzzle in puzzles {
+                        solver.solve_into(puzzle, &mut solution);
+                    }
+                })
+            });
+        }
+        group.bench_with_input(BenchmarkId::new("least_constraining", puzzles.len()), &puzzles, |b, puzzles| {
+            let mut solver = Solver::with_options(SolveOptions::default().value_order(ValueOrder::LeastConstraining));
+            let mut solution = Board::default();
+            b.iter(|| {
+                for puzzle in puzzles {
+                    solver.solve_into(puzzle, &mut solution);
+                }
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("logical", puzzles.len()), &puzzles, |b, puzzles| {
+            b.iter(|| {
+                for puzzle in puzzles {
+                    solve_logically(puzzle);
+                }
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("count", puzzles.len()), &puzzles, |b, puzzles| {
+            b.iter(|| {
+                for puzzle in puzzles {
+                    count_solutions(puzzle, 2);
+                }
+            })
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_collections);
+criterion_main!(benches);
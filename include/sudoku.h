@@ -0,0 +1,92 @@
+#ifndef SUDOKU_H
+#define SUDOKU_H
+
+/* Generated with cbindgen from src/ffi.rs, don't edit by hand. */
+
+#include <stdint.h>
+
+// The size of the buffers that puzzles and solutions are written to: 81
+// cells and the terminating NUL.
+#define SUDOKU_BUFFER_LEN 82
+
+// The result of every call.
+typedef enum SudokuStatus {
+  SUDOKU_STATUS_OK = 0,
+  // A pointer was NULL or a number was out of range.
+  SUDOKU_STATUS_INVALID_ARGUMENT = 1,
+  // The puzzle isn't a valid 81 character string.
+  SUDOKU_STATUS_INVALID_PUZZLE = 2,
+  // The puzzle has no solution.
+  SUDOKU_STATUS_NO_SOLUTION = 3,
+} SudokuStatus;
+
+// The rating of a puzzle. Difficulties are numbered from 0 (easy) to 3
+// (expert) and techniques in order of difficulty from 0 (naked single), or
+// -1 if none was needed.
+typedef struct SudokuRating {
+  uint32_t difficulty;
+  int32_t hardest_technique;
+  uint32_t guesses_needed;
+  uint32_t guesses_total;
+  uint32_t guess_depth;
+  // From 0 for puzzles with nothing left to solve to 10 for those that
+  // need guesses.
+  double score;
+} SudokuRating;
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Solves `puzzle` and writes the solution to `solution`.
+//
+// # Safety
+//
+// `puzzle` must be NULL or a NUL terminated string, and `solution` must be
+// NULL or point to at least `SUDOKU_BUFFER_LEN` writable bytes.
+enum SudokuStatus sudoku_solve(const char *puzzle, char *solution);
+
+// Generates a puzzle of `difficulty`, from 0 (easy) to 3 (expert), and writes
+// it to `puzzle`. The same seed always generates the same puzzle.
+//
+// # Safety
+//
+// `puzzle` must be NULL or point to at least `SUDOKU_BUFFER_LEN` writable
+// bytes.
+enum SudokuStatus sudoku_generate(uint32_t difficulty, uint64_t seed, char *puzzle);
+
+// Rates `puzzle` and writes the result to `rating`.
+//
+// # Safety
+//
+// `puzzle` must be NULL or a NUL terminated string, and `rating` must be
+// NULL or point to a writable `SudokuRating`.
+enum SudokuStatus sudoku_rate(const char *puzzle, struct SudokuRating *rating);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* SUDOKU_H */
@@ -0,0 +1,109 @@
+/**
+ * A puzzle or solution as 81 characters, row by row, with a digit for each
+ * solved cell and `.` or `0` for each empty one.
+ */
+export type Board = string;
+
+export type Difficulty = "easy" | "medium" | "hard" | "expert";
+
+export const DIFFICULTIES: readonly Difficulty[];
+
+export type Technique =
+  | "naked_single"
+  | "hidden_single"
+  | "locked_candidates"
+  | "naked_pair"
+  | "hidden_pair"
+  | "x_wing"
+  | "x_chain"
+  | "xy_chain"
+  | "bug_plus_one"
+  | "forcing_chain"
+  | "guess";
+
+export interface Rating {
+  difficulty: Difficulty;
+  /** From 0 for puzzles with nothing left to solve to 10 for those that need guesses. */
+  score: number;
+  /** The hardest technique needed, not counting guesses. */
+  hardest: Technique | null;
+  guesses: {
+    /** The guesses on the path the solver took to the solution, not necessarily the fewest. */
+    needed: number;
+    /** All guesses made, including the ones that turned out wrong. */
+    total: number;
+    /** The deepest guess nesting. */
+    depth: number;
+  };
+}
+
+export interface House {
+  kind: "row" | "col" | "box";
+  /** From 0 to 8. */
+  index: number;
+}
+
+/** A digit placed in or eliminated from a cell. Rows and columns are from 0 to 8. */
+export interface Candidate {
+  row: number;
+  col: number;
+  digit: number;
+}
+
+export interface Step {
+  technique: Technique;
+  /** The houses the technique was found in. */
+  houses: House[];
+  /** The cells the technique was found in, as [row, col]. */
+  cells: [number, number][];
+  placements: Candidate[];
+  eliminations: Candidate[];
+  elapsed_us: number;
+  /**
+   * The candidates of a chain in order, for chain techniques, with links
+   * alternating between strong and weak. Empty for other techniques.
+   */
+  chain: Candidate[];
+  /** Why the candidate a forcing chain eliminates is wrong, otherwise null. */
+  refutation: string | null;
+}
+
+export interface Hint {
+  /** The text of the hint at the requested level. */
+  hint: string;
+  /** The step the hint is about, which gives everything away. */
+  step: Step;
+}
+
+/** Returns the solution of `puzzle`. Throws if it's invalid or has none. */
+export function solve(puzzle: Board): Board;
+
+/**
+ * Like `solve`, but yields to the event loop every millisecond or so, so that
+ * hard puzzles don't freeze the page. Rejects if the puzzle is invalid or
+ * has no solution.
+ */
+export function solveAsync(puzzle: Board): Promise<Board>;
+
+/**
+ * Generates a puzzle of `difficulty`. The same seed always generates the
+ * same puzzle, and a random one is used if it's omitted.
+ */
+export function generate(difficulty?: Difficulty, seed?: number): Board;
+
+/**
+ * Returns the puzzle of the day of `difficulty`, the same for every user.
+ * `date` is a `Date`, whose local day is taken, or a YYYY-MM-DD string, and
+ * is today if it's omitted.
+ */
+export function daily(difficulty?: Difficulty, date?: Date | string): Board;
+
+/** Rates `puzzle`. Throws if it's invalid or has no solution. */
+export function rate(puzzle: Board): Rating;
+
+/**
+ * Returns a hint for the next step of `puzzle`, revealing more the higher
+ * `level` is: 1 says where to look, 2 what to look for, and 3 the answer.
+ * Throws if the puzzle is solved or has no solution.
+ */
+export function hint(puzzle: Board, level?: 1 | 2 | 3): Hint;
@@ -0,0 +1,47 @@
+// Typed wrappers around the raw bindings in pkg/, which pass reports as JSON
+// strings. See index.d.ts for the shapes of the parsed reports.
+import * as raw from "./pkg/sudoku.js";
+
+export const DIFFICULTIES = ["easy", "medium", "hard", "expert"];
+
+export function solve(puzzle) {
+  return raw.solve(puzzle);
+}
+
+export async function solveAsync(puzzle) {
+  const stepper = new raw.Stepper(puzzle);
+  try {
+    for (;;) {
+      const solution = stepper.step();
+      if (solution !== undefined) {
+        return solution;
+      }
+      await new Promise((resolve) => setTimeout(resolve, 0));
+    }
+  } finally {
+    stepper.free();
+  }
+}
+
+export function generate(difficulty = "medium", seed) {
+  if (seed === undefined) {
+    seed = Math.floor(Math.random() * 0x1_0000_0000);
+  }
+  return raw.generate(difficulty, seed >>> 0);
+}
+
+export function daily(difficulty = "medium", date = new Date()) {
+  if (typeof date !== "string") {
+    const pad = (n) => String(n).padStart(2, "0");
+    date = `${date.getFullYear()}-${pad(date.getMonth() + 1)}-${pad(date.getDate())}`;
+  }
+  return raw.daily(date, difficulty);
+}
+
+export function rate(puzzle) {
+  return JSON.parse(raw.rate(puzzle));
+}
+
+export function hint(puzzle, level = 1) {
+  return JSON.parse(raw.hint(puzzle, level));
+}
@@ -0,0 +1,151 @@
+//! Generating puzzles that are as hard as possible for a naive backtracking
+//! solver, which fills the empty cells in order, row by row, trying the
+//! digits from 1 up. Such puzzles, like the known anti-brute-force 17-clue
+//! ones, make for benchmarks and regression tests of search heuristics: a
+//! heuristic that does no better than the naive order on them is suspect.
+
+use crate::board::{Board, Cell};
+use crate::count::count_solutions;
+use crate::generate::{generate_minimal, Rng};
+use crate::grid::mask_digits;
+use crate::packed::Packed;
+use crate::solver::Sudoku;
+
+/// How many nodes the naive search is run for at most when a puzzle is
+/// scored, which takes a few seconds at most.
+pub const EFFORT_LIMIT: u64 = 1 << 26;
+
+/// Returns the digits the naive search places before it finds the first
+/// solution of `board`, counting the ones it takes back, or `limit` if it
+/// doesn't find it by then. Returns None if the givens conflict.
+pub fn naive_effort(board: &Board, limit: u64) -> Option<u64> {
+    fn search(packed: &mut Packed, cells: &[usize], nodes: &mut u64, limit: u64) -> bool {
+        let (&index, rest) = match cells.split_first() {
+            Some(split) => split,
+            None => return true,
+        };
+        for digit in mask_digits(packed.candidates(index)) {
+            if *nodes >= limit {
+                return false;
+            }
+            *nodes += 1;
+            packed.place(index, digit as u8);
+            if search(packed, rest, nodes, limit) {
+                return true;
+            }
+            packed.unplace(index, digit as u8);
+        }
+        false
+    }
+
+    let mut packed = Packed::from_board(board)?;
+    let cells: Vec<usize> = (0..81).filter(|&index| packed.cells[index] == 0).collect();
+    let mut nodes = 0;
+    search(&mut packed, &cells, &mut nodes, limit);
+    Some(nodes.min(limit))
+}
+
+/// Relabels the digits of `puzzle`, whose solution is `solution`, so that the
+/// first empty cells hold the highest digits, which the naive search tries
+/// last.
+fn relabel_against(puzzle: &Board, solution: &Board) -> Board {
+    let mut labels = [0; 10];
+    let mut next = 9;
+    let cells = puzzle.iter().flatten().zip(solution.iter().flatten());
+    for (_, solved) in cells.filter(|(given, _)| given.solution.is_none()) {
+        let digit = solved.solution.unwrap() as usize;
+        if labels[digit] == 0 {
+            labels[digit] = next;
+            next -= 1;
+        }
+    }
+    for label in labels[1..].iter_mut().filter(|label| **label == 0) {
+        *label = next;
+        next -= 1;
+    }
+    let mut relabeled = *puzzle;
+    for cell in relabeled.iter_mut().flatten() {
+        if let Some(digit) = cell.solution {
+            *cell = Cell::solved(labels[digit as usize]);
+        }
+    }
+    relabeled
+}
+
+/// Moves a random given of `puzzle` to a random empty cell, with the digit
+/// the cell has in `solution`. Returns None if the puzzle loses its unique
+/// solution.
+fn move_given(puzzle: &Board, solution: &Board, rng: &mut Rng) -> Option<Board> {
+    let (givens, empty): (Vec<usize>, Vec<usize>) =
+        (0..81).partition(|&index| puzzle[index / 9][index % 9].solution.is_some());
+    let from = givens[rng.below(givens.len())];
+    let to = empty[rng.below(empty.len())];
+    let mut moved = *puzzle;
+    moved[from / 9][from % 9] = Cell::unsolved();
+    moved[to / 9][to % 9] = solution[to / 9][to % 9];
+    if count_solutions(&moved, 2) == 1 {
+        Some(moved)
+    } else {
+        None
+    }
+}
+
+/// Searches for a uniquely solvable puzzle that takes the naive search as
+/// long as possible, trying `rounds` variations of the best puzzle so far,
+/// each a given moved, and fresh puzzles now and then. Every puzzle is
+/// relabeled to be as bad for the naive order as it can be. Returns the
+/// puzzle and its effort, up to `EFFORT_LIMIT`.
+pub fn generate_adversarial(rounds: usize, rng: &mut Rng) -> (Board, u64) {
+    let score = |puzzle: &Board| {
+        let solution = Sudoku::new(*puzzle).solve().unwrap();
+        let puzzle = relabel_against(puzzle, &solution);
+        (puzzle, naive_effort(&puzzle, EFFORT_LIMIT).unwrap())
+    };
+    let (mut best, mut effort) = score(&generate_minimal(rng));
+    for _ in 0..rounds {
+        if effort >= EFFORT_LIMIT {
+            break;
+        }
+        let candidate = if rng.below(4) == 0 {
+            generate_minimal(rng)
+        } else {
+            let solution = Sudoku::new(best).solve().unwrap();
+            match move_given(&best, &solution, rng) {
+                Some(moved) => moved,
+                None => continue,
+            }
+        };
+        let (candidate, candidate_effort) = score(&candidate);
+        if candidate_effort > effort {
+            event!(DEBUG, effort = candidate_effort, "found a harder puzzle");
+            best = candidate;
+            effort = candidate_effort;
+        }
+    }
+    (best, effort)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::default_board;
+
+    #[test]
+    fn test_generate_adversarial() {
+        let mut broken = default_board();
+        broken[0][0] = Cell::solved(5);
+        assert_eq!(naive_effort(&broken, 100), None);
+        let solution = Sudoku::new(default_board()).solve().unwrap();
+        assert_eq!(naive_effort(&solution, 100), Some(0));
+        assert_eq!(naive_effort(&default_board(), 10), Some(10));
+
+        let mut rng = Rng::new(7);
+        let (puzzle, effort) = generate_adversarial(10, &mut rng);
+        assert_eq!(count_solutions(&puzzle, 2), 1);
+        assert_eq!(naive_effort(&puzzle, EFFORT_LIMIT), Some(effort));
+        // It's no easier than a fresh puzzle relabeled the same way.
+        let fresh = generate_minimal(&mut Rng::new(7));
+        let relabeled = relabel_against(&fresh, &Sudoku::new(fresh).solve().unwrap());
+        assert!(effort >= naive_effort(&relabeled, EFFORT_LIMIT).unwrap());
+    }
+}
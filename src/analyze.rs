@@ -0,0 +1,268 @@
+//! Rating and measuring the search effort of every puzzle of a collection,
+//! to find the hardest ones when curating puzzle packs. The difficulty is how
+//! hard the puzzle is for a person, and the effort how hard it is for the
+//! backtracking solver, which don't always agree.
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::board::{board_to_string, parse_board, Board};
+use crate::estimate::estimate_search;
+use crate::generate::{Difficulty, Rng};
+use crate::json::Json;
+use crate::logic::{Technique, TechniqueConfig};
+use crate::rating::{rate, Rating};
+
+/// How many probes the search effort is estimated from.
+const PROBES: usize = 64;
+
+/// What's known about one puzzle of a collection.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Analysis {
+    /// The position of the puzzle in the collection.
+    pub index: usize,
+    pub puzzle: Board,
+    /// None if the puzzle has no solution.
+    pub rating: Option<Rating>,
+    /// The estimated nodes of a search for every solution.
+    pub effort: f64,
+}
+
+impl Analysis {
+    pub fn to_json(&self) -> Json {
+        Json::object(vec![
+            ("index", Json::from(self.index)),
+            ("puzzle", Json::from(board_to_string(&self.puzzle))),
+            ("rating", self.rating.as_ref().map_or(Json::Null, Rating::to_json)),
+            ("effort", Json::from(self.effort)),
+        ])
+    }
+
+    /// Reads an analysis written with `to_json`. Returns None if the JSON
+    /// isn't one.
+    pub fn from_json(json: &Json) -> Option<Analysis> {
+        let rating = match json.get("rating")? {
+            Json::Null => None,
+            rating => Some(Rating::from_json(rating)?),
+        };
+        Some(Analysis {
+            index: json.get("index")?.as_u64()? as usize,
+            puzzle: parse_board(json.get("puzzle")?.as_str()?)?,
+            rating,
+            effort: json.get("effort")?.as_f64()?,
+        })
+    }
+
+    /// Orders by difficulty, then by the hardest technique, then by the
+    /// guesses needed and made. Puzzles without a solution come first.
+    fn cmp_difficulty(&self, other: &Analysis) -> Ordering {
+        let key = |analysis: &Analysis| {
+            analysis.rating.as_ref().map(|rating| {
+                (Difficulty::of(rating), rating.hardest, rating.guesses.needed, rating.guesses.total)
+            })
+        };
+        key(self).cmp(&key(other))
+    }
+}
+
+/// Rates every puzzle with the techniques in `config` and estimates the
+/// effort of searching it. The estimates are seeded, so they're the same
+/// on every run.
+pub fn analyze(puzzles: &[Board], config: &TechniqueConfig) -> Vec<Analysis> {
+    puzzles.iter().enumerate().map(|(index, puzzle)| analyze_puzzle(index, puzzle, config)).collect()
+}
+
+/// Analyzes the puzzle at `index` of a collection like `analyze`, e.g. to
+/// analyze a collection a bit at a time.
+pub fn analyze_puzzle(index: usize, puzzle: &Board, config: &TechniqueConfig) -> Analysis {
+    Analysis {
+        index,
+        puzzle: *puzzle,
+        rating: rate(puzzle, config),
+        effort: estimate_search(puzzle, PROBES, &mut Rng::new(index as u64)).nodes,
+    }
+}
+
+/// Returns the `n` hardest puzzles with a solution, the hardest first.
+pub fn hardest_by_difficulty(analyses: &[Analysis], n: usize) -> Vec<&Analysis> {
+    let mut hardest: Vec<&Analysis> = analyses.iter().filter(|analysis| analysis.rating.is_some()).collect();
+    hardest.sort_by(|a, b| b.cmp_difficulty(a).then(a.index.cmp(&b.index)));
+    hardest.truncate(n);
+    hardest
+}
+
+/// Returns the `n` puzzles that take the most effort to search, the most
+/// first.
+pub fn hardest_by_effort(analyses: &[Analysis], n: usize) -> Vec<&Analysis> {
+    let mut hardest: Vec<&Analysis> = analyses.iter().collect();
+    hardest.sort_by(|a, b| b.effort.total_cmp(&a.effort).then(a.index.cmp(&b.index)));
+    hardest.truncate(n);
+    hardest
+}
+
+/// Returns the analyses as CSV with a header row. The rating columns are
+/// empty for puzzles without a solution.
+pub fn analysis_csv(analyses: &[Analysis]) -> String {
+    let mut csv = String::from("index,puzzle,difficulty,hardest,guesses_needed,guesses_total,effort\n");
+    for analysis in analyses {
+        let rating = match &analysis.rating {
+            Some(rating) => format!(
+                "{},{},{},{}",
+                Difficulty::of(rating),
+                rating.hardest.map(Technique::id).unwrap_or_default(),
+                rating.guesses.needed,
+                rating.guesses.total
+            ),
+            None => String::from(",,,"),
+        };
+        csv += &format!("{},{},{},{:.0}\n", analysis.index, board_to_string(&analysis.puzzle), rating, analysis.effort);
+    }
+    csv
+}
+
+/// How the puzzles of a collection are spread over the difficulties and the
+/// hardest techniques they need, to balance puzzle packs.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Histogram {
+    /// The puzzles of each difficulty, in the order of `Difficulty::ALL`.
+    pub difficulties: Vec<(Difficulty, usize)>,
+    /// The puzzles whose hardest technique is each of `Technique::ALL` but
+    /// guessing, after those that need no technique at all.
+    pub hardest: Vec<(Option<Technique>, usize)>,
+    /// The puzzles that need guesses, whatever their hardest technique.
+    pub guessing: usize,
+    pub unsolvable: usize,
+}
+
+impl Histogram {
+    /// Returns the number of puzzles counted.
+    pub fn total(&self) -> usize {
+        self.difficulties.iter().map(|&(_, count)| count).sum::<usize>() + self.unsolvable
+    }
+
+    /// Returns the buckets as CSV with a header row, e.g.
+    /// `difficulty,easy,12,0.400`, with the share of all puzzles last.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("group,bucket,puzzles,share\n");
+        for (group, bucket, count) in self.buckets() {
+            csv += &format!("{},{},{},{:.3}\n", group, bucket, count, self.share(count));
+        }
+        csv
+    }
+
+    fn share(&self, count: usize) -> f64 {
+        match self.total() {
+            0 => 0.0,
+            total => count as f64 / total as f64,
+        }
+    }
+
+    fn buckets(&self) -> Vec<(&'static str, &'static str, usize)> {
+        let mut buckets: Vec<_> =
+            self.difficulties.iter().map(|&(difficulty, count)| ("difficulty", difficulty.name(), count)).collect();
+        buckets.extend(
+            self.hardest.iter().map(|&(technique, count)| ("hardest", technique.map_or("none", Technique::id), count)),
+        );
+        buckets.push(("other", "guesses", self.guessing));
+        buckets.push(("other", "unsolvable", self.unsolvable));
+        buckets
+    }
+}
+
+/// Formats the buckets a line each with a bar, grouped under headings.
+impl fmt::Display for Histogram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const WIDTH: usize = 40;
+        let mut group = "";
+        for (g, bucket, count) in self.buckets() {
+            if g != group {
+                group = g;
+                let heading = match group {
+                    "difficulty" => "Difficulty",
+                    "hardest" => "Hardest technique",
+                    _ => "Other",
+                };
+                writeln!(f, "{}:", heading)?;
+            }
+            let share = self.share(count);
+            let bar = "#".repeat((share * WIDTH as f64).round() as usize);
+            let line = format!("  {:<18}{:>6}  {:>5.1}%  {}", bucket, count, share * 100.0, bar);
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+/// Counts the analyses into the buckets of a `Histogram`.
+pub fn histogram(analyses: &[Analysis]) -> Histogram {
+    let mut histogram = Histogram {
+        difficulties: Difficulty::ALL.iter().map(|&difficulty| (difficulty, 0)).collect(),
+        hardest: Some(None)
+            .into_iter()
+            .chain(Technique::ALL.iter().filter(|&&technique| technique != Technique::Guess).map(|&t| Some(t)))
+            .map(|technique| (technique, 0))
+            .collect(),
+        guessing: 0,
+        unsolvable: 0,
+    };
+    for analysis in analyses {
+        let rating = match &analysis.rating {
+            Some(rating) => rating,
+            None => {
+                histogram.unsolvable += 1;
+                continue;
+            }
+        };
+        let difficulty = Difficulty::of(rating);
+        for (d, count) in histogram.difficulties.iter_mut() {
+            if *d == difficulty {
+                *count += 1;
+            }
+        }
+        for (technique, count) in histogram.hardest.iter_mut() {
+            if *technique == rating.hardest {
+                *count += 1;
+            }
+        }
+        if rating.guesses.needed > 0 {
+            histogram.guessing += 1;
+        }
+    }
+    histogram
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{default_board, Cell};
+    use crate::generate::generate;
+
+    #[test]
+    fn test_analyze() {
+        let mut broken = default_board();
+        broken[0][0] = Cell::solved(5);
+        let mut rng = Rng::new(3);
+        let puzzles = [generate(Difficulty::Easy, &mut rng), broken, generate(Difficulty::Hard, &mut rng)];
+        let analyses = analyze(&puzzles, &TechniqueConfig::default());
+        assert_eq!(analyses[1].rating, None);
+
+        let hardest: Vec<usize> = hardest_by_difficulty(&analyses, 5).iter().map(|analysis| analysis.index).collect();
+        assert_eq!(hardest, vec![2, 0]);
+        assert_eq!(hardest_by_effort(&analyses, 1).len(), 1);
+
+        let csv = analysis_csv(&analyses);
+        assert_eq!(csv.lines().count(), 4);
+        assert!(csv.lines().nth(1).unwrap().starts_with(&format!("0,{},easy,", board_to_string(&puzzles[0]))));
+        assert!(csv.lines().nth(2).unwrap().contains(",,,,"));
+
+        let histogram = histogram(&analyses);
+        assert_eq!(histogram.total(), 3);
+        assert_eq!(histogram.difficulties[0], (Difficulty::Easy, 1));
+        assert_eq!(histogram.unsolvable, 1);
+        assert_eq!(histogram.hardest.iter().map(|&(_, count)| count).sum::<usize>(), 2);
+        let csv = histogram.to_csv();
+        assert!(csv.contains("\ndifficulty,easy,1,0.333\n"));
+        assert!(csv.ends_with("other,unsolvable,1,0.333\n"));
+        assert!(histogram.to_string().starts_with("Difficulty:\n  easy"));
+    }
+}
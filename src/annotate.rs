@@ -0,0 +1,126 @@
+//! Notes and color tags that players attach to cells, as most sudoku apps
+//! let them, besides the candidates. They're kept apart from the board, as
+//! nothing but rendering and saving looks at them.
+
+use crate::json::Json;
+
+/// A color a cell can be tagged with.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Color {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+}
+
+impl Color {
+    pub const ALL: [Color; 6] = [Color::Red, Color::Orange, Color::Yellow, Color::Green, Color::Blue, Color::Purple];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Color::Red => "red",
+            Color::Orange => "orange",
+            Color::Yellow => "yellow",
+            Color::Green => "green",
+            Color::Blue => "blue",
+            Color::Purple => "purple",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Color> {
+        Color::ALL.iter().copied().find(|color| color.name() == name)
+    }
+}
+
+/// What a player attached to a cell.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Annotation {
+    pub note: Option<String>,
+    pub color: Option<Color>,
+}
+
+impl Annotation {
+    pub fn is_empty(&self) -> bool {
+        self.note.is_none() && self.color.is_none()
+    }
+}
+
+/// The annotations of the cells of a board.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Annotations {
+    cells: [[Annotation; 9]; 9],
+}
+
+impl Annotations {
+    pub fn get(&self, row: usize, col: usize) -> &Annotation {
+        &self.cells[row][col]
+    }
+
+    pub fn get_mut(&mut self, row: usize, col: usize) -> &mut Annotation {
+        &mut self.cells[row][col]
+    }
+
+    /// Returns the cells that have annotations, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize, &Annotation)> {
+        self.cells.iter().enumerate().flat_map(|(row, cells)| {
+            cells.iter().enumerate().filter(|(_, a)| !a.is_empty()).map(move |(col, a)| (row, col, a))
+        })
+    }
+
+    /// Returns the annotated cells as a JSON array of objects like
+    /// `{"row": 0, "col": 2, "note": "pair with r1c7", "color": "blue"}`,
+    /// to save with a game. Rows and columns are zero-based.
+    pub fn to_json(&self) -> Json {
+        Json::Array(
+            self.iter()
+                .map(|(row, col, annotation)| {
+                    Json::object(vec![
+                        ("row", Json::from(row)),
+                        ("col", Json::from(col)),
+                        ("note", Json::from(annotation.note.clone())),
+                        ("color", Json::from(annotation.color.map(Color::name))),
+                    ])
+                })
+                .collect(),
+        )
+    }
+
+    /// Reads annotations saved with `to_json`. Returns None if the JSON
+    /// isn't an array of such objects.
+    pub fn from_json(json: &Json) -> Option<Annotations> {
+        let mut annotations = Annotations::default();
+        for cell in json.as_array()? {
+            let row = cell.get("row")?.as_u64().filter(|&row| row < 9)? as usize;
+            let col = cell.get("col")?.as_u64().filter(|&col| col < 9)? as usize;
+            let annotation = annotations.get_mut(row, col);
+            annotation.note = cell.get("note").and_then(Json::as_str).map(String::from);
+            annotation.color = match cell.get("color").and_then(Json::as_str) {
+                Some(name) => Some(Color::from_name(name)?),
+                None => None,
+            };
+        }
+        Some(annotations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annotations() {
+        let mut annotations = Annotations::default();
+        annotations.get_mut(0, 2).note = Some(String::from("pair with r1c7"));
+        annotations.get_mut(0, 2).color = Some(Color::Blue);
+        annotations.get_mut(4, 4).color = Some(Color::Red);
+        assert_eq!(annotations.iter().map(|(row, col, _)| (row, col)).collect::<Vec<_>>(), vec![(0, 2), (4, 4)]);
+
+        let json = annotations.to_json();
+        assert_eq!(json.as_array().unwrap()[1].get("note"), Some(&Json::Null));
+        assert_eq!(Annotations::from_json(&Json::parse(&json.to_string()).unwrap()), Some(annotations));
+        assert_eq!(Annotations::from_json(&Json::parse(r#"[{"row": 9, "col": 0}]"#).unwrap()), None);
+        assert_eq!(Annotations::from_json(&Json::parse(r#"[{"row": 0, "col": 0, "color": "pink"}]"#).unwrap()), None);
+    }
+}
@@ -0,0 +1,268 @@
+//! Solving many puzzles at once on all cores. Each thread has its own
+//! `Solver` and takes the next few puzzles off a shared counter, so a few
+//! hard puzzles don't hold up the rest of a shard.
+//!
+//! `solve_stream` does the same for puzzles that don't fit in memory, e.g.
+//! read from a file of millions. It only holds on to the few results that
+//! are ready before the ones of earlier puzzles.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::board::Board;
+use crate::solver::{SolveOptions, SolveStats, Solver};
+
+/// How many puzzles a thread takes at a time.
+const BLOCK: usize = 64;
+
+/// How many puzzles `solve_stream` has read but not output at most, so how
+/// far a slow puzzle lets the others get ahead of it.
+const WINDOW: usize = 1024;
+
+/// The outcome of solving one puzzle of a batch.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct SolveResult {
+    /// The solution, or None if the puzzle has none.
+    pub solution: Option<Board>,
+    pub duration: Duration,
+    pub stats: SolveStats,
+}
+
+/// Totals over the results of a batch.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct BatchStats {
+    pub solved: usize,
+    pub unsolvable: usize,
+    /// The time spent solving, summed over the threads.
+    pub total: Duration,
+    /// The time taken by the hardest puzzle.
+    pub slowest: Duration,
+    /// The restarts of all the puzzles.
+    pub restarts: u64,
+}
+
+impl BatchStats {
+    pub fn new(results: &[SolveResult]) -> BatchStats {
+        let mut stats = BatchStats::default();
+        for result in results {
+            stats.add(result);
+        }
+        stats
+    }
+
+    /// Counts one more result, e.g. of a stream.
+    pub fn add(&mut self, result: &SolveResult) {
+        match result.solution {
+            Some(_) => self.solved += 1,
+            None => self.unsolvable += 1,
+        }
+        self.total += result.duration;
+        self.slowest = self.slowest.max(result.duration);
+        self.restarts += result.stats.restarts;
+    }
+}
+
+/// Solves `puzzles` on as many threads as there are cores. The results are in
+/// the order of the puzzles.
+pub fn solve_all(puzzles: &[Board]) -> Vec<SolveResult> {
+    solve_all_with(puzzles, SolveOptions::default())
+}
+
+/// Like `solve_all`, but with `options`, e.g. to restart on hard puzzles.
+/// Batches always backtrack.
+pub fn solve_all_with(puzzles: &[Board], options: SolveOptions) -> Vec<SolveResult> {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    solve_all_on(puzzles, threads, options)
+}
+
+fn solve_all_on(puzzles: &[Board], threads: usize, options: SolveOptions) -> Vec<SolveResult> {
+    let next = AtomicUsize::new(0);
+    let empty = SolveResult { solution: None, duration: Duration::ZERO, stats: SolveStats::default() };
+    let mut results = vec![empty; puzzles.len()];
+    let solved: Vec<Vec<(usize, SolveResult)>> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads.min(puzzles.len()))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut solver = Solver::with_options(options);
+                    let mut solution = Board::default();
+                    let mut solved = Vec::new();
+                    loop {
+                        let start = next.fetch_add(BLOCK, Ordering::Relaxed);
+                        if start >= puzzles.len() {
+                            break solved;
+                        }
+                        for (i, puzzle) in puzzles.iter().enumerate().skip(start).take(BLOCK) {
+                            let started = Instant::now();
+                            let found = solver.solve_into(puzzle, &mut solution);
+                            let duration = started.elapsed();
+                            let stats = solver.stats();
+                            solved.push((i, SolveResult { solution: found.then_some(solution), duration, stats }));
+                        }
+                    }
+                })
+            })
+            .collect();
+        workers.into_iter().map(|worker| worker.join().unwrap()).collect()
+    });
+    for (i, result) in solved.into_iter().flatten() {
+        results[i] = result;
+    }
+    results
+}
+
+/// Solves `puzzles` on as many threads as there are cores, calling `output`
+/// with each result, in the order of the puzzles, as soon as it and those
+/// before it are ready. Items that are errors, e.g. lines that aren't
+/// puzzles, are passed on to `output` in their place. Puzzles are read as
+/// the results are output, so only a small window of them is in memory.
+/// Stops with the error if `output` fails.
+pub fn solve_stream<I, E, F>(puzzles: I, output: F) -> io::Result<()>
+where
+    I: IntoIterator<Item = Result<Board, E>>,
+    E: Send,
+    F: FnMut(Result<SolveResult, E>) -> io::Result<()> + Send,
+{
+    solve_stream_with(puzzles, SolveOptions::default(), output)
+}
+
+/// Like `solve_stream`, but with `options`.
+pub fn solve_stream_with<I, E, F>(puzzles: I, options: SolveOptions, output: F) -> io::Result<()>
+where
+    I: IntoIterator<Item = Result<Board, E>>,
+    E: Send,
+    F: FnMut(Result<SolveResult, E>) -> io::Result<()> + Send,
+{
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    solve_stream_on(puzzles, threads, WINDOW, options, output)
+}
+
+fn solve_stream_on<I, E, F>(
+    puzzles: I,
+    threads: usize,
+    window: usize,
+    options: SolveOptions,
+    mut output: F,
+) -> io::Result<()>
+where
+    I: IntoIterator<Item = Result<Board, E>>,
+    E: Send,
+    F: FnMut(Result<SolveResult, E>) -> io::Result<()> + Send,
+{
+    let (jobs, queue) = mpsc::channel::<(usize, Board)>();
+    let queue = Mutex::new(queue);
+    let (done, results) = mpsc::channel();
+    // A slot is taken for every puzzle read and given back once its result
+    // is output, which keeps the puzzles in flight within the window.
+    let (free, slots) = mpsc::sync_channel(window);
+    for _ in 0..window {
+        free.send(()).unwrap();
+    }
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            let queue = &queue;
+            let done = done.clone();
+            scope.spawn(move || {
+                let mut solver = Solver::with_options(options);
+                let mut solution = Board::default();
+                loop {
+                    let job = queue.lock().unwrap().recv();
+                    let (i, puzzle) = match job {
+                        Ok(job) => job,
+                        Err(_) => break,
+                    };
+                    let started = Instant::now();
+                    let found = solver.solve_into(&puzzle, &mut solution);
+                    let result = SolveResult {
+                        solution: found.then_some(solution),
+                        duration: started.elapsed(),
+                        stats: solver.stats(),
+                    };
+                    if done.send((i, Ok(result))).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        let writer = scope.spawn(move || {
+            let mut ready = BTreeMap::new();
+            let mut next = 0;
+            for (i, result) in results {
+                ready.insert(i, result);
+                while let Some(result) = ready.remove(&next) {
+                    output(result)?;
+                    next += 1;
+                    let _ = free.send(());
+                }
+            }
+            Ok(())
+        });
+        for (i, puzzle) in puzzles.into_iter().enumerate() {
+            // The writer is gone if output failed.
+            if slots.recv().is_err() {
+                break;
+            }
+            match puzzle {
+                Ok(puzzle) => jobs.send((i, puzzle)).unwrap(),
+                Err(err) => {
+                    let _ = done.send((i, Err(err)));
+                }
+            }
+        }
+        drop(jobs);
+        drop(done);
+        writer.join().unwrap()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{default_board, Cell};
+    use crate::solver::Sudoku;
+
+    #[test]
+    fn test_solve_all() {
+        let mut broken = default_board();
+        broken[0][0] = Cell::solved(5);
+        let puzzles: Vec<Board> = (0..200).map(|i| if i % 3 == 0 { broken } else { default_board() }).collect();
+        let results = solve_all_on(&puzzles, 4, SolveOptions::default());
+        let solution = Sudoku::new(default_board()).solve();
+        for (i, result) in results.iter().enumerate() {
+            assert_eq!(result.solution, if i % 3 == 0 { None } else { solution });
+        }
+        let stats = BatchStats::new(&results);
+        assert_eq!((stats.solved, stats.unsolvable), (133, 67));
+        assert!(stats.slowest <= stats.total);
+        assert_eq!(solve_all(&[]), vec![]);
+        let restarted = solve_all_on(&puzzles, 4, SolveOptions::default().restarts(1));
+        assert!(restarted.iter().zip(results.iter()).all(|(a, b)| a.solution == b.solution));
+
+        // Streaming gives the same results in the same order, with the
+        // errors in between, however small the window.
+        let items = puzzles.iter().enumerate().map(|(i, &puzzle)| if i % 5 == 0 { Err(i) } else { Ok(puzzle) });
+        let mut streamed = Vec::new();
+        solve_stream_on(items, 4, 3, SolveOptions::default(), |result| {
+            streamed.push(result);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(streamed.len(), 200);
+        for (i, result) in streamed.iter().enumerate() {
+            match result {
+                Err(err) => assert_eq!((i % 5, *err), (0, i)),
+                Ok(result) => assert_eq!(result.solution, results[i].solution),
+            }
+        }
+        let mut outputs = 0;
+        let failed = solve_stream(puzzles.iter().map(|&puzzle| Ok::<_, ()>(puzzle)), |_| {
+            outputs += 1;
+            Err(io::Error::other("closed"))
+        });
+        assert_eq!(failed.unwrap_err().to_string(), "closed");
+        assert_eq!(outputs, 1);
+    }
+}
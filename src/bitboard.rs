@@ -0,0 +1,136 @@
+//! The board representation the backtracking search works on, for people
+//! writing search experiments of their own: a byte per cell and a mask of
+//! the digits used in each row, column, and box, so checking and placing a
+//! digit are a few bit operations. Cells are indexed from 0 to 80 row by
+//! row and masks have bit `d` set for digit `d`, as in `Grid`.
+//!
+//! This module is only built with the `unstable` feature and may change in
+//! any release, along with the search it's taken from.
+
+use crate::board::Board;
+use crate::grid::{digit_mask, Mask};
+use crate::packed::Packed;
+
+/// A board of placed digits with the digits used in each unit.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Bitboard {
+    packed: Packed,
+}
+
+impl Bitboard {
+    /// Returns an empty board.
+    pub fn new() -> Bitboard {
+        Bitboard { packed: Packed::new() }
+    }
+
+    /// Packs the digits of `board`. Returns None if a digit is given twice in
+    /// a unit or a cell holds something other than 1 to 9.
+    pub fn from_board(board: &Board) -> Option<Bitboard> {
+        Packed::from_board(board).map(|packed| Bitboard { packed })
+    }
+
+    /// Unpacks the board, with every filled cell solved.
+    pub fn to_board(&self) -> Board {
+        let mut board = Board::default();
+        self.packed.write_to(&mut board);
+        board
+    }
+
+    /// Returns the digit of the cell at `index`, or 0 if it's empty.
+    pub fn digit(&self, index: usize) -> u8 {
+        self.packed.cells[index]
+    }
+
+    /// Returns the digits used in `row`.
+    pub fn row_mask(&self, row: usize) -> Mask {
+        self.packed.used(row, 0, 0).0
+    }
+
+    /// Returns the digits used in `col`.
+    pub fn col_mask(&self, col: usize) -> Mask {
+        self.packed.used(0, col, 0).1
+    }
+
+    /// Returns the digits used in box `b`, numbered row by row.
+    pub fn box_mask(&self, b: usize) -> Mask {
+        self.packed.used(0, 0, b).2
+    }
+
+    /// Returns the digits no peer of the cell at `index` uses, which are
+    /// the candidates of the cell if it's empty.
+    pub fn candidates(&self, index: usize) -> Mask {
+        self.packed.candidates(index)
+    }
+
+    /// Returns whether no peer of the cell at `index` uses `digit`.
+    pub fn allows(&self, index: usize, digit: u8) -> bool {
+        self.packed.allows(index, digit)
+    }
+
+    /// Places `digit` in the empty cell at `index`, which must allow it.
+    pub fn place(&mut self, index: usize, digit: u8) {
+        self.packed.place(index, digit);
+    }
+
+    /// Empties the cell at `index`, which holds `digit`.
+    pub fn unplace(&mut self, index: usize, digit: u8) {
+        self.packed.unplace(index, digit);
+    }
+
+    /// Returns the position in `cells`, which mustn't be empty, of the empty
+    /// cell with the fewest candidates, stopping early at one with at most
+    /// one, as the search picks the cell to branch on.
+    pub fn fewest_candidates(&self, cells: &[u8]) -> usize {
+        self.packed.fewest_candidates(cells)
+    }
+
+    /// Returns how many candidates placing `digit` in the cell at `index`
+    /// would remove from its empty peers.
+    pub fn eliminations(&self, index: usize, digit: u8) -> u32 {
+        self.packed.eliminations(index, digit)
+    }
+
+    /// Returns the empty cells that allow `digit` as a 27-bit mask per band
+    /// of three rows, with bit `row % 3 * 9 + col` set for each cell, the
+    /// layout fast bitboard solvers use.
+    pub fn candidate_bands(&self, digit: u8) -> [u32; 3] {
+        let mut bands = [0; 3];
+        let mask = digit_mask(digit as i8);
+        for index in 0..81 {
+            if self.digit(index) == 0 && self.candidates(index) & mask != 0 {
+                bands[index / 27] |= 1 << (index % 27);
+            }
+        }
+        bands
+    }
+}
+
+impl Default for Bitboard {
+    fn default() -> Bitboard {
+        Bitboard::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{board_to_string, default_board};
+
+    #[test]
+    fn test_bitboard() {
+        let mut bitboard = Bitboard::from_board(&default_board()).unwrap();
+        assert_eq!(board_to_string(&bitboard.to_board()), board_to_string(&default_board()));
+        assert_eq!(bitboard.digit(2), 5);
+        assert_eq!(bitboard.row_mask(0), digit_mask(5) | digit_mask(8));
+        assert_eq!(bitboard.candidates(0), digit_mask(1) | digit_mask(3) | digit_mask(4) | digit_mask(6));
+
+        let bands = bitboard.candidate_bands(3);
+        assert_ne!(bands[0] & 1, 0);
+        bitboard.place(0, 3);
+        assert_eq!(bitboard.box_mask(0) & digit_mask(3), digit_mask(3));
+        // Placing 3 in r1c1 rules it out of the rest of the first row.
+        assert_eq!(bitboard.candidate_bands(3)[0] & 0b1_1111_1111, 0);
+        bitboard.unplace(0, 3);
+        assert_eq!(bitboard.candidate_bands(3), bands);
+    }
+}
@@ -0,0 +1,343 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::symbols::SymbolSet;
+
+/// Where the digit of a cell came from.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Origin {
+    /// A clue of the puzzle.
+    #[default]
+    Given,
+    /// Placed by the solver without guessing.
+    Deduced,
+    /// Placed by the solver while guessing, so it may rest on a guess.
+    Guessed,
+    /// Filled in by a player.
+    UserEntered,
+}
+
+/// Represents a cell in a sudoku board, which is either solved, in which case
+/// `solution` is its digit, or unsolved.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub struct Cell {
+    pub(crate) solution: Option<i8>,
+    pub(crate) origin: Origin,
+}
+
+impl Cell {
+    /// Returns a cell with `solution` as a given.
+    pub fn solved(solution: i8) -> Cell {
+        Cell::with_origin(solution, Origin::Given)
+    }
+
+    pub fn with_origin(solution: i8, origin: Origin) -> Cell {
+        Cell { solution: Some(solution), origin }
+    }
+
+    pub fn unsolved() -> Cell {
+        Cell { solution: None, origin: Origin::Given }
+    }
+
+    /// Returns the digit in this cell, if it's solved.
+    pub fn solution(&self) -> Option<i8> {
+        self.solution
+    }
+
+    /// Returns where the digit in this cell came from, if it's solved.
+    pub fn origin(&self) -> Option<Origin> {
+        self.solution.map(|_| self.origin)
+    }
+}
+
+pub type Board = [[Cell; 9]; 9];
+
+fn solved(n: i8) -> Cell {
+    Cell::solved(n)
+}
+
+fn unsolved() -> Cell {
+    Cell::unsolved()
+}
+
+/// Parses a board from the common 81 character format, where cells are listed
+/// row by row and empty cells are written as `.` or `0`. Whitespace is
+/// ignored. Returns None if the string isn't a valid board.
+pub fn parse_board(s: &str) -> Option<Board> {
+    parse_board_with(s, ParseMode::Strict).ok().map(|parsed| parsed.board)
+}
+
+/// How forgiving `parse_board_with` is.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ParseMode {
+    /// Only digits, `.`, and whitespace are allowed.
+    Strict,
+    /// Other common placeholders for empty cells (`_`, `*`, `x`, and `X`) are
+    /// taken as empty cells, and any other character, like the lines of a
+    /// drawn grid, is skipped. Both are reported as warnings.
+    Lenient,
+}
+
+/// Why a string couldn't be parsed as a board.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseError {
+    /// The byte offset in the string where the problem is.
+    pub offset: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at byte {}", self.message, self.offset)
+    }
+}
+
+impl Error for ParseError {}
+
+/// Something the lenient parser took a guess at.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ParseWarning {
+    /// The character at the byte offset was skipped.
+    Skipped { offset: usize, c: char },
+    /// The character at the byte offset was taken as an empty cell.
+    Placeholder { offset: usize, c: char },
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseWarning::Skipped { offset, c } => write!(f, "skipped {:?} at byte {}", c, offset),
+            ParseWarning::Placeholder { offset, c } => {
+                write!(f, "took {:?} at byte {} as an empty cell", c, offset)
+            }
+        }
+    }
+}
+
+/// A parsed board and the warnings of the lenient parser.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Parsed {
+    pub board: Board,
+    pub warnings: Vec<ParseWarning>,
+}
+
+/// Parses a board in the 81 character format like `parse_board`, but with a
+/// choice of how strict to be and with errors that say where the problem is.
+pub fn parse_board_with(s: &str, mode: ParseMode) -> Result<Parsed, ParseError> {
+    let mut board: Board = Default::default();
+    let mut warnings = Vec::new();
+    let mut num_cells = 0;
+    for (offset, c) in s.char_indices().filter(|(_, c)| !c.is_whitespace()) {
+        let digit = match (c, mode) {
+            ('1'..='9', _) => Some(c as i8 - b'0' as i8),
+            ('.' | '0', _) => None,
+            ('_' | '*' | 'x' | 'X', ParseMode::Lenient) => {
+                warnings.push(ParseWarning::Placeholder { offset, c });
+                None
+            }
+            (_, ParseMode::Lenient) => {
+                warnings.push(ParseWarning::Skipped { offset, c });
+                continue;
+            }
+            (_, ParseMode::Strict) => {
+                return Err(ParseError { offset, message: format!("unexpected {:?}", c) });
+            }
+        };
+        if num_cells == 81 {
+            return Err(ParseError { offset, message: String::from("more than 81 cells") });
+        }
+        if let Some(digit) = digit {
+            board[num_cells / 9][num_cells % 9] = Cell::solved(digit);
+        }
+        num_cells += 1;
+    }
+    if num_cells < 81 {
+        return Err(ParseError { offset: s.len(), message: format!("only {} of 81 cells", num_cells) });
+    }
+    Ok(Parsed { board, warnings })
+}
+
+/// Formats the board in the 81 character format accepted by `parse_board`.
+pub fn board_to_string(board: &Board) -> String {
+    let mut s = String::with_capacity(81);
+    for row in board.iter() {
+        for cell in row.iter() {
+            match cell.solution {
+                Some(solution) => s.push((b'0' + solution as u8) as char),
+                None => s.push('.'),
+            }
+        }
+    }
+    s
+}
+
+/// A cell whose digit differs between two boards.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct CellDiff {
+    pub row: usize,
+    pub col: usize,
+    pub old: Option<i8>,
+    pub new: Option<i8>,
+}
+
+/// Returns the cells whose digits differ between `old` and `new`, row by row.
+/// Where the digits came from isn't compared.
+pub fn diff_boards(old: &Board, new: &Board) -> Vec<CellDiff> {
+    let mut diffs = Vec::new();
+    for row in 0..9 {
+        for col in 0..9 {
+            let (a, b) = (old[row][col].solution, new[row][col].solution);
+            if a != b {
+                diffs.push(CellDiff { row, col, old: a, new: b });
+            }
+        }
+    }
+    diffs
+}
+
+pub fn print_board(board: &Board) {
+    print_board_with(board, &SymbolSet::digits());
+}
+
+/// Prints the board like `print_board`, with the digits written as
+/// `symbols`.
+pub fn print_board_with(board: &Board, symbols: &SymbolSet) {
+    let border = {
+        let mut s = String::new();
+        s.push('|');
+        for _ in 0..35 {
+            s.push('=');
+        }
+        s.push('|');
+        s
+    };
+    let separator = {
+        let mut s = String::new();
+        s.push('|');
+        for _ in 0..3 {
+            for _ in 0..11 {
+                s.push('-');
+            }
+            s.push('|');
+        }
+        s
+    };
+
+    for (num_lines, row) in board.iter().enumerate() {
+        if num_lines % 3 == 0 {
+            println!("{}", border);
+        } else {
+            println!("{}", separator);
+        }
+        let mut line = String::from("|");
+        for col in row.iter() {
+            match col.solution {
+                Some(solution) => {
+                    line += &format!(" {} |", symbols.symbol(solution));
+                },
+                None => {
+                    line += &String::from("   |");
+                }
+            }
+        }
+        println!("{}", line);
+    }
+    println!("{}", border);
+}
+
+pub fn default_board() -> Board {
+    [
+        [
+            unsolved(), unsolved(), solved(5),
+            unsolved(), unsolved(), solved(8),
+            unsolved(), unsolved(), unsolved(),
+        ],
+        [
+            unsolved(), solved(2), unsolved(),
+            unsolved(), unsolved(), unsolved(),
+            solved(5), unsolved(), unsolved(),
+        ],
+        [
+            solved(7), solved(9), unsolved(),
+            solved(3), solved(4), solved(5),
+            solved(6), solved(2), unsolved(),
+        ],
+
+        [
+            unsolved(), unsolved(), unsolved(),
+            solved(6), unsolved(), solved(4),
+            solved(7), solved(1), unsolved(),
+        ],
+        [
+            unsolved(), solved(4), solved(9),
+            solved(5), unsolved(), solved(7),
+            solved(8), solved(3), unsolved(),
+        ],
+        [
+            unsolved(), solved(1), solved(7),
+            solved(8), unsolved(), solved(2),
+            unsolved(), unsolved(), unsolved(),
+        ],
+
+        [
+            unsolved(), solved(5), solved(4),
+            solved(7), solved(8), solved(3),
+            unsolved(), solved(9), solved(6),
+        ],
+        [
+            unsolved(), unsolved(), solved(6),
+            unsolved(), unsolved(), unsolved(),
+            unsolved(), solved(5), unsolved(),
+        ],
+        [
+            unsolved(), unsolved(), unsolved(),
+            solved(1), unsolved(), unsolved(),
+            solved(4), unsolved(), unsolved(),
+        ],
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_board() {
+        let s = "..5..8....2....5..79.34562....6.471..495.783..178.2....54783.96..6....5....1..4..";
+        let board = parse_board(s).unwrap();
+        assert_eq!(board, default_board());
+        assert_eq!(board_to_string(&board), s);
+        assert!(parse_board(&s[1..]).is_none());
+        assert!(parse_board(&s.replace('7', "x")).is_none());
+
+        let err = parse_board_with(&s.replacen('7', "x", 1), ParseMode::Strict).unwrap_err();
+        assert_eq!(err.to_string(), "unexpected 'x' at byte 18");
+        let err = parse_board_with(&format!("{}1", s), ParseMode::Strict).unwrap_err();
+        assert_eq!((err.offset, err.message.as_str()), (81, "more than 81 cells"));
+        assert_eq!(parse_board_with(&s[1..], ParseMode::Lenient).unwrap_err().offset, 80);
+
+        let drawn = format!("|{}|\n{}", &s[..9].replace('.', "_"), &s[9..]);
+        let parsed = parse_board_with(&drawn, ParseMode::Lenient).unwrap();
+        assert_eq!(parsed.board, default_board());
+        assert_eq!(parsed.warnings.len(), 2 + 7);
+        assert_eq!(parsed.warnings[0], ParseWarning::Skipped { offset: 0, c: '|' });
+        assert_eq!(parsed.warnings[1], ParseWarning::Placeholder { offset: 1, c: '_' });
+    }
+
+    #[test]
+    fn test_diff_boards() {
+        let old = default_board();
+        assert_eq!(diff_boards(&old, &old), vec![]);
+        let mut new = old;
+        new[0][0] = Cell::with_origin(1, Origin::UserEntered);
+        new[0][2] = Cell::unsolved();
+        new[2][0] = Cell::with_origin(7, Origin::Deduced);
+        assert_eq!(
+            diff_boards(&old, &new),
+            vec![
+                CellDiff { row: 0, col: 0, old: None, new: Some(1) },
+                CellDiff { row: 0, col: 2, old: Some(5), new: None },
+            ]
+        );
+    }
+}
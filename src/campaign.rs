@@ -0,0 +1,133 @@
+//! A campaign through the bundled puzzles of increasing difficulty, enabled
+//! with the `db` feature. The puzzles are kept in `data/campaign.txt` with
+//! one per line, and they're split into tiers
+//! by their difficulty, and a tier is unlocked once every puzzle of the one
+//! before is played. Progress is kept in the puzzle database as the plays of
+//! the puzzles, so `Database::mark_played` completes a puzzle and the
+//! statistics include the campaign.
+
+use std::fmt;
+
+use crate::board::{parse_board, Board};
+use crate::db::{Database, Entry, Result};
+use crate::generate::Difficulty;
+
+/// The source the campaign's puzzles are stored with.
+const SOURCE: &str = "campaign";
+
+/// Returns the campaign's puzzles, skipping the comments of the file.
+fn puzzles() -> impl Iterator<Item = Board> {
+    include_str!("../data/campaign.txt").lines().filter(|line| !line.starts_with('#')).filter_map(parse_board)
+}
+
+/// The puzzles of one difficulty.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Tier {
+    pub difficulty: Difficulty,
+    pub entries: Vec<Entry>,
+    /// Whether every puzzle of the tiers before has been played.
+    pub unlocked: bool,
+}
+
+impl Tier {
+    /// The puzzles that have been played.
+    pub fn completed(&self) -> usize {
+        self.entries.iter().filter(|entry| entry.plays > 0).count()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.completed() == self.entries.len()
+    }
+}
+
+/// The progress through the campaign, easiest tier first.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Campaign {
+    pub tiers: Vec<Tier>,
+}
+
+impl Campaign {
+    /// Loads the progress from `db`, adding the campaign's puzzles to it the
+    /// first time.
+    pub fn load(db: &Database) -> Result<Campaign> {
+        let mut entries = Vec::new();
+        for puzzle in puzzles() {
+            if db.get(&puzzle)?.is_none() {
+                db.add(&puzzle, SOURCE)?;
+            }
+            // Every bundled puzzle has a unique solution, so it's there now.
+            entries.extend(db.get(&puzzle)?);
+        }
+        let mut tiers: Vec<Tier> = Vec::new();
+        for &difficulty in Difficulty::ALL.iter() {
+            let entries: Vec<Entry> = entries.iter().filter(|entry| entry.difficulty == difficulty).cloned().collect();
+            if entries.is_empty() {
+                continue;
+            }
+            let unlocked = tiers.last().is_none_or(|tier| tier.unlocked && tier.is_complete());
+            tiers.push(Tier { difficulty, entries, unlocked });
+        }
+        Ok(Campaign { tiers })
+    }
+
+    /// Returns the first puzzle of the unlocked tiers that hasn't been
+    /// played, or None once the campaign is complete.
+    pub fn next(&self) -> Option<&Entry> {
+        let mut unlocked = self.tiers.iter().filter(|tier| tier.unlocked);
+        unlocked.find_map(|tier| tier.entries.iter().find(|entry| entry.plays == 0))
+    }
+}
+
+/// Lists the tiers with how many of their puzzles are played, or that
+/// they're locked.
+impl fmt::Display for Campaign {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, tier) in self.tiers.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{:<8}", tier.difficulty.name())?;
+            if tier.unlocked {
+                write!(f, "{}/{} played", tier.completed(), tier.entries.len())?;
+            } else {
+                write!(f, "locked")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_campaign() {
+        let db = Database::open_in_memory().unwrap();
+        let campaign = Campaign::load(&db).unwrap();
+        let difficulties: Vec<Difficulty> = campaign.tiers.iter().map(|tier| tier.difficulty).collect();
+        assert_eq!(difficulties, Difficulty::ALL);
+        assert!(campaign.tiers.iter().skip(1).all(|tier| !tier.unlocked));
+        assert_eq!(campaign.to_string().lines().next(), Some("easy    0/3 played"));
+
+        // Playing the easy puzzles unlocks the medium ones, and loading again
+        // doesn't add the puzzles twice.
+        let first = campaign.next().unwrap().id;
+        for entry in campaign.tiers[0].entries.iter() {
+            assert!(db.mark_played(entry.id, Some(60)).unwrap());
+        }
+        let campaign = Campaign::load(&db).unwrap();
+        assert!(campaign.tiers[0].is_complete());
+        assert!(campaign.tiers[1].unlocked && !campaign.tiers[2].unlocked);
+        assert_eq!(campaign.next().map(|entry| entry.difficulty), Some(Difficulty::Medium));
+        assert_ne!(campaign.next().unwrap().id, first);
+        assert_eq!(db.find(None, false, 100).unwrap().len(), 12);
+
+        for entry in campaign.tiers.iter().flat_map(|tier| tier.entries.iter()) {
+            db.mark_played(entry.id, None).unwrap();
+        }
+        let campaign = Campaign::load(&db).unwrap();
+        assert!(campaign.tiers.iter().all(|tier| tier.unlocked && tier.is_complete()));
+        assert_eq!(campaign.next(), None);
+    }
+}
@@ -0,0 +1,369 @@
+//! Canonical forms of puzzles, for finding the same puzzle in different
+//! guises. Swapping rows within a band, bands, columns within a stack, or
+//! stacks, transposing, and relabeling the digits all keep a puzzle what it
+//! is, so the canonical form is the smallest of the grids that can be made
+//! that way, read row by row with empty cells as 0. The same
+//! transformations tell how close two puzzles are.
+
+use std::collections::HashSet;
+
+use crate::board::{board_to_string, parse_board, Board, Cell};
+
+type Digits = [[u8; 9]; 9];
+
+/// The six orders of three things.
+const ORDERS: [[usize; 3]; 6] = [[0, 1, 2], [0, 2, 1], [1, 0, 2], [1, 2, 0], [2, 0, 1], [2, 1, 0]];
+
+/// Returns the orders of the rows that keep the bands together, which are
+/// the orders of the columns that keep the stacks together too.
+fn line_orders() -> Vec<[usize; 9]> {
+    let mut orders = Vec::with_capacity(6 * 6 * 6 * 6);
+    for stacks in ORDERS.iter() {
+        for a in ORDERS.iter() {
+            for b in ORDERS.iter() {
+                for c in ORDERS.iter() {
+                    let mut order = [0; 9];
+                    for (i, within) in [a, b, c].iter().enumerate() {
+                        for j in 0..3 {
+                            order[i * 3 + j] = stacks[i] * 3 + within[j];
+                        }
+                    }
+                    orders.push(order);
+                }
+            }
+        }
+    }
+    orders
+}
+
+/// Returns the canonical form of `board`, which is the same for every board
+/// that's the same puzzle with its rows, columns, and digits shuffled, and
+/// different for every other. Only the digits of the cells matter.
+pub fn canonical_form(board: &Board) -> Board {
+    let mut digits = [[0; 9]; 9];
+    for row in 0..9 {
+        for col in 0..9 {
+            digits[row][col] = board[row][col].solution.map_or(0, |digit| digit as u8);
+        }
+    }
+    let mut transposed = [[0; 9]; 9];
+    for row in 0..9 {
+        for col in 0..9 {
+            transposed[col][row] = digits[row][col];
+        }
+    }
+    let mut best = [[u8::MAX; 9]; 9];
+    for order in line_orders() {
+        for grid in [digits, transposed].iter() {
+            let mut permuted = [[0; 9]; 9];
+            for row in 0..9 {
+                for col in 0..9 {
+                    permuted[row][col] = grid[row][order[col]];
+                }
+            }
+            order_rows(&permuted, 0, 0, 0, [0; 10], 1, &mut best);
+        }
+    }
+    let mut canonical = Board::default();
+    for row in 0..9 {
+        for col in 0..9 {
+            if best[row][col] != 0 {
+                canonical[row][col] = Cell::solved(best[row][col] as i8);
+            }
+        }
+    }
+    canonical
+}
+
+/// Picks the rows of `grid` from `depth` on, keeping the bands together, and
+/// lowers `best` to the smallest grid it finds. The rows before `depth`,
+/// `used` as a bit set, match `best`. `band` is the band being picked from,
+/// and `labels` the digit each digit seen so far is relabeled as, with
+/// `next` the label for the next new one.
+fn order_rows(grid: &Digits, depth: usize, used: u16, band: usize, labels: [u8; 10], next: u8, best: &mut Digits) {
+    for row in 0..9 {
+        let available = if depth.is_multiple_of(3) {
+            used >> (row / 3 * 3) & 0b111 == 0
+        } else {
+            row / 3 == band && used & 1 << row == 0
+        };
+        if !available {
+            continue;
+        }
+        let mut labels = labels;
+        let mut next = next;
+        let mut labeled = [0; 9];
+        for col in 0..9 {
+            let digit = grid[row][col] as usize;
+            if digit != 0 {
+                if labels[digit] == 0 {
+                    labels[digit] = next;
+                    next += 1;
+                }
+                labeled[col] = labels[digit];
+            }
+        }
+        if labeled > best[depth] {
+            continue;
+        }
+        if labeled < best[depth] {
+            // Whatever the rows below become beats what `best` had.
+            best[depth] = labeled;
+            for rest in best[depth + 1..].iter_mut() {
+                *rest = [u8::MAX; 9];
+            }
+        }
+        if depth < 8 {
+            order_rows(grid, depth + 1, used | 1 << row, row / 3, labels, next, best);
+        }
+    }
+}
+
+/// How close two puzzles are.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Similarity {
+    /// The most givens the puzzles share when the second is transformed to
+    /// fit the first as well as it can.
+    pub shared: usize,
+    /// The number of givens of each puzzle.
+    pub givens: [usize; 2],
+}
+
+impl Similarity {
+    /// Returns the share of the givens of the puzzle with more of them that
+    /// the other has too, from 0 for nothing in common to 1 for the same
+    /// puzzle.
+    pub fn score(&self) -> f64 {
+        match self.givens[0].max(self.givens[1]) {
+            0 => 1.0,
+            givens => self.shared as f64 / givens as f64,
+        }
+    }
+}
+
+/// Compares two puzzles under every transformation of `b` that
+/// `canonical_form` considers, for setters to check that a new puzzle isn't
+/// a published one in disguise. A digit of `a` and a digit of `b` count as
+/// the same if the relabeling that fits best maps one to the other.
+pub fn similarity(a: &Board, b: &Board) -> Similarity {
+    let digits = |board: &Board| {
+        let mut digits = [[0u8; 9]; 9];
+        for row in 0..9 {
+            for col in 0..9 {
+                digits[row][col] = board[row][col].solution.map_or(0, |digit| digit as u8);
+            }
+        }
+        digits
+    };
+    let (a, b) = (digits(a), digits(b));
+    let mut transposed = [[0; 9]; 9];
+    for row in 0..9 {
+        for col in 0..9 {
+            transposed[col][row] = b[row][col];
+        }
+    }
+    let mut pattern = [0u16; 9];
+    for row in 0..9 {
+        for col in 0..9 {
+            if a[row][col] != 0 {
+                pattern[row] |= 1 << col;
+            }
+        }
+    }
+    let count = |digits: &Digits| digits.iter().flatten().filter(|&&digit| digit != 0).count();
+    let givens = [count(&a), count(&b)];
+    let orders = line_orders();
+    let mut shared = 0;
+    for grid in [b, transposed].iter() {
+        for cols in orders.iter() {
+            // How many givens each row of `a` and each row of `grid` with
+            // its columns in this order have in the same places, which is
+            // the most they can share.
+            let mut overlap = [[0; 9]; 9];
+            for j in 0..9 {
+                let mut givens = 0u16;
+                for col in 0..9 {
+                    if grid[j][cols[col]] != 0 {
+                        givens |= 1 << col;
+                    }
+                }
+                for i in 0..9 {
+                    overlap[i][j] = (pattern[i] & givens).count_ones() as usize;
+                }
+            }
+            // Which is at most the best fit of each band on its own.
+            let mut bands = [[0; 3]; 3];
+            for i in 0..3 {
+                for j in 0..3 {
+                    bands[i][j] = ORDERS
+                        .iter()
+                        .map(|order| (0..3).map(|k| overlap[i * 3 + k][j * 3 + order[k]]).sum::<usize>())
+                        .max()
+                        .unwrap();
+                }
+            }
+            if ORDERS.iter().map(|order| (0..3).map(|i| bands[i][order[i]]).sum::<usize>()).max().unwrap() <= shared {
+                continue;
+            }
+            for rows in orders.iter() {
+                if (0..9).map(|i| overlap[i][rows[i]]).sum::<usize>() <= shared {
+                    continue;
+                }
+                let mut pairs = [[0; 9]; 9];
+                for row in 0..9 {
+                    for col in 0..9 {
+                        let (x, y) = (a[row][col], grid[rows[row]][cols[col]]);
+                        if x != 0 && y != 0 {
+                            pairs[x as usize - 1][y as usize - 1] += 1;
+                        }
+                    }
+                }
+                // Matching each digit with the one it's paired with most
+                // often, whether or not another digit is matched with it
+                // too, is as good as any relabeling can be.
+                let most = |x: usize| (0..9).map(|y| pairs[x][y]).max().unwrap();
+                if (0..9).map(most).sum::<usize>() <= shared {
+                    continue;
+                }
+                shared = shared.max(best_relabeling(&pairs));
+                if shared == givens[0].min(givens[1]) {
+                    return Similarity { shared, givens };
+                }
+            }
+        }
+    }
+    Similarity { shared, givens }
+}
+
+/// Returns the most cells that agree under a relabeling of the digits, given
+/// how many cells have each pair of digits. Each digit of the first puzzle
+/// is matched with the digits of the second in turn, remembering the best
+/// for each set of digits of the second taken.
+fn best_relabeling(pairs: &[[usize; 9]; 9]) -> usize {
+    let mut best = [0; 1 << 9];
+    for taken in 0..1usize << 9 {
+        let x = taken.count_ones() as usize;
+        if x == 9 {
+            continue;
+        }
+        for y in 0..9 {
+            if taken & 1 << y == 0 {
+                let next = taken | 1 << y;
+                best[next] = best[next].max(best[taken] + pairs[x][y]);
+            }
+        }
+    }
+    best[(1 << 9) - 1]
+}
+
+/// The puzzles of a pack with the duplicates dropped.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Deduped {
+    /// The first of each puzzle, in the order of the pack.
+    pub puzzles: Vec<Board>,
+    /// How many puzzles were dropped as duplicates.
+    pub duplicates: usize,
+    /// How many lines weren't puzzles.
+    pub invalid: usize,
+}
+
+/// Drops the puzzles of `pack`, one per line, that came before in it. Lines
+/// are read like `Database::import` reads them, so the puzzle comes first
+/// and anything after it is ignored. Puzzles are duplicates if they have
+/// the same givens, however they're written, or with `isomorphic` if they
+/// have the same canonical form.
+pub fn dedupe(pack: &str, isomorphic: bool) -> Deduped {
+    let mut deduped = Deduped::default();
+    let mut seen = HashSet::new();
+    for line in pack.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let board = match line.split_whitespace().next().and_then(parse_board) {
+            Some(board) => board,
+            None => {
+                deduped.invalid += 1;
+                continue;
+            }
+        };
+        let key = if isomorphic { canonical_form(&board) } else { board };
+        if seen.insert(board_to_string(&key)) {
+            deduped.puzzles.push(board);
+        } else {
+            deduped.duplicates += 1;
+        }
+    }
+    deduped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::default_board;
+
+    #[test]
+    fn test_canonical_form() {
+        let puzzle = default_board();
+        // Transpose, swap the first two bands and two columns of a stack,
+        // and swap the digits 1 and 2.
+        let mut other = Board::default();
+        for row in 0..9 {
+            for col in 0..9 {
+                let r = if row < 6 { (row + 3) % 6 } else { row };
+                let c = [0, 1, 2, 3, 5, 4, 6, 7, 8][col];
+                other[row][col] = match puzzle[c][r].solution {
+                    Some(1) => Cell::solved(2),
+                    Some(2) => Cell::solved(1),
+                    _ => puzzle[c][r],
+                };
+            }
+        }
+        assert_ne!(board_to_string(&other), board_to_string(&puzzle));
+        let canonical = canonical_form(&puzzle);
+        assert_eq!(canonical_form(&other), canonical);
+        assert_eq!(canonical_form(&canonical), canonical);
+        let mut changed = puzzle;
+        changed[0][0] = Cell::solved(1);
+        assert_ne!(canonical_form(&changed), canonical);
+
+        let pack = format!(
+            "{}\n{} duplicate\n# comment\nnot a puzzle\n{}\n",
+            board_to_string(&puzzle),
+            board_to_string(&puzzle).replace('.', "0"),
+            board_to_string(&other)
+        );
+        let deduped = dedupe(&pack, false);
+        assert_eq!((deduped.puzzles.len(), deduped.duplicates, deduped.invalid), (2, 1, 1));
+        let deduped = dedupe(&pack, true);
+        assert_eq!(deduped.puzzles, vec![puzzle]);
+        assert_eq!(deduped.duplicates, 2);
+    }
+
+    #[test]
+    fn test_similarity() {
+        let puzzle = default_board();
+        let mut other = puzzle;
+        for row in other.iter_mut() {
+            row.swap(0, 2);
+        }
+        // Swap 5 and 9 and drop two givens.
+        for cell in other.iter_mut().flatten() {
+            match cell.solution {
+                Some(5) => *cell = Cell::solved(9),
+                Some(9) => *cell = Cell::solved(5),
+                _ => {}
+            }
+        }
+        for cell in other.iter_mut().flatten().filter(|cell| cell.solution.is_some()).take(2) {
+            *cell = Cell::unsolved();
+        }
+        let givens = puzzle.iter().flatten().filter(|cell| cell.solution.is_some()).count();
+        let close = similarity(&puzzle, &other);
+        assert_eq!(close.givens, [givens, givens - 2]);
+        assert_eq!(close.shared, givens - 2);
+        assert!(close.score() < 1.0);
+        assert_eq!(similarity(&puzzle, &canonical_form(&puzzle)).score(), 1.0);
+        let hard = parse_board("8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..").unwrap();
+        assert!(similarity(&puzzle, &hard).score() < 0.5);
+    }
+}
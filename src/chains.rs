@@ -0,0 +1,195 @@
+//! Alternating inference chains (AICs), the techniques that take over when
+//! pairs and X-Wings run out. A chain is a path through candidates whose
+//! links alternate between strong ones, where at least one end is true, and
+//! weak ones, where at most one end is. Starting and ending with a strong
+//! link, either the first or the last candidate of the chain is true, so
+//! any candidate that rules out both can go.
+//!
+//! An X-Chain is made of a single digit: its strong links are houses with
+//! two places for the digit left. An XY-Chain hops between cells with two
+//! candidates, its strong links, over weak links between the same digit in
+//! cells that see each other.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::grid::{mask_digits, peers, Grid, House};
+use crate::logic::{Step, Technique};
+
+/// A candidate, as (row, column, digit).
+type Node = (usize, usize, i8);
+
+/// The most candidates a chain may have, which keeps the search quick and
+/// the chains short enough to follow.
+const MAX_NODES: usize = 12;
+
+fn sees(a: Node, b: Node) -> bool {
+    (a.0, a.1) != (b.0, b.1) && (a.0 == b.0 || a.1 == b.1 || (a.0 / 3, a.1 / 3) == (b.0 / 3, b.1 / 3))
+}
+
+/// Returns the candidates `node` is strongly linked to in chains of
+/// `technique`.
+fn strong_links(grid: &Grid, node: Node, technique: Technique) -> Vec<Node> {
+    let (row, col, digit) = node;
+    if technique == Technique::XYChain {
+        let candidates = grid.candidates(row, col);
+        if candidates.count_ones() != 2 {
+            return Vec::new();
+        }
+        return mask_digits(candidates).filter(|&d| d != digit).map(|d| (row, col, d)).collect();
+    }
+    let mut links = Vec::new();
+    for &house in House::containing(row, col).iter() {
+        let places = grid.places_for(house, digit);
+        if places.len() == 2 {
+            let (r, c) = if places[0] == (row, col) { places[1] } else { places[0] };
+            if !links.contains(&(r, c, digit)) {
+                links.push((r, c, digit));
+            }
+        }
+    }
+    links
+}
+
+/// Returns the candidates `node` is weakly linked to in chains of
+/// `technique`: the same digit in the cells that see it.
+fn weak_links(grid: &Grid, node: Node, technique: Technique) -> Vec<Node> {
+    let (row, col, digit) = node;
+    peers(row, col)
+        .into_iter()
+        .filter(|&(r, c)| grid.has_candidate(r, c, digit))
+        .filter(|&(r, c)| technique != Technique::XYChain || grid.candidates(r, c).count_ones() == 2)
+        .map(|(r, c)| (r, c, digit))
+        .collect()
+}
+
+/// Returns the candidates that can be eliminated because `start` or `end`
+/// is true.
+fn chain_eliminations(grid: &Grid, start: Node, end: Node) -> Vec<Node> {
+    let (sr, sc, sd) = start;
+    let (er, ec, ed) = end;
+    if sd == ed {
+        return peers(sr, sc)
+            .into_iter()
+            .filter(|&(r, c)| (r, c) != (er, ec) && sees((r, c, sd), end) && grid.has_candidate(r, c, sd))
+            .map(|(r, c)| (r, c, sd))
+            .collect();
+    }
+    if (sr, sc) == (er, ec) {
+        return mask_digits(grid.candidates(sr, sc)).filter(|&d| d != sd && d != ed).map(|d| (sr, sc, d)).collect();
+    }
+    let mut eliminations = Vec::new();
+    if sees(start, end) {
+        if grid.has_candidate(er, ec, sd) {
+            eliminations.push((er, ec, sd));
+        }
+        if grid.has_candidate(sr, sc, ed) {
+            eliminations.push((sr, sc, ed));
+        }
+    }
+    eliminations
+}
+
+/// Finds the shortest chain starting at `start` that eliminates something,
+/// with a breadth-first search over alternating links.
+fn find_chain_from(grid: &Grid, start: Node, technique: Technique) -> Option<(Vec<Node>, Vec<Node>)> {
+    // The nodes reached so far, with the one before each on its chain and
+    // the chain's length, and whether each was reached by a strong link.
+    let mut nodes: Vec<(Node, Option<usize>, usize)> = vec![(start, None, 1)];
+    let mut visited = [[[[false; 2]; 10]; 9]; 9];
+    visited[start.0][start.1][start.2 as usize][0] = true;
+    let mut queue = VecDeque::from(vec![(0, false)]);
+    while let Some((index, by_strong)) = queue.pop_front() {
+        let (node, _, length) = nodes[index];
+        if by_strong && length >= 4 {
+            let eliminations = chain_eliminations(grid, start, node);
+            if !eliminations.is_empty() {
+                let mut chain = Vec::with_capacity(length);
+                let mut at = Some(index);
+                while let Some(i) = at {
+                    chain.push(nodes[i].0);
+                    at = nodes[i].1;
+                }
+                chain.reverse();
+                return Some((chain, eliminations));
+            }
+        }
+        if length == MAX_NODES {
+            continue;
+        }
+        let next = if by_strong { weak_links(grid, node, technique) } else { strong_links(grid, node, technique) };
+        for link in next {
+            let seen = &mut visited[link.0][link.1][link.2 as usize][!by_strong as usize];
+            if *seen || link == start {
+                continue;
+            }
+            *seen = true;
+            nodes.push((link, Some(index), length + 1));
+            queue.push_back((nodes.len() - 1, !by_strong));
+        }
+    }
+    None
+}
+
+/// Finds the shortest X-Chain or XY-Chain, as `technique` says, that
+/// eliminates a candidate.
+pub(crate) fn find_chain(grid: &Grid, technique: Technique) -> Option<Step> {
+    let mut best: Option<(Vec<Node>, Vec<Node>)> = None;
+    for row in 0..9 {
+        for col in 0..9 {
+            for digit in mask_digits(grid.candidates(row, col)) {
+                let found = match find_chain_from(grid, (row, col, digit), technique) {
+                    Some(found) => found,
+                    None => continue,
+                };
+                if best.as_ref().is_none_or(|(chain, _)| found.0.len() < chain.len()) {
+                    best = Some(found);
+                }
+            }
+        }
+    }
+    let (chain, eliminations) = best?;
+    let mut cells = Vec::new();
+    for &(row, col, _) in chain.iter() {
+        if !cells.contains(&(row, col)) {
+            cells.push((row, col));
+        }
+    }
+    Some(Step {
+        technique,
+        houses: Vec::new(),
+        cells,
+        placements: Vec::new(),
+        eliminations,
+        chain,
+        refutation: None,
+        elapsed: Duration::default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::parse_board;
+    use crate::json::Json;
+    use crate::logic::solve_logically;
+    use crate::solver::Sudoku;
+
+    #[test]
+    fn test_find_chain() {
+        let board =
+            parse_board("4....15397.........8..9........68..2......4....823...7......9......75.2..9....6.1").unwrap();
+        let solution = Sudoku::new(board).solve().unwrap();
+        let trace = solve_logically(&board);
+        let step = trace.steps.iter().find(|step| step.technique == Technique::XYChain).unwrap();
+        assert!(step.chain.len() >= 4 && step.chain.len() % 2 == 0);
+        assert!(!step.eliminations.is_empty());
+        for &(row, col, digit) in step.eliminations.iter() {
+            assert_ne!(solution[row][col].solution, Some(digit));
+        }
+        let (row, col, digit) = step.chain[0];
+        assert!(step.to_string().starts_with(&format!("XY-Chain {}r{}c{}=", digit, row + 1, col + 1)));
+        let json = step.to_json();
+        assert_eq!(json.get("chain").and_then(Json::as_array).map(|chain| chain.len()), Some(step.chain.len()));
+    }
+}
@@ -0,0 +1,147 @@
+//! Replies for chat bots. A bot passes the message it got, like
+//! `solve 53..7....` or `generate hard`, and posts the reply as is, with the
+//! boards drawn so they line up in Discord and Slack.
+//!
+//! The commands are `solve PUZZLE`, `hint PUZZLE [LEVEL]`, `rate PUZZLE`, and
+//! `generate [DIFFICULTY] [SEED]`.
+
+use crate::board::{board_to_string, parse_board, Board};
+use crate::generate::{generate, Difficulty, Rng};
+use crate::hint::{hint, HintLevel};
+use crate::logic::TechniqueConfig;
+use crate::rating::rate;
+use crate::solver::Sudoku;
+
+/// How boards are drawn in chat replies.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ChatStyle {
+    /// Box drawing characters in a code block, which chat clients show in a
+    /// fixed width font.
+    Monospace,
+    /// Keycap emoji, which line up without a code block but take more room.
+    Emoji,
+}
+
+const KEYCAPS: [&str; 10] = ["⬛", "1️⃣", "2️⃣", "3️⃣", "4️⃣", "5️⃣", "6️⃣", "7️⃣", "8️⃣", "9️⃣"];
+
+/// Draws `board` for a chat message in `style`.
+pub fn chat_board(board: &Board, style: ChatStyle) -> String {
+    let digit = |row: usize, col: usize| board[row][col].solution.unwrap_or(0) as usize;
+    let mut s = String::new();
+    match style {
+        ChatStyle::Monospace => {
+            s += "```\n┌───────┬───────┬───────┐\n";
+            for row in 0..9 {
+                if row == 3 || row == 6 {
+                    s += "├───────┼───────┼───────┤\n";
+                }
+                for col in 0..9 {
+                    if col % 3 == 0 {
+                        s += "│ ";
+                    }
+                    match digit(row, col) {
+                        0 => s.push('·'),
+                        d => s.push((b'0' + d as u8) as char),
+                    }
+                    s.push(' ');
+                }
+                s += "│\n";
+            }
+            s += "└───────┴───────┴───────┘\n```";
+        }
+        ChatStyle::Emoji => {
+            for row in 0..9 {
+                if row == 3 || row == 6 {
+                    s.push('\n');
+                }
+                for col in 0..9 {
+                    if col == 3 || col == 6 {
+                        s.push(' ');
+                    }
+                    s += KEYCAPS[digit(row, col)];
+                }
+                s.push('\n');
+            }
+            s.pop();
+        }
+    }
+    s
+}
+
+/// Returns the reply to a chat `message`, with boards drawn in `style`.
+/// Messages that aren't commands get the usage as the reply.
+pub fn chat(message: &str, style: ChatStyle) -> String {
+    let words: Vec<&str> = message.split_whitespace().collect();
+    let puzzle = || words.get(1).and_then(|puzzle| parse_board(puzzle));
+    let config = TechniqueConfig::default();
+    match words.first().map(|command| command.to_ascii_lowercase()).as_deref() {
+        Some("solve") => match puzzle().map(|board| Sudoku::new(board).solve()) {
+            Some(Some(solution)) => format!("Solved:\n{}", chat_board(&solution, style)),
+            Some(None) => String::from("That puzzle has no solution."),
+            None => String::from(BAD_PUZZLE),
+        },
+        Some("hint") => {
+            let level = match words.get(2).map(|level| level.parse::<usize>()) {
+                None => HintLevel::Area,
+                Some(Ok(level @ 1..=3)) => HintLevel::ALL[level - 1],
+                Some(_) => return String::from("The hint level goes from 1 to 3."),
+            };
+            match puzzle().map(|board| hint(&board, &config)) {
+                Some(Some(hint)) => hint.text(level),
+                Some(None) => String::from("That puzzle is either solved or has no solution."),
+                None => String::from(BAD_PUZZLE),
+            }
+        }
+        Some("rate") => match puzzle().map(|board| rate(&board, &config)) {
+            Some(Some(rating)) => rating.to_string(),
+            Some(None) => String::from("That puzzle has no solution."),
+            None => String::from(BAD_PUZZLE),
+        },
+        Some("generate") => {
+            let difficulty = match words.get(1) {
+                None => Difficulty::Medium,
+                Some(name) => match Difficulty::from_name(&name.to_ascii_lowercase()) {
+                    Some(difficulty) => difficulty,
+                    None => return String::from("The difficulty is easy, medium, hard, or expert."),
+                },
+            };
+            let mut rng = match words.get(2).map(|seed| seed.parse()) {
+                None => Rng::from_time(),
+                Some(Ok(seed)) => Rng::new(seed),
+                Some(Err(_)) => return String::from("The seed has to be a number."),
+            };
+            let puzzle = generate(difficulty, &mut rng);
+            format!(
+                "A new {} puzzle:\n{}\n`{}`",
+                difficulty,
+                chat_board(&puzzle, style),
+                board_to_string(&puzzle)
+            )
+        }
+        _ => String::from(USAGE),
+    }
+}
+
+const BAD_PUZZLE: &str = "Give the puzzle as 81 digits with `.` or `0` for empty cells.";
+
+const USAGE: &str = "Try `solve PUZZLE`, `hint PUZZLE [1-3]`, `rate PUZZLE`, or `generate [DIFFICULTY] [SEED]`.";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::default_board;
+
+    #[test]
+    fn test_chat() {
+        let puzzle = board_to_string(&default_board());
+        let reply = chat(&format!("solve {}", puzzle), ChatStyle::Monospace);
+        assert!(reply.starts_with("Solved:\n```\n┌"));
+        assert_eq!(reply.lines().count(), 1 + 1 + 13 + 1);
+        assert!(reply.lines().skip(2).take(13).all(|line| line.chars().count() == 25));
+
+        let reply = chat("generate easy 7", ChatStyle::Emoji);
+        assert_eq!(reply.lines().filter(|line| line.contains('⬛') || line.contains('\u{20e3}')).count(), 9);
+        assert_eq!(chat(&format!("HINT {} 3", puzzle), ChatStyle::Emoji), "r2c4 is 9");
+        assert_eq!(chat("hello", ChatStyle::Emoji), USAGE);
+    }
+}
@@ -0,0 +1,97 @@
+//! Checkpoints of long runs of the command line tool, like generating a
+//! hundred thousand puzzles or analyzing a large collection, so that a run
+//! that's stopped can carry on where it left off. A checkpoint is a JSON
+//! object with the command it's of, how many of its inputs are done, the
+//! state of its random number generator, and its results so far:
+//!
+//! ```text
+//! {"command": "generate", "done": 2, "rng": "5e2d58d8b3bce8a9", "puzzles": ["..5..8...", "1....7..."], "analyses": []}
+//! ```
+//!
+//! The state of the generator is written in hex, as JSON numbers can't hold
+//! every u64.
+
+use crate::analyze::Analysis;
+use crate::board::{board_to_string, parse_board, Board};
+use crate::generate::Rng;
+use crate::json::Json;
+
+/// Where a run is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Checkpoint {
+    /// The command the checkpoint is of, so that it isn't resumed by another.
+    pub command: String,
+    /// How many of the inputs are done, or of the puzzles to generate.
+    pub done: usize,
+    /// The state of the random number generator, for runs that use one.
+    pub rng: Option<Rng>,
+    pub puzzles: Vec<Board>,
+    pub analyses: Vec<Analysis>,
+}
+
+impl Checkpoint {
+    /// Returns the checkpoint of a run of `command` that has just started.
+    pub fn new(command: &str) -> Checkpoint {
+        Checkpoint { command: String::from(command), done: 0, rng: None, puzzles: Vec::new(), analyses: Vec::new() }
+    }
+
+    pub fn to_json(&self) -> Json {
+        Json::object(vec![
+            ("command", Json::from(self.command.as_str())),
+            ("done", Json::from(self.done)),
+            ("rng", Json::from(self.rng.as_ref().map(|rng| format!("{:016x}", rng.state())))),
+            ("puzzles", Json::Array(self.puzzles.iter().map(|puzzle| Json::from(board_to_string(puzzle))).collect())),
+            ("analyses", Json::Array(self.analyses.iter().map(Analysis::to_json).collect())),
+        ])
+    }
+
+    /// Reads a checkpoint written with `to_json`. Returns None if the JSON
+    /// isn't one.
+    pub fn from_json(json: &Json) -> Option<Checkpoint> {
+        let rng = match json.get("rng")? {
+            Json::Null => None,
+            state => Some(Rng::from_state(u64::from_str_radix(state.as_str()?, 16).ok()?)?),
+        };
+        let puzzles = json.get("puzzles")?.as_array()?;
+        let analyses = json.get("analyses")?.as_array()?;
+        Some(Checkpoint {
+            command: String::from(json.get("command")?.as_str()?),
+            done: json.get("done")?.as_u64()? as usize,
+            rng,
+            puzzles: puzzles.iter().map(|puzzle| puzzle.as_str().and_then(parse_board)).collect::<Option<_>>()?,
+            analyses: analyses.iter().map(Analysis::from_json).collect::<Option<_>>()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyze::analyze;
+    use crate::board::{default_board, Cell};
+    use crate::logic::TechniqueConfig;
+
+    #[test]
+    fn test_checkpoint() {
+        let mut rng = Rng::new(1);
+        rng.next_u64();
+        let mut broken = default_board();
+        broken[0][0] = Cell::solved(5);
+        let checkpoint = Checkpoint {
+            command: String::from("analyze"),
+            done: 2,
+            rng: Some(rng.clone()),
+            puzzles: vec![default_board()],
+            analyses: analyze(&[default_board(), broken], &TechniqueConfig::default()),
+        };
+        let json = Json::parse(&checkpoint.to_json().to_string()).unwrap();
+        let resumed = Checkpoint::from_json(&json).unwrap();
+        assert_eq!(resumed, checkpoint);
+        // The generator carries on as it would have.
+        assert_eq!(resumed.rng.unwrap().next_u64(), rng.next_u64());
+
+        assert_eq!(Checkpoint::from_json(&Checkpoint::new("generate").to_json()), Some(Checkpoint::new("generate")));
+        let zero = Json::parse(r#"{"command": "generate", "done": 0, "rng": "0", "puzzles": [], "analyses": []}"#);
+        assert_eq!(Checkpoint::from_json(&zero.unwrap()), None);
+    }
+}
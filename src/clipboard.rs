@@ -0,0 +1,81 @@
+//! Access to the system clipboard through the platform's clipboard tools, so
+//! there's nothing to link against: `pbcopy` and `pbpaste` on macOS, `clip`
+//! and PowerShell on Windows, and `wl-copy`, `xclip`, or `xsel` elsewhere,
+//! whichever is installed.
+
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+/// A clipboard tool as the program and its arguments.
+type Tool = (&'static str, &'static [&'static str]);
+
+#[cfg(target_os = "macos")]
+const PASTE: &[Tool] = &[("pbpaste", &[])];
+#[cfg(target_os = "macos")]
+const COPY: &[Tool] = &[("pbcopy", &[])];
+
+#[cfg(windows)]
+const PASTE: &[Tool] = &[("powershell", &["-NoProfile", "-Command", "Get-Clipboard"])];
+#[cfg(windows)]
+const COPY: &[Tool] = &[("clip", &[])];
+
+#[cfg(not(any(target_os = "macos", windows)))]
+const PASTE: &[Tool] = &[
+    ("wl-paste", &["--no-newline"]),
+    ("xclip", &["-selection", "clipboard", "-out"]),
+    ("xsel", &["--clipboard", "--output"]),
+];
+#[cfg(not(any(target_os = "macos", windows)))]
+const COPY: &[Tool] = &[
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard", "-in"]),
+    ("xsel", &["--clipboard", "--input"]),
+];
+
+fn no_tool(tools: &[Tool]) -> io::Error {
+    let names: Vec<&str> = tools.iter().map(|tool| tool.0).collect();
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("no clipboard tool found, install one of {}", names.join(", ")),
+    )
+}
+
+/// Returns the text on the clipboard.
+pub fn paste() -> io::Result<String> {
+    for &(program, args) in PASTE {
+        let output = match Command::new(program).args(args).stderr(Stdio::null()).output() {
+            Ok(output) => output,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err),
+        };
+        if output.status.success() {
+            return String::from_utf8(output.stdout)
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "the clipboard doesn't hold text"));
+        }
+    }
+    Err(no_tool(PASTE))
+}
+
+/// Puts `text` on the clipboard.
+pub fn copy(text: &str) -> io::Result<()> {
+    for &(program, args) in COPY {
+        let mut child = match Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err),
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        if child.wait()?.success() {
+            return Ok(());
+        }
+    }
+    Err(no_tool(COPY))
+}
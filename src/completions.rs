@@ -0,0 +1,345 @@
+//! Shell completion scripts for the command line tool. The commands and
+//! options are read from its usage text, so that the scripts can't drift
+//! from what `--help` prints: commands are the first words of the lines
+//! indented by four spaces under `Commands:`, and options the `--` flags
+//! under `Options:`, which take a value if a name like `FILE` follows them.
+
+use std::fmt::Write;
+
+/// The shells that scripts can be made for.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    PowerShell,
+}
+
+impl Shell {
+    pub const ALL: [Shell; 4] = [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::PowerShell];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Shell::Bash => "bash",
+            Shell::Zsh => "zsh",
+            Shell::Fish => "fish",
+            Shell::PowerShell => "powershell",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Shell> {
+        Shell::ALL.iter().copied().find(|shell| shell.name() == name)
+    }
+}
+
+/// A command of the usage text.
+struct Command<'a> {
+    name: &'a str,
+    description: String,
+    /// What follows the name on each line of the command.
+    synopses: Vec<&'a str>,
+}
+
+/// An option of the usage text.
+struct Flag<'a> {
+    flag: &'a str,
+    /// The name of its value, if it takes one.
+    value: Option<&'a str>,
+    description: String,
+    /// The values to offer, or none to offer files for `FILE` and `PATH` and
+    /// nothing for other values.
+    choices: &'a [&'a str],
+}
+
+impl Flag<'_> {
+    fn takes_file(&self) -> bool {
+        self.choices.is_empty() && matches!(self.value, Some("FILE") | Some("PATH"))
+    }
+}
+
+/// Splits a line of a command or option list into its synopsis and the
+/// first line of its description, which are at least two spaces apart. The
+/// description starts on the next line if the synopsis leaves no room.
+fn split_entry(line: &str) -> (&str, &str) {
+    let line = line.trim();
+    match line.find("  ") {
+        Some(i) => (&line[..i], line[i..].trim()),
+        None => (line, ""),
+    }
+}
+
+/// Reads the commands and options of `usage`, in order and without repeats,
+/// with their descriptions on one line.
+fn read_usage<'a>(
+    usage: &'a str,
+    choices: &'a [(&'a str, &'a [&'a str])],
+) -> (Vec<Command<'a>>, Vec<Flag<'a>>) {
+    let mut commands: Vec<Command> = Vec::new();
+    let mut flags: Vec<Flag> = Vec::new();
+    let mut section = "";
+    // The section whose last entry deeper lines continue, if it's kept.
+    let mut continuing = None;
+    for line in usage.lines() {
+        if !line.starts_with(' ') {
+            section = line.trim();
+            continue;
+        }
+        if line.starts_with("     ") || !line.starts_with("    ") {
+            let description = match continuing {
+                Some("Commands:") => commands.last_mut().map(|command| &mut command.description),
+                Some(_) => flags.last_mut().map(|flag| &mut flag.description),
+                None => None,
+            };
+            if let Some(description) = description {
+                if !description.is_empty() {
+                    description.push(' ');
+                }
+                description.push_str(line.trim());
+            }
+            continue;
+        }
+        continuing = None;
+        let (synopsis, first_line) = split_entry(line);
+        let mut words = synopsis.split_whitespace();
+        let mut first = words.next().unwrap_or_default();
+        // Of `-v, --verbose`, only the long flag is completed.
+        if first.ends_with(',') {
+            first = words.next().unwrap_or_default();
+        }
+        match section {
+            "Commands:" => {
+                let rest = synopsis[first.len()..].trim();
+                match commands.iter_mut().find(|command| command.name == first) {
+                    Some(command) => command.synopses.push(rest),
+                    None => {
+                        let description = String::from(first_line);
+                        commands.push(Command { name: first, description, synopses: vec![rest] });
+                        continuing = Some(section);
+                    }
+                }
+            }
+            "Options:" if first.starts_with("--") && !flags.iter().any(|flag| flag.flag == first) => {
+                let choices = choices.iter().find(|(flag, _)| *flag == first).map_or(&[][..], |(_, choices)| choices);
+                let description = String::from(first_line);
+                flags.push(Flag { flag: first, value: words.next(), description, choices });
+                continuing = Some(section);
+            }
+            _ => {}
+        }
+    }
+    // A command with subcommands, like `db import FILE` and `db list`, is
+    // described by their synopses instead of the first one's description.
+    for command in commands.iter_mut().filter(|command| command.synopses.len() > 1) {
+        command.description = command.synopses.join(", ");
+    }
+    (commands, flags)
+}
+
+/// Returns the completion script of `shell` for `program`, whose commands
+/// and options are those of `usage`. `choices` lists the values to offer
+/// for options that take one of a few, by flag.
+pub fn completion_script(shell: Shell, program: &str, usage: &str, choices: &[(&str, &[&str])]) -> String {
+    let (commands, flags) = read_usage(usage, choices);
+    match shell {
+        Shell::Bash => bash(program, &commands, &flags),
+        Shell::Zsh => zsh(program, &commands, &flags),
+        Shell::Fish => fish(program, &commands, &flags),
+        Shell::PowerShell => powershell(program, &commands, &flags),
+    }
+}
+
+/// Returns the name of the completion function of `program` in bash and zsh.
+fn function_name(program: &str) -> String {
+    format!("_{}", program.replace(|c: char| !c.is_ascii_alphanumeric(), "_"))
+}
+
+fn bash(program: &str, commands: &[Command], flags: &[Flag]) -> String {
+    let function = function_name(program);
+    let mut script = String::new();
+    writeln!(script, "{}() {{", function).unwrap();
+    script += "    local cur=\"${COMP_WORDS[COMP_CWORD]}\" prev=\"${COMP_WORDS[COMP_CWORD-1]}\"\n";
+    script += "    case \"$prev\" in\n";
+    for flag in flags.iter().filter(|flag| flag.value.is_some()) {
+        let reply = if !flag.choices.is_empty() {
+            format!("COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))", flag.choices.join(" "))
+        } else if flag.takes_file() {
+            String::from("COMPREPLY=($(compgen -f -- \"$cur\"))")
+        } else {
+            String::from("COMPREPLY=()")
+        };
+        writeln!(script, "        {}) {}; return ;;", flag.flag, reply).unwrap();
+    }
+    script += "    esac\n";
+    let options: Vec<&str> = flags.iter().map(|flag| flag.flag).collect();
+    writeln!(script, "    if [[ \"$cur\" == -* ]]; then").unwrap();
+    writeln!(script, "        COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))", options.join(" ")).unwrap();
+    writeln!(script, "    elif [[ $COMP_CWORD -eq 1 ]]; then").unwrap();
+    let names: Vec<&str> = commands.iter().map(|command| command.name).collect();
+    writeln!(script, "        COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))", names.join(" ")).unwrap();
+    script += "    else\n        COMPREPLY=($(compgen -f -- \"$cur\"))\n    fi\n}\n";
+    writeln!(script, "complete -F {} {}", function, program).unwrap();
+    script
+}
+
+/// Escapes `s` for a single quoted zsh string that `_arguments` reads.
+fn zsh_escape(s: &str) -> String {
+    let mut escaped = String::new();
+    for c in s.chars() {
+        match c {
+            '\'' => escaped += "'\\''",
+            '[' | ']' | ':' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn zsh(program: &str, commands: &[Command], flags: &[Flag]) -> String {
+    let function = function_name(program);
+    let mut script = String::new();
+    writeln!(script, "#compdef {}\n\n{}() {{\n    local -a commands\n    commands=(", program, function).unwrap();
+    for command in commands.iter() {
+        writeln!(script, "        '{}:{}'", command.name, zsh_escape(&command.description)).unwrap();
+    }
+    script += "    )\n    _arguments \\\n";
+    for flag in flags.iter() {
+        let value = match flag.value {
+            Some(value) if !flag.choices.is_empty() => format!(":{}:({})", value, flag.choices.join(" ")),
+            Some(value) if flag.takes_file() => format!(":{}:_files", value),
+            Some(value) => format!(":{}: ", value),
+            None => String::new(),
+        };
+        writeln!(script, "        '{}[{}]{}' \\", flag.flag, zsh_escape(&flag.description), value).unwrap();
+    }
+    script += "        '1:command:{_describe command commands}' \\\n        '*:file:_files'\n}\n\n";
+    writeln!(script, "{} \"$@\"", function).unwrap();
+    script
+}
+
+/// Quotes `s` for fish, which takes `\'` and `\\` in single quotes.
+fn fish_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+fn fish(program: &str, commands: &[Command], flags: &[Flag]) -> String {
+    let mut script = String::new();
+    for command in commands.iter() {
+        writeln!(
+            script,
+            "complete -c {} -n __fish_use_subcommand -a {} -d {}",
+            program,
+            command.name,
+            fish_quote(&command.description)
+        )
+        .unwrap();
+    }
+    for flag in flags.iter() {
+        let value = match flag.value {
+            Some(_) if !flag.choices.is_empty() => format!(" -x -a {}", fish_quote(&flag.choices.join(" "))),
+            Some(_) if flag.takes_file() => String::from(" -r"),
+            Some(_) => String::from(" -x"),
+            None => String::new(),
+        };
+        let long = flag.flag.trim_start_matches('-');
+        writeln!(script, "complete -c {} -l {}{} -d {}", program, long, value, fish_quote(&flag.description)).unwrap();
+    }
+    script
+}
+
+/// Returns a PowerShell array of single quoted strings.
+fn powershell_array(items: &[&str]) -> String {
+    let quoted: Vec<String> = items.iter().map(|item| format!("'{}'", item.replace('\'', "''"))).collect();
+    format!("@({})", quoted.join(", "))
+}
+
+fn powershell(program: &str, commands: &[Command], flags: &[Flag]) -> String {
+    let mut script = String::new();
+    writeln!(script, "Register-ArgumentCompleter -Native -CommandName '{}' -ScriptBlock {{", program).unwrap();
+    script += "    param($wordToComplete, $commandAst, $cursorPosition)\n";
+    script += "    $words = @($commandAst.CommandElements | ForEach-Object { $_.ToString() })\n";
+    script += "    $previous = if ($wordToComplete) { $words[-2] } else { $words[-1] }\n";
+    script += "    $candidates = switch ($previous) {\n";
+    // Values without choices get nothing, so PowerShell completes paths.
+    for flag in flags.iter().filter(|flag| flag.value.is_some()) {
+        writeln!(script, "        '{}' {{ {} }}", flag.flag, powershell_array(flag.choices)).unwrap();
+    }
+    script += "        default {\n";
+    let options: Vec<&str> = flags.iter().map(|flag| flag.flag).collect();
+    let names: Vec<&str> = commands.iter().map(|command| command.name).collect();
+    writeln!(script, "            if ($wordToComplete.StartsWith('-')) {{ {} }}", powershell_array(&options)).unwrap();
+    writeln!(script, "            elseif ($words.Count -le 2 -and $previous -eq $words[0]) {{").unwrap();
+    writeln!(script, "                {}", powershell_array(&names)).unwrap();
+    script += "            }\n        }\n    }\n";
+    script += "    $candidates | Where-Object { $_ -like \"$wordToComplete*\" } | ForEach-Object {\n";
+    script += "        [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)\n";
+    script += "    }\n}\n";
+    script
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USAGE: &str = "\
+Usage: tool [COMMAND] [OPTIONS]
+
+Commands:
+    solve PUZZLE     Solve the puzzle, or say it's
+                     impossible
+    db list          List puzzles
+    db play ID       Play a puzzle
+    completions SHELL
+                     Print a script
+
+Options:
+    --level LEVEL    How hard [1-3]: easy or hard
+    --db PATH        Database
+    --seed N         Seed
+    -j, --json       Print JSON
+
+Anything after the lists is ignored:
+    --ignored        Not an option";
+
+    #[test]
+    fn test_completion_script() {
+        let choices: &[(&str, &[&str])] = &[("--level", &["easy", "hard"])];
+        let (commands, flags) = read_usage(USAGE, choices);
+        let commands: Vec<(&str, &str)> =
+            commands.iter().map(|command| (command.name, command.description.as_str())).collect();
+        let solve = "Solve the puzzle, or say it's impossible";
+        assert_eq!(commands, [("solve", solve), ("db", "list, play ID"), ("completions", "Print a script")]);
+        let names: Vec<&str> = flags.iter().map(|flag| flag.flag).collect();
+        assert_eq!(names, vec!["--level", "--db", "--seed", "--json"]);
+        assert_eq!(flags[0].value, Some("LEVEL"));
+        assert!(flags[1].takes_file() && !flags[2].takes_file());
+        assert_eq!(flags[3].value, None);
+
+        let bash = completion_script(Shell::Bash, "tool", USAGE, choices);
+        assert!(bash.contains("--level) COMPREPLY=($(compgen -W \"easy hard\" -- \"$cur\")); return ;;"));
+        assert!(bash.contains("--db) COMPREPLY=($(compgen -f -- \"$cur\")); return ;;"));
+        assert!(bash.contains("compgen -W \"solve db completions\""));
+        assert!(bash.ends_with("complete -F _tool tool\n"));
+
+        let zsh = completion_script(Shell::Zsh, "tool", USAGE, choices);
+        assert!(zsh.starts_with("#compdef tool\n"));
+        assert!(zsh.contains("'solve:Solve the puzzle, or say it'\\''s impossible'"));
+        assert!(zsh.contains("'--level[How hard \\[1-3\\]\\: easy or hard]:LEVEL:(easy hard)' \\"));
+
+        let fish = completion_script(Shell::Fish, "tool", USAGE, choices);
+        let solve = "-n __fish_use_subcommand -a solve -d 'Solve the puzzle, or say it\\'s impossible'";
+        assert!(fish.contains(&format!("complete -c tool {}", solve)));
+        assert!(fish.contains("complete -c tool -l level -x -a 'easy hard' -d"));
+        assert!(fish.contains("complete -c tool -l db -r -d 'Database'"));
+        assert!(!fish.contains("ignored"));
+
+        let powershell = completion_script(Shell::PowerShell, "tool", USAGE, choices);
+        assert!(powershell.contains("'--level' { @('easy', 'hard') }"));
+        assert!(powershell.contains("@('solve', 'db', 'completions')"));
+
+        assert_eq!(Shell::from_name("powershell"), Some(Shell::PowerShell));
+        assert_eq!(Shell::from_name("csh"), None);
+    }
+}
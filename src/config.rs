@@ -0,0 +1,169 @@
+//! The configuration file of the command line tool, which sets defaults for
+//! its options. It's written in the subset of TOML that flat settings need:
+//!
+//! ```toml
+//! # Comments and blank lines are ignored.
+//! difficulty = "hard"
+//! color = "never"
+//! techniques = ["naked_single", "hidden_single", "guess"]
+//! ```
+//!
+//! Values are strings, booleans, numbers, or arrays of strings on one line.
+//! Tables aren't supported, as nothing needs them.
+
+use std::error::Error;
+use std::fmt;
+
+/// Why a configuration file couldn't be read.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ConfigError {
+    /// The line of the problem, from 1.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} on line {}", self.message, self.line)
+    }
+}
+
+impl Error for ConfigError {}
+
+/// The settings of a configuration file, in the order they're set.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Config {
+    settings: Vec<(String, String)>,
+}
+
+impl Config {
+    /// Parses a configuration file. Arrays are kept as their items joined
+    /// with commas, and booleans and numbers as they're written.
+    pub fn parse(text: &str) -> Result<Config, ConfigError> {
+        let mut config = Config::default();
+        for (i, line) in text.lines().enumerate() {
+            let err = |message: &str| ConfigError { line: i + 1, message: String::from(message) };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('[') {
+                return Err(err("tables aren't supported"));
+            }
+            let (key, value) = line.split_once('=').ok_or_else(|| err("expected `key = value`"))?;
+            let key = key.trim();
+            if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+                return Err(err("expected a bare key"));
+            }
+            if config.get(key).is_some() {
+                return Err(err("the key is set twice"));
+            }
+            let (value, rest) =
+                parse_value(value.trim()).ok_or_else(|| err("expected a string, boolean, number, or array"))?;
+            let rest = rest.trim();
+            if !rest.is_empty() && !rest.starts_with('#') {
+                return Err(err("unexpected text after the value"));
+            }
+            config.settings.push((String::from(key), value));
+        }
+        Ok(config)
+    }
+
+    /// Returns the value of `key`, if it's set.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.settings.iter().find(|(k, _)| k == key).map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the settings in the order they're set.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.settings.iter().map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+}
+
+/// Parses the value at the start of `s`, returning it and what follows.
+fn parse_value(s: &str) -> Option<(String, &str)> {
+    if s.starts_with('"') {
+        return parse_string(s);
+    }
+    if let Some(mut rest) = s.strip_prefix('[') {
+        let mut items = Vec::new();
+        loop {
+            rest = rest.trim_start();
+            if let Some(after) = rest.strip_prefix(']') {
+                return Some((items.join(","), after));
+            }
+            let (item, after) = parse_string(rest)?;
+            items.push(item);
+            let after = after.trim_start();
+            rest = match after.strip_prefix(',') {
+                Some(after) => after,
+                None if after.starts_with(']') => after,
+                None => return None,
+            };
+        }
+    }
+    let end = s.find(|c: char| c.is_whitespace() || c == '#').unwrap_or(s.len());
+    let (word, rest) = s.split_at(end);
+    let number = word.parse::<i64>().is_ok() || (word.contains('.') && word.parse::<f64>().is_ok());
+    let valid = word == "true" || word == "false" || number;
+    if valid {
+        Some((String::from(word), rest))
+    } else {
+        None
+    }
+}
+
+/// Parses the basic string at the start of `s`, with its escapes of quotes
+/// and backslashes.
+fn parse_string(s: &str) -> Option<(String, &str)> {
+    let mut value = String::new();
+    let mut chars = s.strip_prefix('"')?.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((value, &s[i + 2..])),
+            '\\' => match chars.next()?.1 {
+                c @ ('"' | '\\') => value.push(c),
+                'n' => value.push('\n'),
+                't' => value.push('\t'),
+                _ => return None,
+            },
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config() {
+        let config = Config::parse(
+            "# Defaults\n\
+             difficulty = \"hard\"  # for the commute\n\
+             json = true\n\
+             limit = 50\n\
+             ratio = 0.5\n\
+             techniques = [\"naked_single\", \"guess\"]\n\
+             title = \"say \\\"hi\\\"\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.get("difficulty"), Some("hard"));
+        assert_eq!(config.get("json"), Some("true"));
+        assert_eq!(config.get("limit"), Some("50"));
+        assert_eq!(config.get("ratio"), Some("0.5"));
+        assert_eq!(config.get("techniques"), Some("naked_single,guess"));
+        assert_eq!(config.get("title"), Some("say \"hi\""));
+        assert_eq!(config.get("color"), None);
+        assert_eq!(config.iter().next(), Some(("difficulty", "hard")));
+        assert_eq!(Config::parse("techniques = []\n").unwrap().get("techniques"), Some(""));
+
+        let err = Config::parse("json = true\n\n[server]\n").unwrap_err();
+        assert_eq!(err.to_string(), "tables aren't supported on line 3");
+        assert_eq!(Config::parse("difficulty = hard").unwrap_err().line, 1);
+        assert!(Config::parse("a = \"x\" y").is_err());
+        assert!(Config::parse("a = [\"x\" \"y\"]").is_err());
+        assert!(Config::parse("a = 1\na = 2").is_err());
+    }
+}
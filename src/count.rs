@@ -0,0 +1,85 @@
+use crate::board::Board;
+use crate::dlx::Dlx;
+use crate::grid::{mask_digits, Grid};
+use crate::refute::find_contradiction;
+
+/// Counts the solutions of `board`, stopping once `limit` are found. Use a
+/// limit of 2 to check whether a puzzle has a unique solution.
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(board)))]
+pub fn count_solutions(board: &Board, limit: usize) -> usize {
+    let grid = Grid::from_board(board);
+    if find_contradiction(&grid).is_some() {
+        return 0;
+    }
+    let mut count = 0;
+    count_grid(grid, limit, &mut count);
+    count
+}
+
+/// Counts every solution of `board`, however many there are, saturating at
+/// `u64::MAX`, e.g. to find out how many completions a partial grid has.
+/// Unlike `count_solutions`, it doesn't stop early, so it can take very long
+/// for grids with few givens. It's faster for counting all solutions though.
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(board)))]
+pub fn count_all_solutions(board: &Board) -> u64 {
+    match Dlx::new(board) {
+        Some(mut dlx) => dlx.count(),
+        None => 0,
+    }
+}
+
+/// Returns the empty cell with the fewest candidates, which searches branch
+/// on, or None if the grid is full. A cell without any is a dead end.
+pub(crate) fn branch_cell(grid: &Grid) -> Option<(usize, usize)> {
+    let mut cell = None;
+    let mut fewest = 10;
+    for row in 0..9 {
+        for col in 0..9 {
+            if grid.value(row, col).is_some() {
+                continue;
+            }
+            let num_candidates = grid.candidates(row, col).count_ones();
+            if num_candidates < fewest {
+                fewest = num_candidates;
+                cell = Some((row, col));
+            }
+        }
+    }
+    cell
+}
+
+fn count_grid(grid: Grid, limit: usize, count: &mut usize) {
+    let (row, col) = match branch_cell(&grid) {
+        Some(cell) => cell,
+        None => {
+            *count += 1;
+            return;
+        }
+    };
+    for digit in mask_digits(grid.candidates(row, col)) {
+        let mut branch = grid;
+        branch.place(row, col, digit);
+        count_grid(branch, limit, count);
+        if *count >= limit {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{default_board, parse_board};
+
+    #[test]
+    fn test_count_solutions() {
+        assert_eq!(count_solutions(&default_board(), 2), 1);
+        let empty = parse_board(&".".repeat(81)).unwrap();
+        assert_eq!(count_solutions(&empty, 10), 10);
+        let mut board = default_board();
+        board[0][0] = crate::board::Cell::solved(5);
+        assert_eq!(count_solutions(&board, 2), 0);
+        assert_eq!(count_all_solutions(&board), 0);
+        assert_eq!(count_all_solutions(&default_board()), 1);
+    }
+}
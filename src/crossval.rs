@@ -0,0 +1,233 @@
+//! Comparing our ratings with those of other graders, to tell whether a
+//! change to the techniques or the score table brings them closer or moves
+//! them apart. The puzzles come with a reference rating each, a line per
+//! puzzle:
+//!
+//! ```text
+//! # Sudoku Explainer ratings
+//! 4...3.......6..8..........1....5..9..8....6...7.2........1.27..5.3....4.9........ 9.0
+//! ..5..8..18......9.......78....4.....64....9......53..2.6.........138..5....9.714. hard
+//! ```
+//!
+//! A reference rating is a number on the grader's scale, or a difficulty
+//! name, which counts as 0 for easy up to 3 for expert.
+
+use std::fmt;
+
+use crate::board::{parse_board, Board};
+use crate::generate::Difficulty;
+use crate::json::Json;
+use crate::logic::TechniqueConfig;
+use crate::rating::rate;
+use crate::score::ScoreTable;
+
+/// A rating given by another grader.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReferenceRating {
+    Score(f64),
+    Difficulty(Difficulty),
+}
+
+impl ReferenceRating {
+    pub fn parse(s: &str) -> Option<ReferenceRating> {
+        match s.parse::<f64>() {
+            Ok(score) if score.is_finite() => Some(ReferenceRating::Score(score)),
+            _ => Difficulty::from_name(&s.to_lowercase()).map(ReferenceRating::Difficulty),
+        }
+    }
+
+    /// Returns the rating as a number to correlate.
+    fn value(self) -> f64 {
+        match self {
+            ReferenceRating::Score(score) => score,
+            ReferenceRating::Difficulty(difficulty) => difficulty as usize as f64,
+        }
+    }
+}
+
+/// Reads lines of a puzzle and its reference rating, separated by spaces,
+/// tabs, or a comma. Comments and lines that aren't a puzzle and a rating
+/// are skipped.
+pub fn parse_references(text: &str) -> Vec<(Board, ReferenceRating)> {
+    text.lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.split(|c: char| c == ',' || c.is_whitespace()).filter(|field| !field.is_empty());
+            let puzzle = parse_board(fields.next()?)?;
+            Some((puzzle, ReferenceRating::parse(fields.next()?)?))
+        })
+        .collect()
+}
+
+/// How well our ratings agree with the reference ones. The measures are
+/// None when there are too few puzzles, or too little spread, to tell.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CrossValidation {
+    /// The puzzles compared.
+    pub puzzles: usize,
+    /// The puzzles left out because we found no solution.
+    pub unsolved: usize,
+    /// The Pearson correlation of our scores and the reference ones.
+    pub pearson: Option<f64>,
+    /// The Spearman correlation, which only looks at the order of the
+    /// ratings, so it doesn't mind scales that aren't linear in each other.
+    pub spearman: Option<f64>,
+    /// Of the pairs of puzzles that both graders tell apart, the share that
+    /// they put in the same order.
+    pub concordance: Option<f64>,
+    /// Of the puzzles whose reference rating is a difficulty, the share we
+    /// give the same difficulty.
+    pub agreement: Option<f64>,
+}
+
+impl CrossValidation {
+    pub fn to_json(&self) -> Json {
+        Json::object(vec![
+            ("puzzles", Json::from(self.puzzles)),
+            ("unsolved", Json::from(self.unsolved)),
+            ("pearson", Json::from(self.pearson)),
+            ("spearman", Json::from(self.spearman)),
+            ("concordance", Json::from(self.concordance)),
+            ("agreement", Json::from(self.agreement)),
+        ])
+    }
+}
+
+impl fmt::Display for CrossValidation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let measure = |value: Option<f64>| value.map_or(String::from("-"), |value| format!("{:.3}", value));
+        let share = |value: Option<f64>| value.map_or(String::from("-"), |value| format!("{:.1}%", value * 100.0));
+        writeln!(f, "Puzzles: {} ({} unsolved)", self.puzzles, self.unsolved)?;
+        writeln!(f, "Pearson: {}", measure(self.pearson))?;
+        writeln!(f, "Spearman: {}", measure(self.spearman))?;
+        writeln!(f, "Concordant pairs: {}", share(self.concordance))?;
+        write!(f, "Difficulty agreement: {}", share(self.agreement))
+    }
+}
+
+/// Rates the puzzles of `references` with the techniques of `config`, scores
+/// them with `scores`, and compares them with the reference ratings.
+pub fn cross_validate(
+    references: &[(Board, ReferenceRating)],
+    config: &TechniqueConfig,
+    scores: &ScoreTable,
+) -> CrossValidation {
+    let mut ours = Vec::new();
+    let mut theirs = Vec::new();
+    let mut matching = 0;
+    let mut named = 0;
+    for (puzzle, reference) in references.iter() {
+        let rating = match rate(puzzle, config) {
+            Some(rating) => rating,
+            None => continue,
+        };
+        if let ReferenceRating::Difficulty(difficulty) = reference {
+            named += 1;
+            if Difficulty::of(&rating) == *difficulty {
+                matching += 1;
+            }
+        }
+        ours.push(scores.score(&rating));
+        theirs.push(reference.value());
+    }
+    CrossValidation {
+        puzzles: ours.len(),
+        unsolved: references.len() - ours.len(),
+        pearson: pearson(&ours, &theirs),
+        spearman: pearson(&ranks(&ours), &ranks(&theirs)),
+        concordance: concordance(&ours, &theirs),
+        agreement: if named > 0 { Some(matching as f64 / named as f64) } else { None },
+    }
+}
+
+fn pearson(xs: &[f64], ys: &[f64]) -> Option<f64> {
+    let n = xs.len() as f64;
+    let mean_x = xs.iter().sum::<f64>() / n;
+    let mean_y = ys.iter().sum::<f64>() / n;
+    let mut covariance = 0.0;
+    let mut variance_x = 0.0;
+    let mut variance_y = 0.0;
+    for (x, y) in xs.iter().zip(ys.iter()) {
+        covariance += (x - mean_x) * (y - mean_y);
+        variance_x += (x - mean_x) * (x - mean_x);
+        variance_y += (y - mean_y) * (y - mean_y);
+    }
+    if xs.len() < 2 || variance_x == 0.0 || variance_y == 0.0 {
+        return None;
+    }
+    Some(covariance / (variance_x * variance_y).sqrt())
+}
+
+/// Returns the rank of each value from 1, with tied values sharing the mean
+/// of their ranks.
+fn ranks(values: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|&a, &b| values[a].total_cmp(&values[b]));
+    let mut ranks = vec![0.0; values.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start + 1;
+        while end < order.len() && values[order[end]] == values[order[start]] {
+            end += 1;
+        }
+        let rank = (start + end + 1) as f64 / 2.0;
+        for &i in order[start..end].iter() {
+            ranks[i] = rank;
+        }
+        start = end;
+    }
+    ranks
+}
+
+fn concordance(xs: &[f64], ys: &[f64]) -> Option<f64> {
+    let mut concordant = 0;
+    let mut discordant = 0;
+    for i in 0..xs.len() {
+        for j in i + 1..xs.len() {
+            let product = (xs[i] - xs[j]) * (ys[i] - ys[j]);
+            if product > 0.0 {
+                concordant += 1;
+            } else if product < 0.0 {
+                discordant += 1;
+            }
+        }
+    }
+    match concordant + discordant {
+        0 => None,
+        pairs => Some(concordant as f64 / pairs as f64),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{board_to_string, default_board, Cell};
+
+    #[test]
+    fn test_cross_validate() {
+        let mut broken = default_board();
+        broken[0][0] = Cell::solved(5);
+        let text = format!(
+            "# Reference ratings\n{} 2.0\n{},9.5\n{}\tEasy\n{} 3\nnot a puzzle 1\n",
+            board_to_string(&default_board()),
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..",
+            board_to_string(&default_board()),
+            board_to_string(&broken),
+        );
+        let references = parse_references(&text);
+        assert_eq!(references.len(), 4);
+        assert_eq!(references[1].1, ReferenceRating::Score(9.5));
+        assert_eq!(references[2].1, ReferenceRating::Difficulty(Difficulty::Easy));
+
+        let report = cross_validate(&references, &TechniqueConfig::default(), &ScoreTable::default());
+        assert_eq!((report.puzzles, report.unsolved), (3, 1));
+        assert!(report.pearson.unwrap() > 0.0);
+        assert_eq!(report.concordance, Some(1.0));
+        let difficulty = Difficulty::of(&rate(&default_board(), &TechniqueConfig::default()).unwrap());
+        assert_eq!(report.agreement, Some(if difficulty == Difficulty::Easy { 1.0 } else { 0.0 }));
+        assert!(report.to_string().starts_with("Puzzles: 3 (1 unsolved)\nPearson: 0."));
+
+        assert_eq!(ranks(&[3.0, 1.0, 3.0, 2.0]), vec![3.5, 1.0, 3.5, 2.0]);
+        assert_eq!(pearson(&[1.0], &[2.0]), None);
+    }
+}
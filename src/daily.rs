@@ -0,0 +1,127 @@
+//! The puzzle of the day. It's derived from the date alone, so every app
+//! built on this crate, natively or to WebAssembly, shows the same puzzle on
+//! the same day without asking a server. Changes to the generator or the
+//! techniques change the puzzles, so they only agree across one version.
+
+use std::fmt;
+
+use crate::generate::{generate, Difficulty, Rng};
+use crate::puzzle::{Metadata, Puzzle};
+
+/// A day of the Gregorian calendar.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct Date {
+    pub year: i32,
+    /// From 1 to 12.
+    pub month: u32,
+    /// From 1 to 31.
+    pub day: u32,
+}
+
+impl Date {
+    /// Returns the date, or None if there's no such day.
+    pub fn new(year: i32, month: u32, day: u32) -> Option<Date> {
+        let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+        let days = match month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if leap => 29,
+            2 => 28,
+            _ => return None,
+        };
+        if (1..=days).contains(&day) {
+            Some(Date { year, month, day })
+        } else {
+            None
+        }
+    }
+
+    /// Parses a date written as YYYY-MM-DD.
+    pub fn parse(s: &str) -> Option<Date> {
+        let mut parts = s.splitn(3, '-');
+        let year = parts.next()?.parse().ok()?;
+        let month = parts.next()?.parse().ok()?;
+        let day = parts.next()?.parse().ok()?;
+        Date::new(year, month, day)
+    }
+
+    /// Returns the date `days` days after 1970-01-01, which may be negative.
+    pub fn from_unix_days(days: i64) -> Date {
+        // Howard Hinnant's algorithm, on eras of 400 years starting in March.
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+        let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 } as u32;
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+        Date { year: year as i32, month, day }
+    }
+
+    /// Returns how many days the date is after 1970-01-01.
+    pub fn unix_days(self) -> i64 {
+        let year = self.year as i64 - if self.month <= 2 { 1 } else { 0 };
+        let era = year.div_euclid(400);
+        let year_of_era = year.rem_euclid(400);
+        let shifted_month = (self.month as i64 + 9) % 12;
+        let day_of_year = (153 * shifted_month + 2) / 5 + self.day as i64 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146_097 + day_of_era - 719_468
+    }
+}
+
+/// Formats the date as YYYY-MM-DD.
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// Returns the puzzle of `difficulty` for `date`, which is the same
+/// wherever it's generated. The puzzles of different difficulties on the
+/// same day have nothing to do with each other. The metadata has the date
+/// and a title like `Daily hard 2024-03-01`.
+pub fn daily_puzzle(date: Date, difficulty: Difficulty) -> Puzzle {
+    let seed = ((date.unix_days() as u64) << 2) | difficulty as u64;
+    let board = generate(difficulty, &mut Rng::new(seed));
+    let metadata = Metadata {
+        title: Some(format!("Daily {} {}", difficulty, date)),
+        date: Some(date.to_string()),
+        ..Metadata::default()
+    };
+    match Puzzle::new(board) {
+        Ok(puzzle) => puzzle.with_metadata(metadata),
+        Err(err) => unreachable!("generated an improper puzzle: {}", err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::board_to_string;
+
+    #[test]
+    fn test_daily_puzzle() {
+        let date = Date::parse("2024-02-29").unwrap();
+        assert_eq!(date.to_string(), "2024-02-29");
+        assert_eq!(Date::from_unix_days(date.unix_days()), date);
+        assert_eq!(Date::from_unix_days(0), Date { year: 1970, month: 1, day: 1 });
+        assert_eq!(Date::new(2024, 3, 1).unwrap().unix_days(), date.unix_days() + 1);
+        assert_eq!(Date::new(1969, 12, 31).unwrap().unix_days(), -1);
+        assert_eq!(Date::parse("2023-02-29"), None);
+        assert_eq!(Date::parse("2024-13-01"), None);
+
+        let puzzle = daily_puzzle(date, Difficulty::Easy);
+        assert_eq!(puzzle, daily_puzzle(date, Difficulty::Easy));
+        assert_ne!(puzzle.givens(), daily_puzzle(Date::new(2024, 3, 1).unwrap(), Difficulty::Easy).givens());
+        assert_eq!(puzzle.metadata().title.as_deref(), Some("Daily easy 2024-02-29"));
+        // The generator is seeded the same way on every platform, so this
+        // is the puzzle everywhere.
+        assert_eq!(
+            board_to_string(puzzle.givens()),
+            "5...213......9.46.8.4.....225.....18..93.........1.....12....4.......8...7.6..29."
+        );
+    }
+}
@@ -0,0 +1,360 @@
+//! A local puzzle database in a SQLite file, enabled with the `db` feature.
+//! Puzzles are stored with their solution and rating when they're added, so
+//! they can be queried by difficulty, and every time one is played it's
+//! recorded for the statistics. Their metadata is kept too, so a collection
+//! keeps its attribution.
+
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub use rusqlite::Error;
+
+use crate::board::{board_to_string, parse_board, Board};
+use crate::generate::Difficulty;
+use crate::logic::{Technique, TechniqueConfig};
+use crate::puzzle::{Metadata, Puzzle};
+use crate::sepb::parse_sepb;
+
+pub type Result<T> = rusqlite::Result<T>;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS puzzles (
+    id INTEGER PRIMARY KEY,
+    puzzle TEXT NOT NULL UNIQUE,
+    solution TEXT NOT NULL,
+    difficulty TEXT NOT NULL,
+    hardest TEXT,
+    guesses INTEGER NOT NULL,
+    source TEXT NOT NULL,
+    added_at INTEGER NOT NULL,
+    title TEXT,
+    author TEXT,
+    date TEXT,
+    rating TEXT,
+    source_id TEXT
+);
+CREATE INDEX IF NOT EXISTS puzzles_difficulty ON puzzles (difficulty);
+CREATE TABLE IF NOT EXISTS plays (
+    id INTEGER PRIMARY KEY,
+    puzzle_id INTEGER NOT NULL REFERENCES puzzles (id),
+    played_at INTEGER NOT NULL,
+    seconds INTEGER
+);
+";
+
+/// The metadata columns, which databases made before puzzles had metadata
+/// don't have yet.
+const METADATA_COLUMNS: [&str; 5] = ["title", "author", "date", "rating", "source_id"];
+
+/// A puzzle stored in the database.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Entry {
+    pub id: i64,
+    pub puzzle: Board,
+    pub solution: Board,
+    pub difficulty: Difficulty,
+    /// The hardest technique needed, not counting guesses.
+    pub hardest: Option<Technique>,
+    /// The guesses needed on the way to the solution.
+    pub guesses: usize,
+    /// The title, author, and so on. The source is where the puzzle came
+    /// from, e.g. the file of a pack.
+    pub metadata: Metadata,
+    /// How many times the puzzle has been played.
+    pub plays: usize,
+}
+
+/// What happened to the lines of an imported pack.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub struct ImportReport {
+    pub added: usize,
+    /// Puzzles that were already in the database.
+    pub duplicates: usize,
+    /// Lines that aren't puzzles or puzzles without a unique solution.
+    pub invalid: usize,
+}
+
+/// Play statistics of the puzzles of one difficulty.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stats {
+    pub difficulty: Difficulty,
+    pub puzzles: usize,
+    /// Puzzles that have been played at least once.
+    pub played: usize,
+    /// The average time of the plays that recorded one, in seconds.
+    pub average_seconds: Option<f64>,
+}
+
+fn source_metadata(source: &str) -> Metadata {
+    Metadata { source: Some(String::from(source)), ..Metadata::default() }
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+fn add_to(conn: &Connection, puzzle: &Puzzle) -> Result<Option<i64>> {
+    let metadata = puzzle.metadata();
+    let rating = match puzzle.rate(&TechniqueConfig::default()) {
+        Some(rating) => rating,
+        None => return Ok(None),
+    };
+    let inserted = conn.execute(
+        "INSERT OR IGNORE INTO puzzles
+         (puzzle, solution, difficulty, hardest, guesses, source, added_at, title, author, date, rating, source_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+        params![
+            board_to_string(puzzle.givens()),
+            board_to_string(puzzle.solution()),
+            Difficulty::of(&rating).name(),
+            rating.hardest.map(Technique::id),
+            rating.guesses.needed as i64,
+            metadata.source.as_deref().unwrap_or_default(),
+            now(),
+            metadata.title,
+            metadata.author,
+            metadata.date,
+            metadata.rating,
+            metadata.id,
+        ],
+    )?;
+    Ok(if inserted == 1 { Some(conn.last_insert_rowid()) } else { None })
+}
+
+/// The query for entries, which `entry_from_row` reads.
+const SELECT_ENTRIES: &str = "SELECT p.id, p.puzzle, p.solution, p.difficulty, p.hardest, p.guesses, p.source,
+        (SELECT COUNT(*) FROM plays WHERE puzzle_id = p.id) AS plays,
+        p.title, p.author, p.date, p.rating, p.source_id
+    FROM puzzles p";
+
+fn entry_from_row(row: &Row) -> Result<Entry> {
+    let puzzle: String = row.get(1)?;
+    let solution: String = row.get(2)?;
+    let difficulty: String = row.get(3)?;
+    let hardest: Option<String> = row.get(4)?;
+    let source: String = row.get(6)?;
+    Ok(Entry {
+        id: row.get(0)?,
+        // The columns are only ever written by `add`, so they always parse.
+        puzzle: parse_board(&puzzle).unwrap_or_default(),
+        solution: parse_board(&solution).unwrap_or_default(),
+        difficulty: Difficulty::from_name(&difficulty).unwrap_or(Difficulty::Expert),
+        hardest: hardest.as_deref().and_then(Technique::from_id),
+        guesses: row.get::<_, i64>(5)? as usize,
+        metadata: Metadata {
+            title: row.get(8)?,
+            author: row.get(9)?,
+            source: Some(source).filter(|source| !source.is_empty()),
+            id: row.get(12)?,
+            date: row.get(10)?,
+            rating: row.get(11)?,
+        },
+        plays: row.get::<_, i64>(7)? as usize,
+    })
+}
+
+/// A connection to a puzzle database.
+pub struct Database {
+    conn: Connection,
+}
+
+impl Database {
+    /// Opens the database at `path`, creating it if it doesn't exist.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Database> {
+        Database::init(Connection::open(path)?)
+    }
+
+    /// Opens a database that only lives in memory, e.g. for tests.
+    pub fn open_in_memory() -> Result<Database> {
+        Database::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Database> {
+        conn.execute_batch(SCHEMA)?;
+        for column in METADATA_COLUMNS.iter() {
+            let missing = conn.query_row(
+                "SELECT COUNT(*) = 0 FROM pragma_table_info('puzzles') WHERE name = ?1",
+                [column],
+                |row| row.get(0),
+            )?;
+            if missing {
+                conn.execute(&format!("ALTER TABLE puzzles ADD COLUMN {} TEXT", column), [])?;
+            }
+        }
+        Ok(Database { conn })
+    }
+
+    /// Solves, rates, and stores `puzzle`. Returns its id, or None if it
+    /// doesn't have a unique solution or is already stored.
+    pub fn add(&self, puzzle: &Board, source: &str) -> Result<Option<i64>> {
+        match Puzzle::new(*puzzle) {
+            Ok(puzzle) => self.add_puzzle(&puzzle.with_metadata(source_metadata(source))),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Rates and stores `puzzle` with its metadata. Returns its id, or None if
+    /// it's already stored.
+    pub fn add_puzzle(&self, puzzle: &Puzzle) -> Result<Option<i64>> {
+        add_to(&self.conn, puzzle)
+    }
+
+    /// Adds the puzzles of a pack with one puzzle per line, in the 81
+    /// character format or that of the Sudoku Exchange Puzzle Bank, whose id
+    /// and rating are kept. Blank lines and lines starting with `#` are
+    /// skipped, as is anything after the puzzle on a line of the former.
+    pub fn import(&mut self, pack: &str, source: &str) -> Result<ImportReport> {
+        let mut report = ImportReport::default();
+        // One transaction for the whole pack is much faster than one per
+        // puzzle.
+        let tx = self.conn.transaction()?;
+        for line in pack.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let plain = || line.split_whitespace().next().and_then(parse_board).map(|board| (board, Metadata::default()));
+            let (board, mut metadata) = match parse_sepb(line).or_else(plain) {
+                Some(parsed) => parsed,
+                None => {
+                    report.invalid += 1;
+                    continue;
+                }
+            };
+            let exists: Option<i64> = tx
+                .query_row(
+                    "SELECT id FROM puzzles WHERE puzzle = ?1",
+                    [board_to_string(&board)],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if exists.is_some() {
+                report.duplicates += 1;
+                continue;
+            }
+            let added = match Puzzle::new(board) {
+                Ok(puzzle) => {
+                    metadata.source = Some(String::from(source));
+                    add_to(&tx, &puzzle.with_metadata(metadata))?
+                }
+                Err(_) => None,
+            };
+            match added {
+                Some(_) => report.added += 1,
+                None => report.invalid += 1,
+            }
+        }
+        tx.commit()?;
+        Ok(report)
+    }
+
+    /// Returns up to `limit` puzzles, optionally only of `difficulty` and only
+    /// ones that haven't been played, oldest first.
+    pub fn find(&self, difficulty: Option<Difficulty>, unplayed: bool, limit: usize) -> Result<Vec<Entry>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{}
+             WHERE (?1 IS NULL OR p.difficulty = ?1) AND (NOT ?2 OR plays = 0)
+             ORDER BY p.id
+             LIMIT ?3",
+            SELECT_ENTRIES
+        ))?;
+        let rows = stmt.query_map(params![difficulty.map(Difficulty::name), unplayed, limit as i64], entry_from_row)?;
+        rows.collect()
+    }
+
+    /// Returns the stored puzzle with the givens of `puzzle`, if any.
+    pub fn get(&self, puzzle: &Board) -> Result<Option<Entry>> {
+        self.conn
+            .query_row(&format!("{} WHERE p.puzzle = ?1", SELECT_ENTRIES), [board_to_string(puzzle)], entry_from_row)
+            .optional()
+    }
+
+    /// Records that the puzzle with `id` was played, optionally with the time
+    /// it took. Returns false if there's no such puzzle.
+    pub fn mark_played(&self, id: i64, seconds: Option<u64>) -> Result<bool> {
+        let exists: Option<i64> = self
+            .conn
+            .query_row("SELECT id FROM puzzles WHERE id = ?1", [id], |row| row.get(0))
+            .optional()?;
+        if exists.is_none() {
+            return Ok(false);
+        }
+        self.conn.execute(
+            "INSERT INTO plays (puzzle_id, played_at, seconds) VALUES (?1, ?2, ?3)",
+            params![id, now(), seconds.map(|s| s as i64)],
+        )?;
+        Ok(true)
+    }
+
+    /// Returns the statistics of every difficulty that has puzzles, easiest
+    /// first.
+    pub fn stats(&self) -> Result<Vec<Stats>> {
+        let mut stats = Vec::new();
+        for &difficulty in Difficulty::ALL.iter() {
+            let (puzzles, played, average_seconds) = self.conn.query_row(
+                "SELECT COUNT(*),
+                        COUNT(*) FILTER (WHERE EXISTS (SELECT 1 FROM plays WHERE puzzle_id = p.id)),
+                        (SELECT AVG(seconds) FROM plays JOIN puzzles q ON q.id = puzzle_id
+                         WHERE q.difficulty = ?1)
+                 FROM puzzles p WHERE p.difficulty = ?1",
+                [difficulty.name()],
+                |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get(2)?)),
+            )?;
+            if puzzles > 0 {
+                stats.push(Stats {
+                    difficulty,
+                    puzzles: puzzles as usize,
+                    played: played as usize,
+                    average_seconds,
+                });
+            }
+        }
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::default_board;
+    use crate::generate::{generate, Rng};
+
+    #[test]
+    fn test_database() {
+        let mut db = Database::open_in_memory().unwrap();
+        let pack = format!(
+            "# A pack\n{} first\n{}\nnot a puzzle\n",
+            board_to_string(&default_board()),
+            board_to_string(&default_board())
+        );
+        let report = db.import(&pack, "pack.txt").unwrap();
+        assert_eq!(report, ImportReport { added: 1, duplicates: 1, invalid: 1 });
+
+        let entries = db.find(Some(Difficulty::Easy), true, 10).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].puzzle, default_board());
+        assert_eq!(entries[0].metadata.source.as_deref(), Some("pack.txt"));
+
+        assert!(db.mark_played(entries[0].id, Some(120)).unwrap());
+        assert!(!db.mark_played(entries[0].id + 1, None).unwrap());
+        assert!(db.find(None, true, 10).unwrap().is_empty());
+        let stats = db.stats().unwrap();
+        assert_eq!((stats[0].puzzles, stats[0].played), (1, 1));
+        assert_eq!(stats[0].average_seconds, Some(120.0));
+
+        let mut puzzle = Puzzle::new(generate(Difficulty::Easy, &mut Rng::new(1))).unwrap();
+        puzzle.metadata_mut().title = Some(String::from("First"));
+        let id = db.add_puzzle(&puzzle).unwrap().unwrap();
+        let entry = db.find(None, false, 10).unwrap().into_iter().find(|entry| entry.id == id).unwrap();
+        assert_eq!(&entry.metadata, puzzle.metadata());
+
+        let pack = "0000183b305c  050703060007000800000816000000030000005000100730040086906000204840572093000409000  1.2";
+        assert_eq!(db.import(pack, "easy.txt").unwrap().added, 1);
+        let entry = db.find(None, false, 10).unwrap().pop().unwrap();
+        assert_eq!(entry.metadata.id.as_deref(), Some("0000183b305c"));
+        assert_eq!(entry.metadata.rating.as_deref(), Some("1.2"));
+    }
+}
@@ -0,0 +1,132 @@
+//! The "digit scan" view of a puzzle that competitive solvers work from:
+//! for each digit, how often it's placed and how few places it has left in
+//! the houses it's missing from. The digits closest to done, or with a
+//! house where they have one or two places left, are where to look first.
+
+use std::fmt;
+
+use crate::grid::{Grid, House};
+use crate::json::Json;
+
+/// How constrained one digit is.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DigitSummary {
+    pub digit: i8,
+    /// How many times the digit is placed.
+    pub placed: usize,
+    /// The empty cells that still have the digit as a candidate.
+    pub cells: usize,
+    /// The places left for the digit in each house it isn't placed in, rows
+    /// first, then columns, then boxes.
+    pub places: Vec<(House, usize)>,
+}
+
+impl DigitSummary {
+    /// Returns the house with the fewest places left for the digit, the
+    /// first of them if several tie, or None if the digit is placed
+    /// everywhere.
+    pub fn fewest(&self) -> Option<(House, usize)> {
+        self.places.iter().copied().min_by_key(|&(_, places)| places)
+    }
+
+    /// Returns how many houses have a single place left for the digit, each
+    /// of which is a hidden single.
+    pub fn singles(&self) -> usize {
+        self.places.iter().filter(|&&(_, places)| places == 1).count()
+    }
+
+    pub fn to_json(&self) -> Json {
+        let fewest = self.fewest();
+        Json::object(vec![
+            ("digit", Json::from(self.digit)),
+            ("placed", Json::from(self.placed)),
+            ("cells", Json::from(self.cells)),
+            ("fewest_house", Json::from(fewest.map(|(house, _)| house.to_string()))),
+            ("fewest_places", Json::from(fewest.map(|(_, places)| places))),
+            ("singles", Json::from(self.singles())),
+        ])
+    }
+}
+
+/// The summaries of the digits 1 to 9 of a grid.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DigitScan {
+    pub digits: Vec<DigitSummary>,
+}
+
+impl DigitScan {
+    pub fn to_json(&self) -> Json {
+        Json::Array(self.digits.iter().map(DigitSummary::to_json).collect())
+    }
+}
+
+/// Formats the scan as a plain text table with a row per digit.
+impl fmt::Display for DigitScan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:<7}{:>7}{:>7}  {:<16}{:>7}", "Digit", "Placed", "Cells", "Fewest places", "Singles")?;
+        for summary in self.digits.iter() {
+            let fewest = match summary.fewest() {
+                Some((house, places)) => format!("{} in {}", places, house),
+                None => String::from("-"),
+            };
+            let (digit, placed, cells, singles) = (summary.digit, summary.placed, summary.cells, summary.singles());
+            write!(f, "\n{:<7}{:>7}{:>7}  {:<16}{:>7}", digit, placed, cells, fewest, singles)?;
+        }
+        Ok(())
+    }
+}
+
+/// Scans the digits of `grid`, whose candidates may already have been
+/// narrowed down by techniques.
+pub fn digit_scan(grid: &Grid) -> DigitScan {
+    let digits = (1..10)
+        .map(|digit| {
+            let mut placed = 0;
+            let mut cells = 0;
+            for row in 0..9 {
+                for col in 0..9 {
+                    match grid.value(row, col) {
+                        Some(value) if value == digit => placed += 1,
+                        Some(_) => {}
+                        None if grid.has_candidate(row, col, digit) => cells += 1,
+                        None => {}
+                    }
+                }
+            }
+            let places = House::all()
+                .filter(|&house| !grid.is_placed_in(house, digit))
+                .map(|house| (house, grid.places_for(house, digit).len()))
+                .collect();
+            DigitSummary { digit, placed, cells, places }
+        })
+        .collect();
+    DigitScan { digits }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::default_board;
+
+    #[test]
+    fn test_digit_scan() {
+        let scan = digit_scan(&Grid::from_board(&default_board()));
+        assert_eq!(scan.digits.len(), 9);
+        let fives = &scan.digits[4];
+        assert_eq!(fives.placed, 6);
+        // The digit is missing from a row, a column, and a box for every
+        // time it isn't placed.
+        assert_eq!(fives.places.len(), 3 * (9 - fives.placed));
+        assert!(fives.fewest().unwrap().1 >= 1);
+
+        let table = scan.to_string();
+        assert_eq!(table.lines().count(), 10);
+        assert!(table.lines().nth(5).unwrap().starts_with("5            6"));
+        let json = scan.to_json();
+        assert_eq!(json.as_array().unwrap()[4].get("placed").and_then(Json::as_u64), Some(6));
+
+        let solved = crate::solver::Sudoku::new(default_board()).solve().unwrap();
+        let scan = digit_scan(&Grid::from_board(&solved));
+        assert!(scan.digits.iter().all(|summary| summary.placed == 9 && summary.fewest().is_none()));
+    }
+}
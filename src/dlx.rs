@@ -0,0 +1,321 @@
+//! Knuth's Algorithm X with dancing links, solving sudoku as an exact cover
+//! problem. Each of the 729 rows is a digit in a cell, and each of the 324
+//! columns a constraint that exactly one row has to satisfy: every cell has
+//! a digit, and every row, column, and box has every digit once. It does no
+//! more than pick the most constrained column, which is enough to enumerate
+//! the completions of sparse grids much faster than the other searches.
+//!
+//! The matrix has a fixed size for 9×9 grids, so it's allocated once per
+//! `DlxSolver` and rebuilt in place for every puzzle, without going back to
+//! the allocator.
+
+use std::fmt;
+use std::mem;
+
+use crate::board::Board;
+use crate::packed::Packed;
+use crate::solver::{write_solution, SolverBackend};
+
+/// The columns of the constraints on cells, digits in rows, digits in
+/// columns, and digits in boxes, in that order.
+const COLUMNS: usize = 4 * 81;
+
+/// The nodes of the matrix: the root, the column headers, and four for each
+/// row.
+const NODES: usize = 1 + COLUMNS + 729 * 4;
+
+/// How many bytes of memory a solver allocates, once: the links, columns,
+/// and column sizes of the matrix, and the rows of the solution.
+pub(crate) const MEMORY_BYTES: usize = mem::size_of::<Dlx>();
+
+/// The root node, whose right neighbour is the first column not yet covered.
+const ROOT: usize = 0;
+
+/// The matrix with the nodes of the rows and column headers in parallel
+/// arrays, linked in circular lists both ways.
+#[derive(Clone)]
+pub(crate) struct Dlx {
+    left: [usize; NODES],
+    right: [usize; NODES],
+    up: [usize; NODES],
+    down: [usize; NODES],
+    /// The column header of each node.
+    column: [usize; NODES],
+    /// The rows remaining in each column, by header.
+    size: [usize; 1 + COLUMNS],
+    /// The rows of the solution found, in the order they were taken.
+    solution: [usize; 81],
+}
+
+/// Returns the four columns that placing `digit` at `index` satisfies.
+fn columns(index: usize, digit: usize) -> [usize; 4] {
+    let (row, col) = (index / 9, index % 9);
+    let b = row / 3 * 3 + col / 3;
+    // Headers start at 1, after the root.
+    [1 + index, 1 + 81 + row * 9 + digit, 1 + 162 + col * 9 + digit, 1 + 243 + b * 9 + digit]
+}
+
+impl Dlx {
+    /// Returns an empty matrix on the heap, to be built with `reset`.
+    fn boxed() -> Box<Dlx> {
+        Box::new(Dlx {
+            left: [0; NODES],
+            right: [0; NODES],
+            up: [0; NODES],
+            down: [0; NODES],
+            column: [0; NODES],
+            size: [0; 1 + COLUMNS],
+            solution: [0; 81],
+        })
+    }
+
+    /// Builds the matrix of `board` with the rows of its givens taken. Returns
+    /// None if the givens conflict.
+    pub(crate) fn new(board: &Board) -> Option<Box<Dlx>> {
+        let mut dlx = Dlx::boxed();
+        dlx.reset(board).then_some(dlx)
+    }
+
+    /// Rebuilds the matrix in place for `board`, whatever it held before.
+    /// Returns false if the givens conflict.
+    fn reset(&mut self, board: &Board) -> bool {
+        let packed = match Packed::from_board(board) {
+            Some(packed) => packed,
+            None => return false,
+        };
+        for header in 0..=COLUMNS {
+            self.left[header] = if header == 0 { COLUMNS } else { header - 1 };
+            self.right[header] = if header == COLUMNS { 0 } else { header + 1 };
+            self.up[header] = header;
+            self.down[header] = header;
+            self.column[header] = header;
+            self.size[header] = 0;
+        }
+        for index in 0..81 {
+            for digit in 0..9 {
+                self.add_row(index * 9 + digit, columns(index, digit));
+            }
+        }
+        for (index, &digit) in packed.cells.iter().enumerate() {
+            if digit != 0 {
+                for &header in columns(index, digit as usize - 1).iter() {
+                    self.cover(header);
+                }
+            }
+        }
+        true
+    }
+
+    fn add_row(&mut self, row: usize, headers: [usize; 4]) {
+        let first = 1 + COLUMNS + row * 4;
+        for (i, &header) in headers.iter().enumerate() {
+            let node = first + i;
+            self.left[node] = if i == 0 { first + 3 } else { node - 1 };
+            self.right[node] = if i == 3 { first } else { node + 1 };
+            // Append to the bottom of the column.
+            let last = self.up[header];
+            self.up[node] = last;
+            self.down[node] = header;
+            self.down[last] = node;
+            self.up[header] = node;
+            self.column[node] = header;
+            self.size[header] += 1;
+        }
+    }
+
+    /// Removes the column and every row that satisfies it.
+    fn cover(&mut self, header: usize) {
+        self.right[self.left[header]] = self.right[header];
+        self.left[self.right[header]] = self.left[header];
+        let mut row = self.down[header];
+        while row != header {
+            let mut node = self.right[row];
+            while node != row {
+                self.down[self.up[node]] = self.down[node];
+                self.up[self.down[node]] = self.up[node];
+                self.size[self.column[node]] -= 1;
+                node = self.right[node];
+            }
+            row = self.down[row];
+        }
+    }
+
+    /// Undoes `cover`, in the reverse order.
+    fn uncover(&mut self, header: usize) {
+        let mut row = self.up[header];
+        while row != header {
+            let mut node = self.left[row];
+            while node != row {
+                self.size[self.column[node]] += 1;
+                self.down[self.up[node]] = node;
+                self.up[self.down[node]] = node;
+                node = self.left[node];
+            }
+            row = self.up[row];
+        }
+        self.right[self.left[header]] = header;
+        self.left[self.right[header]] = header;
+    }
+
+    /// Returns the uncovered column with the fewest rows, or None if every
+    /// column is covered, i.e. the grid is complete.
+    fn fewest_rows(&self) -> Option<usize> {
+        let mut best = None;
+        let mut fewest = usize::MAX;
+        let mut header = self.right[ROOT];
+        while header != ROOT {
+            if self.size[header] < fewest {
+                fewest = self.size[header];
+                best = Some(header);
+                if fewest <= 1 {
+                    break;
+                }
+            }
+            header = self.right[header];
+        }
+        best
+    }
+
+    /// Returns the row of the matrix that `node` is in, as `index * 9 + digit`.
+    fn row(node: usize) -> usize {
+        (node - 1 - COLUMNS) / 4
+    }
+
+    /// Finds a solution and writes its rows into `solution` from `depth` on,
+    /// with the rows taken so far before it. Returns false if there's none.
+    /// The matrix is left as it was either way.
+    fn solve(&mut self, depth: usize) -> bool {
+        let header = match self.fewest_rows() {
+            Some(header) => header,
+            None => {
+                // The rest of the solution is the givens.
+                self.solution[depth..].fill(usize::MAX);
+                return true;
+            }
+        };
+        let mut found = false;
+        self.cover(header);
+        let mut row = self.down[header];
+        while row != header && !found {
+            let mut node = self.right[row];
+            while node != row {
+                self.cover(self.column[node]);
+                node = self.right[node];
+            }
+            self.solution[depth] = Dlx::row(row);
+            found = self.solve(depth + 1);
+            let mut node = self.left[row];
+            while node != row {
+                self.uncover(self.column[node]);
+                node = self.left[node];
+            }
+            row = self.down[row];
+        }
+        self.uncover(header);
+        found
+    }
+
+    /// Counts the solutions, saturating at `u64::MAX`.
+    pub(crate) fn count(&mut self) -> u64 {
+        let header = match self.fewest_rows() {
+            Some(header) => header,
+            None => return 1,
+        };
+        let mut count: u64 = 0;
+        self.cover(header);
+        let mut row = self.down[header];
+        while row != header {
+            let mut node = self.right[row];
+            while node != row {
+                self.cover(self.column[node]);
+                node = self.right[node];
+            }
+            count = count.saturating_add(self.count());
+            let mut node = self.left[row];
+            while node != row {
+                self.uncover(self.column[node]);
+                node = self.left[node];
+            }
+            row = self.down[row];
+        }
+        self.uncover(header);
+        count
+    }
+}
+
+/// The DLX backend. It keeps the matrix between puzzles and rebuilds it for
+/// each.
+#[derive(Clone)]
+pub struct DlxSolver {
+    matrix: Box<Dlx>,
+}
+
+impl DlxSolver {
+    pub fn new() -> DlxSolver {
+        DlxSolver { matrix: Dlx::boxed() }
+    }
+}
+
+impl Default for DlxSolver {
+    fn default() -> DlxSolver {
+        DlxSolver::new()
+    }
+}
+
+impl fmt::Debug for DlxSolver {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DlxSolver").finish_non_exhaustive()
+    }
+}
+
+impl SolverBackend for DlxSolver {
+    fn solve_into(&mut self, puzzle: &Board, solution: &mut Board) -> bool {
+        let dlx = &mut self.matrix;
+        if !dlx.reset(puzzle) || !dlx.solve(0) {
+            return false;
+        }
+        // The givens are valid, or there'd be no matrix.
+        let mut digits = Packed::from_board(puzzle).unwrap().cells;
+        for &row in dlx.solution.iter().take_while(|&&row| row != usize::MAX) {
+            digits[row / 9] = (row % 9) as u8 + 1;
+        }
+        write_solution(puzzle, &digits, solution);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{board_to_string, default_board, parse_board, Cell};
+    use crate::solver::Sudoku;
+    use crate::count::count_solutions;
+
+    #[test]
+    fn test_dlx() {
+        assert_eq!(Dlx::new(&default_board()).unwrap().count(), 1);
+        let board = parse_board(
+            ".......................562....6.471..495.783..178.2....54783.96..6....5....1..4..",
+        )
+        .unwrap();
+        let mut dlx = Dlx::new(&board).unwrap();
+        assert_eq!(dlx.count(), count_solutions(&board, usize::MAX) as u64);
+        // Counting leaves the matrix as it was.
+        assert_eq!(dlx.count(), 2332);
+
+        let mut board = default_board();
+        board[0][0] = Cell::solved(5);
+        assert!(Dlx::new(&board).is_none());
+
+        // A solver rebuilds its matrix for each puzzle, whatever the last
+        // one left in it.
+        let mut solver = DlxSolver::new();
+        let mut solution = Board::default();
+        let expected = Sudoku::new(default_board()).solve().unwrap();
+        assert!(solver.solve_into(&default_board(), &mut solution));
+        assert!(!solver.solve_into(&board, &mut solution));
+        let mut solution = Board::default();
+        assert!(solver.solve_into(&default_board(), &mut solution));
+        assert_eq!(board_to_string(&solution), board_to_string(&expected));
+    }
+}
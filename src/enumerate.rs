@@ -0,0 +1,245 @@
+//! Enumerating every solution of a puzzle in steps, for puzzles with so many
+//! solutions that counting them takes hours. The state of the search can be
+//! saved as a snapshot between steps and resumed from it later, e.g. after a
+//! restart or on another machine.
+//!
+//! A snapshot is the magic `SDKE`, a format version byte, the solutions
+//! counted so far as a little endian u64, the puzzle as 81 bytes of digits,
+//! 0 for empty cells, the number of grids left to explore as a little endian
+//! u32, and then each of those grids as 81 bytes of digits followed by the
+//! share of the search space under it as a little endian f64. Candidates
+//! aren't stored since they follow from the digits. Snapshots of version 1
+//! lack the shares, and those before version 3 the puzzle.
+
+use std::convert::TryInto;
+use std::mem;
+
+use crate::board::{Board, Cell};
+use crate::count::branch_cell;
+use crate::grid::{mask_digits, Grid};
+use crate::refute::find_contradiction;
+use crate::solver::{MemoryLimitExceeded, SolveOptions};
+
+const MAGIC: &[u8; 4] = b"SDKE";
+
+/// About how many bytes of memory the grids left to explore take up at most.
+/// Each of the 81 cells filled in on the way down leaves at most eight
+/// other candidates to explore, and the stack grows in powers of two.
+const MEMORY_BYTES: usize = (1 + 8 * 81usize).next_power_of_two() * mem::size_of::<(Grid, f64)>();
+const VERSION: u8 = 3;
+
+/// A search for every solution of a puzzle that can be run a bit at a time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Enumeration {
+    /// The puzzle whose solutions are enumerated, unless it was resumed from
+    /// a snapshot that lacks it.
+    puzzle: Option<Board>,
+    /// The grids left to explore, the next one last, with the share of the
+    /// search space under each: a grid with several candidates in the cell
+    /// branched on splits its share evenly between the branches.
+    stack: Vec<(Grid, f64)>,
+    count: u64,
+}
+
+impl Enumeration {
+    pub fn new(board: &Board) -> Enumeration {
+        let grid = Grid::from_board(board);
+        let stack = if find_contradiction(&grid).is_some() { Vec::new() } else { vec![(grid, 1.0)] };
+        Enumeration { puzzle: Some(*board), stack, count: 0 }
+    }
+
+    /// Like `new`, but fails if the search could need more memory than
+    /// `options.max_memory_bytes`. The other options don't apply.
+    pub fn with_options(board: &Board, options: SolveOptions) -> Result<Enumeration, MemoryLimitExceeded> {
+        options.check_memory(Enumeration::memory_bytes())?;
+        Ok(Enumeration::new(board))
+    }
+
+    /// Returns about how many bytes of memory a search needs at most.
+    pub fn memory_bytes() -> usize {
+        MEMORY_BYTES
+    }
+
+    /// Returns the number of solutions found so far.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns whether every solution has been found.
+    pub fn is_finished(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    /// Returns whether this is the enumeration of `board`, e.g. to tell
+    /// whether a snapshot belongs to a puzzle. Snapshots without the puzzle
+    /// are taken to be of it if every grid left to explore has its digits.
+    pub fn is_of(&self, board: &Board) -> bool {
+        let digits = |board: &Board| -> Vec<Option<i8>> { board.iter().flatten().map(|cell| cell.solution).collect() };
+        match &self.puzzle {
+            Some(puzzle) => digits(puzzle) == digits(board),
+            None => self.stack.iter().all(|(grid, _)| {
+                (0..81).all(|i: usize| {
+                    board[i / 9][i % 9].solution.is_none_or(|digit| grid.value(i / 9, i % 9) == Some(digit))
+                })
+            }),
+        }
+    }
+
+    /// Returns an estimate of the fraction of the search done so far, from 0
+    /// to 1. It assumes every branch of the search is as large as its
+    /// siblings, so it's rough at first and gets better as branches finish.
+    pub fn progress(&self) -> f64 {
+        1.0 - self.stack.iter().map(|&(_, share)| share).sum::<f64>()
+    }
+
+    /// Explores up to `nodes` grids of the search. Returns whether it's
+    /// finished.
+    pub fn run(&mut self, nodes: u64) -> bool {
+        self.run_with_progress(nodes, 0, &mut |_| {})
+    }
+
+    /// Like `run`, but calls `report` with the `progress` every `every` grids,
+    /// or never if `every` is 0, e.g. to show a progress bar.
+    pub fn run_with_progress(&mut self, nodes: u64, every: u64, report: &mut dyn FnMut(f64)) -> bool {
+        for node in 1..=nodes {
+            if every > 0 && node % every == 0 {
+                report(self.progress());
+            }
+            let (grid, share) = match self.stack.pop() {
+                Some(entry) => entry,
+                None => break,
+            };
+            let (row, col) = match branch_cell(&grid) {
+                Some(cell) => cell,
+                None => {
+                    self.count += 1;
+                    continue;
+                }
+            };
+            // Push the largest digit first so the smallest is explored first.
+            let digits: Vec<i8> = mask_digits(grid.candidates(row, col)).collect();
+            for &digit in digits.iter().rev() {
+                let mut branch = grid;
+                branch.place(row, col, digit);
+                self.stack.push((branch, share / digits.len() as f64));
+            }
+        }
+        self.is_finished()
+    }
+
+    /// Returns a snapshot of the search to resume it from with `from_snapshot`.
+    pub fn snapshot(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(17 + 81 + self.stack.len() * (81 + 8));
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&self.count.to_le_bytes());
+        let puzzle = self.puzzle.unwrap_or_default();
+        bytes.extend(puzzle.iter().flatten().map(|cell| cell.solution.unwrap_or(0) as u8));
+        bytes.extend_from_slice(&(self.stack.len() as u32).to_le_bytes());
+        for (grid, share) in self.stack.iter() {
+            for row in 0..9 {
+                for col in 0..9 {
+                    bytes.push(grid.value(row, col).unwrap_or(0) as u8);
+                }
+            }
+            bytes.extend_from_slice(&share.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Resumes a search from a snapshot taken with `snapshot`. Returns None if
+    /// the bytes aren't a snapshot. The grids of version 1 snapshots are taken
+    /// to be equal shares of the whole search, so their progress starts over.
+    pub fn from_snapshot(bytes: &[u8]) -> Option<Enumeration> {
+        let version = *bytes.get(4)?;
+        let header = if version >= 3 { 17 + 81 } else { 17 };
+        if bytes.len() < header || &bytes[..4] != MAGIC || !(1..=VERSION).contains(&version) {
+            return None;
+        }
+        let count = u64::from_le_bytes(bytes[5..13].try_into().ok()?);
+        let puzzle = if version >= 3 { Some(read_board(&bytes[13..13 + 81])?) } else { None };
+        let len = u32::from_le_bytes(bytes[header - 4..header].try_into().ok()?) as usize;
+        let grids = &bytes[header..];
+        let size = if version == 1 { 81 } else { 81 + 8 };
+        if grids.len() != len * size {
+            return None;
+        }
+        let mut stack = Vec::with_capacity(len);
+        for entry in grids.chunks(size) {
+            let share = match entry.get(81..) {
+                Some(share) if !share.is_empty() => f64::from_le_bytes(share.try_into().ok()?),
+                _ => 1.0 / len as f64,
+            };
+            stack.push((Grid::from_board(&read_board(&entry[..81])?), share));
+        }
+        Some(Enumeration { puzzle, stack, count })
+    }
+}
+
+/// Reads 81 bytes of digits, 0 for empty cells, as a board.
+fn read_board(digits: &[u8]) -> Option<Board> {
+    let mut board: Board = Default::default();
+    for (i, &digit) in digits.iter().enumerate() {
+        match digit {
+            0 => {}
+            1..=9 => board[i / 9][i % 9] = Cell::solved(digit as i8),
+            _ => return None,
+        }
+    }
+    Some(board)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{default_board, parse_board};
+    use crate::count::count_solutions;
+
+    #[test]
+    fn test_enumeration() {
+        let mut enumeration = Enumeration::new(&default_board());
+        assert!(enumeration.run(1000));
+        assert_eq!(enumeration.count(), 1);
+
+        // Without its top rows, the puzzle has a couple thousand solutions.
+        let board = parse_board(
+            ".......................562....6.471..495.783..178.2....54783.96..6....5....1..4..",
+        )
+        .unwrap();
+        let total = count_solutions(&board, usize::MAX) as u64;
+        assert_eq!(total, 2332);
+        let mut enumeration = Enumeration::new(&board);
+        assert!(!enumeration.run(10));
+        let snapshot = enumeration.snapshot();
+        let mut resumed = Enumeration::from_snapshot(&snapshot).unwrap();
+        assert_eq!(resumed, enumeration);
+        while !resumed.run(10) {}
+        assert_eq!(resumed.count(), total);
+        assert!(Enumeration::from_snapshot(&snapshot[..snapshot.len() - 1]).is_none());
+
+        // A snapshot tells which puzzle it's of, as do the grids of a version
+        // 2 snapshot without the puzzle.
+        assert!(resumed.is_of(&board) && !resumed.is_of(&default_board()));
+        let mut old = snapshot[..13].to_vec();
+        old[4] = 2;
+        old.extend_from_slice(&snapshot[13 + 81..]);
+        let old = Enumeration::from_snapshot(&old).unwrap();
+        assert!(old.is_of(&board) && !old.is_of(&default_board()));
+
+        let mut enumeration = Enumeration::new(&board);
+        let mut reports = Vec::new();
+        while !enumeration.run_with_progress(100, 10, &mut |progress| reports.push(progress)) {}
+        assert!(reports.len() > 10);
+        assert!(reports.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert!((enumeration.progress() - 1.0).abs() < 1e-9);
+
+        let options = SolveOptions::default().max_memory_bytes(MEMORY_BYTES - 1);
+        assert!(Enumeration::with_options(&board, options).is_err());
+        let mut enumeration = Enumeration::with_options(&board, options.max_memory_bytes(MEMORY_BYTES)).unwrap();
+        let mut largest = 0;
+        while !enumeration.run(1) {
+            largest = largest.max(enumeration.stack.capacity() * mem::size_of::<(Grid, f64)>());
+        }
+        assert!(largest <= MEMORY_BYTES);
+    }
+}
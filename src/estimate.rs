@@ -0,0 +1,142 @@
+//! Estimating the size of the search tree of a puzzle with Knuth's method,
+//! without searching it. Each probe follows random branches from the root to
+//! a leaf, as `count_solutions` would branch, and counts every node on the
+//! way as standing for as many nodes as there were choices above it. The
+//! average over the probes is an unbiased estimate, so a few hundred probes
+//! are enough to tell a puzzle that takes microseconds from one that takes
+//! hours.
+//!
+//! The same probes estimate how many solutions a grid with few clues has,
+//! for grids where counting them is out of the question, and the spread of
+//! the probes tells how far off the estimate may be.
+
+use crate::board::Board;
+use crate::count::branch_cell;
+use crate::generate::Rng;
+use crate::grid::{mask_digits, Grid};
+use crate::refute::find_contradiction;
+
+/// The estimated size of the search tree of a puzzle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchEstimate {
+    /// The nodes a full enumeration would visit.
+    pub nodes: f64,
+    /// The solutions it would find.
+    pub solutions: f64,
+    pub probes: usize,
+}
+
+/// An estimate of the number of solutions of a grid, with bounds that hold
+/// it with about 95% confidence.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CountEstimate {
+    pub solutions: f64,
+    /// The standard error of the estimate.
+    pub std_error: f64,
+    pub lower: f64,
+    pub upper: f64,
+    pub probes: usize,
+}
+
+/// Estimates the search tree of `board` from `probes` random probes.
+pub fn estimate_search(board: &Board, probes: usize, rng: &mut Rng) -> SearchEstimate {
+    let root = Grid::from_board(board);
+    let mut estimate = SearchEstimate { nodes: 0.0, solutions: 0.0, probes };
+    if probes == 0 || find_contradiction(&root).is_some() {
+        return estimate;
+    }
+    for _ in 0..probes {
+        let (nodes, solutions) = probe(&root, rng);
+        estimate.nodes += nodes;
+        estimate.solutions += solutions;
+    }
+    estimate.nodes /= probes as f64;
+    estimate.solutions /= probes as f64;
+    estimate
+}
+
+/// Estimates the number of solutions of `board` from `probes` random probes,
+/// e.g. of a grid with so few clues that `count_solutions` would take
+/// years. The bounds come from the spread of the probes as if their mean
+/// were normally distributed, which takes thousands of probes to be close
+/// to true, as a few rare probes make up most of the estimate. The lower
+/// bound is never below 1 once a probe has found a solution.
+pub fn estimate_solutions(board: &Board, probes: usize, rng: &mut Rng) -> CountEstimate {
+    let root = Grid::from_board(board);
+    let mut estimate = CountEstimate { solutions: 0.0, std_error: 0.0, lower: 0.0, upper: 0.0, probes };
+    if probes == 0 || find_contradiction(&root).is_some() {
+        return estimate;
+    }
+    // The running mean and sum of squared deviations (Welford's method).
+    let mut squares = 0.0;
+    for i in 0..probes {
+        let (_, solutions) = probe(&root, rng);
+        let delta = solutions - estimate.solutions;
+        estimate.solutions += delta / (i + 1) as f64;
+        squares += delta * (solutions - estimate.solutions);
+    }
+    if probes > 1 {
+        estimate.std_error = (squares / (probes - 1) as f64 / probes as f64).sqrt();
+    }
+    let margin = 1.96 * estimate.std_error;
+    let floor = if estimate.solutions > 0.0 { 1.0 } else { 0.0 };
+    estimate.lower = (estimate.solutions - margin).max(floor);
+    estimate.upper = estimate.solutions + margin;
+    estimate
+}
+
+/// Follows random branches from `root` down to a leaf, returning the nodes
+/// and the solutions of the search tree the path stands for.
+fn probe(root: &Grid, rng: &mut Rng) -> (f64, f64) {
+    let mut grid = *root;
+    let mut nodes = 0.0;
+    // How many nodes the current one stands for.
+    let mut weight = 1.0;
+    loop {
+        nodes += weight;
+        let (row, col) = match branch_cell(&grid) {
+            Some(cell) => cell,
+            None => return (nodes, weight),
+        };
+        let candidates = grid.candidates(row, col);
+        let branches = candidates.count_ones() as usize;
+        if branches == 0 {
+            return (nodes, 0.0);
+        }
+        weight *= branches as f64;
+        let digit = mask_digits(candidates).nth(rng.below(branches)).unwrap();
+        grid.place(row, col, digit);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{default_board, parse_board};
+    use crate::count::count_solutions;
+
+    #[test]
+    fn test_estimate_search() {
+        let solution = crate::solver::Sudoku::new(default_board()).solve().unwrap();
+        let estimate = estimate_search(&solution, 10, &mut Rng::new(1));
+        assert_eq!((estimate.nodes, estimate.solutions), (1.0, 1.0));
+
+        let board = parse_board(
+            ".......................562....6.471..495.783..178.2....54783.96..6....5....1..4..",
+        )
+        .unwrap();
+        let estimate = estimate_search(&board, 2000, &mut Rng::new(1));
+        let solutions = count_solutions(&board, usize::MAX) as f64;
+        assert!(estimate.solutions > solutions / 2.0 && estimate.solutions < solutions * 2.0);
+        assert!(estimate.nodes > estimate.solutions);
+
+        let estimate = estimate_solutions(&board, 2000, &mut Rng::new(1));
+        assert!(estimate.lower < solutions && solutions < estimate.upper);
+        assert!(estimate.lower >= 1.0 && estimate.std_error > 0.0);
+        // An empty grid has about 6.67e21 solutions.
+        let estimate = estimate_solutions(&Board::default(), 2000, &mut Rng::new(1));
+        assert!(estimate.solutions > 1e21 && estimate.solutions < 3e22);
+        let solution = estimate_solutions(&solution, 10, &mut Rng::new(1));
+        assert_eq!((solution.solutions, solution.lower, solution.upper), (1.0, 1.0, 1.0));
+    }
+}
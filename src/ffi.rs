@@ -0,0 +1,164 @@
+//! C bindings, enabled with the `ffi` feature. The header is generated into
+//! `include/sudoku.h` with `cbindgen --config cbindgen.toml --output
+//! include/sudoku.h`.
+//!
+//! Puzzles are passed as NUL terminated strings in the 81 character format,
+//! and results are written to buffers of at least `SUDOKU_BUFFER_LEN` bytes
+//! owned by the caller, so no memory is ever freed across the boundary.
+
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::ptr;
+
+use crate::board::{board_to_string, parse_board, Board};
+use crate::generate::{generate, Difficulty, Rng};
+use crate::logic::TechniqueConfig;
+use crate::rating::rate;
+use crate::solver::Sudoku;
+
+/// The size of the buffers that puzzles and solutions are written to: 81
+/// cells and the terminating NUL.
+pub const SUDOKU_BUFFER_LEN: usize = 82;
+
+/// The result of every call.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SudokuStatus {
+    Ok = 0,
+    /// A pointer was NULL or a number was out of range.
+    InvalidArgument = 1,
+    /// The puzzle isn't a valid 81 character string.
+    InvalidPuzzle = 2,
+    /// The puzzle has no solution.
+    NoSolution = 3,
+}
+
+/// The rating of a puzzle. Difficulties are numbered from 0 (easy) to 3
+/// (expert) and techniques in order of difficulty from 0 (naked single), or
+/// -1 if none was needed.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SudokuRating {
+    pub difficulty: u32,
+    pub hardest_technique: i32,
+    pub guesses_needed: u32,
+    pub guesses_total: u32,
+    pub guess_depth: u32,
+    /// From 0 for puzzles with nothing left to solve to 10 for those that
+    /// need guesses.
+    pub score: f64,
+}
+
+unsafe fn read_puzzle(puzzle: *const c_char) -> Result<Board, SudokuStatus> {
+    if puzzle.is_null() {
+        return Err(SudokuStatus::InvalidArgument);
+    }
+    let puzzle = CStr::from_ptr(puzzle).to_str().map_err(|_| SudokuStatus::InvalidPuzzle)?;
+    parse_board(puzzle).ok_or(SudokuStatus::InvalidPuzzle)
+}
+
+unsafe fn write_board(board: &Board, out: *mut c_char) {
+    let s = board_to_string(board);
+    ptr::copy_nonoverlapping(s.as_ptr() as *const c_char, out, s.len());
+    *out.add(s.len()) = 0;
+}
+
+/// Solves `puzzle` and writes the solution to `solution`.
+///
+/// # Safety
+///
+/// `puzzle` must be NULL or a NUL terminated string, and `solution` must be
+/// NULL or point to at least `SUDOKU_BUFFER_LEN` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_solve(puzzle: *const c_char, solution: *mut c_char) -> SudokuStatus {
+    if solution.is_null() {
+        return SudokuStatus::InvalidArgument;
+    }
+    let board = match read_puzzle(puzzle) {
+        Ok(board) => board,
+        Err(status) => return status,
+    };
+    match Sudoku::new(board).solve() {
+        Some(solved) => {
+            write_board(&solved, solution);
+            SudokuStatus::Ok
+        }
+        None => SudokuStatus::NoSolution,
+    }
+}
+
+/// Generates a puzzle of `difficulty`, from 0 (easy) to 3 (expert), and writes
+/// it to `puzzle`. The same seed always generates the same puzzle.
+///
+/// # Safety
+///
+/// `puzzle` must be NULL or point to at least `SUDOKU_BUFFER_LEN` writable
+/// bytes.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_generate(difficulty: u32, seed: u64, puzzle: *mut c_char) -> SudokuStatus {
+    let difficulty = match Difficulty::ALL.get(difficulty as usize) {
+        Some(&difficulty) if !puzzle.is_null() => difficulty,
+        _ => return SudokuStatus::InvalidArgument,
+    };
+    write_board(&generate(difficulty, &mut Rng::new(seed)), puzzle);
+    SudokuStatus::Ok
+}
+
+/// Rates `puzzle` and writes the result to `rating`.
+///
+/// # Safety
+///
+/// `puzzle` must be NULL or a NUL terminated string, and `rating` must be
+/// NULL or point to a writable `SudokuRating`.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_rate(puzzle: *const c_char, rating: *mut SudokuRating) -> SudokuStatus {
+    if rating.is_null() {
+        return SudokuStatus::InvalidArgument;
+    }
+    let board = match read_puzzle(puzzle) {
+        Ok(board) => board,
+        Err(status) => return status,
+    };
+    match rate(&board, &TechniqueConfig::default()) {
+        Some(r) => {
+            *rating = SudokuRating {
+                difficulty: Difficulty::of(&r) as u32,
+                hardest_technique: r.hardest.map_or(-1, |technique| technique as i32),
+                guesses_needed: r.guesses.needed as u32,
+                guesses_total: r.guesses.total as u32,
+                guess_depth: r.guesses.depth as u32,
+                score: r.score(),
+            };
+            SudokuStatus::Ok
+        }
+        None => SudokuStatus::NoSolution,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    #[test]
+    fn test_ffi() {
+        let puzzle = CString::new(board_to_string(&crate::board::default_board())).unwrap();
+        let mut buf = [0 as c_char; SUDOKU_BUFFER_LEN];
+        unsafe {
+            assert_eq!(sudoku_solve(puzzle.as_ptr(), buf.as_mut_ptr()), SudokuStatus::Ok);
+            let solution = CStr::from_ptr(buf.as_ptr()).to_str().unwrap();
+            assert!(solution.starts_with("365278149"));
+
+            let mut rating = SudokuRating::default();
+            assert_eq!(sudoku_rate(puzzle.as_ptr(), &mut rating), SudokuStatus::Ok);
+            assert_eq!(rating.guesses_needed, 0);
+            let expected = rate(&crate::board::default_board(), &TechniqueConfig::default()).unwrap();
+            assert_eq!(rating.score, expected.score());
+
+            let bad = CString::new("123").unwrap();
+            assert_eq!(sudoku_solve(bad.as_ptr(), buf.as_mut_ptr()), SudokuStatus::InvalidPuzzle);
+            assert_eq!(sudoku_solve(ptr::null(), buf.as_mut_ptr()), SudokuStatus::InvalidArgument);
+            assert_eq!(sudoku_generate(4, 0, buf.as_mut_ptr()), SudokuStatus::InvalidArgument);
+        }
+    }
+}
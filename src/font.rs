@@ -0,0 +1,23 @@
+//! A 5x7 pixel font of the digits, used both to draw boards and as the
+//! templates that printed digits are matched against.
+
+pub(crate) const GLYPH_WIDTH: usize = 5;
+pub(crate) const GLYPH_HEIGHT: usize = 7;
+
+/// The digits 1-9, one row per byte with the leftmost pixel in bit 4.
+const GLYPHS: [[u8; GLYPH_HEIGHT]; 9] = [
+    [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+    [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111],
+    [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110],
+    [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010],
+    [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110],
+    [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110],
+    [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000],
+    [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110],
+    [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100],
+];
+
+/// Returns whether the pixel at (`x`, `y`) of the glyph of `digit` is set.
+pub(crate) fn glyph_pixel(digit: i8, x: usize, y: usize) -> bool {
+    (GLYPHS[digit as usize - 1][y] >> (GLYPH_WIDTH - 1 - x)) & 1 == 1
+}
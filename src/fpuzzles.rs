@@ -0,0 +1,175 @@
+//! The JSON format of the f-puzzles editor, which SudokuPad reads too, for
+//! exchanging variant puzzles with them. Only 9x9 puzzles and the constraints
+//! in `Variant` are supported, and only plain JSON, not the compressed form
+//! used in f-puzzles links.
+
+use crate::board::{Board, Cell};
+use crate::json::Json;
+use crate::variant::{Arrow, Cage, Variant};
+
+/// Parses a cell name like `R1C2`.
+fn parse_cell(json: &Json) -> Option<(usize, usize)> {
+    match json.as_str()?.to_ascii_uppercase().as_bytes() {
+        [b'R', row @ b'1'..=b'9', b'C', col @ b'1'..=b'9'] => Some(((row - b'1') as usize, (col - b'1') as usize)),
+        _ => None,
+    }
+}
+
+fn parse_cells(json: &Json) -> Option<Vec<(usize, usize)>> {
+    json.as_array()?.iter().map(parse_cell).collect()
+}
+
+/// Returns the lines of each constraint in the list under `key`, as
+/// thermometers and arrows may have several.
+fn parse_lines<'a>(json: &'a Json, key: &str) -> impl Iterator<Item = (&'a Json, Option<Vec<(usize, usize)>>)> {
+    let constraints = json.get(key).and_then(Json::as_array).unwrap_or_default();
+    constraints.iter().flat_map(|constraint| {
+        let lines = constraint.get("lines").and_then(Json::as_array).unwrap_or_default();
+        lines.iter().map(move |line| (constraint, parse_cells(line)))
+    })
+}
+
+/// Parses a puzzle in the f-puzzles format. Only the given digits of the grid
+/// are read, not the ones a solver has entered. Returns None if it isn't a
+/// 9x9 puzzle in this format.
+pub fn parse_fpuzzles(s: &str) -> Option<(Board, Variant)> {
+    let json = Json::parse(s)?;
+    if json.get("size").and_then(Json::as_u64) != Some(9) {
+        return None;
+    }
+    let mut board: Board = Default::default();
+    let rows = json.get("grid")?.as_array()?;
+    if rows.len() != 9 {
+        return None;
+    }
+    for (row, cells) in rows.iter().enumerate() {
+        let cells = cells.as_array()?;
+        if cells.len() != 9 {
+            return None;
+        }
+        for (col, cell) in cells.iter().enumerate() {
+            let given = cell.get("given").and_then(Json::as_bool).unwrap_or(false);
+            match cell.get("value").and_then(Json::as_u64) {
+                Some(digit @ 1..=9) if given => board[row][col] = Cell::solved(digit as i8),
+                Some(_) if given => return None,
+                _ => {}
+            }
+        }
+    }
+
+    let flag = |key| json.get(key).and_then(Json::as_bool).unwrap_or(false);
+    let mut variant = Variant {
+        main_diagonal: flag("diagonal-"),
+        anti_diagonal: flag("diagonal+"),
+        ..Variant::default()
+    };
+    for cage in json.get("killercage").and_then(Json::as_array).unwrap_or_default() {
+        let cells = parse_cells(cage.get("cells")?)?;
+        // Sums are strings, and may be missing or empty for cages without one.
+        let sum = match cage.get("value") {
+            Some(Json::String(sum)) if !sum.is_empty() => Some(sum.parse().ok()?),
+            Some(Json::Number(sum)) => Some(*sum as u32),
+            _ => None,
+        };
+        variant.cages.push(Cage { cells, sum });
+    }
+    for (_, line) in parse_lines(&json, "thermometer") {
+        variant.thermos.push(line?);
+    }
+    for (arrow, line) in parse_lines(&json, "arrow") {
+        let circle = parse_cells(arrow.get("cells")?)?;
+        let line = line?.into_iter().filter(|cell| !circle.contains(cell)).collect();
+        variant.arrows.push(Arrow { circle, line });
+    }
+    Some((board, variant))
+}
+
+fn cells_json(cells: &[(usize, usize)]) -> Json {
+    Json::Array(cells.iter().map(|&(row, col)| Json::from(format!("R{}C{}", row + 1, col + 1))).collect())
+}
+
+/// Returns `board` and `variant` in the f-puzzles format, with the digits of
+/// the board as givens.
+pub fn fpuzzles_json(board: &Board, variant: &Variant) -> Json {
+    let grid = board
+        .iter()
+        .map(|row| {
+            let cells = row
+                .iter()
+                .map(|cell| match cell.solution {
+                    Some(digit) => Json::object(vec![("value", Json::from(digit)), ("given", Json::from(true))]),
+                    None => Json::object(Vec::<(&str, Json)>::new()),
+                })
+                .collect();
+            Json::Array(cells)
+        })
+        .collect();
+    let mut members = vec![("size", Json::from(9usize)), ("grid", Json::Array(grid))];
+    if variant.main_diagonal {
+        members.push(("diagonal-", Json::from(true)));
+    }
+    if variant.anti_diagonal {
+        members.push(("diagonal+", Json::from(true)));
+    }
+    if !variant.cages.is_empty() {
+        let cages = variant.cages.iter().map(|cage| {
+            let sum = cage.sum.map(|sum| sum.to_string()).unwrap_or_default();
+            Json::object(vec![("cells", cells_json(&cage.cells)), ("value", Json::from(sum))])
+        });
+        members.push(("killercage", Json::Array(cages.collect())));
+    }
+    if !variant.thermos.is_empty() {
+        let thermos = variant
+            .thermos
+            .iter()
+            .map(|thermo| Json::object(vec![("lines", Json::Array(vec![cells_json(thermo)]))]));
+        members.push(("thermometer", Json::Array(thermos.collect())));
+    }
+    if !variant.arrows.is_empty() {
+        let arrows = variant.arrows.iter().map(|arrow| {
+            // Lines start from the circle.
+            let mut line = arrow.circle[arrow.circle.len().saturating_sub(1)..].to_vec();
+            line.extend_from_slice(&arrow.line);
+            Json::object(vec![
+                ("lines", Json::Array(vec![cells_json(&line)])),
+                ("cells", cells_json(&arrow.circle)),
+            ])
+        });
+        members.push(("arrow", Json::Array(arrows.collect())));
+    }
+    Json::object(members)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::board_to_string;
+    use crate::logic::{solve_variant, TechniqueConfig};
+
+    #[test]
+    fn test_parse_fpuzzles() {
+        let mut grid = vec![vec![String::from("{}"); 9]; 9];
+        grid[0][0] = String::from(r#"{"value":1,"given":true}"#);
+        grid[0][1] = String::from(r#"{"value":7}"#);
+        let grid: Vec<String> = grid.iter().map(|row| format!("[{}]", row.join(","))).collect();
+        let json = format!(
+            r#"{{"size":9,"grid":[{}],"diagonal-":true,"diagonal+":true,
+               "killercage":[{{"cells":["R9C8","R9C9"],"value":"17"}}],
+               "thermometer":[{{"lines":[["R2C1","R2C2","R2C3"]]}}],
+               "arrow":[{{"lines":[["R5C5","R6C6","R7C7"]],"cells":["R5C5"]}}]}}"#,
+            grid.join(",")
+        );
+        let (board, variant) = parse_fpuzzles(&json).unwrap();
+        assert_eq!(board_to_string(&board), format!("1{}", ".".repeat(80)));
+        assert!(variant.main_diagonal && variant.anti_diagonal);
+        assert_eq!(variant.cages, vec![Cage { cells: vec![(8, 7), (8, 8)], sum: Some(17) }]);
+        assert_eq!(variant.thermos, vec![vec![(1, 0), (1, 1), (1, 2)]]);
+        assert_eq!(variant.arrows, vec![Arrow { circle: vec![(4, 4)], line: vec![(5, 5), (6, 6)] }]);
+        assert_eq!(parse_fpuzzles(&fpuzzles_json(&board, &variant).to_string()), Some((board, variant.clone())));
+
+        let trace = solve_variant(&board, &TechniqueConfig::default(), &variant);
+        let solution = trace.solution.unwrap();
+        let grid = crate::grid::Grid::from_board(&solution);
+        assert!(grid.is_complete() && variant.allows(&grid));
+    }
+}
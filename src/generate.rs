@@ -0,0 +1,312 @@
+use std::error::Error;
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::board::{Board, Cell};
+use crate::count::count_solutions;
+use crate::grid::{mask_digits, Grid};
+use crate::logic::{Technique, TechniqueConfig};
+use crate::rating::{rate, Rating};
+
+/// A small xorshift random number generator. It's not suitable for anything
+/// but shuffling, but it's seedable, so the same seed always generates the
+/// same puzzle on every platform.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        // Multiplying by an odd number keeps distinct seeds distinct. The
+        // state must never be zero though.
+        let state = (seed ^ 0x9e37_79b9_7f4a_7c15).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        Rng { state: if state == 0 { 0x9e37_79b9_7f4a_7c15 } else { state } }
+    }
+
+    /// Seeds the generator from the current time.
+    pub fn from_time() -> Rng {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or_default();
+        Rng::new(nanos)
+    }
+
+    /// Returns the state of the generator, to carry on from later with
+    /// `from_state`.
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    /// Returns the generator in `state`, or None if it's zero, which no
+    /// generator is ever in.
+    pub fn from_state(state: u64) -> Option<Rng> {
+        if state == 0 {
+            None
+        } else {
+            Some(Rng { state })
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    /// Returns a number in `0..n`.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+/// A coarse difficulty level of a puzzle, based on the hardest technique
+/// needed to solve it. `Rating::score` places puzzles on a continuous scale
+/// instead, which the levels divide up at `min_score`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub enum Difficulty {
+    /// Singles are enough.
+    Easy,
+    /// Needs locked candidates.
+    Medium,
+    /// Needs pairs, X-Wings, or chains.
+    Hard,
+    /// Can't be solved without guessing or forcing chains.
+    Expert,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] =
+        [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard, Difficulty::Expert];
+
+    /// Returns the difficulty of a puzzle with `rating`.
+    pub fn of(rating: &Rating) -> Difficulty {
+        if rating.guesses.needed > 0 {
+            return Difficulty::Expert;
+        }
+        rating.hardest.map_or(Difficulty::Easy, Difficulty::of_technique)
+    }
+
+    /// Returns the difficulty of the puzzles that need `technique` at most.
+    pub fn of_technique(technique: Technique) -> Difficulty {
+        match technique {
+            Technique::NakedSingle | Technique::HiddenSingle => Difficulty::Easy,
+            Technique::LockedCandidates => Difficulty::Medium,
+            Technique::ForcingChain | Technique::Guess => Difficulty::Expert,
+            _ => Difficulty::Hard,
+        }
+    }
+
+    /// Returns the lowest score of the puzzles of this difficulty, with the
+    /// default weights of `ScoreTable`: 0 for easy, 2.5 for medium, as
+    /// locked candidates weigh 2.6, 2.8 for hard, as pairs start at 3.0, and
+    /// 8.0 for expert, as forcing chains weigh 8.3.
+    pub fn min_score(self) -> f64 {
+        match self {
+            Difficulty::Easy => 0.0,
+            Difficulty::Medium => 2.5,
+            Difficulty::Hard => 2.8,
+            Difficulty::Expert => 8.0,
+        }
+    }
+
+    /// Returns the difficulty of puzzles with `score`, the highest whose
+    /// `min_score` it reaches. For scores of the default `ScoreTable` it's
+    /// the same as `Difficulty::of` the rating.
+    pub fn from_score(score: f64) -> Difficulty {
+        let reached = Difficulty::ALL.iter().rev().copied().find(|difficulty| score >= difficulty.min_score());
+        reached.unwrap_or(Difficulty::Easy)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+            Difficulty::Expert => "expert",
+        }
+    }
+
+    /// Returns the difficulty with the given name, if any.
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        Difficulty::ALL.iter().cloned().find(|difficulty| difficulty.name() == name)
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The most puzzles `generate_with` tries before giving up, a few seconds'
+/// worth. Expert puzzles are the rarest, and with all techniques enabled one
+/// turns up within a few dozen tries.
+pub const MAX_ATTEMPTS: usize = 1000;
+
+/// The error of generating a puzzle of a difficulty.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum GenerateError {
+    /// None of the enabled techniques is of the difficulty, so no puzzle
+    /// rated with them ever is.
+    Unreachable(Difficulty),
+    /// No puzzle of the difficulty turned up in `MAX_ATTEMPTS` tries.
+    GaveUp(Difficulty),
+}
+
+impl fmt::Display for GenerateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GenerateError::Unreachable(difficulty) => {
+                write!(f, "no {} puzzle can be rated with the enabled techniques", difficulty)
+            }
+            GenerateError::GaveUp(difficulty) => {
+                write!(f, "found no {} puzzle in {} tries", difficulty, MAX_ATTEMPTS)
+            }
+        }
+    }
+}
+
+impl Error for GenerateError {}
+
+/// Generates a random, uniquely solvable puzzle of `difficulty`, rated with
+/// all techniques enabled.
+pub fn generate(difficulty: Difficulty, rng: &mut Rng) -> Board {
+    generate_with(difficulty, &TechniqueConfig::default(), rng).expect("every difficulty is reachable")
+}
+
+/// Like `generate`, but rates the puzzles using only the techniques enabled in
+/// `config`, so e.g. an easy puzzle is guaranteed to be solvable with them.
+/// Fails if none of the techniques is of `difficulty`, or if no puzzle of it
+/// turns up in `MAX_ATTEMPTS` tries.
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(config, rng)))]
+pub fn generate_with(difficulty: Difficulty, config: &TechniqueConfig, rng: &mut Rng) -> Result<Board, GenerateError> {
+    check_reachable(difficulty, config)?;
+    for _ in 0..MAX_ATTEMPTS {
+        if let Some(puzzle) = attempt(difficulty, config, rng) {
+            return Ok(puzzle);
+        }
+    }
+    Err(GenerateError::GaveUp(difficulty))
+}
+
+/// Like `generate_with`, but gives up and returns None once `cancelled`
+/// returns true, which is checked between the puzzles tried.
+#[cfg(feature = "async")]
+pub(crate) fn generate_until(
+    difficulty: Difficulty,
+    config: &TechniqueConfig,
+    rng: &mut Rng,
+    cancelled: &dyn Fn() -> bool,
+) -> Option<Result<Board, GenerateError>> {
+    if let Err(err) = check_reachable(difficulty, config) {
+        return Some(Err(err));
+    }
+    for _ in 0..MAX_ATTEMPTS {
+        if cancelled() {
+            return None;
+        }
+        if let Some(puzzle) = attempt(difficulty, config, rng) {
+            return Some(Ok(puzzle));
+        }
+    }
+    Some(Err(GenerateError::GaveUp(difficulty)))
+}
+
+/// Fails unless one of the techniques enabled in `config` is of `difficulty`,
+/// as the hardest technique a puzzle needs decides its difficulty.
+fn check_reachable(difficulty: Difficulty, config: &TechniqueConfig) -> Result<(), GenerateError> {
+    if config.techniques().iter().any(|&technique| Difficulty::of_technique(technique) == difficulty) {
+        Ok(())
+    } else {
+        Err(GenerateError::Unreachable(difficulty))
+    }
+}
+
+/// Generates a minimal puzzle, and returns it if it's of `difficulty`.
+fn attempt(difficulty: Difficulty, config: &TechniqueConfig, rng: &mut Rng) -> Option<Board> {
+    let puzzle = generate_minimal(rng);
+    let rating = rate(&puzzle, config)?;
+    if Difficulty::of(&rating) == difficulty {
+        return Some(puzzle);
+    }
+    event!(DEBUG, rated = %Difficulty::of(&rating), "discarded puzzle of another difficulty");
+    None
+}
+
+/// Generates a random puzzle from which no clue can be removed without losing
+/// its unique solution.
+pub(crate) fn generate_minimal(rng: &mut Rng) -> Board {
+    let mut board = random_solution(rng);
+    let mut cells: Vec<(usize, usize)> = (0..81).map(|i| (i / 9, i % 9)).collect();
+    rng.shuffle(&mut cells);
+    for (row, col) in cells {
+        let clue = std::mem::replace(&mut board[row][col], Cell::unsolved());
+        if count_solutions(&board, 2) != 1 {
+            board[row][col] = clue;
+        }
+    }
+    board
+}
+
+/// Fills an empty board with a random valid solution.
+fn random_solution(rng: &mut Rng) -> Board {
+    fn fill(grid: &mut Grid, index: usize, rng: &mut Rng) -> bool {
+        if index == 81 {
+            return true;
+        }
+        let (row, col) = (index / 9, index % 9);
+        let mut digits: Vec<i8> = mask_digits(grid.candidates(row, col)).collect();
+        rng.shuffle(&mut digits);
+        for digit in digits {
+            let saved = *grid;
+            grid.place(row, col, digit);
+            if fill(grid, index + 1, rng) {
+                return true;
+            }
+            *grid = saved;
+        }
+        false
+    }
+
+    let mut grid = Grid::from_board(&Default::default());
+    assert!(fill(&mut grid, 0, rng));
+    grid.to_board()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate() {
+        let mut rng = Rng::new(42);
+        let puzzle = generate(Difficulty::Easy, &mut rng);
+        assert_eq!(count_solutions(&puzzle, 2), 1);
+        let rating = rate(&puzzle, &TechniqueConfig::default()).unwrap();
+        assert_eq!(Difficulty::of(&rating), Difficulty::Easy);
+
+        // The same seed must always give the same puzzle.
+        assert_eq!(generate(Difficulty::Easy, &mut Rng::new(42)), puzzle);
+
+        // Without locked candidates no puzzle is medium, and without guesses
+        // and forcing chains none is expert.
+        let mut config = TechniqueConfig::default();
+        config.disable(Technique::LockedCandidates);
+        config.disable(Technique::ForcingChain);
+        let result = generate_with(Difficulty::Medium, &config, &mut Rng::new(1));
+        assert_eq!(result, Err(GenerateError::Unreachable(Difficulty::Medium)));
+        assert!(generate_with(Difficulty::Expert, &config, &mut Rng::new(1)).is_ok());
+        config.disable(Technique::Guess);
+        let result = generate_with(Difficulty::Expert, &config, &mut Rng::new(1));
+        assert_eq!(result, Err(GenerateError::Unreachable(Difficulty::Expert)));
+    }
+}
@@ -0,0 +1,183 @@
+//! Showing boards as images in terminals that support the kitty graphics
+//! protocol or sixels.
+
+use std::env;
+
+use crate::board::Board;
+use crate::raster::{draw_board, Bitmap};
+
+/// A protocol for showing images in a terminal.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Graphics {
+    /// The kitty graphics protocol, which takes PNGs as they are.
+    Kitty,
+    /// DEC sixels, which are supported by more terminals but need the image
+    /// reduced to a palette.
+    Sixel,
+}
+
+impl Graphics {
+    /// Guesses the protocol the terminal supports from the environment. It
+    /// can be overridden by setting `SUDOKU_GRAPHICS` to `kitty`, `sixel`, or
+    /// `none`.
+    pub fn detect() -> Option<Graphics> {
+        detect_from(|name| env::var(name).ok())
+    }
+
+    /// Encodes `bitmap` as an escape sequence that makes the terminal show it.
+    pub(crate) fn encode(self, bitmap: &Bitmap) -> String {
+        match self {
+            Graphics::Kitty => encode_kitty(&bitmap.to_png()),
+            Graphics::Sixel => encode_sixel(bitmap),
+        }
+    }
+}
+
+fn detect_from<F: Fn(&str) -> Option<String>>(var: F) -> Option<Graphics> {
+    match var("SUDOKU_GRAPHICS").as_deref() {
+        Some("kitty") => return Some(Graphics::Kitty),
+        Some("sixel") => return Some(Graphics::Sixel),
+        Some("none") => return None,
+        _ => {}
+    }
+    let term = var("TERM").unwrap_or_default();
+    let program = var("TERM_PROGRAM").unwrap_or_default();
+    if var("KITTY_WINDOW_ID").is_some()
+        || term == "xterm-kitty"
+        || term == "xterm-ghostty"
+        || program == "WezTerm"
+        || program == "ghostty"
+    {
+        Some(Graphics::Kitty)
+    } else if ["foot", "mlterm", "yaft", "sixel"].iter().any(|name| term.contains(name)) {
+        Some(Graphics::Sixel)
+    } else {
+        None
+    }
+}
+
+/// Returns the escape sequence that shows `puzzle` as an image, followed by a
+/// newline. If `solution` is given, the digits it fills in are drawn in gray.
+pub fn board_graphics(puzzle: &Board, solution: Option<&Board>, graphics: Graphics) -> String {
+    let mut s = graphics.encode(&draw_board(puzzle, solution));
+    s.push('\n');
+    s
+}
+
+fn encode_base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut s = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                s.push(ALPHABET[(n >> (18 - 6 * i)) as usize & 63] as char);
+            } else {
+                s.push('=');
+            }
+        }
+    }
+    s
+}
+
+fn encode_kitty(png: &[u8]) -> String {
+    // The payload has to be sent in chunks of at most 4096 bytes.
+    let payload = encode_base64(png);
+    let chunks: Vec<&[u8]> = payload.as_bytes().chunks(4096).collect();
+    let mut s = String::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = if i + 1 < chunks.len() { 1 } else { 0 };
+        let control = if i == 0 { format!("a=T,f=100,m={}", more) } else { format!("m={}", more) };
+        s.push_str(&format!("\x1b_G{};{}\x1b\\", control, String::from_utf8_lossy(chunk)));
+    }
+    s
+}
+
+/// Reduces the image to 16 shades of gray. Sixels are drawn in bands six
+/// pixels high, one pass per color, where each character sets the pixels of
+/// one column of the band.
+fn encode_sixel(bitmap: &Bitmap) -> String {
+    const LEVELS: usize = 16;
+    let level = |x: usize, y: usize| (bitmap.pixels[y * bitmap.width + x] as usize * (LEVELS - 1) + 127) / 255;
+
+    let mut s = format!("\x1bPq\"1;1;{};{}", bitmap.width, bitmap.height);
+    for i in 0..LEVELS {
+        let percent = i * 100 / (LEVELS - 1);
+        s.push_str(&format!("#{};2;{};{};{}", i, percent, percent, percent));
+    }
+    for band in (0..bitmap.height).step_by(6) {
+        let rows = band..(band + 6).min(bitmap.height);
+        let mut used = [false; LEVELS];
+        for y in rows.clone() {
+            for x in 0..bitmap.width {
+                used[level(x, y)] = true;
+            }
+        }
+        for color in (0..LEVELS).filter(|&color| used[color]) {
+            s.push_str(&format!("#{}", color));
+            let mut run: Option<(char, usize)> = None;
+            for x in 0..bitmap.width {
+                let bits = rows.clone().fold(0, |bits, y| {
+                    if level(x, y) == color {
+                        bits | 1 << (y - band)
+                    } else {
+                        bits
+                    }
+                });
+                let c = (63 + bits) as u8 as char;
+                run = match run {
+                    Some((last, n)) if last == c => Some((last, n + 1)),
+                    Some((last, n)) => {
+                        push_run(&mut s, last, n);
+                        Some((c, 1))
+                    }
+                    None => Some((c, 1)),
+                };
+            }
+            if let Some((last, n)) = run {
+                push_run(&mut s, last, n);
+            }
+            // Return to the start of the band for the next color.
+            s.push('$');
+        }
+        s.push('-');
+    }
+    s.push_str("\x1b\\");
+    s
+}
+
+fn push_run(s: &mut String, c: char, n: usize) {
+    if n > 3 {
+        s.push_str(&format!("!{}{}", n, c));
+    } else {
+        s.extend(std::iter::repeat_n(c, n));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::default_board;
+
+    #[test]
+    fn test_detect() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| vars.iter().find(|(k, _)| *k == name).map(|(_, v)| v.to_string())
+        };
+        assert_eq!(detect_from(env(&[("TERM", "xterm-kitty")])), Some(Graphics::Kitty));
+        assert_eq!(detect_from(env(&[("TERM", "foot")])), Some(Graphics::Sixel));
+        assert_eq!(detect_from(env(&[("TERM", "xterm-256color")])), None);
+        assert_eq!(detect_from(env(&[("TERM", "foot"), ("SUDOKU_GRAPHICS", "none")])), None);
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode_base64(b"Man"), "TWFu");
+        assert_eq!(encode_base64(b"Ma"), "TWE=");
+        let bitmap = Bitmap { width: 5, height: 1, pixels: vec![0, 0, 0, 0, 255] };
+        assert!(encode_sixel(&bitmap).ends_with("#0!4@?$#15!4?@$-\x1b\\"));
+
+        let s = board_graphics(&default_board(), None, Graphics::Kitty);
+        assert!(s.starts_with("\x1b_Ga=T,f=100,m=1;iVBORw0KGgo"));
+    }
+}
@@ -0,0 +1,336 @@
+use std::fmt;
+
+use crate::board::{Board, Cell};
+use crate::json::Json;
+use crate::locale::Catalog;
+
+/// A set of digits stored as a bitmask, where bit `d` is set if digit `d` is
+/// in the set.
+pub type Mask = u16;
+
+/// Mask with all digits 1-9 set.
+pub const ALL_DIGITS: Mask = 0b11_1111_1110;
+
+/// Returns the mask with only `digit` set.
+pub fn digit_mask(digit: i8) -> Mask {
+    assert!((1..10).contains(&digit));
+    1 << digit
+}
+
+/// Returns the digits in `mask` in ascending order.
+pub fn mask_digits(mask: Mask) -> impl Iterator<Item = i8> {
+    (1..10).filter(move |d| mask & (1 << d) != 0)
+}
+
+/// A row, column, or 3x3 block of a board. The index is zero-based but rows,
+/// columns, and boxes are displayed one-based, as is customary.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum House {
+    Row(usize),
+    Col(usize),
+    Box(usize),
+}
+
+impl House {
+    /// Returns all 27 houses of a board, rows first.
+    pub fn all() -> impl Iterator<Item = House> {
+        (0..9)
+            .map(House::Row)
+            .chain((0..9).map(House::Col))
+            .chain((0..9).map(House::Box))
+    }
+
+    /// Returns the row, column, and box that contain the cell.
+    pub fn containing(row: usize, col: usize) -> [House; 3] {
+        [House::Row(row), House::Col(col), House::Box(row / 3 * 3 + col / 3)]
+    }
+
+    /// Returns the (row, column) coordinates of the cells in this house.
+    pub fn cells(self) -> [(usize, usize); 9] {
+        let mut cells = [(0, 0); 9];
+        for (i, cell) in cells.iter_mut().enumerate() {
+            *cell = match self {
+                House::Row(row) => (row, i),
+                House::Col(col) => (i, col),
+                House::Box(b) => (b / 3 * 3 + i / 3, b % 3 * 3 + i % 3),
+            };
+        }
+        cells
+    }
+}
+
+impl fmt::Display for House {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&Catalog::default().house(*self))
+    }
+}
+
+/// Returns the cell's name in the usual `r1c1` notation.
+pub fn cell_name(row: usize, col: usize) -> String {
+    format!("r{}c{}", row + 1, col + 1)
+}
+
+/// A compact snapshot of a board with the candidates of every empty cell kept
+/// as a bitmask, i.e. the pencil marks a human would have. Unlike `Sudoku`,
+/// this is cheap to copy and is what the analysis code works on.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Grid {
+    /// The placed digit of each cell or 0 if it's empty.
+    values: [[i8; 9]; 9],
+    /// The remaining candidates of each empty cell. Always 0 for placed cells.
+    candidates: [[Mask; 9]; 9],
+}
+
+impl Grid {
+    /// Creates a grid from `board`, assigning every empty cell all digits not
+    /// yet placed among its peers. The board is not validated, so conflicting
+    /// givens are kept as they are.
+    pub fn from_board(board: &Board) -> Grid {
+        let mut grid = Grid {
+            values: [[0; 9]; 9],
+            candidates: [[ALL_DIGITS; 9]; 9],
+        };
+        for row in 0..9 {
+            for col in 0..9 {
+                if let Some(digit) = board[row][col].solution {
+                    grid.place(row, col, digit);
+                }
+            }
+        }
+        grid
+    }
+
+    /// Returns the digit placed in the cell, if any.
+    pub fn value(&self, row: usize, col: usize) -> Option<i8> {
+        match self.values[row][col] {
+            0 => None,
+            digit => Some(digit),
+        }
+    }
+
+    /// Returns the candidates of the cell as a mask where bit `d` is set if
+    /// digit `d` is a candidate. Placed cells have none.
+    pub fn candidates(&self, row: usize, col: usize) -> Mask {
+        self.candidates[row][col]
+    }
+
+    /// Returns whether `digit` is a candidate of the cell.
+    pub fn has_candidate(&self, row: usize, col: usize, digit: i8) -> bool {
+        self.candidates[row][col] & digit_mask(digit) != 0
+    }
+
+    /// Returns the candidates of the cell in ascending order.
+    pub fn candidate_digits(&self, row: usize, col: usize) -> Vec<i8> {
+        mask_digits(self.candidates[row][col]).collect()
+    }
+
+    /// Places `digit` in the cell and removes it from the candidates of all of
+    /// the cell's peers.
+    pub fn place(&mut self, row: usize, col: usize, digit: i8) {
+        self.values[row][col] = digit;
+        self.candidates[row][col] = 0;
+        for (r, c) in peers(row, col) {
+            self.candidates[r][c] &= !digit_mask(digit);
+        }
+        invariant!(self.check_invariants());
+    }
+
+    /// Removes `digit` from the candidates of the cell. Returns whether the
+    /// candidate was present.
+    pub fn eliminate(&mut self, row: usize, col: usize, digit: i8) -> bool {
+        let present = self.candidates[row][col] & digit_mask(digit) != 0;
+        self.candidates[row][col] &= !digit_mask(digit);
+        invariant!(self.check_invariants());
+        present
+    }
+
+    /// Panics unless placed cells have no candidates and no empty cell has a
+    /// digit placed in one of its peers as a candidate.
+    #[cfg(feature = "debug-invariants")]
+    pub(crate) fn check_invariants(&self) {
+        for row in 0..9 {
+            for col in 0..9 {
+                let candidates = self.candidates[row][col];
+                if self.values[row][col] != 0 {
+                    assert_eq!(candidates, 0, "{} is placed but has candidates", cell_name(row, col));
+                    continue;
+                }
+                for (r, c) in peers(row, col) {
+                    let value = self.values[r][c];
+                    assert!(
+                        value == 0 || candidates & digit_mask(value) == 0,
+                        "{} has {} as a candidate, which {} holds",
+                        cell_name(row, col),
+                        value,
+                        cell_name(r, c)
+                    );
+                }
+            }
+        }
+    }
+
+    /// Returns whether `digit` is placed anywhere in `house`.
+    pub fn is_placed_in(&self, house: House, digit: i8) -> bool {
+        house.cells().iter().any(|&(r, c)| self.values[r][c] == digit)
+    }
+
+    /// Returns the cells of `house` that still have `digit` as a candidate.
+    pub fn places_for(&self, house: House, digit: i8) -> Vec<(usize, usize)> {
+        house
+            .cells()
+            .iter()
+            .cloned()
+            .filter(|&(r, c)| self.candidates[r][c] & digit_mask(digit) != 0)
+            .collect()
+    }
+
+    /// Returns whether every cell has a digit placed.
+    pub fn is_complete(&self) -> bool {
+        self.values.iter().all(|row| row.iter().all(|&v| v != 0))
+    }
+
+    /// Returns the grid as a JSON object with the placed digits as an 81
+    /// character string and the candidates of each cell, row by row, as
+    /// strings of digits.
+    pub fn to_json(&self) -> Json {
+        let mut values = String::with_capacity(81);
+        let mut candidates = Vec::with_capacity(81);
+        for row in 0..9 {
+            for col in 0..9 {
+                match self.value(row, col) {
+                    Some(digit) => values.push((b'0' + digit as u8) as char),
+                    None => values.push('.'),
+                }
+                let digits: String = mask_digits(self.candidates[row][col])
+                    .map(|digit| (b'0' + digit as u8) as char)
+                    .collect();
+                candidates.push(Json::from(digits));
+            }
+        }
+        Json::object(vec![("values", Json::from(values)), ("candidates", Json::Array(candidates))])
+    }
+
+    /// Returns the grid as a line of 729 characters that other solvers and
+    /// their benchmarks exchange pencil marks in: nine for each cell, row by
+    /// row, where the `d`th is `d` if it's a candidate and `.` if not. Placed
+    /// cells have only their digit.
+    pub fn to_pencilmarks(&self) -> String {
+        let mut s = String::with_capacity(729);
+        for row in 0..9 {
+            for col in 0..9 {
+                let mask = match self.value(row, col) {
+                    Some(digit) => digit_mask(digit),
+                    None => self.candidates[row][col],
+                };
+                for digit in 1..10 {
+                    s.push(if mask & digit_mask(digit) != 0 { (b'0' + digit as u8) as char } else { '.' });
+                }
+            }
+        }
+        s
+    }
+
+    /// Parses a grid written by `to_pencilmarks`, which may also have `0`
+    /// for missing candidates. The format doesn't tell placed cells from
+    /// ones with a single candidate left, so those whose candidate none of
+    /// their peers have are taken as placed. Returns None unless there are
+    /// 729 characters, each its digit or missing.
+    pub fn from_pencilmarks(s: &str) -> Option<Grid> {
+        let chars: Vec<char> = s.trim().chars().collect();
+        if chars.len() != 729 {
+            return None;
+        }
+        let mut grid = Grid { values: [[0; 9]; 9], candidates: [[0; 9]; 9] };
+        for (i, &c) in chars.iter().enumerate() {
+            let digit = (i % 9 + 1) as i8;
+            match c {
+                '.' | '0' => {}
+                _ if c == (b'0' + digit as u8) as char => grid.candidates[i / 81][i / 9 % 9] |= digit_mask(digit),
+                _ => return None,
+            }
+        }
+        let candidates = grid.candidates;
+        for row in 0..9 {
+            for col in 0..9 {
+                let mask = candidates[row][col];
+                if mask.count_ones() == 1 && peers(row, col).iter().all(|&(r, c)| candidates[r][c] & mask == 0) {
+                    grid.values[row][col] = mask.trailing_zeros() as i8;
+                    grid.candidates[row][col] = 0;
+                }
+            }
+        }
+        invariant!(grid.check_invariants());
+        Some(grid)
+    }
+
+    /// Converts the grid to a board with only the placed digits solved.
+    pub fn to_board(self) -> Board {
+        let mut board: Board = Default::default();
+        for row in 0..9 {
+            for col in 0..9 {
+                if let Some(digit) = self.value(row, col) {
+                    board[row][col] = Cell::solved(digit);
+                }
+            }
+        }
+        board
+    }
+}
+
+/// Returns the 20 cells that share a row, column, or box with the cell.
+pub fn peers(row: usize, col: usize) -> Vec<(usize, usize)> {
+    let mut peers = Vec::with_capacity(20);
+    for i in 0..9 {
+        if i != col {
+            peers.push((row, i));
+        }
+        if i != row {
+            peers.push((i, col));
+        }
+    }
+    let block_row_start = (row / 3) * 3;
+    let block_col_start = (col / 3) * 3;
+    for r in block_row_start..block_row_start + 3 {
+        for c in block_col_start..block_col_start + 3 {
+            if r != row && c != col {
+                peers.push((r, c));
+            }
+        }
+    }
+    peers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::default_board;
+
+    #[test]
+    fn test_grid_candidates() {
+        let grid = Grid::from_board(&default_board());
+        assert_eq!(grid.value(0, 2), Some(5));
+        assert_eq!(grid.candidates(0, 2), 0);
+        // r3c3 sees 7, 9, 2, 5, 3, 4, 6 in its row and box and 4, 9, 7, 6 in
+        // its column.
+        assert_eq!(mask_digits(grid.candidates(2, 2)).collect::<Vec<_>>(), vec![1, 8]);
+        assert_eq!(peers(4, 4).len(), 20);
+    }
+
+    #[test]
+    fn test_pencilmarks() {
+        let grid = Grid::from_board(&default_board());
+        let line = grid.to_pencilmarks();
+        assert_eq!(line.len(), 729);
+        assert!(line.starts_with("1.34.6.....3..6.......5.....2......912...67.9.......8."));
+        assert_eq!(Grid::from_pencilmarks(&line), Some(grid));
+        assert_eq!(Grid::from_pencilmarks(&line.replace('.', "0")), Some(grid));
+        // A single candidate that peers still have isn't placed yet.
+        let mut single = grid;
+        for digit in [3, 4, 6] {
+            single.eliminate(0, 0, digit);
+        }
+        assert_eq!(Grid::from_pencilmarks(&single.to_pencilmarks()), Some(single));
+        assert_eq!(Grid::from_pencilmarks(&line[..728]), None);
+        assert_eq!(Grid::from_pencilmarks(&format!("2{}", &line[1..])), None);
+    }
+}
@@ -0,0 +1,257 @@
+use std::error::Error;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::board::Board;
+use crate::grid::Grid;
+use crate::locale::Catalog;
+use crate::logic::{find_step, solve_with, Step, Technique, TechniqueConfig};
+use crate::refute::find_contradiction;
+use crate::validate::validate;
+
+/// How much a hint gives away, from least to most.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+pub enum HintLevel {
+    /// Where to look, e.g. "Look at box 7".
+    Area,
+    /// What to look for, e.g. "Look for a hidden single in box 7".
+    Technique,
+    /// The answer, e.g. "r9c1 is 2".
+    Answer,
+}
+
+impl HintLevel {
+    pub const ALL: [HintLevel; 3] = [HintLevel::Area, HintLevel::Technique, HintLevel::Answer];
+}
+
+/// A hint for the next step of a puzzle that can be revealed gradually.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Hint {
+    /// The step the hint is about. If no enabled technique applies, it's a
+    /// `Guess` step revealing a digit of the solution.
+    pub step: Step,
+}
+
+impl Hint {
+    /// Returns the text of the hint at `level`, in English. `Catalog::hint`
+    /// returns it in other languages.
+    pub fn text(&self, level: HintLevel) -> String {
+        Catalog::default().hint(self, level)
+    }
+}
+
+/// Finds a hint for the next step of `board` using the techniques enabled in
+/// `config`. The candidates are worked out from the digits on the board, so
+/// the hint doesn't depend on the player's pencil marks. Returns None if the
+/// board is complete, its givens conflict, or it has no solution.
+pub fn hint(board: &Board, config: &TechniqueConfig) -> Option<Hint> {
+    validate(board).ok()?;
+    let grid = Grid::from_board(board);
+    if grid.is_complete() || find_contradiction(&grid).is_some() {
+        return None;
+    }
+    if let Some(step) = find_step(&grid, config) {
+        return Some(Hint { step });
+    }
+
+    // Nothing applies, so reveal the digit of the cell with the fewest
+    // candidates, which is where a human would guess.
+    let solution = solve_with(board, &TechniqueConfig::default()).solution?;
+    let mut cell = None;
+    let mut fewest = 10;
+    for row in 0..9 {
+        for col in 0..9 {
+            let num_candidates = grid.candidates(row, col).count_ones();
+            if grid.value(row, col).is_none() && num_candidates < fewest {
+                fewest = num_candidates;
+                cell = Some((row, col));
+            }
+        }
+    }
+    let (row, col) = cell?;
+    let digit = solution[row][col].solution?;
+    Some(Hint {
+        step: Step::placement(Technique::Guess, Vec::new(), row, col, digit),
+    })
+}
+
+/// How hints count against a player, for apps that ration them. Both
+/// methods cost nothing by default, so a policy only implements the rules
+/// it needs.
+pub trait HintPolicy {
+    /// The points taken off the player's score for `hint`, revealed at
+    /// `level`, when `taken` hints were given before it.
+    fn penalty(&self, _hint: &Hint, _level: HintLevel, _taken: usize) -> f64 {
+        0.0
+    }
+
+    /// How long the player has to wait after `hint` before the next one.
+    fn cool_down(&self, _hint: &Hint, _level: HintLevel, _taken: usize) -> Duration {
+        Duration::from_secs(0)
+    }
+}
+
+/// A fixed penalty for each hint level and a fixed wait between hints.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StandardHintPolicy {
+    /// The penalties of the levels, in the order of `HintLevel::ALL`.
+    pub penalties: [f64; 3],
+    pub cool_down: Duration,
+}
+
+impl Default for StandardHintPolicy {
+    fn default() -> StandardHintPolicy {
+        StandardHintPolicy { penalties: [1.0, 3.0, 10.0], cool_down: Duration::from_secs(30) }
+    }
+}
+
+impl HintPolicy for StandardHintPolicy {
+    fn penalty(&self, _hint: &Hint, level: HintLevel, _taken: usize) -> f64 {
+        self.penalties[level as usize]
+    }
+
+    fn cool_down(&self, _hint: &Hint, _level: HintLevel, _taken: usize) -> Duration {
+        self.cool_down
+    }
+}
+
+/// Why a hint was refused.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum HintRefusal {
+    /// The player has to wait this much longer.
+    CoolingDown(Duration),
+    /// The board is complete, its givens conflict, or it has no solution.
+    NoHint,
+}
+
+impl fmt::Display for HintRefusal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HintRefusal::CoolingDown(wait) => {
+                write!(f, "the next hint is available in {}s", wait.as_millis().div_ceil(1000))
+            }
+            HintRefusal::NoHint => write!(f, "there's no next step"),
+        }
+    }
+}
+
+impl Error for HintRefusal {}
+
+/// The hints of one game, given out by `hint` under a policy that keeps the
+/// player's penalty and when the next hint is allowed. Times are passed in
+/// rather than read from the clock, so games can be paused and replayed.
+#[derive(Debug, Clone)]
+pub struct HintSession<P: HintPolicy> {
+    policy: P,
+    config: TechniqueConfig,
+    taken: usize,
+    penalty: f64,
+    next_at: Option<Instant>,
+}
+
+impl<P: HintPolicy> HintSession<P> {
+    pub fn new(policy: P, config: TechniqueConfig) -> HintSession<P> {
+        HintSession { policy, config, taken: 0, penalty: 0.0, next_at: None }
+    }
+
+    /// Gives a hint for `board` to reveal at `level` at time `now`, unless
+    /// the last one is still cooling down. Refused hints cost nothing.
+    pub fn request(&mut self, board: &Board, level: HintLevel, now: Instant) -> Result<Hint, HintRefusal> {
+        let wait = self.wait(now);
+        if wait > Duration::from_secs(0) {
+            return Err(HintRefusal::CoolingDown(wait));
+        }
+        let hint = hint(board, &self.config).ok_or(HintRefusal::NoHint)?;
+        self.penalty += self.policy.penalty(&hint, level, self.taken);
+        self.next_at = Some(now + self.policy.cool_down(&hint, level, self.taken));
+        self.taken += 1;
+        Ok(hint)
+    }
+
+    /// How much longer from `now` until the next hint is allowed.
+    pub fn wait(&self, now: Instant) -> Duration {
+        self.next_at.map_or(Duration::from_secs(0), |next_at| next_at.saturating_duration_since(now))
+    }
+
+    /// The hints given so far.
+    pub fn taken(&self) -> usize {
+        self.taken
+    }
+
+    /// The sum of the penalties of the hints given so far.
+    pub fn penalty(&self) -> f64 {
+        self.penalty
+    }
+
+    pub fn policy(&self) -> &P {
+        &self.policy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{default_board, parse_board};
+
+    #[test]
+    fn test_hint_levels() {
+        let hint = hint(&default_board(), &TechniqueConfig::default()).unwrap();
+        assert_eq!(hint.text(HintLevel::Area), "Look at r2c4");
+        assert_eq!(hint.text(HintLevel::Technique), "Look for a naked single in r2c4");
+        assert_eq!(hint.text(HintLevel::Answer), "r2c4 is 9");
+
+        let config = TechniqueConfig::new(vec![Technique::HiddenSingle]);
+        let hint = super::hint(&default_board(), &config).unwrap();
+        assert!(hint.text(HintLevel::Technique).starts_with("Look for a hidden single in "));
+
+        // Two fives in a row have no next step, even if cells are left that
+        // look like they could be filled in.
+        let conflicting = parse_board(&format!("55{}", ".".repeat(79))).unwrap();
+        assert_eq!(super::hint(&conflicting, &TechniqueConfig::default()), None);
+    }
+
+    #[test]
+    fn test_hint_guess() {
+        let board = parse_board(
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..",
+        )
+        .unwrap();
+        let config = TechniqueConfig::new(vec![Technique::NakedSingle]);
+        let hint = hint(&board, &config).unwrap();
+        assert_eq!(hint.step.technique, Technique::Guess);
+        assert!(hint.text(HintLevel::Technique).starts_with("There's no logical step left"));
+    }
+
+    #[test]
+    fn test_hint_session() {
+        let mut session = HintSession::new(StandardHintPolicy::default(), TechniqueConfig::default());
+        let start = Instant::now();
+        assert!(session.request(&default_board(), HintLevel::Area, start).is_ok());
+        let later = start + Duration::from_secs(10);
+        assert_eq!(
+            session.request(&default_board(), HintLevel::Answer, later),
+            Err(HintRefusal::CoolingDown(Duration::from_secs(20)))
+        );
+        let hint = session.request(&default_board(), HintLevel::Answer, start + Duration::from_secs(30)).unwrap();
+        assert_eq!(hint.text(HintLevel::Answer), "r2c4 is 9");
+        assert_eq!((session.taken(), session.penalty()), (2, 11.0));
+
+        // A policy with its own rules, here hints that get dearer.
+        struct Doubling;
+        impl HintPolicy for Doubling {
+            fn penalty(&self, _hint: &Hint, _level: HintLevel, taken: usize) -> f64 {
+                (1 << taken) as f64
+            }
+        }
+        let mut session = HintSession::new(Doubling, TechniqueConfig::default());
+        for _ in 0..3 {
+            session.request(&default_board(), HintLevel::Area, start).unwrap();
+        }
+        assert_eq!(session.penalty(), 7.0);
+        let solved = solve_with(&default_board(), &TechniqueConfig::default()).solution.unwrap();
+        assert_eq!(session.request(&solved, HintLevel::Area, start), Err(HintRefusal::NoHint));
+        let conflicting = parse_board(&format!("55{}", ".".repeat(79))).unwrap();
+        assert_eq!(session.request(&conflicting, HintLevel::Area, start), Err(HintRefusal::NoHint));
+        assert_eq!(session.taken(), 3);
+    }
+}
@@ -0,0 +1,266 @@
+//! The library format of HoDoKu, in which collections of technique examples
+//! are kept, one per line:
+//!
+//! ```text
+//! :0003:2:..5+2.8....2.+9..5..79.34562....6.471..495+1783..178.2....54783.96..6....5....1..4..:::259:
+//! ```
+//!
+//! The fields are the technique's code, the digits of its pattern, the grid
+//! with `+` before digits that were placed rather than given, the
+//! candidates that were already eliminated, and the eliminations and
+//! placements the technique makes. Candidates are written as digit, row, and
+//! column, e.g. `259` for 2 in r5c9. A last field with anything else, such
+//! as the chain of a chain example, may follow.
+//!
+//! Reading examples lets the technique engine be checked against the
+//! libraries, and writing the steps of a trace adds examples to them.
+
+use crate::board::{Board, Cell};
+use crate::grid::{digit_mask, mask_digits, Grid, House};
+use crate::logic::{find_technique, Step, Technique};
+
+/// The codes of the techniques HoDoKu and this crate both have. The first
+/// code of a technique is the one it's written with.
+const CODES: [(&str, Technique); 13] = [
+    ("0003", Technique::NakedSingle),
+    ("0002", Technique::HiddenSingle),
+    // A full house is a hidden single in a house with one empty cell.
+    ("0000", Technique::HiddenSingle),
+    // Pointing, then claiming.
+    ("0100", Technique::LockedCandidates),
+    ("0101", Technique::LockedCandidates),
+    ("0200", Technique::NakedPair),
+    ("0210", Technique::HiddenPair),
+    ("0300", Technique::XWing),
+    ("0701", Technique::XChain),
+    ("0702", Technique::XYChain),
+    ("0610", Technique::BugPlusOne),
+    // Contradiction, then verity.
+    ("1101", Technique::ForcingChain),
+    ("1102", Technique::ForcingChain),
+];
+
+/// A technique example of a library.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct HsolEntry {
+    /// The code of the technique, which is kept as it was read.
+    pub code: String,
+    /// The technique of the code, if this crate has it.
+    pub technique: Option<Technique>,
+    /// The digits of the pattern.
+    pub digits: Vec<i8>,
+    pub givens: Board,
+    /// The givens, the placed digits, and the remaining candidates.
+    pub grid: Grid,
+    pub eliminations: Vec<(usize, usize, i8)>,
+    pub placements: Vec<(usize, usize, i8)>,
+    /// The last field, if there's one.
+    pub extra: Option<String>,
+}
+
+/// How the technique engine does on an example.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum HsolCheck {
+    /// The engine makes the same eliminations and placements.
+    Matched,
+    /// The engine finds another step with the technique first.
+    Different(Box<Step>),
+    /// The engine doesn't find the technique in the grid.
+    NotFound,
+    /// This crate doesn't have the technique.
+    Unsupported,
+}
+
+impl HsolEntry {
+    /// Makes an example of `step`, taken in `grid` of a puzzle with
+    /// `givens`. Returns None for guesses, which HoDoKu has no code for.
+    pub fn from_step(givens: &Board, grid: &Grid, step: &Step) -> Option<HsolEntry> {
+        let code = match step.technique {
+            Technique::LockedCandidates if !matches!(step.houses.first(), Some(House::Box(_))) => "0101",
+            technique => CODES.iter().find(|&&(_, t)| t == technique)?.0,
+        };
+        Some(HsolEntry {
+            code: String::from(code),
+            technique: Some(step.technique),
+            digits: pattern_digits(grid, step),
+            givens: *givens,
+            grid: *grid,
+            eliminations: step.eliminations.clone(),
+            placements: step.placements.clone(),
+            extra: None,
+        })
+    }
+
+    /// Looks for the example's technique in its grid.
+    pub fn check(&self) -> HsolCheck {
+        let technique = match self.technique {
+            Some(technique) => technique,
+            None => return HsolCheck::Unsupported,
+        };
+        let step = match find_technique(&self.grid, technique) {
+            Some(step) => step,
+            None => return HsolCheck::NotFound,
+        };
+        let sorted = |candidates: &[(usize, usize, i8)]| {
+            let mut candidates = candidates.to_vec();
+            candidates.sort();
+            candidates
+        };
+        if sorted(&step.eliminations) == sorted(&self.eliminations) && sorted(&step.placements) == sorted(&self.placements)
+        {
+            HsolCheck::Matched
+        } else {
+            HsolCheck::Different(Box::new(step))
+        }
+    }
+}
+
+/// Returns the digits a step's pattern is made of: the pair of a pair, and
+/// otherwise the digits it places or eliminates.
+fn pattern_digits(grid: &Grid, step: &Step) -> Vec<i8> {
+    let mask = match step.technique {
+        Technique::NakedPair => step.cells.iter().fold(0, |mask, &(row, col)| mask | grid.candidates(row, col)),
+        Technique::HiddenPair => {
+            let cells = step.cells.iter().fold(0, |mask, &(row, col)| mask | grid.candidates(row, col));
+            step.eliminations.iter().fold(cells, |mask, &(_, _, digit)| mask & !digit_mask(digit))
+        }
+        _ => step
+            .placements
+            .iter()
+            .chain(step.eliminations.iter())
+            .fold(0, |mask, &(_, _, digit)| mask | digit_mask(digit)),
+    };
+    mask_digits(mask).collect()
+}
+
+fn parse_candidates(s: &str) -> Option<Vec<(usize, usize, i8)>> {
+    s.split_whitespace()
+        .map(|candidate| match candidate.as_bytes() {
+            &[d @ b'1'..=b'9', r @ b'1'..=b'9', c @ b'1'..=b'9'] => {
+                Some(((r - b'1') as usize, (c - b'1') as usize, (d - b'0') as i8))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn write_candidates(candidates: &[(usize, usize, i8)]) -> String {
+    let names: Vec<String> =
+        candidates.iter().map(|&(row, col, digit)| format!("{}{}{}", digit, row + 1, col + 1)).collect();
+    names.join(" ")
+}
+
+/// Parses a line of a library. Returns None if it isn't an example.
+pub fn parse_hsol(line: &str) -> Option<HsolEntry> {
+    let fields: Vec<&str> = line.trim().strip_prefix(':')?.split(':').collect();
+    if fields.len() < 6 {
+        return None;
+    }
+    let code = fields[0];
+    let digits = fields[1].chars().map(|c| c.to_digit(10).filter(|&d| d > 0).map(|d| d as i8)).collect::<Option<_>>()?;
+
+    let mut givens = Board::default();
+    let mut placed = Board::default();
+    let mut chars = fields[2].chars();
+    let mut cell = 0;
+    while let Some(c) = chars.next() {
+        if cell == 81 {
+            return None;
+        }
+        let (row, col) = (cell / 9, cell % 9);
+        match c {
+            '.' | '0' => {}
+            '1'..='9' => {
+                givens[row][col] = Cell::solved(c as i8 - b'0' as i8);
+                placed[row][col] = givens[row][col];
+            }
+            '+' => match chars.next()? {
+                c @ '1'..='9' => placed[row][col] = Cell::solved(c as i8 - b'0' as i8),
+                _ => return None,
+            },
+            _ => return None,
+        }
+        cell += 1;
+    }
+    if cell != 81 {
+        return None;
+    }
+    let mut grid = Grid::from_board(&placed);
+    for (row, col, digit) in parse_candidates(fields[3])? {
+        grid.eliminate(row, col, digit);
+    }
+    let extra = fields.get(6).map(|extra| String::from(*extra)).filter(|extra| !extra.is_empty());
+    Some(HsolEntry {
+        code: String::from(code),
+        technique: CODES.iter().find(|&&(c, _)| c == code).map(|&(_, technique)| technique),
+        digits,
+        givens,
+        grid,
+        eliminations: parse_candidates(fields[4])?,
+        placements: parse_candidates(fields[5])?,
+        extra,
+    })
+}
+
+/// Formats an example as a line of a library, without a line break.
+pub fn to_hsol(entry: &HsolEntry) -> String {
+    let mut cells = String::new();
+    for row in 0..9 {
+        for col in 0..9 {
+            match (entry.givens[row][col].solution, entry.grid.value(row, col)) {
+                (Some(digit), _) => cells.push((b'0' + digit as u8) as char),
+                (None, Some(digit)) => cells += &format!("+{}", digit),
+                (None, None) => cells.push('.'),
+            }
+        }
+    }
+    let placed = Grid::from_board(&entry.grid.to_board());
+    let mut deleted = Vec::new();
+    for row in 0..9 {
+        for col in 0..9 {
+            for digit in mask_digits(placed.candidates(row, col) & !entry.grid.candidates(row, col)) {
+                deleted.push((row, col, digit));
+            }
+        }
+    }
+    let digits: String = entry.digits.iter().map(|&digit| (b'0' + digit as u8) as char).collect();
+    format!(
+        ":{}:{}:{}:{}:{}:{}:{}",
+        entry.code,
+        digits,
+        cells,
+        write_candidates(&deleted),
+        write_candidates(&entry.eliminations),
+        write_candidates(&entry.placements),
+        entry.extra.as_deref().unwrap_or_default()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::parse_board;
+    use crate::logic::{solve_with, TechniqueConfig};
+
+    #[test]
+    fn test_hsol() {
+        let board =
+            parse_board("5....84.....9..156.6..4..2.842.9.5.....7......31............6...53.....2...43..81").unwrap();
+        let trace = solve_with(&board, &TechniqueConfig::default());
+        for (i, step) in trace.steps.iter().enumerate() {
+            let entry = HsolEntry::from_step(&board, &trace.grid_before(i), step).unwrap();
+            let line = to_hsol(&entry);
+            assert_eq!(parse_hsol(&line).as_ref(), Some(&entry), "{}", line);
+            assert_eq!(entry.check(), HsolCheck::Matched);
+        }
+        assert!(trace.steps.iter().any(|step| step.technique == Technique::LockedCandidates));
+
+        let line = ":0003:5:..5..8....2....5..79.34562....6.471..495.783..178.2....54783.96..6....5....1..4..:::511:";
+        let entry = parse_hsol(line).unwrap();
+        assert_eq!(entry.technique, Some(Technique::NakedSingle));
+        assert_eq!(entry.placements, vec![(0, 0, 5)]);
+        assert!(matches!(entry.check(), HsolCheck::Different(_)));
+        assert_eq!(parse_hsol(":9999:1:..5:::").map(|entry| entry.technique), None);
+        assert_eq!(parse_hsol(&line.replace(":0003:", ":0901:")).unwrap().check(), HsolCheck::Unsupported);
+    }
+}
@@ -0,0 +1,140 @@
+//! Solving a board that's edited one cell at a time, as in interactive use.
+//! Setting or clearing a cell only updates the masks of its row, column,
+//! and box, and a solve reuses the last solution as long as it agrees with
+//! the edits, so only edits that contradict it start a new search.
+
+use crate::board::Board;
+use crate::grid::Mask;
+use crate::packed::Packed;
+use crate::solver::Solver;
+
+/// A board being edited, with its candidates and a solution kept up to date.
+#[derive(Debug, Clone)]
+pub struct IncrementalSolver {
+    packed: Packed,
+    /// A solution that agrees with every digit on the board, if one was
+    /// found since the last edit that contradicted it.
+    solution: Option<Board>,
+    /// Whether the board is known to have no solution. Setting digits can't
+    /// change that, only clearing them.
+    unsolvable: bool,
+}
+
+impl IncrementalSolver {
+    /// Starts from `board`. Returns None if a digit is given twice in a row,
+    /// column, or box.
+    pub fn new(board: &Board) -> Option<IncrementalSolver> {
+        let packed = Packed::from_board(board)?;
+        Some(IncrementalSolver { packed, solution: None, unsolvable: false })
+    }
+
+    /// Returns the board with its edits.
+    pub fn board(&self) -> Board {
+        let mut board = Board::default();
+        self.packed.write_to(&mut board);
+        board
+    }
+
+    /// Returns the digits that can go in the cell at `row`, `col`, or 0 if
+    /// it's filled.
+    pub fn candidates(&self, row: usize, col: usize) -> Mask {
+        match self.packed.cells[row * 9 + col] {
+            0 => self.packed.candidates(row * 9 + col),
+            _ => 0,
+        }
+    }
+
+    /// Puts `digit` in the cell at `row`, `col`, replacing its digit if it
+    /// has one. Returns false, changing nothing, if a peer has the digit.
+    pub fn set(&mut self, row: usize, col: usize, digit: i8) -> bool {
+        assert!((1..10).contains(&digit));
+        let index = row * 9 + col;
+        let previous = self.packed.cells[index];
+        if previous != 0 {
+            self.packed.unplace(index, previous);
+        }
+        if !self.packed.allows(index, digit as u8) {
+            if previous != 0 {
+                self.packed.place(index, previous);
+            }
+            return false;
+        }
+        self.packed.place(index, digit as u8);
+        if previous != 0 {
+            // Replacing a digit is clearing it and setting another.
+            self.unsolvable = false;
+        }
+        if self.solution.is_some_and(|solution| solution[row][col].solution != Some(digit)) {
+            self.solution = None;
+        }
+        true
+    }
+
+    /// Empties the cell at `row`, `col`.
+    pub fn clear(&mut self, row: usize, col: usize) {
+        let index = row * 9 + col;
+        let digit = self.packed.cells[index];
+        if digit != 0 {
+            self.packed.unplace(index, digit);
+            self.unsolvable = false;
+        }
+    }
+
+    /// Returns a solution of the board, or None if it has none. Only
+    /// searches if an edit contradicted the last solution found.
+    pub fn solve(&mut self, solver: &mut Solver) -> Option<Board> {
+        if self.solution.is_none() && !self.unsolvable {
+            let mut solution = Board::default();
+            if solver.solve_into(&self.board(), &mut solution) {
+                self.solution = Some(solution);
+            } else {
+                self.unsolvable = true;
+            }
+        }
+        self.solution
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{board_to_string, default_board, Cell};
+    use crate::grid::{digit_mask, mask_digits};
+    use crate::solver::Sudoku;
+
+    #[test]
+    fn test_incremental_solver() {
+        // Solving after edits marks the edited cells as given, so solutions
+        // are compared by their digits.
+        let digits = |board: Option<Board>| board.map(|board| board_to_string(&board));
+        let mut solver = Solver::new();
+        let expected = Sudoku::new(default_board()).solve().unwrap();
+        let mut board = IncrementalSolver::new(&default_board()).unwrap();
+        let solution = board.solve(&mut solver);
+        assert_eq!(digits(solution), digits(Some(expected)));
+
+        // Digits of the solution keep it, and candidates follow the edits.
+        let digit = expected[1][3].solution.unwrap();
+        assert_ne!(board.candidates(1, 5) & digit_mask(digit), 0);
+        assert!(board.set(1, 3, digit));
+        assert_eq!(board.candidates(1, 5) & digit_mask(digit), 0);
+        assert_eq!(board.solve(&mut Solver::new()), solution);
+        assert!(!board.set(1, 5, digit));
+
+        // A wrong digit that doesn't clash leaves no solution until it's
+        // cleared again.
+        let empty = (0..81).find(|&i| board.board()[i / 9][i % 9].solution.is_none()).unwrap();
+        let (row, col) = (empty / 9, empty % 9);
+        let right = expected[row][col].solution.unwrap();
+        let wrong = mask_digits(board.candidates(row, col)).find(|&d| d != right).unwrap();
+        assert!(board.set(row, col, wrong));
+        assert_eq!(board.solve(&mut solver), None);
+        board.clear(row, col);
+        assert_eq!(board.board()[row][col], Cell::unsolved());
+        assert_eq!(digits(board.solve(&mut solver)), digits(solution));
+
+        let mut clash = default_board();
+        clash[0][0] = Cell::solved(5);
+        assert!(IncrementalSolver::new(&clash).is_none());
+    }
+}
@@ -0,0 +1,222 @@
+//! Reading the grids of printable text dumps, which puzzle sites lay out in
+//! slightly different ways: cells of one or more characters, lines and
+//! boxes drawn with `|`, `-`, and `+`, and empty cells written as `.`, `0`,
+//! `_`, or blanks. Rather than code for each site, a layout describes the
+//! dump, and is written in the configuration format:
+//!
+//! ```toml
+//! cell_width = 2
+//! separators = "|-+"
+//! blanks = ["", ".", "_"]
+//! comments = ["#", "Puzzle"]
+//! ```
+//!
+//! Lines are read as rows once the separators are dropped, skipping lines
+//! with nothing else and lines starting with a comment prefix, and every
+//! nine rows make a grid.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::board::{Board, Cell, ParseError};
+use crate::config::{Config, ConfigError};
+
+/// Why a layout couldn't be read.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum GridLayoutError {
+    /// The file isn't a valid configuration file.
+    Config(ConfigError),
+    /// A key isn't one of the settings.
+    UnknownKey(String),
+    /// The value of `key` isn't valid for it.
+    InvalidValue { key: String, value: String },
+}
+
+impl fmt::Display for GridLayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GridLayoutError::Config(err) => write!(f, "{}", err),
+            GridLayoutError::UnknownKey(key) => write!(f, "unknown setting `{}`", key),
+            GridLayoutError::InvalidValue { key, value } => write!(f, "`{}` isn't a valid {}", value, key),
+        }
+    }
+}
+
+impl Error for GridLayoutError {}
+
+/// How the grids of a text dump are laid out.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct GridLayout {
+    /// The characters of each cell, or 0 if cells are separated by
+    /// whitespace. Cells of a fixed width are trimmed, and rows that are
+    /// short, e.g. because trailing blanks were cut off, are filled up with
+    /// empty cells.
+    pub cell_width: usize,
+    /// The characters dropped from every line, such as those of drawn lines.
+    pub separators: String,
+    /// The cells that are empty, besides `.` and `0`. An empty string stands
+    /// for a cell of only spaces.
+    pub blanks: Vec<String>,
+    /// The prefixes of lines to skip, such as titles.
+    pub comments: Vec<String>,
+}
+
+/// Cells separated by whitespace, with `.` or `0` for empty ones and lines
+/// drawn with `|`, `-`, and `+`.
+impl Default for GridLayout {
+    fn default() -> GridLayout {
+        GridLayout {
+            cell_width: 0,
+            separators: String::from("|-+"),
+            blanks: Vec::new(),
+            comments: vec![String::from("#")],
+        }
+    }
+}
+
+impl GridLayout {
+    /// Reads a layout. Settings it leaves out keep their default.
+    pub fn parse(text: &str) -> Result<GridLayout, GridLayoutError> {
+        let config = Config::parse(text).map_err(GridLayoutError::Config)?;
+        let mut layout = GridLayout::default();
+        let list = |value: &str| value.split(',').map(String::from).collect();
+        for (key, value) in config.iter() {
+            match key {
+                "cell_width" => {
+                    layout.cell_width = value.parse().map_err(|_| GridLayoutError::InvalidValue {
+                        key: key.into(),
+                        value: value.into(),
+                    })?
+                }
+                "separators" => layout.separators = value.into(),
+                "blanks" => layout.blanks = list(value),
+                "comments" => layout.comments = list(value),
+                _ => return Err(GridLayoutError::UnknownKey(key.into())),
+            }
+        }
+        Ok(layout)
+    }
+
+    /// Reads every grid of `text`. Fails if a row has a cell that isn't a
+    /// digit or blank, or other than nine cells, or if the rows of the last
+    /// grid don't add up to nine.
+    pub fn parse_all(&self, text: &str) -> Result<Vec<Board>, ParseError> {
+        let mut boards = Vec::new();
+        let mut board = Board::default();
+        let mut rows = 0;
+        let mut offset = 0;
+        for line in text.split_inclusive('\n') {
+            let start = offset;
+            offset += line.len();
+            let line = line.trim_end_matches(['\n', '\r']);
+            if self.comments.iter().any(|prefix| !prefix.is_empty() && line.trim_start().starts_with(prefix.as_str())) {
+                continue;
+            }
+            let kept: String = line.chars().filter(|&c| !self.separators.contains(c)).collect();
+            if kept.trim().is_empty() {
+                continue;
+            }
+            let cells = self.cells(&kept);
+            if cells.len() != 9 {
+                let message = format!("a row of {} cells", cells.len());
+                return Err(ParseError { offset: start, message });
+            }
+            for (col, cell) in cells.iter().enumerate() {
+                board[rows][col] = match self.cell(cell) {
+                    Some(cell) => cell,
+                    None => return Err(ParseError { offset: start, message: format!("unexpected cell {:?}", cell) }),
+                };
+            }
+            rows += 1;
+            if rows == 9 {
+                boards.push(board);
+                board = Board::default();
+                rows = 0;
+            }
+        }
+        if rows > 0 {
+            return Err(ParseError { offset: text.len(), message: format!("a grid of only {} rows", rows) });
+        }
+        Ok(boards)
+    }
+
+    /// Reads the first grid of `text`.
+    pub fn parse_board(&self, text: &str) -> Result<Board, ParseError> {
+        let boards = self.parse_all(text)?;
+        boards.first().copied().ok_or_else(|| ParseError { offset: text.len(), message: String::from("no grid") })
+    }
+
+    /// Splits a row, with its separators dropped, into its cells.
+    fn cells<'a>(&self, row: &'a str) -> Vec<&'a str> {
+        if self.cell_width == 0 {
+            return row.split_whitespace().collect();
+        }
+        let row = row.trim_end();
+        let mut cells = Vec::new();
+        let mut rest = row;
+        while !rest.is_empty() {
+            let end = rest.char_indices().nth(self.cell_width).map_or(rest.len(), |(i, _)| i);
+            cells.push(rest[..end].trim());
+            rest = &rest[end..];
+        }
+        while cells.len() < 9 {
+            cells.push("");
+        }
+        cells
+    }
+
+    fn cell(&self, cell: &str) -> Option<Cell> {
+        match cell {
+            "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9" => Some(Cell::solved(cell.parse().ok()?)),
+            "." | "0" => Some(Cell::unsolved()),
+            _ if self.blanks.iter().any(|blank| blank == cell) => Some(Cell::unsolved()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{board_to_string, parse_board};
+
+    #[test]
+    fn test_grid_layout() {
+        let puzzle = "..5..8....2....5..79.34562....6.471..495.783..178.2....54783.96..6....5....1..4..";
+        let drawn = "\
+# Puzzle 1
++-------+-------+-------+
+| . . 5 | . . 8 | . . . |
+| . 2 . | . . . | 5 . . |
+| 7 9 . | 3 4 5 | 6 2 . |
++-------+-------+-------+
+| . . . | 6 . 4 | 7 1 . |
+| . 4 9 | 5 . 7 | 8 3 . |
+| . 1 7 | 8 . 2 | . . . |
++-------+-------+-------+
+| . 5 4 | 7 8 3 | . 9 6 |
+| . . 6 | . . . | . 5 . |
+| . . . | 1 . . | 4 . . |
++-------+-------+-------+
+";
+        let boards = GridLayout::default().parse_all(&format!("{}\n{}", drawn, drawn)).unwrap();
+        assert_eq!(boards.len(), 2);
+        assert_eq!(board_to_string(&boards[1]), puzzle);
+
+        // Cells two wide with spaces for blanks and trailing blanks cut off.
+        let layout = GridLayout::parse("cell_width = 2\nblanks = [\"\", \"_\"]\ncomments = [\"Puzzle\"]").unwrap();
+        let mut text = String::from("Puzzle 1\n");
+        for row in puzzle.as_bytes().chunks(9) {
+            let cells: String = row.iter().map(|&c| format!(" {}", if c == b'.' { ' ' } else { c as char })).collect();
+            text += cells.trim_end();
+            text += "\n";
+        }
+        assert_eq!(layout.parse_board(&text), Ok(parse_board(puzzle).unwrap()));
+
+        let err = GridLayout::default().parse_all("1 2 3\n").unwrap_err();
+        assert_eq!(err.message, "a row of 3 cells");
+        assert!(GridLayout::default().parse_all(&drawn.replace(". . 5", "? . 5")).is_err());
+        assert!(GridLayout::default().parse_all(&drawn[..drawn.len() - 60]).is_err());
+        assert_eq!(GridLayout::parse("width = 2"), Err(GridLayoutError::UnknownKey("width".into())));
+    }
+}
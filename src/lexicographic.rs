@@ -0,0 +1,208 @@
+//! Enumerating the completions of a partial grid in lexicographic order of
+//! their 81 character strings, for research that needs the solutions in a
+//! fixed order rather than just their number. The search fills the empty
+//! cells row by row with the digits from 1 up, so it can be split across
+//! machines by prefix: each skips to the first completion of its shard
+//! with `skip_to` and stops at the first that's past it.
+
+use std::cmp::Ordering;
+
+use crate::board::{Board, Cell, Origin};
+use crate::grid::{digit_mask, mask_digits, Mask};
+use crate::packed::Packed;
+
+/// The completions of a grid, in lexicographic order.
+#[derive(Debug, Clone)]
+pub struct LexCompletions {
+    puzzle: Board,
+    packed: Packed,
+    /// The empty cells of the puzzle, row by row.
+    cells: Vec<u8>,
+    /// The digits not tried yet in each cell filled in so far.
+    untried: Vec<Mask>,
+    /// The digits the completions have to be at least, if `skip_to` was
+    /// called and the search hasn't got past them yet.
+    floor: Vec<u8>,
+    /// Whether the givens conflict, so there are no completions.
+    conflicting: bool,
+    finished: bool,
+}
+
+impl LexCompletions {
+    /// Enumerates the completions of `puzzle`. There are none if its givens
+    /// conflict.
+    pub fn new(puzzle: &Board) -> LexCompletions {
+        let packed = Packed::from_board(puzzle);
+        let cells = (0..81u8).filter(|&index| puzzle[index as usize / 9][index as usize % 9].solution.is_none());
+        let mut completions = LexCompletions {
+            puzzle: *puzzle,
+            packed: packed.unwrap_or_else(Packed::new),
+            cells: cells.collect(),
+            untried: Vec::new(),
+            floor: Vec::new(),
+            conflicting: packed.is_none(),
+            finished: true,
+        };
+        completions.rewind(Vec::new());
+        completions
+    }
+
+    /// Goes back or on to the first completion whose string is at least
+    /// `prefix`, e.g. to the first one starting with it. Returns false,
+    /// changing nothing, if `prefix` has anything but digits 1 to 9 or more
+    /// than 81 of them.
+    pub fn skip_to(&mut self, prefix: &str) -> bool {
+        if prefix.len() > 81 || !prefix.bytes().all(|c| (b'1'..=b'9').contains(&c)) {
+            return false;
+        }
+        self.rewind(prefix.bytes().map(|c| c - b'0').collect());
+        true
+    }
+
+    /// Empties the cells filled in so far and starts again from the first
+    /// completion at least `floor`.
+    fn rewind(&mut self, floor: Vec<u8>) {
+        for (&index, _) in self.cells.iter().zip(self.untried.iter()) {
+            let digit = self.packed.cells[index as usize];
+            if digit != 0 {
+                self.packed.unplace(index as usize, digit);
+            }
+        }
+        self.untried.clear();
+        self.floor = floor;
+        self.finished = self.conflicting;
+        if !self.finished {
+            self.enter();
+        }
+    }
+
+    /// Moves on to the next empty cell, with all its candidates left to try,
+    /// or marks it as a dead end by leaving none.
+    fn enter(&mut self) {
+        let depth = self.untried.len();
+        let mut untried = match self.compare_floor(depth) {
+            Some(Ordering::Less) => 0,
+            Some(Ordering::Greater) => {
+                // Everything from here on comes after the floor.
+                self.floor.clear();
+                Mask::MAX
+            }
+            _ => Mask::MAX,
+        };
+        if let Some(&index) = self.cells.get(depth) {
+            untried &= self.packed.candidates(index as usize);
+            if let Some(&floor) = self.floor.get(index as usize) {
+                untried &= !(digit_mask(floor as i8) - 1);
+            }
+            // There's no point going on if a later cell has no candidates.
+            if self.cells[depth + 1..].iter().any(|&later| self.packed.candidates(later as usize) == 0) {
+                untried = 0;
+            }
+        }
+        self.untried.push(untried);
+    }
+
+    /// Compares the digits fixed once `depth` cells are filled in, those
+    /// before the next empty cell, with the floor. Returns None if there's
+    /// no floor.
+    fn compare_floor(&self, depth: usize) -> Option<Ordering> {
+        if self.floor.is_empty() {
+            return None;
+        }
+        let end = self.cells.get(depth).map_or(81, |&index| index as usize).min(self.floor.len());
+        Some(self.packed.cells[..end].cmp(&self.floor[..end]))
+    }
+}
+
+impl Iterator for LexCompletions {
+    type Item = Board;
+
+    fn next(&mut self) -> Option<Board> {
+        if self.finished {
+            return None;
+        }
+        loop {
+            let depth = self.untried.len() - 1;
+            if depth == self.cells.len() && self.untried[depth] != 0 {
+                // A completion. Its node is taken back, so the next call goes
+                // on from after it.
+                self.untried[depth] = 0;
+                self.floor.clear();
+                let mut solution = Board::default();
+                self.packed.write_to(&mut solution);
+                for (row, cells) in solution.iter_mut().enumerate() {
+                    for (col, cell) in cells.iter_mut().enumerate() {
+                        *cell = match self.puzzle[row][col].solution {
+                            Some(_) => self.puzzle[row][col],
+                            None => Cell { origin: Origin::Guessed, ..*cell },
+                        };
+                    }
+                }
+                return Some(solution);
+            }
+            if depth < self.cells.len() {
+                let index = self.cells[depth] as usize;
+                let previous = self.packed.cells[index];
+                if previous != 0 {
+                    self.packed.unplace(index, previous);
+                }
+                if let Some(digit) = mask_digits(self.untried[depth]).next() {
+                    self.untried[depth] &= !digit_mask(digit);
+                    self.packed.place(index, digit as u8);
+                    self.enter();
+                    continue;
+                }
+            }
+            // Nothing left to try here, so back up a cell.
+            self.untried.pop();
+            if self.untried.is_empty() {
+                self.finished = true;
+                return None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{board_to_string, default_board, parse_board};
+    use crate::count::count_solutions;
+
+    #[test]
+    fn test_lex_completions() {
+        let mut puzzle = default_board();
+        for row in 0..2 {
+            for col in 0..9 {
+                puzzle[row][col] = Cell::unsolved();
+            }
+        }
+        let keys: Vec<String> = LexCompletions::new(&puzzle).map(|board| board_to_string(&board)).collect();
+        assert_eq!(keys.len(), count_solutions(&puzzle, usize::MAX));
+        assert!(keys.len() > 2);
+        assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+
+        // Skipping to a prefix starts at the first completion at or after
+        // it, wherever the enumeration was.
+        let mut completions = LexCompletions::new(&puzzle);
+        completions.next();
+        let middle = &keys[keys.len() / 2];
+        assert!(completions.skip_to(&middle[..12]));
+        let first = keys.iter().position(|key| key[..12] >= middle[..12]).unwrap();
+        let rest: Vec<String> = completions.map(|board| board_to_string(&board)).collect();
+        assert_eq!(rest, keys[first..]);
+
+        let mut completions = LexCompletions::new(&puzzle);
+        assert!(!completions.skip_to("12a"));
+        assert!(completions.skip_to(&"9".repeat(81)));
+        assert_eq!(completions.next(), None);
+        assert!(completions.skip_to(""));
+        assert_eq!(completions.next().map(|board| board_to_string(&board)).as_ref(), keys.first());
+
+        let solved = parse_board(&keys[0]).unwrap();
+        assert_eq!(LexCompletions::new(&solved).count(), 1);
+        let mut broken = default_board();
+        broken[0][0] = Cell::solved(5);
+        assert_eq!(LexCompletions::new(&broken).count(), 0);
+    }
+}
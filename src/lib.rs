@@ -0,0 +1,165 @@
+// Most of the solver works with explicit row and column indices.
+#![allow(clippy::needless_range_loop)]
+
+#[macro_use]
+mod macros;
+
+mod adversarial;
+mod analyze;
+mod annotate;
+mod batch;
+#[cfg(feature = "unstable")]
+pub mod bitboard;
+mod board;
+#[cfg(feature = "db")]
+mod campaign;
+mod canonical;
+mod chains;
+mod chat;
+mod checkpoint;
+pub mod clipboard;
+mod completions;
+pub mod config;
+mod count;
+mod crossval;
+mod daily;
+mod digits;
+#[cfg(feature = "db")]
+pub mod db;
+mod dlx;
+mod enumerate;
+mod estimate;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod font;
+mod fpuzzles;
+mod generate;
+mod graphics;
+mod grid;
+mod hint;
+mod hsol;
+mod incremental;
+mod json;
+mod layout;
+mod lexicographic;
+mod locale;
+mod logic;
+mod mine;
+#[cfg(feature = "ocr")]
+pub mod ocr;
+mod observe;
+mod packed;
+mod path;
+mod pattern;
+#[cfg(feature = "pdf")]
+mod pdf;
+mod png;
+mod puzzle;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "qr")]
+mod qr;
+#[cfg(feature = "server")]
+mod race;
+mod rating;
+mod raster;
+mod refute;
+mod render;
+mod replace;
+mod report;
+mod rpc;
+mod sat;
+mod score;
+mod sdk;
+mod selection;
+mod sepb;
+#[cfg(feature = "server")]
+pub mod server;
+mod solver;
+mod symbols;
+#[cfg(feature = "async")]
+mod tasks;
+mod transform;
+mod tune;
+mod uniqueness;
+mod validate;
+mod variant;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use crate::adversarial::{generate_adversarial, naive_effort, EFFORT_LIMIT};
+pub use crate::analyze::{
+    analysis_csv, analyze, analyze_puzzle, hardest_by_difficulty, hardest_by_effort, histogram, Analysis, Histogram,
+};
+pub use crate::annotate::{Annotation, Annotations, Color};
+pub use crate::batch::{solve_all, solve_all_with, solve_stream, solve_stream_with, BatchStats, SolveResult};
+pub use crate::board::{
+    board_to_string, default_board, diff_boards, parse_board, parse_board_with, print_board, print_board_with, Board,
+    Cell, CellDiff, Origin, ParseError, ParseMode, ParseWarning, Parsed,
+};
+#[cfg(feature = "db")]
+pub use crate::campaign::{Campaign, Tier};
+pub use crate::canonical::{canonical_form, dedupe, similarity, Deduped, Similarity};
+pub use crate::chat::{chat, chat_board, ChatStyle};
+pub use crate::checkpoint::Checkpoint;
+pub use crate::completions::{completion_script, Shell};
+pub use crate::count::{count_all_solutions, count_solutions};
+pub use crate::crossval::{cross_validate, parse_references, CrossValidation, ReferenceRating};
+pub use crate::daily::{daily_puzzle, Date};
+pub use crate::digits::{digit_scan, DigitScan, DigitSummary};
+pub use crate::dlx::DlxSolver;
+pub use crate::enumerate::Enumeration;
+pub use crate::estimate::{estimate_search, estimate_solutions, CountEstimate, SearchEstimate};
+pub use crate::fpuzzles::{fpuzzles_json, parse_fpuzzles};
+pub use crate::generate::{generate, generate_with, Difficulty, GenerateError, Rng, MAX_ATTEMPTS};
+pub use crate::graphics::{board_graphics, Graphics};
+pub use crate::grid::{Grid, House};
+pub use crate::hint::{hint, Hint, HintLevel, HintPolicy, HintRefusal, HintSession, StandardHintPolicy};
+pub use crate::hsol::{parse_hsol, to_hsol, HsolCheck, HsolEntry};
+pub use crate::incremental::IncrementalSolver;
+pub use crate::json::Json;
+pub use crate::layout::{GridLayout, GridLayoutError};
+pub use crate::lexicographic::LexCompletions;
+pub use crate::locale::{Catalog, LocaleError};
+pub use crate::logic::{
+    solve_logically, solve_observed, solve_variant, solve_with, Step, Technique, TechniqueConfig, TechniqueUsage, Trace,
+};
+pub use crate::mine::{mine_examples, only_way_forward};
+pub use crate::observe::SolveObserver;
+pub use crate::path::{best_path, PathGoal};
+pub use crate::pattern::{givens_pattern, GivensPattern};
+pub use crate::puzzle::{Metadata, Puzzle, PuzzleError};
+#[cfg(feature = "pdf")]
+pub use crate::pdf::PdfSheet;
+#[cfg(feature = "qr")]
+pub use crate::qr::{qr_payload, qr_png, qr_text};
+#[cfg(feature = "server")]
+pub use crate::race::serve_races;
+pub use crate::rating::{rate, rate_variant, GuessReport, Rating};
+pub use crate::raster::board_to_png;
+pub use crate::render::{
+    render_candidate_diff_html, render_candidate_diff_text, render_diff_text, render_step_html,
+    render_step_html_annotated, render_step_text, render_step_text_annotated,
+};
+pub use crate::replace::{replace_clue, Replacement};
+pub use crate::report::{report_csv, ReportFormat, ReportRow};
+pub use crate::refute::{refute, Contradiction, Forced, Refutation};
+pub use crate::rpc::{respond, serve_lines};
+pub use crate::sat::SatSolver;
+pub use crate::score::{ScoreTable, ScoreTableError};
+pub use crate::sdk::{parse_sdk, to_sdk};
+pub use crate::sepb::{parse_sepb, to_sepb};
+pub use crate::selection::Selection;
+pub use crate::solver::{
+    Backend, CellOrder, MemoryLimitExceeded, Solution, SolveOptions, SolveStats, Solver, SolverBackend, StepOutcome,
+    Sudoku, ValueOrder, STEP_GUESSES,
+};
+pub use crate::symbols::SymbolSet;
+#[cfg(feature = "async")]
+pub use crate::tasks::{generate_async, solve_async, Cancelled, Task};
+pub use crate::transform::{relabel, rotation_pack, Symmetry};
+pub use crate::tune::{tune, TuningProfile, TuningProfileError, TuningReport, TuningResult};
+pub use crate::validate::{
+    check_puzzle, preflight, validate, verify, Conflict, Discrepancy, InvalidPuzzle, PuzzleProblem, Verification, Warning,
+};
+pub use crate::variant::{Arrow, Cage, Variant};
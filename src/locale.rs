@@ -0,0 +1,314 @@
+//! The text meant for people, such as hints, step descriptions, and the
+//! messages of the command line tool, kept in a catalog so it can be
+//! translated. Every message has an id and an English template whose
+//! `{name}` placeholders are filled in when it's used. A locale is a file
+//! in the configuration format replacing any of the templates:
+//!
+//! ```toml
+//! hint_area = "Regarde {area}"
+//! technique_naked_single = "Singleton nu"
+//! ```
+//!
+//! Messages a locale leaves out stay in English. Cell names, digits, and
+//! the notation of steps and chains aren't translated.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::config::{Config, ConfigError};
+use crate::grid::{cell_name, House};
+use crate::hint::{Hint, HintLevel};
+use crate::logic::{Step, Technique};
+use crate::refute::{Contradiction, Refutation};
+
+/// The English templates of the messages, by id.
+const ENGLISH: [(&str, &str); 42] = [
+    ("row", "row {number}"),
+    ("column", "column {number}"),
+    ("box", "box {number}"),
+    ("technique_naked_single", "Naked single"),
+    ("technique_hidden_single", "Hidden single"),
+    ("technique_locked_candidates", "Locked candidates"),
+    ("technique_naked_pair", "Naked pair"),
+    ("technique_hidden_pair", "Hidden pair"),
+    ("technique_x_wing", "X-Wing"),
+    ("technique_x_chain", "X-Chain"),
+    ("technique_xy_chain", "XY-Chain"),
+    ("technique_bug_plus_one", "BUG+1"),
+    ("technique_forcing_chain", "Forcing chain"),
+    ("technique_guess", "Guess"),
+    // The techniques as they're looked for in hints.
+    ("look_for_naked_single", "a naked single"),
+    ("look_for_hidden_single", "a hidden single"),
+    ("look_for_locked_candidates", "locked candidates"),
+    ("look_for_naked_pair", "a naked pair"),
+    ("look_for_hidden_pair", "a hidden pair"),
+    ("look_for_x_wing", "an X-Wing"),
+    ("look_for_x_chain", "an X-Chain"),
+    ("look_for_xy_chain", "an XY-Chain"),
+    ("look_for_bug_plus_one", "a BUG+1"),
+    ("look_for_forcing_chain", "a forcing chain"),
+    ("look_for_guess", "a guess"),
+    ("step_houses", "{technique} in {houses}"),
+    ("step_refutation", "{step}, since {refutation}"),
+    ("refutation", "{move} {contradiction}"),
+    ("refutation_forced", "{move} forces {forced}, which {contradiction}"),
+    ("contradiction_duplicate", "clashes with {cell} in {house}"),
+    ("contradiction_no_candidates", "leaves {cell} without any candidates"),
+    ("contradiction_no_place", "leaves {house} without any place for {digit}"),
+    ("hint_area", "Look at {area}"),
+    ("hint_technique", "Look for {technique} in {area}"),
+    ("hint_guess", "There's no logical step left, try guessing in {area}"),
+    ("hint_board", "the board"),
+    ("answer_placement", "{cell} is {digit}"),
+    ("answer_elimination", "{digit} can't be in {cells}"),
+    ("answer_refutation", "{answer}, because {refutation}"),
+    ("no_hint", "The puzzle is either solved or has no solution."),
+    ("no_solution", "No solution found."),
+    ("no_solution_without_guessing", "No solution found without guessing."),
+];
+
+/// Why a locale couldn't be read.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum LocaleError {
+    /// The file isn't a valid configuration file.
+    Config(ConfigError),
+    /// A key isn't the id of a message.
+    UnknownMessage(String),
+    /// The template of `id` has a placeholder its English one doesn't.
+    UnknownPlaceholder { id: String, name: String },
+}
+
+impl fmt::Display for LocaleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LocaleError::Config(err) => write!(f, "{}", err),
+            LocaleError::UnknownMessage(id) => write!(f, "unknown message `{}`", id),
+            LocaleError::UnknownPlaceholder { id, name } => write!(f, "`{}` has no placeholder {{{}}}", id, name),
+        }
+    }
+}
+
+impl Error for LocaleError {}
+
+/// The templates of the messages in one language. The default is English.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Catalog {
+    /// The templates replacing the English ones.
+    translations: Vec<(String, String)>,
+}
+
+impl Catalog {
+    /// Reads a locale.
+    pub fn parse(text: &str) -> Result<Catalog, LocaleError> {
+        let config = Config::parse(text).map_err(LocaleError::Config)?;
+        let mut catalog = Catalog::default();
+        for (id, template) in config.iter() {
+            let english = english(id).ok_or_else(|| LocaleError::UnknownMessage(id.into()))?;
+            let english_names: Vec<&str> = placeholders(english).collect();
+            if let Some(name) = placeholders(template).find(|name| !english_names.contains(name)) {
+                return Err(LocaleError::UnknownPlaceholder { id: id.into(), name: name.into() });
+            }
+            catalog.translations.push((id.into(), template.into()));
+        }
+        Ok(catalog)
+    }
+
+    /// Returns the template of the message `id`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there's no such message.
+    pub fn template(&self, id: &str) -> &str {
+        match self.translations.iter().find(|(key, _)| key == id) {
+            Some((_, template)) => template,
+            None => english(id).unwrap_or_else(|| panic!("unknown message `{}`", id)),
+        }
+    }
+
+    /// Returns the message `id` with its placeholders filled in from `args`,
+    /// as (name, value) pairs. Placeholders missing from `args` are left as
+    /// they are.
+    pub fn message(&self, id: &str, args: &[(&str, &str)]) -> String {
+        let mut message = String::new();
+        let mut rest = self.template(id);
+        while let Some(start) = rest.find('{') {
+            message.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let end = rest.find('}').map_or(rest.len(), |end| end + 1);
+            let name = rest[1..end].trim_end_matches('}');
+            match args.iter().find(|&&(key, _)| key == name) {
+                Some((_, value)) => message.push_str(value),
+                None => message.push_str(&rest[..end]),
+            }
+            rest = &rest[end..];
+        }
+        message.push_str(rest);
+        message
+    }
+
+    pub fn technique(&self, technique: Technique) -> String {
+        self.message(&format!("technique_{}", technique.id()), &[])
+    }
+
+    pub fn house(&self, house: House) -> String {
+        let (id, index) = match house {
+            House::Row(row) => ("row", row),
+            House::Col(col) => ("column", col),
+            House::Box(b) => ("box", b),
+        };
+        self.message(id, &[("number", &(index + 1).to_string())])
+    }
+
+    /// Describes `step` as its `Display` impl does.
+    pub fn step(&self, step: &Step) -> String {
+        let mut text = self.technique(step.technique);
+        if !step.houses.is_empty() {
+            let houses: Vec<String> = step.houses.iter().map(|&house| self.house(house)).collect();
+            text = self.message("step_houses", &[("technique", &text), ("houses", &houses.join(", "))]);
+        }
+        for (i, &(row, col, digit)) in step.chain.iter().enumerate() {
+            let link = match i {
+                0 => " ",
+                _ if i % 2 == 1 => "=",
+                _ => "-",
+            };
+            text += &format!("{}{}{}", link, digit, cell_name(row, col));
+        }
+        text.push(':');
+        for &(row, col, digit) in step.placements.iter() {
+            text += &format!(" {}={}", cell_name(row, col), digit);
+        }
+        for &(row, col, digit) in step.eliminations.iter() {
+            text += &format!(" {}<>{}", cell_name(row, col), digit);
+        }
+        match &step.refutation {
+            Some(refutation) => {
+                self.message("step_refutation", &[("step", &text), ("refutation", &self.refutation(refutation))])
+            }
+            None => text,
+        }
+    }
+
+    pub fn refutation(&self, refutation: &Refutation) -> String {
+        let mov = format!("{}={}", cell_name(refutation.row, refutation.col), refutation.digit);
+        let contradiction = self.contradiction(&refutation.contradiction);
+        if refutation.forced.is_empty() {
+            return self.message("refutation", &[("move", &mov), ("contradiction", &contradiction)]);
+        }
+        let forced: Vec<String> = refutation
+            .forced
+            .iter()
+            .map(|forced| format!("{}={}", cell_name(forced.row, forced.col), forced.digit))
+            .collect();
+        let args = [("move", mov.as_str()), ("forced", &forced.join(", ")), ("contradiction", &contradiction)];
+        self.message("refutation_forced", &args)
+    }
+
+    pub fn contradiction(&self, contradiction: &Contradiction) -> String {
+        match *contradiction {
+            Contradiction::Duplicate { house, row, col } => self.message(
+                "contradiction_duplicate",
+                &[("cell", &cell_name(row, col)), ("house", &self.house(house))],
+            ),
+            Contradiction::NoCandidates { row, col } => {
+                self.message("contradiction_no_candidates", &[("cell", &cell_name(row, col))])
+            }
+            Contradiction::NoPlace { house, digit } => self.message(
+                "contradiction_no_place",
+                &[("house", &self.house(house)), ("digit", &digit.to_string())],
+            ),
+        }
+    }
+
+    /// Returns the text of `hint` at `level`, as `Hint::text` does.
+    pub fn hint(&self, hint: &Hint, level: HintLevel) -> String {
+        let area = match (hint.step.houses.first(), hint.step.cells.first()) {
+            (Some(&house), _) => self.house(house),
+            (None, Some(&(row, col))) => cell_name(row, col),
+            (None, None) => self.message("hint_board", &[]),
+        };
+        match level {
+            HintLevel::Area => self.message("hint_area", &[("area", &area)]),
+            HintLevel::Technique if hint.step.technique == Technique::Guess => {
+                self.message("hint_guess", &[("area", &area)])
+            }
+            HintLevel::Technique => {
+                let technique = self.message(&format!("look_for_{}", hint.step.technique.id()), &[]);
+                self.message("hint_technique", &[("technique", &technique), ("area", &area)])
+            }
+            HintLevel::Answer => self.answer(&hint.step),
+        }
+    }
+
+    fn answer(&self, step: &Step) -> String {
+        let mut parts = Vec::new();
+        for &(row, col, digit) in step.placements.iter() {
+            let (cell, digit) = (cell_name(row, col), digit.to_string());
+            parts.push(self.message("answer_placement", &[("cell", &cell), ("digit", &digit)]));
+        }
+        for digit in 1..10 {
+            let cells: Vec<String> = step
+                .eliminations
+                .iter()
+                .filter(|&&(_, _, d)| d == digit)
+                .map(|&(row, col, _)| cell_name(row, col))
+                .collect();
+            if !cells.is_empty() {
+                let (digit, cells) = (digit.to_string(), cells.join(", "));
+                parts.push(self.message("answer_elimination", &[("digit", &digit), ("cells", &cells)]));
+            }
+        }
+        let answer = parts.join("; ");
+        match &step.refutation {
+            Some(refutation) => {
+                self.message("answer_refutation", &[("answer", &answer), ("refutation", &self.refutation(refutation))])
+            }
+            None => answer,
+        }
+    }
+}
+
+fn english(id: &str) -> Option<&'static str> {
+    ENGLISH.iter().find(|&&(key, _)| key == id).map(|&(_, template)| template)
+}
+
+/// Returns the names of the placeholders of `template`.
+fn placeholders(template: &str) -> impl Iterator<Item = &str> {
+    template.split('{').skip(1).filter_map(|part| part.split_once('}')).map(|(name, _)| name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::default_board;
+    use crate::hint::hint;
+    use crate::logic::{solve_with, TechniqueConfig};
+
+    #[test]
+    fn test_catalog() {
+        // The English catalog says what the Display impls and hints say.
+        let english = Catalog::default();
+        let trace = solve_with(&default_board(), &TechniqueConfig::default());
+        assert!(trace.steps.iter().all(|step| english.step(step) == step.to_string()));
+        let hint = hint(&default_board(), &TechniqueConfig::default()).unwrap();
+        assert_eq!(english.hint(&hint, HintLevel::Technique), "Look for a naked single in r2c4");
+        assert!(Technique::ALL.iter().all(|&technique| english.technique(technique) == technique.name()));
+
+        let french = Catalog::parse(
+            "hint_technique = \"Cherche {technique} en {area}\"\nlook_for_naked_single = \"un singleton nu\"\n\
+             box = \"bloc {number}\"",
+        )
+        .unwrap();
+        assert_eq!(french.hint(&hint, HintLevel::Technique), "Cherche un singleton nu en r2c4");
+        assert_eq!(french.hint(&hint, HintLevel::Area), "Look at r2c4");
+        assert_eq!(french.house(House::Box(2)), "bloc 3");
+        assert_eq!(french.message("answer_placement", &[("cell", "r1c1")]), "r1c1 is {digit}");
+
+        assert!(matches!(Catalog::parse("hello = \"salut\""), Err(LocaleError::UnknownMessage(_))));
+        assert_eq!(
+            Catalog::parse("hint_area = \"Regarde {cell}\""),
+            Err(LocaleError::UnknownPlaceholder { id: "hint_area".into(), name: "cell".into() })
+        );
+    }
+}
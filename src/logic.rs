@@ -0,0 +1,911 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::board::{board_to_string, Board, Origin};
+use crate::chains::find_chain;
+use crate::grid::{digit_mask, mask_digits, Grid, House, Mask};
+use crate::json::Json;
+use crate::locale::Catalog;
+use crate::observe::{NoObserver, SolveObserver};
+use crate::refute::{find_contradiction, find_forcing_chain, Refutation};
+use crate::uniqueness::find_bug_plus_one;
+use crate::variant::Variant;
+
+/// A solving technique a human would use, ordered roughly from easiest to
+/// hardest. `Guess` is what we fall back to when no technique applies.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub enum Technique {
+    NakedSingle,
+    HiddenSingle,
+    LockedCandidates,
+    NakedPair,
+    HiddenPair,
+    XWing,
+    XChain,
+    XYChain,
+    /// Needs the puzzle to have a single solution, see
+    /// `TechniqueConfig::assume_unique`.
+    BugPlusOne,
+    /// Rules out a candidate by following its consequences to a
+    /// contradiction. It's a last resort before guessing, so
+    /// `TechniqueConfig::default` leaves it out.
+    ForcingChain,
+    Guess,
+}
+
+impl Technique {
+    /// All techniques, from easiest to hardest.
+    pub const ALL: [Technique; 11] = [
+        Technique::NakedSingle,
+        Technique::HiddenSingle,
+        Technique::LockedCandidates,
+        Technique::NakedPair,
+        Technique::HiddenPair,
+        Technique::XWing,
+        Technique::XChain,
+        Technique::XYChain,
+        Technique::BugPlusOne,
+        Technique::ForcingChain,
+        Technique::Guess,
+    ];
+
+    /// Returns the name of the technique for display.
+    pub fn name(self) -> &'static str {
+        match self {
+            Technique::NakedSingle => "Naked single",
+            Technique::HiddenSingle => "Hidden single",
+            Technique::LockedCandidates => "Locked candidates",
+            Technique::NakedPair => "Naked pair",
+            Technique::HiddenPair => "Hidden pair",
+            Technique::XWing => "X-Wing",
+            Technique::XChain => "X-Chain",
+            Technique::XYChain => "XY-Chain",
+            Technique::BugPlusOne => "BUG+1",
+            Technique::ForcingChain => "Forcing chain",
+            Technique::Guess => "Guess",
+        }
+    }
+
+    /// Returns the identifier of the technique used in machine readable
+    /// output.
+    pub fn id(self) -> &'static str {
+        match self {
+            Technique::NakedSingle => "naked_single",
+            Technique::HiddenSingle => "hidden_single",
+            Technique::LockedCandidates => "locked_candidates",
+            Technique::NakedPair => "naked_pair",
+            Technique::HiddenPair => "hidden_pair",
+            Technique::XWing => "x_wing",
+            Technique::XChain => "x_chain",
+            Technique::XYChain => "xy_chain",
+            Technique::BugPlusOne => "bug_plus_one",
+            Technique::ForcingChain => "forcing_chain",
+            Technique::Guess => "guess",
+        }
+    }
+
+    /// Returns whether the technique relies on the puzzle having a single
+    /// solution.
+    pub fn needs_uniqueness(self) -> bool {
+        self == Technique::BugPlusOne
+    }
+
+    /// Returns the technique with the given `id`, if any.
+    pub fn from_id(id: &str) -> Option<Technique> {
+        Technique::ALL.iter().cloned().find(|technique| technique.id() == id)
+    }
+}
+
+/// Which techniques the logical solver may use and the order in which it
+/// tries them. Different publications allow different technique sets, so
+/// this lets callers restrict the solver to what their audience knows.
+///
+/// `Guess` is always tried last, regardless of its position, and disabling it
+/// makes the solver give up instead of guessing. Techniques that need
+/// uniqueness are only tried once `assume_unique` is set.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TechniqueConfig {
+    techniques: Vec<Technique>,
+    assume_unique: bool,
+}
+
+impl TechniqueConfig {
+    /// Creates a config that allows only `techniques`, preferring them in the
+    /// given order.
+    pub fn new(techniques: Vec<Technique>) -> TechniqueConfig {
+        let mut config = TechniqueConfig { techniques: Vec::new(), assume_unique: false };
+        for technique in techniques {
+            config.enable(technique);
+        }
+        config
+    }
+
+    /// Returns the enabled techniques in order of preference.
+    pub fn techniques(&self) -> &[Technique] {
+        &self.techniques
+    }
+
+    pub fn is_enabled(&self, technique: Technique) -> bool {
+        self.techniques.contains(&technique)
+    }
+
+    /// Enables `technique` as the least preferred one, if it isn't already
+    /// enabled.
+    pub fn enable(&mut self, technique: Technique) {
+        if !self.is_enabled(technique) {
+            self.techniques.push(technique);
+        }
+    }
+
+    pub fn disable(&mut self, technique: Technique) {
+        self.techniques.retain(|&t| t != technique);
+    }
+
+    /// Returns whether the solver may assume puzzles have a single solution.
+    pub fn assumes_unique(&self) -> bool {
+        self.assume_unique
+    }
+
+    /// Sets whether the solver may assume puzzles have a single solution,
+    /// which allows the techniques that need uniqueness, like BUG+1. Only
+    /// set it for standard puzzles known to have one: on others they can
+    /// place wrong digits.
+    pub fn assume_unique(&mut self, assume: bool) {
+        self.assume_unique = assume;
+    }
+}
+
+/// Allows all techniques but forcing chains from easiest to hardest.
+impl Default for TechniqueConfig {
+    fn default() -> TechniqueConfig {
+        let mut config = TechniqueConfig::new(Technique::ALL.to_vec());
+        config.disable(Technique::ForcingChain);
+        config
+    }
+}
+
+impl fmt::Display for Technique {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A single deduction made by the logical solver.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Step {
+    pub technique: Technique,
+    /// The houses the deduction is based on.
+    pub houses: Vec<House>,
+    /// The cells whose candidates define the pattern.
+    pub cells: Vec<(usize, usize)>,
+    /// The digits placed by this step as (row, column, digit).
+    pub placements: Vec<(usize, usize, i8)>,
+    /// The candidates removed by this step as (row, column, digit).
+    pub eliminations: Vec<(usize, usize, i8)>,
+    /// The candidates of a chain in order, for chain techniques. The links
+    /// between them alternate between strong and weak, starting with a
+    /// strong one.
+    pub chain: Vec<(usize, usize, i8)>,
+    /// Why the candidate a forcing chain eliminates is wrong.
+    pub refutation: Option<Refutation>,
+    /// How long it took to find the step.
+    pub elapsed: Duration,
+}
+
+impl Step {
+    pub(crate) fn placement(technique: Technique, houses: Vec<House>, row: usize, col: usize, digit: i8) -> Step {
+        Step {
+            technique,
+            houses,
+            cells: vec![(row, col)],
+            placements: vec![(row, col, digit)],
+            eliminations: Vec::new(),
+            chain: Vec::new(),
+            refutation: None,
+            elapsed: Duration::default(),
+        }
+    }
+
+    /// Returns the step as a JSON object. Rows and columns are zero-based.
+    pub fn to_json(&self) -> Json {
+        let houses = self.houses.iter().map(|&house| house_json(house)).collect();
+        let cells = self.cells.iter().map(|&(row, col)| Json::from(vec![row, col])).collect();
+        Json::object(vec![
+            ("technique", Json::from(self.technique.id())),
+            ("houses", Json::Array(houses)),
+            ("cells", Json::Array(cells)),
+            ("placements", candidates_json(&self.placements)),
+            ("eliminations", candidates_json(&self.eliminations)),
+            ("elapsed_us", Json::from(self.elapsed.as_micros() as f64)),
+            ("chain", candidates_json(&self.chain)),
+            ("refutation", Json::from(self.refutation.as_ref().map(Refutation::to_string))),
+        ])
+    }
+
+    /// Applies the step's placements and eliminations to `grid`.
+    pub(crate) fn apply(&self, grid: &mut Grid) {
+        for &(row, col, digit) in self.placements.iter() {
+            grid.place(row, col, digit);
+        }
+        for &(row, col, digit) in self.eliminations.iter() {
+            grid.eliminate(row, col, digit);
+        }
+    }
+}
+
+fn house_json(house: House) -> Json {
+    let (kind, index) = match house {
+        House::Row(row) => ("row", row),
+        House::Col(col) => ("col", col),
+        House::Box(b) => ("box", b),
+    };
+    Json::object(vec![("kind", Json::from(kind)), ("index", Json::from(index))])
+}
+
+fn candidates_json(candidates: &[(usize, usize, i8)]) -> Json {
+    let candidates = candidates
+        .iter()
+        .map(|&(row, col, digit)| {
+            Json::object(vec![
+                ("row", Json::from(row)),
+                ("col", Json::from(col)),
+                ("digit", Json::from(digit)),
+            ])
+        })
+        .collect();
+    Json::Array(candidates)
+}
+
+/// Describes the step in one line, e.g. `Hidden single in box 3: r2c7=4`.
+/// Chains are written with `=` for strong links and `-` for weak ones, e.g.
+/// `X-Chain 4r1c2=4r1c7-4r5c7=4r5c3: r2c3<>4`. `Catalog::step` describes it
+/// in other languages.
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&Catalog::default().step(self))
+    }
+}
+
+/// The steps taken by the logical solver to solve a puzzle.
+#[derive(Debug, Clone)]
+pub struct Trace {
+    pub steps: Vec<Step>,
+    /// The solved board, or None if the puzzle has no solution.
+    pub solution: Option<Board>,
+    /// How long the whole solve took.
+    pub elapsed: Duration,
+    /// The number of guesses made, including the wrong ones that had to be
+    /// taken back. The guesses that led to the solution are in `steps`.
+    pub guesses: usize,
+    /// The deepest nesting of guesses reached during the search.
+    pub guess_depth: usize,
+    /// The pencil marks before the first step.
+    pub(crate) initial: Grid,
+}
+
+impl Trace {
+    /// Returns the grid with its pencil marks as it was right before step
+    /// `index` was taken. Passing the number of steps gives the final grid.
+    pub fn grid_before(&self, index: usize) -> Grid {
+        let mut grid = self.initial;
+        for step in self.steps[..index].iter() {
+            step.apply(&mut grid);
+        }
+        grid
+    }
+
+    /// Returns the grid with its pencil marks right after step `index`.
+    pub fn grid_after(&self, index: usize) -> Grid {
+        self.grid_before(index + 1)
+    }
+
+    /// Returns the whole trace as a JSON object, for frontends that want to
+    /// replay the solve. The pencil marks are only included for the initial
+    /// grid, the steps' placements and eliminations give the rest.
+    pub fn to_json(&self) -> Json {
+        Json::object(vec![
+            ("solved", Json::from(self.solution.is_some())),
+            ("solution", Json::from(self.solution.as_ref().map(board_to_string))),
+            ("elapsed_us", Json::from(self.elapsed.as_micros() as f64)),
+            ("guesses", Json::from(self.guesses)),
+            ("guess_depth", Json::from(self.guess_depth)),
+            ("usage", self.usage().to_json()),
+            ("initial", self.initial.to_json()),
+            ("steps", Json::Array(self.steps.iter().map(Step::to_json).collect())),
+        ])
+    }
+
+    /// Counts how many times each technique was needed.
+    pub fn usage(&self) -> TechniqueUsage {
+        let mut counts = [0; Technique::ALL.len()];
+        for step in self.steps.iter() {
+            counts[step.technique as usize] += 1;
+        }
+        TechniqueUsage { counts }
+    }
+}
+
+/// The number of times each technique was applied while solving a puzzle.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct TechniqueUsage {
+    counts: [usize; Technique::ALL.len()],
+}
+
+impl TechniqueUsage {
+    /// Returns how many times `technique` was applied.
+    pub fn count(&self, technique: Technique) -> usize {
+        self.counts[technique as usize]
+    }
+
+    /// Returns the total number of steps.
+    pub fn total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    /// Returns the counts as a JSON object keyed by technique id.
+    pub fn to_json(&self) -> Json {
+        let mut members = Vec::new();
+        for &technique in Technique::ALL.iter() {
+            members.push((technique.id(), Json::from(self.count(technique))));
+        }
+        members.push(("total", Json::from(self.total())));
+        Json::object(members)
+    }
+}
+
+/// Formats the usage as a plain text table.
+impl fmt::Display for TechniqueUsage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:<20}{:>6}", "Technique", "Count")?;
+        for &technique in Technique::ALL.iter() {
+            writeln!(f, "{:<20}{:>6}", technique.name(), self.count(technique))?;
+        }
+        write!(f, "{:<20}{:>6}", "Total", self.total())
+    }
+}
+
+/// Solves the board the way a human would, recording each step. When none of
+/// the techniques apply, the solver guesses and the guess is recorded as a
+/// step too, so every solvable puzzle yields a solution.
+pub fn solve_logically(board: &Board) -> Trace {
+    solve_with(board, &TechniqueConfig::default())
+}
+
+/// Like `solve_logically`, but only uses the techniques enabled in `config`.
+/// If guessing is disabled and the techniques run out, the trace has no
+/// solution.
+pub fn solve_with(board: &Board, config: &TechniqueConfig) -> Trace {
+    solve_variant(board, config, &Variant::default())
+}
+
+/// Returns the solved `grid` as a board, with the origin of each digit: the
+/// givens of `board` keep theirs, digits of guesses are guessed, and the
+/// rest are deduced.
+pub(crate) fn solution_board(board: &Board, grid: Grid, steps: &[Step]) -> Board {
+    let mut solution = grid.to_board();
+    for row in 0..9 {
+        for col in 0..9 {
+            solution[row][col].origin = Origin::Deduced;
+        }
+    }
+    for step in steps.iter().filter(|step| step.technique == Technique::Guess) {
+        for &(row, col, _) in step.placements.iter() {
+            solution[row][col].origin = Origin::Guessed;
+        }
+    }
+    for row in 0..9 {
+        for col in 0..9 {
+            if board[row][col].solution.is_some() {
+                solution[row][col] = board[row][col];
+            }
+        }
+    }
+    solution
+}
+
+/// Like `solve_with`, but the solution also has to satisfy the constraints
+/// of `variant`.
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+pub fn solve_variant(board: &Board, config: &TechniqueConfig, variant: &Variant) -> Trace {
+    solve_observed(board, config, variant, &mut NoObserver)
+}
+
+/// Like `solve_variant`, but tells `observer` about every placement,
+/// elimination, guess, and backtrack as it happens.
+pub fn solve_observed(
+    board: &Board,
+    config: &TechniqueConfig,
+    variant: &Variant,
+    observer: &mut dyn SolveObserver,
+) -> Trace {
+    let start = Instant::now();
+    let mut search = Search {
+        config,
+        variant,
+        observer,
+        steps: Vec::new(),
+        guesses: 0,
+        guess_depth: 0,
+    };
+    let initial = Grid::from_board(board);
+    let solution = search.solve(initial, 0).map(|grid| solution_board(board, grid, &search.steps));
+    event!(
+        DEBUG,
+        solved = solution.is_some(),
+        steps = search.steps.len(),
+        guesses = search.guesses,
+        "logical solve finished"
+    );
+    Trace {
+        initial,
+        steps: search.steps,
+        solution,
+        elapsed: start.elapsed(),
+        guesses: search.guesses,
+        guess_depth: search.guess_depth,
+    }
+}
+
+/// The state of a logical solve, which turns into a depth-first search once
+/// guesses are needed.
+struct Search<'a> {
+    config: &'a TechniqueConfig,
+    variant: &'a Variant,
+    observer: &'a mut dyn SolveObserver,
+    steps: Vec<Step>,
+    guesses: usize,
+    guess_depth: usize,
+}
+
+impl<'a> Search<'a> {
+    /// Solves `grid`, where `depth` is the number of guesses it is based on.
+    fn solve(&mut self, mut grid: Grid, depth: usize) -> Option<Grid> {
+        loop {
+            if find_contradiction(&grid).is_some() || !self.variant.allows(&grid) {
+                return None;
+            }
+            if grid.is_complete() {
+                return Some(grid);
+            }
+            let start = Instant::now();
+            match find_step(&grid, self.config) {
+                Some(mut step) => {
+                    step.elapsed = start.elapsed();
+                    event!(DEBUG, technique = step.technique.id(), step = %step, "applied technique");
+                    step.apply(&mut grid);
+                    for &(row, col, digit) in step.placements.iter() {
+                        self.observer.on_placement(row, col, digit);
+                    }
+                    for &(row, col, digit) in step.eliminations.iter() {
+                        self.observer.on_elimination(row, col, digit);
+                    }
+                    self.steps.push(step);
+                }
+                None => break,
+            }
+        }
+
+        // We're stuck so we need to guess. Guess in the cell with the fewest
+        // candidates to keep the search small.
+        if !self.config.is_enabled(Technique::Guess) {
+            return None;
+        }
+        let start = Instant::now();
+        let mut guess_cell = None;
+        let mut fewest = 10;
+        for row in 0..9 {
+            for col in 0..9 {
+                if grid.value(row, col).is_some() {
+                    continue;
+                }
+                let candidates = self.guess_candidates(&grid, row, col);
+                if candidates.count_ones() < fewest {
+                    fewest = candidates.count_ones();
+                    guess_cell = Some((row, col, candidates));
+                }
+            }
+        }
+        let (row, col, candidates) = guess_cell?;
+        let elapsed = start.elapsed();
+        self.guess_depth = self.guess_depth.max(depth + 1);
+        for digit in mask_digits(candidates) {
+            let num_steps = self.steps.len();
+            let mut guess = Step::placement(Technique::Guess, Vec::new(), row, col, digit);
+            guess.elapsed = elapsed;
+            let mut branch = grid;
+            event!(DEBUG, cell = %crate::grid::cell_name(row, col), digit, depth = depth + 1, "guess");
+            guess.apply(&mut branch);
+            self.observer.on_guess(row, col, digit);
+            self.steps.push(guess);
+            self.guesses += 1;
+            if let Some(solved) = self.solve(branch, depth + 1) {
+                return Some(solved);
+            }
+            // Wrong guess, forget about everything that followed from it.
+            event!(DEBUG, cell = %crate::grid::cell_name(row, col), digit, depth = depth + 1, "backtrack");
+            self.steps.truncate(num_steps);
+            self.observer.on_backtrack(row, col);
+        }
+        None
+    }
+
+    /// Returns the candidates of the cell worth guessing. For variants, these
+    /// are only the ones the variant's constraints allow, which keeps the
+    /// search from wandering far before finding out a guess was wrong.
+    fn guess_candidates(&self, grid: &Grid, row: usize, col: usize) -> Mask {
+        let candidates = grid.candidates(row, col);
+        if self.variant.is_classic() {
+            return candidates;
+        }
+        mask_digits(candidates)
+            .filter(|&digit| {
+                let mut branch = *grid;
+                branch.place(row, col, digit);
+                self.variant.allows(&branch)
+            })
+            .fold(0, |mask, digit| mask | digit_mask(digit))
+    }
+}
+
+/// Returns the next step using the most preferred technique that applies.
+pub(crate) fn find_step(grid: &Grid, config: &TechniqueConfig) -> Option<Step> {
+    config
+        .techniques()
+        .iter()
+        .filter(|technique| !technique.needs_uniqueness() || config.assumes_unique())
+        .find_map(|&technique| find_technique(grid, technique))
+}
+
+pub(crate) fn find_technique(grid: &Grid, technique: Technique) -> Option<Step> {
+    match technique {
+        Technique::NakedSingle => find_naked_single(grid),
+        Technique::HiddenSingle => find_hidden_single(grid),
+        Technique::LockedCandidates => find_locked_candidates(grid),
+        Technique::NakedPair => find_naked_pair(grid),
+        Technique::HiddenPair => find_hidden_pair(grid),
+        Technique::XWing => find_x_wing(grid),
+        Technique::XChain | Technique::XYChain => find_chain(grid, technique),
+        Technique::BugPlusOne => find_bug_plus_one(grid),
+        Technique::ForcingChain => find_forcing_chain(grid),
+        // Guesses are made by the solver when everything else fails.
+        Technique::Guess => None,
+    }
+}
+
+fn find_naked_single(grid: &Grid) -> Option<Step> {
+    for row in 0..9 {
+        for col in 0..9 {
+            let candidates = grid.candidates(row, col);
+            if candidates.count_ones() == 1 {
+                let digit = mask_digits(candidates).next().unwrap();
+                return Some(Step::placement(Technique::NakedSingle, Vec::new(), row, col, digit));
+            }
+        }
+    }
+    None
+}
+
+fn find_hidden_single(grid: &Grid) -> Option<Step> {
+    for house in House::all() {
+        for digit in 1..10 {
+            let places = grid.places_for(house, digit);
+            if places.len() == 1 {
+                let (row, col) = places[0];
+                return Some(Step::placement(Technique::HiddenSingle, vec![house], row, col, digit));
+            }
+        }
+    }
+    None
+}
+
+/// Returns the candidates for `digit` in `cells` that can be eliminated,
+/// skipping the cells in `except`.
+fn eliminations(
+    grid: &Grid,
+    cells: &[(usize, usize)],
+    except: &[(usize, usize)],
+    digit: i8,
+) -> Vec<(usize, usize, i8)> {
+    cells
+        .iter()
+        .filter(|cell| !except.contains(cell))
+        .filter(|&&(r, c)| grid.candidates(r, c) & digit_mask(digit) != 0)
+        .map(|&(r, c)| (r, c, digit))
+        .collect()
+}
+
+/// Finds a digit whose places in a box are confined to a single row or column
+/// (pointing), or whose places in a row or column are confined to a single box
+/// (claiming). The digit can then be eliminated from the rest of the other
+/// house.
+fn find_locked_candidates(grid: &Grid) -> Option<Step> {
+    for house in House::all() {
+        for digit in 1..10 {
+            let places = grid.places_for(house, digit);
+            if places.len() < 2 {
+                continue;
+            }
+            let (row, col) = places[0];
+            let others = match house {
+                House::Box(_) if places.iter().all(|&(r, _)| r == row) => vec![House::Row(row)],
+                House::Box(_) if places.iter().all(|&(_, c)| c == col) => vec![House::Col(col)],
+                House::Row(_) | House::Col(_) => {
+                    let b = House::containing(row, col)[2];
+                    if places.iter().all(|&(r, c)| House::containing(r, c)[2] == b) {
+                        vec![b]
+                    } else {
+                        Vec::new()
+                    }
+                }
+                _ => Vec::new(),
+            };
+            for other in others {
+                let eliminations = eliminations(grid, &other.cells(), &places, digit);
+                if !eliminations.is_empty() {
+                    return Some(Step {
+                        technique: Technique::LockedCandidates,
+                        houses: vec![house, other],
+                        cells: places,
+                        placements: Vec::new(),
+                        eliminations,
+                        chain: Vec::new(),
+                        refutation: None,
+                        elapsed: Duration::default(),
+                    });
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Finds two cells in a house with the same two candidates. Those digits must
+/// go in these two cells, so they can be eliminated from the rest of the house.
+fn find_naked_pair(grid: &Grid) -> Option<Step> {
+    for house in House::all() {
+        let cells = house.cells();
+        for (i, &(r1, c1)) in cells.iter().enumerate() {
+            let pair = grid.candidates(r1, c1);
+            if pair.count_ones() != 2 {
+                continue;
+            }
+            for &(r2, c2) in cells[i + 1..].iter() {
+                if grid.candidates(r2, c2) != pair {
+                    continue;
+                }
+                let defining = vec![(r1, c1), (r2, c2)];
+                let mut elims = Vec::new();
+                for digit in mask_digits(pair) {
+                    elims.extend(eliminations(grid, &cells, &defining, digit));
+                }
+                if !elims.is_empty() {
+                    return Some(Step {
+                        technique: Technique::NakedPair,
+                        houses: vec![house],
+                        cells: defining,
+                        placements: Vec::new(),
+                        eliminations: elims,
+                        chain: Vec::new(),
+                        refutation: None,
+                        elapsed: Duration::default(),
+                    });
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Finds two digits that can only go in the same two cells of a house. Other
+/// candidates can then be eliminated from those cells.
+fn find_hidden_pair(grid: &Grid) -> Option<Step> {
+    for house in House::all() {
+        for d1 in 1..10 {
+            let places = grid.places_for(house, d1);
+            if places.len() != 2 {
+                continue;
+            }
+            for d2 in d1 + 1..10 {
+                if grid.places_for(house, d2) != places {
+                    continue;
+                }
+                let pair = digit_mask(d1) | digit_mask(d2);
+                let mut elims = Vec::new();
+                for &(r, c) in places.iter() {
+                    for digit in mask_digits(grid.candidates(r, c) & !pair) {
+                        elims.push((r, c, digit));
+                    }
+                }
+                if !elims.is_empty() {
+                    return Some(Step {
+                        technique: Technique::HiddenPair,
+                        houses: vec![house],
+                        cells: places,
+                        placements: Vec::new(),
+                        eliminations: elims,
+                        chain: Vec::new(),
+                        refutation: None,
+                        elapsed: Duration::default(),
+                    });
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Finds two rows (or columns) in which a digit can only go in the same two
+/// columns (or rows). The digit then must be in those corners, so it can be
+/// eliminated from the rest of the two columns (or rows).
+fn find_x_wing(grid: &Grid) -> Option<Step> {
+    for digit in 1..10 {
+        for &by_rows in [true, false].iter() {
+            let line = |i| if by_rows { House::Row(i) } else { House::Col(i) };
+            let cross = |i| if by_rows { House::Col(i) } else { House::Row(i) };
+            // The positions of the digit along a line, e.g. the columns of a
+            // row.
+            let positions = |i| -> Vec<usize> {
+                grid.places_for(line(i), digit)
+                    .iter()
+                    .map(|&(r, c)| if by_rows { c } else { r })
+                    .collect()
+            };
+            for i in 0..9 {
+                let first = positions(i);
+                if first.len() != 2 {
+                    continue;
+                }
+                for j in i + 1..9 {
+                    if positions(j) != first {
+                        continue;
+                    }
+                    let mut defining = grid.places_for(line(i), digit);
+                    defining.extend(grid.places_for(line(j), digit));
+                    let mut elims = Vec::new();
+                    for &p in first.iter() {
+                        elims.extend(eliminations(grid, &cross(p).cells(), &defining, digit));
+                    }
+                    if !elims.is_empty() {
+                        return Some(Step {
+                            technique: Technique::XWing,
+                            houses: vec![line(i), line(j), cross(first[0]), cross(first[1])],
+                            cells: defining,
+                            placements: Vec::new(),
+                            eliminations: elims,
+                            chain: Vec::new(),
+                            refutation: None,
+                            elapsed: Duration::default(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{default_board, parse_board};
+
+    /// Checks that every house of `solution` has all nine digits and that it
+    /// agrees with the givens of `board`.
+    fn assert_solves(board: &Board, solution: &Board) {
+        for house in House::all() {
+            let mut seen = 0;
+            for &(r, c) in house.cells().iter() {
+                seen |= digit_mask(solution[r][c].solution().unwrap());
+                if let Some(given) = board[r][c].solution() {
+                    assert_eq!(solution[r][c].solution(), Some(given));
+                }
+            }
+            assert_eq!(seen, crate::grid::ALL_DIGITS);
+        }
+    }
+
+    #[test]
+    fn test_solve_logically() {
+        let board = default_board();
+        let trace = solve_logically(&board);
+        assert_eq!(trace.usage().count(Technique::Guess), 0);
+        assert_solves(&board, &trace.solution.unwrap());
+
+        // Arto Inkala's puzzle can't be solved without guessing.
+        let board = parse_board(
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..",
+        )
+        .unwrap();
+        let trace = solve_logically(&board);
+        assert!(trace.usage().count(Technique::Guess) > 0);
+        assert_solves(&board, &trace.solution.unwrap());
+    }
+
+    #[test]
+    fn test_technique_config() {
+        let board = parse_board(
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..",
+        )
+        .unwrap();
+        let mut config = TechniqueConfig::default();
+        config.disable(Technique::Guess);
+        assert!(solve_with(&board, &config).solution.is_none());
+
+        let config = TechniqueConfig::new(vec![Technique::HiddenSingle, Technique::NakedSingle]);
+        assert_eq!(config.techniques(), &[Technique::HiddenSingle, Technique::NakedSingle]);
+        let trace = solve_with(&default_board(), &config);
+        assert_eq!(trace.steps[0].technique, Technique::HiddenSingle);
+        assert!(trace.solution.is_some());
+    }
+
+    #[test]
+    fn test_usage_report() {
+        let trace = solve_logically(&default_board());
+        let usage = trace.usage();
+        let text = usage.to_string();
+        assert!(text.starts_with("Technique"));
+        assert!(text.ends_with(&format!("{:>6}", usage.total())));
+        let json = usage.to_json().to_string();
+        assert!(json.starts_with(&format!("{{\"naked_single\":{}", usage.count(Technique::NakedSingle))));
+    }
+
+    #[test]
+    fn test_trace_json() {
+        let trace = solve_logically(&default_board());
+        let step = &trace.steps[0];
+        assert_eq!(step.to_string(), "Naked single: r2c4=9");
+        let json = step.to_json().to_string();
+        assert!(json.starts_with(
+            r#"{"technique":"naked_single","houses":[],"cells":[[1,3]],"placements":[{"row":1,"col":3,"digit":9}],"eliminations":[],"elapsed_us":"#
+        ));
+        let json = trace.to_json().to_string();
+        assert!(json.starts_with(r#"{"solved":true,"solution":"365278149"#));
+        assert!(json.contains(r#""initial":{"values":"..5..8....2"#));
+
+        // Replaying the first step must place its digit.
+        assert_eq!(trace.grid_before(0).value(1, 3), None);
+        assert_eq!(trace.grid_after(0).value(1, 3), Some(9));
+        let solution = trace.solution.unwrap();
+        assert_eq!(board_to_string(&trace.grid_before(trace.steps.len()).to_board()), board_to_string(&solution));
+        assert_eq!(solution[1][3].origin(), Some(Origin::Deduced));
+        assert_eq!(solution[0][2].origin(), Some(Origin::Given));
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_tracing_events() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata, Subscriber};
+
+        /// Counts the guesses and backtracks reported.
+        struct Counter(Arc<AtomicUsize>);
+
+        impl Subscriber for Counter {
+            fn enabled(&self, _: &Metadata) -> bool {
+                true
+            }
+            fn new_span(&self, _: &Attributes) -> Id {
+                Id::from_u64(1)
+            }
+            fn record(&self, _: &Id, _: &Record) {}
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+            fn event(&self, event: &Event) {
+                if event.metadata().level() == &tracing::Level::DEBUG {
+                    self.0.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+            fn enter(&self, _: &Id) {}
+            fn exit(&self, _: &Id) {}
+        }
+
+        let count = Arc::new(AtomicUsize::new(0));
+        let board = parse_board(
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..",
+        )
+        .unwrap();
+        tracing::subscriber::with_default(Counter(count.clone()), || solve_logically(&board));
+        assert!(count.load(Ordering::SeqCst) > 1);
+    }
+}
@@ -0,0 +1,20 @@
+/// Emits a `tracing` event at `$level` if the `tracing` feature is enabled,
+/// and compiles to nothing otherwise, so the solver pays nothing for it by
+/// default.
+macro_rules! event {
+    ($level:ident, $($arg:tt)+) => {
+        #[cfg(feature = "tracing")]
+        tracing::event!(tracing::Level::$level, $($arg)+);
+    };
+}
+
+/// Runs `$check` if the `debug-invariants` feature is enabled, and compiles
+/// to nothing otherwise. It's for checks of the internal state that are too
+/// slow to leave in debug builds, to catch solver bugs when developing and
+/// fuzzing.
+macro_rules! invariant {
+    ($check:expr) => {
+        #[cfg(feature = "debug-invariants")]
+        $check;
+    };
+}
@@ -1,480 +1,1799 @@
-use std::collections::HashSet;
+use std::env;
+use std::fs::{File, OpenOptions};
+use std::path::PathBuf;
+use std::io::{self, BufRead, BufReader, IsTerminal, LineWriter, Write};
+use std::process;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-fn main() {
-    let board = default_board();
-    print_board(&board);
-    let mut sudoku = Sudoku::new(board);
-    if let Some(solved_board) = sudoku.solve() {
-        print_board(&solved_board);
-    } else {
-        println!("No solution found.");
-    }
-}
+use sudoku::config::Config;
+use sudoku::{
+    analysis_csv, analyze_puzzle, best_path, board_graphics, board_to_string, chat, check_puzzle, clipboard,
+    completion_script, count_solutions, cross_validate, daily_puzzle, dedupe, default_board, digit_scan,
+    estimate_solutions, fpuzzles_json, generate_adversarial, generate_with, givens_pattern, hardest_by_difficulty,
+    hardest_by_effort, hint, histogram, mine_examples, parse_board, parse_board_with, parse_fpuzzles, parse_hsol,
+    parse_references, parse_sdk, preflight, print_board_with, rate_variant, render_candidate_diff_text,
+    render_diff_text, render_step_text, replace_clue, rotation_pack, serve_lines, solve_stream_with, solve_variant,
+    solve_with, to_hsol, tune, validate, Backend, BatchStats, Board, Catalog, ChatStyle, Checkpoint, Date, Difficulty,
+    Enumeration, Graphics, Grid, GridLayout, HintLevel, HsolCheck, HsolEntry, Json, LexCompletions, Metadata,
+    ParseError, ParseMode, PathGoal, ReportFormat, ReportRow, Rng, ScoreTable, Shell, Solution, SolveOptions, Sudoku,
+    SymbolSet, Technique, TechniqueConfig, TuningProfile, Variant,
+};
+
+const USAGE: &str = "\
+Usage: sudoku [COMMAND] [OPTIONS] [PUZZLE]
+
+Without a command, solves and prints the built-in example puzzle. PUZZLE is
+an 81 character string with `.` or `0` for empty cells, the path of a
+`.sdk` file, whose title, author, and so on are printed as a header, or is
+read from the clipboard with --paste. `solve` and `rate`
+also take the path of a `.json` file in the f-puzzles format, which may have
+diagonals, killer cages, thermometers, and arrows.
+
+Commands:
+    solve PUZZLE     Solve the puzzle and print the solution
+    generate         Print a new puzzle, or --count of them, saving progress
+                     to --checkpoint FILE and resuming from it if it exists
+    daily [DATE]     Print the puzzle of the day of --difficulty for DATE,
+                     written as YYYY-MM-DD, or for today in UTC
+    hint PUZZLE      Print a hint for the next step
+    rate PUZZLE      Print the hardest technique and the guesses needed
+    report PUZZLE    Print how many times each technique was needed
+    pattern PUZZLE   Print where the givens are, their symmetry, how many
+                     each band and stack has, and which are lopsided
+    pencilmarks PUZZLE
+                     Print the candidates of every cell as a line of 729
+                     characters, the way other solvers exchange them
+    digits PUZZLE    Print how often each digit is placed and the fewest
+                     places it has left in a row, column, or box
+    count PUZZLE     Count every solution of the puzzle, saving progress to
+                     --checkpoint FILE and resuming from it if it exists, or
+                     with --estimate N estimate them from N random probes
+    solutions PUZZLE Print the solutions of the puzzle in the order of their
+                     strings, from the first at least --from PREFIX, at most
+                     --limit of them
+    check PUZZLE     Check that the givens don't conflict and that the puzzle
+                     has exactly one solution
+    diff OLD NEW     Print NEW with the cells that differ from OLD marked, or
+                     with --candidates the pencil marks they rule out
+    analyze FILE     Rate every puzzle of a file with one per line and print
+                     how many there are of each difficulty, or with --hardest
+                     the hardest to solve by hand and by search, or with
+                     --histogram how they're spread over difficulties and
+                     hardest techniques, saving progress to --checkpoint FILE
+    mine TECHNIQUE FILE
+                     Print the positions of the puzzles of a file with one
+                     per line, or of stdin for `-`, where TECHNIQUE, e.g.
+                     x_wing, is the only way forward, as examples in HoDoKu's
+                     library format, at most --limit of them
+    tune FILE        Time the search with each cell and value order on the
+                     puzzles of a file with one per line, print how each
+                     did, and write the fastest as a profile to --output FILE
+    hsol FILE        Check that the techniques find the examples of a
+                     library in HoDoKu's format, and list those they don't
+    crossval FILE    Compare the scores of `rate` with the ratings of another
+                     grader, given after each puzzle of a file with one per
+                     line, and print how well they correlate and agree
+    batch FILE       Solve every puzzle of a file with one per line, or of
+                     stdin for `-`, printing a line for each as soon as it's
+                     solved, or writing them to --output FILE, which a run
+                     that was stopped resumes from
+    dedupe FILE      Drop the puzzles of a file with one per line that are
+                     already in it, writing the rest to --output FILE or
+                     printing them
+    trace PUZZLE     Print the steps taken to solve the puzzle
+    replace PUZZLE CELL
+                     List the cells the given at CELL, e.g. r1c3, can move to
+                     with the puzzle still unique and as hard, at most --limit
+    pack PUZZLE      Print the 8 rotations and reflections of the puzzle, and
+                     --relabel N copies of each with the digits shuffled, one
+                     per line with its name
+    fpuzzles PUZZLE  Print the puzzle in the f-puzzles format
+    grids FILE       Print the grids of a text dump laid out as --layout FILE
+                     says, one per line
+    rpc              Answer JSON requests read from stdin, one per line
+    chat MESSAGE     Reply to a chat bot command such as `solve PUZZLE` or
+                     `generate hard`, with boards drawn for Discord and Slack
+    serve            Serve the solver over HTTP, and with --ui a page to play
+                     on (needs the `server` feature)
+    race             Pair up players connecting over TCP and have them race
+                     to solve the same puzzle of --difficulty (needs the
+                     `server` feature)
+    qr PUZZLE        Print a QR code of the puzzle, or write it to --png FILE
+                     (needs the `qr` feature)
+    ocr IMAGE        Read a puzzle from a photo or scan and list the cells that
+                     may be misread (needs the `ocr` feature)
+    db import FILE   Add the puzzles of a pack, one per line, or of a `.sdk`
+                     file with its metadata, to the database
+    db list          List puzzles in the database, filtered by --difficulty
+    db export        Print the puzzles db list would list in the format of
+                     the Sudoku Exchange Puzzle Bank
+    db play ID       Mark a puzzle as played, taking --seconds N
+    db stats         Print how many puzzles of each difficulty were played
+    db campaign      Print the progress through the bundled puzzles from easy
+                     to expert and the next one to play; a difficulty is
+                     unlocked once the ones before are all played
+                     (the db commands need the `db` feature)
+    completions SHELL
+                     Print the completion script for bash, zsh, fish, or
+                     powershell
+
+Options:
+    --difficulty LEVEL     Difficulty of generated puzzles: easy, medium,
+                           hard, or expert (default: medium)
+    --seed N               Seed for the puzzle generator
+    --count N              How many puzzles to generate (default: 1)
+    --adversarial          Generate a puzzle that's as slow as possible for a
+                           naive backtracking search, for benchmarks
+    --rounds N             How many variations --adversarial tries
+                           (default: 100)
+    --relabel N            How many copies of each rotation `pack` shuffles
+                           the digits of, with --seed (default: 0)
+    --json                 Print reports and traces as JSON
+    --ascii                Print boards as text even if the terminal can show
+                           images
+    --emoji                Draw chat replies with emoji instead of a code block
+    --paste                Read the puzzle from the clipboard
+    --symbols NAME         Read puzzles and print boards with digits, letters
+                           (A to I), or emoji instead of 1 to 9
+    --lenient              Take `_`, `*`, and `x` as empty cells in puzzles
+                           and skip other characters, like grid lines
+    --layout FILE          Read PUZZLE as the path of a text dump laid out as
+                           FILE says, with its cell width, separators, blanks,
+                           and comment lines, and `grids` every grid of it
+    --copy                 Put the solution, or the generated puzzle, on the
+                           clipboard
+    --boards               Print the pencil marks before each step of a trace
+    --hsol                 Print the steps of a trace as examples in HoDoKu's
+                           library format
+    --shortest             Trace the path with the fewest steps rather than
+                           the first steps found, without guessing
+    --simplest             Trace the path with the easiest hardest technique,
+                           without guessing
+    --candidates           Print the candidates a `diff` eliminates and the
+                           cells it fills in
+    --level N              How much a hint reveals, from 1 (where to look) to
+                           3 (the answer)
+    --techniques LIST      Comma separated techniques to use, in order of
+                           preference (e.g. naked_single,hidden_single,guess)
+    --assume-unique        Let the solver assume puzzles have one solution,
+                           which allows uniqueness techniques like BUG+1
+    --listen ADDR          Address to serve on (default: 127.0.0.1:8080)
+    --ui                   Serve a playable page at / as well
+    --png FILE             Write the QR code as a PNG image
+    --pdf FILE             Also write the puzzles of `generate` to a printable
+                           PDF, --per-page N of them on each page (default: 4),
+                           and with --solutions their solutions after them
+                           (needs the `pdf` feature)
+    --fix LIST             Comma separated corrections of misread cells, e.g.
+                           r1c2=5,r4c4=0 where 0 empties the cell
+    --db PATH              Puzzle database (default: sudoku.db)
+    --unplayed             List only puzzles that haven't been played
+    --limit N              List at most N puzzles (default: 20)
+    --from PREFIX          Where `solutions` starts, e.g. the first digits of
+                           a shard of the solutions
+    --seconds N            How long playing a puzzle took
+    --estimate N           Estimate the solutions of `count` from N probes,
+                           with 95% confidence bounds, for grids with too few
+                           clues to count
+    --checkpoint FILE      Where `count`, `generate`, and `analyze` save
+                           their progress
+    --hardest              List the hardest puzzles, at most --limit of them
+    --csv FILE             Write the ratings of `analyze` to a CSV file, or
+                           the histogram with --histogram
+    --histogram            Print the spread of difficulties of `analyze`
+    --locale FILE          Translations of hints, traces, and messages, as
+                           `id = \"template\"` lines (default: English)
+    --scores FILE          Weights of the techniques for the scores of `rate`
+                           and `crossval`, as `technique = weight` lines
+    --report FORMAT        Print a row per puzzle of `rate` or `analyze` as csv
+                           or json, with its clues, difficulty, techniques,
+                           solve time, and backtracks
+    --output FILE          Where `dedupe` writes the puzzles it keeps, `batch`
+                           the solutions, or `tune` the profile
+    --isomorphic           Also take puzzles that are the same up to swapping
+                           rows, columns, and digits as duplicates
+    --backend NAME         How `solve` searches: backtracking, dlx, or sat
+                           (default: backtracking)
+    --profile FILE         The cell and value order and restarts of `solve`,
+                           `count`, and `batch`, as written by `tune`
+    --restarts GUESSES     Restart the search of `solve`, `count`, and `batch`
+                           after GUESSES guesses, then on a Luby schedule, with
+                           ties broken at random
+    --max-memory BYTES     Fail rather than start a search of `solve` or
+                           `count` that may need more memory, e.g. 64K or 1M
+    --format FORMAT        Print reports and traces as text or json
+    --color WHEN           Color diffs and traces: auto, always, or never
+                           (default: auto, when printing to a terminal)
+    -v, --verbose          Log guesses and the techniques applied, or with
+                           -vv every placement and backtrack too (needs the
+                           `logging` feature)
+    -q, --quiet            Print nothing to stderr but errors
+    --validate-only        Only read the puzzles a command takes and report
+                           the lines that aren't puzzles or whose givens
+                           conflict, without solving or writing anything
+    --unique               With --validate-only, also report puzzles without
+                           exactly one solution
+
+Defaults for format, color, difficulty, techniques, backend, profile,
+symbols, db, listen, limit, scores, and locale are read from ~/.config/sudoku/config.toml, or
+the file in $SUDOKU_CONFIG, as `key = value` lines, e.g. `difficulty =
+\"hard\"` or `techniques = [\"naked_single\", \"guess\"]`. Environment variables
+like SUDOKU_DIFFICULTY=hard override the file, and options override both.";
+
+/// The options that can be given defaults in the configuration file and in
+/// `SUDOKU_*` environment variables, by key, with their flag.
+const SETTINGS: [(&str, &str); 12] = [
+    ("format", "--format"),
+    ("color", "--color"),
+    ("difficulty", "--difficulty"),
+    ("techniques", "--techniques"),
+    ("backend", "--backend"),
+    ("profile", "--profile"),
+    ("symbols", "--symbols"),
+    ("db", "--db"),
+    ("listen", "--listen"),
+    ("limit", "--limit"),
+    ("scores", "--scores"),
+    ("locale", "--locale"),
+];
 
-/// Represents a cell in a sudoku board. It may be solved, in which case
-/// `solution` needs to be some number, and `candidates`, `candidate`, and
-/// `candidate_idx` need be None; or it's unsolved in which case the above
-/// relationship is reversed.
-#[derive(Debug, Clone, Eq, PartialEq)]
-pub struct Cell {
-    solution: Option<i8>,
-    candidates: HashSet<i8>,
-    candidate: Option<i8>,
-    candidate_idx: Option<usize>,
+/// The parsed command line.
+struct Args {
+    command: Option<String>,
+    puzzle: Option<String>,
+    /// Positional arguments after the puzzle, for commands that take more.
+    operands: Vec<String>,
+    json: bool,
+    difficulty: Option<Difficulty>,
+    seed: Option<u64>,
+    count: usize,
+    boards: bool,
+    hsol: bool,
+    path_goal: Option<PathGoal>,
+    candidates: bool,
+    ascii: bool,
+    paste: bool,
+    copy: bool,
+    emoji: bool,
+    level: HintLevel,
+    techniques: TechniqueConfig,
+    listen: String,
+    png: Option<String>,
+    pdf: Option<String>,
+    per_page: usize,
+    solutions: bool,
+    fixes: Vec<(usize, usize, Option<i8>)>,
+    db: String,
+    unplayed: bool,
+    limit: usize,
+    seconds: Option<u64>,
+    checkpoint: Option<String>,
+    from: Option<String>,
+    estimate: Option<usize>,
+    hardest: bool,
+    histogram: bool,
+    adversarial: bool,
+    ui: bool,
+    symbols: SymbolSet,
+    /// Whether to color output, or None to color it only for terminals.
+    color: Option<bool>,
+    rounds: usize,
+    relabel: usize,
+    csv: Option<String>,
+    report: Option<ReportFormat>,
+    scores: ScoreTable,
+    layout: Option<GridLayout>,
+    catalog: Catalog,
+    output: Option<String>,
+    isomorphic: bool,
+    lenient: bool,
+    validate_only: bool,
+    unique: bool,
+    backend: Backend,
+    profile: TuningProfile,
+    max_memory: Option<usize>,
+    /// How much to log: -1 for only errors, 0 by default, and 1 or 2 for
+    /// the solver's debug or trace events.
+    verbosity: i8,
 }
 
-impl Cell {
-    pub fn solved(solution: i8) -> Cell {
-        Cell {
-            solution: Some(solution),
-            candidates: HashSet::new(),
-            candidate: None,
-            candidate_idx: None,
+impl Args {
+    fn parse() -> Args {
+        let mut args = Args {
+            command: None,
+            puzzle: None,
+            operands: Vec::new(),
+            json: false,
+            difficulty: None,
+            seed: None,
+            count: 1,
+            boards: false,
+            hsol: false,
+            path_goal: None,
+            candidates: false,
+            ascii: false,
+            paste: false,
+            copy: false,
+            emoji: false,
+            level: HintLevel::Area,
+            techniques: TechniqueConfig::default(),
+            listen: String::from("127.0.0.1:8080"),
+            png: None,
+            pdf: None,
+            per_page: 4,
+            solutions: false,
+            fixes: Vec::new(),
+            db: String::from("sudoku.db"),
+            unplayed: false,
+            limit: 20,
+            seconds: None,
+            checkpoint: None,
+            from: None,
+            estimate: None,
+            hardest: false,
+            histogram: false,
+            adversarial: false,
+            ui: false,
+            symbols: SymbolSet::digits(),
+            color: None,
+            rounds: 100,
+            relabel: 0,
+            csv: None,
+            report: None,
+            scores: ScoreTable::default(),
+            layout: None,
+            catalog: Catalog::default(),
+            output: None,
+            isomorphic: false,
+            lenient: false,
+            validate_only: false,
+            unique: false,
+            backend: Backend::Backtracking,
+            profile: TuningProfile::default(),
+            max_memory: None,
+            verbosity: 0,
+        };
+        // The defaults come first, so that the options given override them.
+        let mut iter = defaults().into_iter().chain(env::args().skip(1));
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--json" => args.json = true,
+                "--boards" => args.boards = true,
+                "--hsol" => args.hsol = true,
+                "--shortest" => args.path_goal = Some(PathGoal::FewestSteps),
+                "--simplest" => args.path_goal = Some(PathGoal::SimplestTechniques),
+                "--candidates" => args.candidates = true,
+                "--ascii" => args.ascii = true,
+                "--paste" => args.paste = true,
+                "--copy" => args.copy = true,
+                "--emoji" => args.emoji = true,
+                "--unplayed" => args.unplayed = true,
+                "--hardest" => args.hardest = true,
+                "--histogram" => args.histogram = true,
+                "--adversarial" => args.adversarial = true,
+                "--ui" => args.ui = true,
+                "--lenient" => args.lenient = true,
+                "--isomorphic" => args.isomorphic = true,
+                "--validate-only" => args.validate_only = true,
+                "--unique" => args.unique = true,
+                "--assume-unique" => args.techniques.assume_unique(true),
+                "--difficulty" => {
+                    let name = iter.next().unwrap_or_default();
+                    match Difficulty::from_name(&name) {
+                        Some(difficulty) => args.difficulty = Some(difficulty),
+                        None => fail(&format!("Unknown difficulty `{}`.", name)),
+                    }
+                }
+                "--seed" => match iter.next().and_then(|seed| seed.parse().ok()) {
+                    Some(seed) => args.seed = Some(seed),
+                    None => fail("Expected a number as the seed."),
+                },
+                "--count" => match iter.next().and_then(|n| n.parse().ok()) {
+                    Some(count) => args.count = count,
+                    None => fail("Expected a number of puzzles to generate."),
+                },
+                "--level" => {
+                    let level = iter.next().and_then(|level| level.parse::<usize>().ok());
+                    match level {
+                        Some(level) if (1..=HintLevel::ALL.len()).contains(&level) => {
+                            args.level = HintLevel::ALL[level - 1];
+                        }
+                        _ => fail("Expected a hint level from 1 to 3."),
+                    }
+                }
+                "--techniques" => {
+                    let list = iter.next().unwrap_or_default();
+                    let assume_unique = args.techniques.assumes_unique();
+                    args.techniques = parse_techniques(&list);
+                    args.techniques.assume_unique(assume_unique);
+                }
+                "--listen" => match iter.next() {
+                    Some(addr) => args.listen = addr,
+                    None => fail("Expected an address to listen on."),
+                },
+                "--png" => match iter.next() {
+                    Some(path) => args.png = Some(path),
+                    None => fail("Expected a path to write the PNG to."),
+                },
+                "--pdf" => match iter.next() {
+                    Some(path) => args.pdf = Some(path),
+                    None => fail("Expected a path to write the PDF to."),
+                },
+                "--per-page" => match iter.next().and_then(|n| n.parse().ok()) {
+                    Some(per_page) if per_page > 0 => args.per_page = per_page,
+                    _ => fail("Expected a number of puzzles per page."),
+                },
+                "--solutions" => args.solutions = true,
+                "--fix" => {
+                    let list = iter.next().unwrap_or_default();
+                    args.fixes = parse_fixes(&list);
+                }
+                "--db" => match iter.next() {
+                    Some(path) => args.db = path,
+                    None => fail("Expected a path to the database."),
+                },
+                "--format" => match iter.next().as_deref() {
+                    Some("json") => args.json = true,
+                    Some("text") => args.json = false,
+                    _ => fail("Expected text or json as the format."),
+                },
+                "--color" => match iter.next().as_deref() {
+                    Some("auto") => args.color = None,
+                    Some("always") => args.color = Some(true),
+                    Some("never") => args.color = Some(false),
+                    _ => fail("Expected auto, always, or never as when to color."),
+                },
+                "--symbols" => {
+                    let name = iter.next().unwrap_or_default();
+                    match SymbolSet::from_name(&name) {
+                        Some(symbols) => args.symbols = symbols,
+                        None => fail(&format!("Unknown symbols `{}`.", name)),
+                    }
+                }
+                "--rounds" => match iter.next().and_then(|n| n.parse().ok()) {
+                    Some(rounds) => args.rounds = rounds,
+                    None => fail("Expected a number of rounds."),
+                },
+                "--relabel" => match iter.next().and_then(|n| n.parse().ok()) {
+                    Some(relabel) => args.relabel = relabel,
+                    None => fail("Expected a number of relabeled copies."),
+                },
+                "--limit" => match iter.next().and_then(|n| n.parse().ok()) {
+                    Some(limit) => args.limit = limit,
+                    None => fail("Expected a number as the limit."),
+                },
+                "--seconds" => match iter.next().and_then(|n| n.parse().ok()) {
+                    Some(seconds) => args.seconds = Some(seconds),
+                    None => fail("Expected a number of seconds."),
+                },
+                "--from" => match iter.next() {
+                    Some(prefix) => args.from = Some(prefix),
+                    None => fail("Expected the digits to start from."),
+                },
+                "--checkpoint" => match iter.next() {
+                    Some(path) => args.checkpoint = Some(path),
+                    None => fail("Expected a path to save the progress to."),
+                },
+                "--estimate" => match iter.next().and_then(|n| n.parse().ok()) {
+                    Some(probes) => args.estimate = Some(probes),
+                    None => fail("Expected a number of probes to estimate from."),
+                },
+                "--backend" => {
+                    let name = iter.next().unwrap_or_default();
+                    match Backend::from_name(&name) {
+                        Some(backend) => args.backend = backend,
+                        None => fail(&format!("Unknown backend `{}`.", name)),
+                    }
+                }
+                "--profile" => {
+                    let path = iter.next().unwrap_or_else(|| fail("Expected a path to read the profile from."));
+                    let text = std::fs::read_to_string(&path)
+                        .unwrap_or_else(|err| fail(&format!("Can't read {}: {}", path, err)));
+                    args.profile = TuningProfile::parse(&text)
+                        .unwrap_or_else(|err| fail(&format!("Can't read {}: {}.", path, err)));
+                }
+                "--restarts" => match iter.next().and_then(|n| n.parse().ok()) {
+                    Some(unit) => args.profile.restarts = Some(unit).filter(|&unit| unit > 0),
+                    None => fail("Expected a number of guesses to restart after."),
+                },
+                "--max-memory" => match iter.next().as_deref().and_then(parse_bytes) {
+                    Some(bytes) => args.max_memory = Some(bytes),
+                    None => fail("Expected a number of bytes as the memory limit, e.g. 64K or 1M."),
+                },
+                "--csv" => match iter.next() {
+                    Some(path) => args.csv = Some(path),
+                    None => fail("Expected a path to write the CSV to."),
+                },
+                "--report" => {
+                    let name = iter.next().unwrap_or_default();
+                    match ReportFormat::from_name(&name) {
+                        Some(format) => args.report = Some(format),
+                        None => fail("Expected csv or json as the report format."),
+                    }
+                }
+                "--scores" => {
+                    let path = iter.next().unwrap_or_else(|| fail("Expected a path to read the scores from."));
+                    let text = std::fs::read_to_string(&path)
+                        .unwrap_or_else(|err| fail(&format!("Can't read {}: {}", path, err)));
+                    args.scores = ScoreTable::parse(&text)
+                        .unwrap_or_else(|err| fail(&format!("Can't read {}: {}.", path, err)));
+                }
+                "--layout" => {
+                    let path = iter.next().unwrap_or_else(|| fail("Expected a path to read the layout from."));
+                    let text = std::fs::read_to_string(&path)
+                        .unwrap_or_else(|err| fail(&format!("Can't read {}: {}", path, err)));
+                    let layout =
+                        GridLayout::parse(&text).unwrap_or_else(|err| fail(&format!("Can't read {}: {}.", path, err)));
+                    args.layout = Some(layout);
+                }
+                "--locale" => {
+                    let path = iter.next().unwrap_or_else(|| fail("Expected a path to read the locale from."));
+                    let text = std::fs::read_to_string(&path)
+                        .unwrap_or_else(|err| fail(&format!("Can't read {}: {}", path, err)));
+                    args.catalog =
+                        Catalog::parse(&text).unwrap_or_else(|err| fail(&format!("Can't read {}: {}.", path, err)));
+                }
+                "--output" => match iter.next() {
+                    Some(path) => args.output = Some(path),
+                    None => fail("Expected a path to write the puzzles to."),
+                },
+                "-v" | "--verbose" => args.verbosity = args.verbosity.max(0) + 1,
+                "-vv" => args.verbosity = args.verbosity.max(0) + 2,
+                "-q" | "--quiet" => args.verbosity = -1,
+                "-h" | "--help" => {
+                    println!("{}", USAGE);
+                    process::exit(0);
+                }
+                // A lone `-` stands for stdin.
+                _ if arg.starts_with('-') && arg != "-" => fail(USAGE),
+                _ if args.command.is_none() => args.command = Some(arg),
+                _ if args.puzzle.is_none() => args.puzzle = Some(arg),
+                _ => args.operands.push(arg),
+            }
+        }
+        let takes_operands = matches!(args.command.as_deref(), Some("db" | "chat" | "diff" | "replace" | "mine"));
+        if !args.operands.is_empty() && !takes_operands {
+            fail(USAGE);
         }
+        args
     }
 
-    pub fn unsolved() -> Cell {
-        Cell {
-            solution: None,
-            candidates: HashSet::new(),
-            candidate: None,
-            candidate_idx: None,
+    /// Returns the options to solve with, or exits with an error if a search
+    /// that needs `memory_bytes` goes over the memory limit.
+    fn solve_options(&self, memory_bytes: usize) -> SolveOptions {
+        let mut options = self.profile.apply(SolveOptions::default().backend(self.backend));
+        if let Some(bytes) = self.max_memory {
+            options = options.max_memory_bytes(bytes);
+        }
+        if let Err(err) = options.check_memory(memory_bytes) {
+            fail(&format!("Can't search: {}.", err));
         }
+        options
     }
-}
 
-pub type Board = [[Cell; 9]; 9];
+    /// Prints a message that isn't an error to stderr, unless `-q` is given.
+    fn note(&self, message: &str) {
+        if self.verbosity >= 0 {
+            eprintln!("{}", message);
+        }
+    }
 
-pub struct Sudoku {
-    board: Board,
-    blocks: [Block; 9],
-}
+    /// Returns whether to color the output.
+    fn color(&self) -> bool {
+        self.color.unwrap_or_else(|| io::stdout().is_terminal())
+    }
 
-impl Sudoku {
-    pub fn new(board: Board) -> Sudoku {
-        let blocks = make_blocks(&board);
-        Sudoku {
-            board: board,
-            blocks: blocks,
+    /// Returns the protocol to show boards as images with, if the terminal
+    /// supports one and they weren't turned off.
+    fn graphics(&self) -> Option<Graphics> {
+        if self.ascii || !io::stdout().is_terminal() {
+            None
+        } else {
+            Graphics::detect()
         }
     }
 
-    /// If the board passed to the constructor is solvable, it returns a copy of
-    /// the solved board. If it's unsolvable, None is returned.
-    pub fn solve(&mut self) -> Option<Board> {
-        self.find_candidates();
-        self.guess_solutions()
+    /// Parses the puzzle argument, or the clipboard with `--paste`, or exits
+    /// with an error.
+    fn board(&self) -> Board {
+        if self.paste {
+            let text = clipboard::paste().unwrap_or_else(|err| fail(&format!("Can't read the clipboard: {}", err)));
+            return match parse_board(&text) {
+                Some(board) => board,
+                None => fail("The clipboard doesn't hold a puzzle of 81 digits."),
+            };
+        }
+        if let Some((board, _)) = self.sdk() {
+            return board;
+        }
+        if self.layout.is_some() {
+            return self.dump().into_iter().next().unwrap_or_else(|| fail("The dump has no grid."));
+        }
+        self.parse_board(self.puzzle.as_deref().unwrap_or_else(|| fail(USAGE)))
     }
 
-    /// Narrows down the search-space by assigning valid candidates to each cell
-    /// and marks cells as solved that only have a single candidate.
-    fn find_candidates(&mut self) {
-        for row in 0..9 {
-            for col in 0..9 {
-                // Skip solved cells.
-                if self.board[row][col].solution.is_some() {
-                    continue;
-                }
+    /// Reads the puzzle argument as a `.sdk` file, if it's the path of one.
+    fn sdk(&self) -> Option<(Board, Metadata)> {
+        let path = self.puzzle.as_deref().filter(|path| path.ends_with(".sdk") && !self.paste)?;
+        Some(read_sdk(path))
+    }
 
-                let candidates = self.find_cell_candidates(row, col);
-                if candidates.len() == 1 {
-                    // We have a solution for this cell.
-                    let solution = *candidates.iter().next().unwrap();
-                    self.found_solution(row, col, solution);
-                } else if !candidates.is_empty() {
-                    self.board[row][col].candidates = candidates;
-                }
+    /// Reads the grids of the text dump at the path of PUZZLE as laid out by
+    /// `--layout`, or exits with an error.
+    fn dump(&self) -> Vec<Board> {
+        let path = self.puzzle.as_deref().unwrap_or_else(|| fail(USAGE));
+        let layout = self.layout.as_ref().unwrap_or_else(|| fail("Expected a --layout FILE to read the grids with."));
+        let text = std::fs::read_to_string(path).unwrap_or_else(|err| fail(&format!("Can't read {}: {}", path, err)));
+        layout.parse_all(&text).unwrap_or_else(|err| fail(&format!("Can't read {}: {}.", path, err)))
+    }
+
+    /// Prints the metadata of a `.sdk` puzzle, unless the output is JSON.
+    fn print_header(&self) {
+        match self.sdk() {
+            Some((_, metadata)) if !self.json && !metadata.is_empty() => println!("{}", metadata),
+            _ => {}
+        }
+    }
+
+    /// Parses a puzzle given on the command line, or exits with an error.
+    fn parse_board(&self, puzzle: &str) -> Board {
+        match self.read_board(puzzle) {
+            Ok(board) => board,
+            Err(err) if self.symbols != SymbolSet::digits() => {
+                fail(&format!("Expected a puzzle of 81 symbols of the set: {}.", err))
             }
+            Err(err) => fail(&format!(
+                "Expected a puzzle of 81 digits, with `.` or `0` for empty cells: {}.",
+                err
+            )),
         }
     }
 
-    /// Finds all possible candidates for a cell by checking solved cells in the
-    /// same row, column, and its block.
-    fn find_cell_candidates(&self, row: usize, col: usize) -> HashSet<i8> {
-        let mut candidates = HashSet::new();
-        let block = &self.blocks[block_index(row, col)];
-        assert!(block.solutions.len() < 9);
+    /// Parses a puzzle with the symbols and in the mode of the options,
+    /// noting the warnings of `--lenient`.
+    fn read_board(&self, puzzle: &str) -> Result<Board, ParseError> {
+        if self.symbols != SymbolSet::digits() {
+            return self.symbols.parse(puzzle);
+        }
+        let mode = if self.lenient { ParseMode::Lenient } else { ParseMode::Strict };
+        let parsed = parse_board_with(puzzle, mode)?;
+        for warning in parsed.warnings.iter() {
+            self.note(&format!("Warning: {}", warning));
+        }
+        Ok(parsed.board)
+    }
 
-        'candidate_selection: for candidate in 1..10 {
-            // Don't add to candidates if already in block.
-            if block.solutions.iter().any(|solved| *solved == candidate) {
-                continue;
+    /// Puts `board` on the clipboard in the 81 character format with
+    /// `--copy`.
+    fn copy_board(&self, board: &Board) {
+        if self.copy {
+            if let Err(err) = clipboard::copy(&board_to_string(board)) {
+                fail(&format!("Can't copy to the clipboard: {}", err));
             }
+        }
+    }
 
-            // Disregard candidates that are present in this row or
-            // column.
-            for other_row in 0..9 {
-                if let Some(solution) = self.board[other_row][col].solution {
-                    if solution == candidate {
-                        continue 'candidate_selection;
-                    }
+    /// Parses the puzzle argument, which may also be an f-puzzles file with
+    /// variant constraints, or exits with an error.
+    fn variant_board(&self) -> (Board, Variant) {
+        match self.puzzle.as_deref() {
+            Some(path) if path.ends_with(".json") && !self.paste => {
+                let json = std::fs::read_to_string(path)
+                    .unwrap_or_else(|err| fail(&format!("Can't read {}: {}", path, err)));
+                match parse_fpuzzles(&json) {
+                    Some(puzzle) => puzzle,
+                    None => fail(&format!("{} isn't a 9x9 puzzle in the f-puzzles format.", path)),
                 }
             }
-            for other_col in 0..9 {
-                if let Some(solution) = self.board[row][other_col].solution {
-                    if solution == candidate {
-                        continue 'candidate_selection;
-                    }
+            _ => (self.board(), Variant::default()),
+        }
+    }
+}
+
+/// Returns the options set by the configuration file and then by `SUDOKU_*`
+/// environment variables, or exits with an error if the file can't be read.
+fn defaults() -> Vec<String> {
+    let mut flags = Vec::new();
+    let mut add = |key: &str, value: &str, source: &dyn Fn() -> String| {
+        match SETTINGS.iter().find(|(k, _)| *k == key) {
+            Some((_, flag)) => {
+                flags.push(String::from(*flag));
+                flags.push(String::from(value));
+            }
+            None => fail(&format!("Unknown setting `{}` in {}.", key, source())),
+        }
+    };
+    if let Some(path) = config_path() {
+        match std::fs::read_to_string(&path) {
+            Ok(text) => {
+                let config = Config::parse(&text)
+                    .unwrap_or_else(|err| fail(&format!("Can't read {}: {}.", path.display(), err)));
+                for (key, value) in config.iter() {
+                    add(key, value, &|| path.display().to_string());
                 }
             }
-
-            candidates.insert(candidate);
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => fail(&format!("Can't read {}: {}", path.display(), err)),
         }
+    }
+    for (key, _) in SETTINGS.iter() {
+        if let Ok(value) = env::var(format!("SUDOKU_{}", key.to_uppercase())) {
+            add(key, &value, &|| String::from("the environment"));
+        }
+    }
+    flags
+}
 
-        candidates
+/// Returns where the configuration file is: `$SUDOKU_CONFIG`, or
+/// `sudoku/config.toml` in `$XDG_CONFIG_HOME` or `~/.config`.
+fn config_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("SUDOKU_CONFIG") {
+        return Some(PathBuf::from(path));
     }
+    let dir = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(dir.join("sudoku").join("config.toml"))
+}
 
-    /// Called when a solution for a cell is found in the preliminary candidate
-    /// assignment phase. The solution is removed from the candidate list of all
-    /// cells in the same row, column, and square, thus further narrowing down
-    /// the search-space.
-    fn found_solution(&mut self, row: usize, col: usize, solution: i8) {
-        // We have a solution for this cell.
-        let cell = &mut self.board[row][col];
-        let block = &mut self.blocks[block_index(row, col)];
-        cell.solution = Some(solution);
-        cell.candidates.clear();
-        block.solutions.insert(solution);
+/// Prints the solver's events to stderr at the level `-v`, `-vv`, or `-q`
+/// asks for.
+#[cfg(feature = "logging")]
+fn init_logging(args: &Args) {
+    use tracing::Level;
 
-        // Remove candidates in this block, row, and column that are the same as
-        // this solution.
-        for other_row in 0..9 {
-            self.board[other_row][col].candidates.remove(&solution);
-        }
+    let level = match args.verbosity {
+        i8::MIN..=-1 => Level::ERROR,
+        0 => Level::INFO,
+        1 => Level::DEBUG,
+        _ => Level::TRACE,
+    };
+    tracing_subscriber::fmt().with_max_level(level).with_writer(io::stderr).init();
+}
 
-        for other_col in 0..9 {
-            self.board[row][other_col].candidates.remove(&solution);
+#[cfg(not(feature = "logging"))]
+fn init_logging(args: &Args) {
+    if args.verbosity > 0 {
+        args.note("This build doesn't log the solver's events, rebuild with `--features logging`.");
+    }
+}
+
+fn parse_techniques(list: &str) -> TechniqueConfig {
+    let mut techniques = Vec::new();
+    for id in list.split(',') {
+        match Technique::from_id(id.trim()) {
+            Some(technique) => techniques.push(technique),
+            None => fail(&format!("Unknown technique `{}`.", id)),
         }
+    }
+    TechniqueConfig::new(techniques)
+}
 
-        let block_row_start = (row / 3) * 3;
-        let block_col_start = (col / 3) * 3;
-        for block_row in block_row_start..block_row_start + 3 {
-            for block_col in block_col_start..block_col_start + 3 {
-                self.board[block_row][block_col].candidates.remove(&solution);
+/// Parses a number of bytes, which may end in K, M, or G for units of 1024.
+fn parse_bytes(s: &str) -> Option<usize> {
+    let (digits, unit) = match s.char_indices().last()? {
+        (i, 'K') | (i, 'k') => (&s[..i], 1 << 10),
+        (i, 'M') | (i, 'm') => (&s[..i], 1 << 20),
+        (i, 'G') | (i, 'g') => (&s[..i], 1 << 30),
+        _ => (s, 1),
+    };
+    digits.parse::<usize>().ok()?.checked_mul(unit)
+}
+
+/// Parses a cell like `r1c2` into its row and column from 0.
+fn parse_cell(cell: &str) -> Option<(usize, usize)> {
+    match cell.trim().as_bytes() {
+        [b'r', row @ b'1'..=b'9', b'c', col @ b'1'..=b'9'] => Some(((row - b'1') as usize, (col - b'1') as usize)),
+        _ => None,
+    }
+}
+
+/// Parses corrections like `r1c2=5,r4c4=0`.
+fn parse_fixes(list: &str) -> Vec<(usize, usize, Option<i8>)> {
+    let mut fixes = Vec::new();
+    for fix in list.split(',') {
+        let bytes = fix.trim().as_bytes();
+        match bytes {
+            [b'r', row @ b'1'..=b'9', b'c', col @ b'1'..=b'9', b'=', digit @ b'0'..=b'9'] => {
+                let digit = match digit - b'0' {
+                    0 => None,
+                    digit => Some(digit as i8),
+                };
+                fixes.push(((row - b'1') as usize, (col - b'1') as usize, digit));
             }
+            _ => fail(&format!("Expected a fix like r1c2=5, got `{}`.", fix)),
         }
     }
+    fixes
+}
 
-    /// A brute-force, backtracking algorithm that attempts to guess solutions for cells as
-    /// a function of previous guesses made for other cells.
-    fn guess_solutions(&mut self) -> Option<Board> {
-        let unsolved_cells = self.unsolved_cells();
-        let mut cell_idx = 0;
-        'cell_iteration: while cell_idx < unsolved_cells.len() {
-            let (row, col) = unsolved_cells[cell_idx];
-            let mut cand_idx = match self.board[row][col].candidate_idx {
-                Some(idx) => idx,
-                None => 0,
+fn main() {
+    let args = Args::parse();
+    init_logging(&args);
+    let command = match args.command.as_ref() {
+        Some(command) => command.as_str(),
+        None => {
+            solve(default_board(), &args);
+            return;
+        }
+    };
+
+    if args.validate_only {
+        validate_only(&args, command);
+        return;
+    }
+
+    match command {
+        "solve" => {
+            args.print_header();
+            match args.variant_board() {
+                (board, variant) if variant.is_classic() => solve(board, &args),
+                (board, variant) => {
+                    let graphics = args.graphics();
+                    show_board(&board, None, graphics, &args.symbols);
+                    match solve_variant(&board, &args.techniques, &variant).solution {
+                        Some(solution) => {
+                            show_board(&board, Some(&solution), graphics, &args.symbols);
+                            args.copy_board(&solution);
+                        }
+                        None => println!("{}", args.catalog.message("no_solution", &[])),
+                    }
+                }
+            }
+        }
+        "generate" => run_generate(&args),
+        "hint" => match hint(&args.board(), &args.techniques) {
+            Some(hint) => println!("{}", args.catalog.hint(&hint, args.level)),
+            None => fail(&args.catalog.message("no_hint", &[])),
+        },
+        "rate" => {
+            args.print_header();
+            let (board, variant) = args.variant_board();
+            if let Some(format) = args.report {
+                if !variant.is_classic() {
+                    fail("Reports are only written for classic puzzles.");
+                }
+                print!("{}", format.write(&[ReportRow::new(0, &board, &args.techniques)]));
+                return;
+            }
+            match rate_variant(&board, &args.techniques, &variant) {
+                Some(rating) if args.json => println!("{}", rating.to_json_with(&args.scores)),
+                Some(rating) => println!("{}\nScore: {:.1}", rating, args.scores.score(&rating)),
+                None => fail(&args.catalog.message("no_solution", &[])),
+            }
+        }
+        "replace" => {
+            let puzzle = args.board();
+            let (row, col) = match args.operands.as_slice() {
+                [cell] => match parse_cell(cell) {
+                    Some(cell) => cell,
+                    None => fail(&format!("Expected a cell like r1c3, got `{}`.", cell)),
+                },
+                _ => fail(USAGE),
+            };
+            let replacements = replace_clue(&puzzle, row, col, &args.techniques).unwrap_or_else(|| {
+                fail("The cell isn't a given, or the puzzle doesn't have a unique solution.")
+            });
+            if replacements.is_empty() {
+                args.note("The given can't move anywhere without changing the puzzle.");
+            }
+            for replacement in replacements.iter().take(args.limit) {
+                let rating = replacement.rating;
+                println!(
+                    "r{}c{}={}  {}  {}, {} guesses",
+                    replacement.row + 1,
+                    replacement.col + 1,
+                    replacement.digit,
+                    args.symbols.format(&replacement.puzzle),
+                    rating.hardest.map(Technique::id).unwrap_or("none"),
+                    rating.guesses.total
+                );
+            }
+        }
+        "pack" => {
+            let mut rng = match args.seed {
+                Some(seed) => Rng::new(seed),
+                None => Rng::from_time(),
+            };
+            for (name, board) in rotation_pack(&args.board(), args.relabel, &mut rng) {
+                println!("{} {}", args.symbols.format(&board), name);
+            }
+        }
+        "digits" => {
+            let scan = digit_scan(&Grid::from_board(&args.board()));
+            if args.json {
+                println!("{}", scan.to_json());
+            } else {
+                println!("{}", scan);
+            }
+        }
+        "fpuzzles" => println!("{}", fpuzzles_json(&args.board(), &Variant::default())),
+        "grids" => {
+            for board in args.dump() {
+                println!("{}", args.symbols.format(&board));
+            }
+        }
+        "count" => count(&args),
+        "solutions" => {
+            let mut completions = LexCompletions::new(&args.board());
+            if let Some(prefix) = args.from.as_deref() {
+                if !completions.skip_to(prefix) {
+                    fail("Expected at most 81 digits from 1 to 9 to start from.");
+                }
+            }
+            for solution in completions.take(args.limit) {
+                println!("{}", board_to_string(&solution));
+            }
+        }
+        "analyze" => run_analyze(&args),
+        "daily" => {
+            let date = match args.puzzle.as_deref() {
+                Some(date) => Date::parse(date).unwrap_or_else(|| fail("Expected a date like 2024-03-01.")),
+                None => {
+                    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+                    Date::from_unix_days((now.as_secs() / 86_400) as i64)
+                }
+            };
+            let puzzle = daily_puzzle(date, args.difficulty.unwrap_or(Difficulty::Medium));
+            if args.json {
+                println!("{}", puzzle.to_json());
+            } else {
+                println!("{}\n{}", puzzle.metadata(), args.symbols.format(puzzle.givens()));
+            }
+        }
+        "pattern" => {
+            let pattern = givens_pattern(&args.board());
+            if args.json {
+                println!("{}", pattern.to_json());
+            } else {
+                println!("{}", pattern);
+            }
+        }
+        "pencilmarks" => println!("{}", Grid::from_board(&args.board()).to_pencilmarks()),
+        "tune" => run_tune(&args),
+        "hsol" => run_hsol(&args),
+        "mine" => run_mine(&args),
+        "crossval" => {
+            let path = args.puzzle.as_deref().unwrap_or_else(|| fail(USAGE));
+            let text =
+                std::fs::read_to_string(path).unwrap_or_else(|err| fail(&format!("Can't read {}: {}", path, err)));
+            let references = parse_references(&text);
+            if references.is_empty() {
+                fail(&format!("{} has no puzzles with ratings.", path));
+            }
+            let report = cross_validate(&references, &args.techniques, &args.scores);
+            if args.json {
+                println!("{}", report.to_json());
+            } else {
+                println!("{}", report);
+            }
+        }
+        "batch" => run_batch(&args),
+        "dedupe" => run_dedupe(&args),
+        "check" => check(&args.board()),
+        "diff" => {
+            let old = args.board();
+            let new = match args.operands.as_slice() {
+                [new] => args.parse_board(new),
+                _ => fail(USAGE),
+            };
+            if args.candidates {
+                let (old, new) = (Grid::from_board(&old), Grid::from_board(&new));
+                print!("{}", render_candidate_diff_text(&old, &new, args.color()));
+            } else {
+                print!("{}", render_diff_text(&old, &new, args.color()));
+            }
+        }
+        "report" => {
+            let trace = solve_with(&args.board(), &args.techniques);
+            if trace.solution.is_none() {
+                fail(&args.catalog.message("no_solution", &[]));
+            }
+            if args.json {
+                println!("{}", trace.usage().to_json());
+            } else {
+                println!("{}", trace.usage());
+            }
+        }
+        "trace" => {
+            args.print_header();
+            let board = args.board();
+            let trace = match args.path_goal {
+                Some(goal) => best_path(&board, &args.techniques, goal, PATH_BUDGET)
+                    .unwrap_or_else(|| fail(&args.catalog.message("no_solution_without_guessing", &[]))),
+                None => solve_with(&board, &args.techniques),
             };
-            while cand_idx < self.board[row][col].candidates.len() {
-                let candidate = *self.board[row][col].candidates
-                    .iter()
-                    .nth(cand_idx)
-                    .unwrap();
-                self.board[row][col].candidate = Some(candidate);
-                // Make sure to increment candidate index *before* going to the
-                // next cell so should we backtrack and end up here again, we
-                // choose the next candidate instead of this one.
-                cand_idx += 1;
-                self.board[row][col].candidate_idx = Some(cand_idx);
-                // If this candidate is good, go to the next cell.
-                if self.can_choose_candidate(row, col, candidate) {
-                    cell_idx += 1;
-                    continue 'cell_iteration;
+            if args.json {
+                println!("{}", trace.to_json());
+            } else if args.hsol {
+                for (i, step) in trace.steps.iter().enumerate() {
+                    if let Some(entry) = HsolEntry::from_step(&board, &trace.grid_before(i), step) {
+                        println!("{}", to_hsol(&entry));
+                    }
+                }
+            } else {
+                let color = args.color();
+                for (i, step) in trace.steps.iter().enumerate() {
+                    if args.boards {
+                        println!("{}", render_step_text(&trace, i, color));
+                    }
+                    println!("{}", args.catalog.step(step));
+                }
+                if trace.solution.is_none() {
+                    fail(&args.catalog.message("no_solution", &[]));
+                }
+            }
+        }
+        "chat" => {
+            // The message may be quoted or not.
+            let mut message = args.puzzle.clone().unwrap_or_default();
+            for operand in args.operands.iter() {
+                message.push(' ');
+                message += operand;
+            }
+            let style = if args.emoji { ChatStyle::Emoji } else { ChatStyle::Monospace };
+            println!("{}", chat(&message, style));
+        }
+        "rpc" => {
+            let stdin = io::stdin();
+            if let Err(err) = serve_lines(stdin.lock(), io::stdout()) {
+                fail(&format!("Can't answer requests: {}", err));
+            }
+        }
+        #[cfg(feature = "server")]
+        "serve" => {
+            args.note(&format!("Listening on http://{}", args.listen));
+            if args.ui {
+                args.note(&format!("Open http://{}/ to play.", args.listen));
+            }
+            if let Err(err) = sudoku::server::serve_with(&args.listen, args.ui) {
+                fail(&format!("Can't serve on {}: {}", args.listen, err));
+            }
+        }
+        #[cfg(not(feature = "server"))]
+        "serve" => fail("This build doesn't include the server, rebuild with `--features server`."),
+        #[cfg(feature = "server")]
+        "race" => {
+            args.note(&format!("Waiting for players on {}", args.listen));
+            if let Err(err) = sudoku::serve_races(&args.listen, args.difficulty.unwrap_or(Difficulty::Medium)) {
+                fail(&format!("Can't serve on {}: {}", args.listen, err));
+            }
+        }
+        #[cfg(not(feature = "server"))]
+        "race" => fail("This build doesn't include the server, rebuild with `--features server`."),
+        #[cfg(feature = "qr")]
+        "qr" => {
+            let board = args.board();
+            match args.png.as_ref() {
+                Some(path) => {
+                    if let Err(err) = std::fs::write(path, sudoku::qr_png(&board, 8)) {
+                        fail(&format!("Can't write {}: {}", path, err));
+                    }
                 }
+                None => print!("{}", sudoku::qr_text(&board)),
             }
+        }
+        #[cfg(not(feature = "qr"))]
+        "qr" => fail("This build doesn't include QR codes, rebuild with `--features qr`."),
+        #[cfg(feature = "ocr")]
+        "ocr" => {
+            let path = args.puzzle.as_deref().unwrap_or_else(|| fail(USAGE));
+            let mut recognition = match sudoku::ocr::recognize_file(path) {
+                Ok(Some(recognition)) => recognition,
+                Ok(None) => fail("Couldn't find a sudoku grid in the image."),
+                Err(err) => fail(&format!("Can't read {}: {}", path, err)),
+            };
+            for &(row, col, digit) in args.fixes.iter() {
+                recognition.correct(row, col, digit);
+            }
+            println!("{}", recognition);
+        }
+        #[cfg(not(feature = "ocr"))]
+        "ocr" => fail("This build doesn't include OCR, rebuild with `--features ocr`."),
+        #[cfg(feature = "db")]
+        "db" => run_db(&args),
+        #[cfg(not(feature = "db"))]
+        "db" => fail("This build doesn't include the database, rebuild with `--features db`."),
+        "completions" => {
+            let name = args.puzzle.as_deref().unwrap_or_else(|| fail(USAGE));
+            let shell = Shell::from_name(name).unwrap_or_else(|| fail(&format!("Unknown shell `{}`.", name)));
+            let difficulties: Vec<&str> = Difficulty::ALL.iter().map(|difficulty| difficulty.name()).collect();
+            let backends: Vec<&str> = Backend::ALL.iter().map(|backend| backend.name()).collect();
+            let reports: Vec<&str> = ReportFormat::ALL.iter().map(|format| format.name()).collect();
+            let choices: [(&str, &[&str]); 6] = [
+                ("--difficulty", &difficulties),
+                ("--backend", &backends),
+                ("--symbols", &SymbolSet::NAMES),
+                ("--format", &["text", "json"]),
+                ("--report", &reports),
+                ("--color", &["auto", "always", "never"]),
+            ];
+            print!("{}", completion_script(shell, "sudoku", USAGE, &choices));
+        }
+        "help" => println!("{}", USAGE),
+        _ => fail(USAGE),
+    }
+}
 
-            // If we're here, it means we haven't found any eligible candidate for this
-            // cell, so we need to backtrack. Reset candidate and its index so the next
-            // time we're here we can retry all candidates again.
-            self.board[row][col].candidate = None;
-            self.board[row][col].candidate_idx = None;
-            // If we're back at the first field after not finding any
-            // candidates, it means there is no solution.
-            if cell_idx == 0 {
-                return None;
+/// Reads the puzzles `command` takes without running it, printing a line for
+/// each problem, and exits with an error if there are any.
+fn validate_only(args: &Args, command: &str) {
+    let problems = match command {
+        "analyze" | "batch" | "dedupe" => validate_lines(args, args.puzzle.as_deref().unwrap_or_else(|| fail(USAGE))),
+        "db" if args.puzzle.as_deref() == Some("import") => match args.operands.first() {
+            Some(path) if path.ends_with(".sdk") => validate_puzzle(args, &read_sdk(path).0, true),
+            Some(path) => validate_lines(args, path),
+            None => fail(USAGE),
+        },
+        "tune" => validate_lines(args, args.puzzle.as_deref().unwrap_or_else(|| fail(USAGE))),
+        "mine" => validate_lines(args, args.operands.first().unwrap_or_else(|| fail(USAGE))),
+        "hsol" => validate_hsol(args, args.puzzle.as_deref().unwrap_or_else(|| fail(USAGE))),
+        "grids" => {
+            let boards = args.dump();
+            let mut problems = 0;
+            for (i, board) in boards.iter().enumerate() {
+                if let Err(err) = check_puzzle(board, args.unique) {
+                    println!("Grid {}: {}", i + 1, err);
+                    problems += 1;
+                }
             }
-            cell_idx -= 1;
+            problems
+        }
+        // Reference ratings may follow the puzzle after a comma.
+        "crossval" => validate_column(args, args.puzzle.as_deref().unwrap_or_else(|| fail(USAGE)), |line| {
+            line.split(|c: char| c == ',' || c.is_whitespace()).find(|field| !field.is_empty())
+        }),
+        // Only the givens of variants are checked, as counting their
+        // solutions needs the variant solver.
+        "solve" | "rate" => {
+            let (board, variant) = args.variant_board();
+            validate_puzzle(args, &board, variant.is_classic())
         }
+        "hint" | "report" | "digits" | "pattern" | "pencilmarks" | "count" | "solutions" | "check" | "trace" | "replace"
+        | "pack" | "fpuzzles" | "qr" => validate_puzzle(args, &args.board(), true),
+        "diff" => {
+            let new = match args.operands.as_slice() {
+                [new] => args.parse_board(new),
+                _ => fail(USAGE),
+            };
+            validate_puzzle(args, &args.board(), true) + validate_puzzle(args, &new, true)
+        }
+        _ => fail(&format!("`{}` doesn't take puzzles to validate.", command)),
+    };
+    if problems > 0 {
+        fail(&format!("Found {} problem{}.", problems, if problems == 1 { "" } else { "s" }));
+    }
+    args.note("No problems found.");
+}
 
-        self.use_final_candidates();
+/// Checks a puzzle with `check_puzzle`, and for uniqueness with `--unique`
+/// if `unique` is set, printing the problem if there is one. Returns how
+/// many problems there were.
+fn validate_puzzle(args: &Args, board: &Board, unique: bool) -> usize {
+    match check_puzzle(board, args.unique && unique) {
+        Ok(()) => 0,
+        Err(err) => {
+            println!("{}", err);
+            1
+        }
+    }
+}
 
-        Some(self.board.clone())
+/// Checks the puzzles of a file with one per line, or of stdin for `-`,
+/// skipping comments and the names after puzzles like `analyze`, and prints
+/// a line for each problem with the line it's on. Returns how many problems
+/// there were.
+fn validate_lines(args: &Args, path: &str) -> usize {
+    validate_column(args, path, |line| line.split_whitespace().next())
+}
+
+/// Like `validate_lines`, but with `puzzle` picking the puzzle out of each
+/// line.
+fn validate_column(args: &Args, path: &str, puzzle: fn(&str) -> Option<&str>) -> usize {
+    let input: Box<dyn BufRead> = match path {
+        "-" => Box::new(io::stdin().lock()),
+        path => match File::open(path) {
+            Ok(file) => Box::new(BufReader::new(file)),
+            Err(err) => fail(&format!("Can't read {}: {}", path, err)),
+        },
+    };
+    let mut problems = 0;
+    for (i, line) in input.lines().enumerate() {
+        let line = line.unwrap_or_else(|err| fail(&format!("Can't read {}: {}", path, err)));
+        let puzzle = match puzzle(&line) {
+            Some(puzzle) if !puzzle.starts_with('#') => puzzle,
+            _ => continue,
+        };
+        // The commands that read files parse them strictly, whatever
+        // `--symbols` and `--lenient` say, so they're checked that way.
+        let problem = match parse_board_with(puzzle, ParseMode::Strict) {
+            Ok(parsed) => check_puzzle(&parsed.board, args.unique).err().map(|err| err.to_string()),
+            Err(err) => Some(err.to_string()),
+        };
+        if let Some(problem) = problem {
+            println!("{}:{}: {}", path, i + 1, problem);
+            problems += 1;
+        }
+    }
+    problems
+}
+
+/// Checks the examples of a HoDoKu library, printing a line for each that
+/// isn't an example or whose givens aren't a valid puzzle. Returns how
+/// many problems there were.
+fn validate_hsol(args: &Args, path: &str) -> usize {
+    let text = std::fs::read_to_string(path).unwrap_or_else(|err| fail(&format!("Can't read {}: {}", path, err)));
+    let mut problems = 0;
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let problem = match parse_hsol(line) {
+            Some(entry) => check_puzzle(&entry.givens, args.unique).err().map(|err| err.to_string()),
+            None => Some(String::from("not an example")),
+        };
+        if let Some(problem) = problem {
+            println!("{}:{}: {}", path, i + 1, problem);
+            problems += 1;
+        }
     }
+    problems
+}
+
+/// Runs a `db` subcommand, which is in the puzzle argument.
+#[cfg(feature = "db")]
+fn run_db(args: &Args) {
+    use sudoku::db::Database;
+    use sudoku::{to_sepb, Puzzle};
 
-    /// Returns a vector of (row, column) coordinates of the cells that are yet
-    /// to be solved.
-    fn unsolved_cells(&self) -> Vec<(usize, usize)> {
-        let mut unsolved_cells = Vec::new();
-        for row in 0..9 {
-            for col in 0..9 {
-                if self.board[row][col].solution.is_none() {
-                    unsolved_cells.push((row, col));
+    let mut db = match Database::open(&args.db) {
+        Ok(db) => db,
+        Err(err) => fail(&format!("Can't open {}: {}", args.db, err)),
+    };
+    let operand = args.operands.first().map(String::as_str);
+    let result = match (args.puzzle.as_deref(), operand) {
+        (Some("import"), Some(path)) if path.ends_with(".sdk") => {
+            let (board, mut metadata) = read_sdk(path);
+            metadata.source.get_or_insert_with(|| String::from(path));
+            let puzzle = Puzzle::new(board).unwrap_or_else(|err| fail(&format!("Can't add {}: {}.", path, err)));
+            db.add_puzzle(&puzzle.with_metadata(metadata)).map(|id| match id {
+                Some(id) => println!("Added puzzle {}.", id),
+                None => println!("The puzzle is already in the database."),
+            })
+        }
+        (Some("import"), Some(path)) => {
+            let pack = std::fs::read_to_string(path)
+                .unwrap_or_else(|err| fail(&format!("Can't read {}: {}", path, err)));
+            db.import(&pack, path).map(|report| {
+                println!(
+                    "Added {} puzzles, skipped {} duplicates and {} invalid lines.",
+                    report.added, report.duplicates, report.invalid
+                );
+            })
+        }
+        (Some("list"), None) => db.find(args.difficulty, args.unplayed, args.limit).map(|entries| {
+            for entry in entries {
+                println!(
+                    "{:>6}  {}  {:<6}  played {}{}",
+                    entry.id,
+                    board_to_string(&entry.puzzle),
+                    entry.difficulty,
+                    entry.plays,
+                    entry.metadata.title.map(|title| format!("  {}", title)).unwrap_or_default()
+                );
+            }
+        }),
+        (Some("export"), None) => db.find(args.difficulty, args.unplayed, args.limit).map(|entries| {
+            for entry in entries {
+                println!("{}", to_sepb(&entry.puzzle, &entry.metadata));
+            }
+        }),
+        (Some("play"), Some(id)) => {
+            let id = id.parse().unwrap_or_else(|_| fail("Expected a puzzle id."));
+            db.mark_played(id, args.seconds).map(|found| {
+                if !found {
+                    fail(&format!("There's no puzzle {}.", id));
+                }
+            })
+        }
+        (Some("campaign"), None) => sudoku::Campaign::load(&db).map(|campaign| {
+            println!("{}", campaign);
+            match campaign.next() {
+                Some(entry) => {
+                    println!("\nNext, puzzle {}:", entry.id);
+                    println!("{}", board_to_string(&entry.puzzle));
+                    args.note(&format!("Mark it as played with `sudoku db play {}`.", entry.id));
+                }
+                None => println!("\nThe campaign is complete."),
+            }
+        }),
+        (Some("stats"), None) => db.stats().map(|stats| {
+            for s in stats {
+                print!("{:<8}{:>6} puzzles{:>6} played", s.difficulty.name(), s.puzzles, s.played);
+                match s.average_seconds {
+                    Some(seconds) => println!(", {:.0}s on average", seconds),
+                    None => println!(),
                 }
             }
+        }),
+        _ => fail(USAGE),
+    };
+    if let Err(err) = result {
+        fail(&format!("Database error: {}", err));
+    }
+}
+
+/// Checks the givens of `board` and whether it has a unique solution, and
+/// exits with an error if not. The cheap checks come first, so puzzles that
+/// obviously have several solutions aren't counted.
+fn check(board: &Board) {
+    if let Err(err) = validate(board) {
+        fail(&err.to_string());
+    }
+    let warnings = preflight(board);
+    if !warnings.is_empty() {
+        for warning in warnings.iter() {
+            eprintln!("{}", warning);
         }
-        unsolved_cells
+        fail("The puzzle can't have a unique solution.");
     }
+    match count_solutions(board, 2) {
+        0 => fail("The puzzle has no solution."),
+        1 => println!("The puzzle has a unique solution."),
+        _ => fail("The puzzle has several solutions."),
+    }
+}
 
-    /// Iterates over unsolved cells and makes their chosen candidate as their solution.
-    fn use_final_candidates(&mut self) {
-        for row in 0..9 {
-            for col in 0..9 {
-                let cell = &mut self.board[row][col];
-                if cell.solution.is_none() {
-                    if let Some(cand) = cell.candidate {
-                        cell.solution = Some(cand);
-                    } else {
-                        println!("WARN: missing solution at {}:{}", row, col);
-                    }
-                }
+/// Solves the puzzles of the file in the puzzle argument, one per line and
+/// maybe followed by a name like in `analyze`, and writes a line for each:
+/// the solution, `unsolvable`, or `invalid`. With `--output`, the lines
+/// already in the file are taken to be of the first puzzles, which are
+/// skipped, so a stopped run carries on where it was.
+fn run_batch(args: &Args) {
+    let path = args.puzzle.as_deref().unwrap_or_else(|| fail(USAGE));
+    let input: Box<dyn BufRead> = match path {
+        "-" => Box::new(io::stdin().lock()),
+        path => match File::open(path) {
+            Ok(file) => Box::new(BufReader::new(file)),
+            Err(err) => fail(&format!("Can't read {}: {}", path, err)),
+        },
+    };
+    let (output, done): (Box<dyn Write + Send>, usize) = match args.output.as_ref() {
+        Some(output) => {
+            let done = resume_output(output).unwrap_or_else(|err| fail(&format!("Can't read {}: {}", output, err)));
+            let file = OpenOptions::new().create(true).append(true).open(output);
+            let file = file.unwrap_or_else(|err| fail(&format!("Can't write {}: {}", output, err)));
+            (Box::new(file), done)
+        }
+        None => (Box::new(io::stdout()), 0),
+    };
+    if done > 0 {
+        args.note(&format!("Skipping the {} puzzles already solved.", done));
+    }
+    let mut output = LineWriter::new(output);
+    let puzzles = input
+        .lines()
+        .map_while(Result::ok)
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .skip(done)
+        .map(|line| line.split_whitespace().next().and_then(parse_board).ok_or(()));
+    let mut stats = BatchStats::default();
+    let mut invalid = 0;
+    let options = args.solve_options(Backend::Backtracking.memory_bytes());
+    let written = solve_stream_with(puzzles, options, |result| match result {
+        Ok(result) => {
+            stats.add(&result);
+            match result.solution {
+                Some(solution) => writeln!(output, "{}", board_to_string(&solution)),
+                None => writeln!(output, "unsolvable"),
             }
         }
+        Err(()) => {
+            invalid += 1;
+            writeln!(output, "invalid")
+        }
+    });
+    match written {
+        // The reader has had enough, like `head` does.
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => return,
+        Err(err) => fail(&format!("Can't write the solutions: {}", err)),
+        Ok(()) => {}
     }
+    args.note(&format!(
+        "Solved {}, {} unsolvable and {} invalid, slowest in {:?}.",
+        stats.solved, stats.unsolvable, invalid, stats.slowest
+    ));
+    if stats.restarts > 0 {
+        args.note(&format!("Restarted {} times.", stats.restarts));
+    }
+}
 
-    /// Determines whether we can choose candidate for this cell based on
-    /// previous candidate choices. Candidate is otherwise assumed to be correct
-    /// based on other cells solved in its block, row, and column.
-    fn can_choose_candidate(&self, row: usize, col: usize, candidate: i8) -> bool {
-        // TODO: maybe we could use a reverse index to avoid all these iterations?
-        for other_col in 0..col {
-            let other_cell = &self.board[row][other_col];
-            if other_cell.solution.is_none() {
-                if let Some(other_cand) = other_cell.candidate {
-                    if other_cand == candidate {
-                        return false;
-                    }
-                }
+/// Returns how many lines the output of an earlier `batch` run has, after
+/// cutting off a line it was stopped halfway through writing.
+fn resume_output(path: &str) -> io::Result<usize> {
+    let text = match std::fs::read(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(err),
+    };
+    let complete = text.iter().rposition(|&byte| byte == b'\n').map_or(0, |i| i + 1);
+    if complete < text.len() {
+        OpenOptions::new().write(true).open(path)?.set_len(complete as u64)?;
+    }
+    Ok(text[..complete].iter().filter(|&&byte| byte == b'\n').count())
+}
+
+/// Drops the duplicates of the file in the puzzle argument and reports how
+/// many there were. The report goes to stderr if the puzzles go to stdout.
+fn run_dedupe(args: &Args) {
+    let path = args.puzzle.as_deref().unwrap_or_else(|| fail(USAGE));
+    let text = std::fs::read_to_string(path).unwrap_or_else(|err| fail(&format!("Can't read {}: {}", path, err)));
+    let deduped = dedupe(&text, args.isomorphic);
+    let mut unique = String::new();
+    for puzzle in deduped.puzzles.iter() {
+        unique += &board_to_string(puzzle);
+        unique.push('\n');
+    }
+    let report = format!(
+        "Kept {} puzzles, removed {} duplicates and skipped {} invalid lines.",
+        deduped.puzzles.len(),
+        deduped.duplicates,
+        deduped.invalid
+    );
+    match args.output.as_ref() {
+        Some(output) => {
+            if let Err(err) = std::fs::write(output, unique) {
+                fail(&format!("Can't write {}: {}", output, err));
             }
+            println!("{}", report);
         }
+        None => {
+            print!("{}", unique);
+            args.note(&report);
+        }
+    }
+}
 
-        for other_row in 0..row {
-            let other_cell = &self.board[other_row][col];
-            if other_cell.solution.is_none() {
-                if let Some(other_cand) = other_cell.candidate {
-                    if other_cand == candidate {
-                        return false;
-                    }
+/// Rates every puzzle of the file in the puzzle argument and prints a
+/// summary, or the hardest puzzles with `--hardest`.
+fn run_analyze(args: &Args) {
+    let path = args.puzzle.as_deref().unwrap_or_else(|| fail(USAGE));
+    let text = std::fs::read_to_string(path).unwrap_or_else(|err| fail(&format!("Can't read {}: {}", path, err)));
+    // A line may have a name after the puzzle.
+    let puzzles: Vec<Board> = text
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_whitespace().next().and_then(parse_board))
+        .collect();
+    if let Some(format) = args.report {
+        let rows: Vec<ReportRow> =
+            puzzles.iter().enumerate().map(|(id, puzzle)| ReportRow::new(id, puzzle, &args.techniques)).collect();
+        print!("{}", format.write(&rows));
+        return;
+    }
+    let path = args.checkpoint.as_deref();
+    let checkpoint = path.and_then(|path| load_checkpoint(path, "analyze"));
+    let mut checkpoint = checkpoint.unwrap_or_else(|| Checkpoint::new("analyze"));
+    let mismatch = checkpoint.analyses.iter().any(|analysis| puzzles.get(analysis.index) != Some(&analysis.puzzle));
+    if mismatch || checkpoint.done != checkpoint.analyses.len() {
+        fail(&format!("{} is a checkpoint of other puzzles.", path.unwrap_or_default()));
+    }
+    let mut saved = Instant::now();
+    while checkpoint.done < puzzles.len() {
+        let index = checkpoint.done;
+        checkpoint.analyses.push(analyze_puzzle(index, &puzzles[index], &args.techniques));
+        checkpoint.done += 1;
+        if let Some(path) = path {
+            if saved.elapsed() >= CHECKPOINT_INTERVAL || checkpoint.done == puzzles.len() {
+                save_checkpoint(path, checkpoint.to_json().to_string().as_bytes());
+                args.note(&format!("Analyzed {} of {} puzzles", checkpoint.done, puzzles.len()));
+                saved = Instant::now();
+            }
+        }
+    }
+    let analyses = checkpoint.analyses;
+    if args.histogram {
+        let histogram = histogram(&analyses);
+        match args.csv.as_ref() {
+            Some(csv) => {
+                if let Err(err) = std::fs::write(csv, histogram.to_csv()) {
+                    fail(&format!("Can't write {}: {}", csv, err));
                 }
             }
+            None => print!("{}", histogram),
         }
+        return;
+    }
+    if let Some(csv) = args.csv.as_ref() {
+        if let Err(err) = std::fs::write(csv, analysis_csv(&analyses)) {
+            fail(&format!("Can't write {}: {}", csv, err));
+        }
+    }
 
-        true
+    if args.hardest {
+        println!("Hardest to solve by hand:");
+        for (i, analysis) in hardest_by_difficulty(&analyses, args.limit).iter().enumerate() {
+            let rating = analysis.rating.as_ref().unwrap();
+            let hardest = rating.hardest.map(Technique::id).unwrap_or("none");
+            let difficulty = Difficulty::of(rating);
+            let puzzle = board_to_string(&analysis.puzzle);
+            println!("{:>4}. {}  {:<6}  {}, {} guesses", i + 1, puzzle, difficulty, hardest, rating.guesses.total);
+        }
+        println!("Hardest to search:");
+        for (i, analysis) in hardest_by_effort(&analyses, args.limit).iter().enumerate() {
+            println!("{:>4}. {}  ~{:.0} nodes", i + 1, board_to_string(&analysis.puzzle), analysis.effort);
+        }
+        return;
     }
+    for difficulty in Difficulty::ALL.iter() {
+        let count = analyses
+            .iter()
+            .filter(|analysis| analysis.rating.as_ref().map(Difficulty::of) == Some(*difficulty))
+            .count();
+        println!("{:<12}{:>6}", difficulty.name(), count);
+    }
+    let unsolvable = analyses.iter().filter(|analysis| analysis.rating.is_none()).count();
+    println!("{:<12}{:>6}", "unsolvable", unsolvable);
 }
 
-/// Represents a 3x3 block of cells in a Sudoku board. This is used by the
-/// solver to quickly verify that a candidate is not already solved in its
-/// block.
-#[derive(Debug, Eq, PartialEq)]
-struct Block {
-    // TODO: use BitSet or just a u16
-    solutions: HashSet<i8>,
-}
+/// How many grids `count` explores between checkpoints, which takes a few
+/// seconds.
+const CHECKPOINT_NODES: u64 = 1_000_000;
 
-/// Partitions a Sudoku board into a vector of blocks.
-fn make_blocks(board: &Board) -> [Block; 9] {
-    // TODO remove unsafe code once Block is copyable (i.e. when switching to an
-    // i16 bitmask for solutions)
-    let mut blocks: [Block; 9] = unsafe {
-        let mut blocks: [Block; 9] = std::mem::uninitialized();
-        // Fill blocks vec. TODO more idiomatic way of doing this?
-        for element in blocks.iter_mut() {
-            let block = Block { solutions: HashSet::new() };
-            // Overwrite element without running the destructor of the old value.
-            std::ptr::write(element, block);
-        }
-        blocks
+/// Counts every solution of the puzzle, resuming from and saving to the
+/// checkpoint file if there is one.
+fn count(args: &Args) {
+    if let Some(probes) = args.estimate {
+        let mut rng = match args.seed {
+            Some(seed) => Rng::new(seed),
+            None => Rng::from_time(),
+        };
+        let estimate = estimate_solutions(&args.board(), probes, &mut rng);
+        println!(
+            "About {:.3e} solutions, from {:.3e} to {:.3e} with 95% confidence",
+            estimate.solutions, estimate.lower, estimate.upper
+        );
+        return;
+    }
+    args.solve_options(Enumeration::memory_bytes());
+    let checkpoint = args.checkpoint.as_deref();
+    let mut enumeration = match checkpoint.and_then(|path| std::fs::read(path).ok()) {
+        Some(bytes) => match Enumeration::from_snapshot(&bytes) {
+            Some(enumeration) if enumeration.is_of(&args.board()) => enumeration,
+            Some(_) => fail(&format!("{} is a checkpoint of another puzzle.", checkpoint.unwrap_or_default())),
+            None => fail(&format!("{} isn't a checkpoint.", checkpoint.unwrap_or_default())),
+        },
+        None => Enumeration::new(&args.board()),
     };
+    while !enumeration.run(CHECKPOINT_NODES) {
+        if let Some(path) = checkpoint {
+            save_checkpoint(path, &enumeration.snapshot());
+            args.note(&format!(
+                "{} solutions so far, about {:.1}% done",
+                enumeration.count(),
+                enumeration.progress() * 100.0
+            ));
+        }
+    }
+    println!("{}", enumeration.count());
+}
 
-    for (row_idx, row) in board.iter().enumerate() {
-        for (col_idx, col) in row.iter().enumerate() {
-            if let Some(num) = col.solution {
-                let block_idx = block_index(row_idx, col_idx);
-                assert!(block_idx < blocks.len());
-                blocks[block_idx].solutions.insert(num);
-            }
-        }
-    }
-
-    blocks
-}
-
-/// Returns the index of a block (in a vector of nine blocks) to which the cell
-/// at `row:col` belongs.
-fn block_index(row: usize, col: usize) -> usize {
-    let block_idx = row / 3 * 3 + col / 3;
-    assert!(block_idx < 9);
-    block_idx
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_make_blocks() {
-        let board = default_board();
-        let blocks = make_blocks(&board);
-        println!("{:#?}", blocks);
-
-        assert_eq!(blocks, vec![
-            Block { solutions: vec![5, 2, 7, 9].iter().cloned().collect::<HashSet<i8>>(), },
-            Block { solutions: vec![8, 3, 4, 5].iter().cloned().collect::<HashSet<i8>>(), },
-            Block { solutions: vec![5, 6, 2].iter().cloned().collect::<HashSet<i8>>(), },
-            Block { solutions: vec![4, 9, 1, 7].iter().cloned().collect::<HashSet<i8>>(), },
-            Block { solutions: vec![6, 4, 5, 7, 8, 2].iter().cloned().collect::<HashSet<i8>>(), },
-            Block { solutions: vec![7, 8, 1, 3].iter().cloned().collect::<HashSet<i8>>(), },
-            Block { solutions: vec![5, 4, 6].iter().cloned().collect::<HashSet<i8>>(), },
-            Block { solutions: vec![7, 8, 3, 1].iter().cloned().collect::<HashSet<i8>>(), },
-            Block { solutions: vec![9, 6, 5, 4].iter().cloned().collect::<HashSet<i8>>(), },
-        ]);
-    }
-
-    #[test]
-    fn test_solver() {
-        let board = default_board();
-        let mut sudoku = Sudoku::new(board);
-        if let Some(solved_board) = sudoku.solve() {
-            for row in 0..9 {
-                for col in 0..9 {
-                    let solution = solved_board[row][col].solution;
-
-                    // Check that this cell's solution is unique in its block.
-                    let block_row_start = (row / 3) * 3;
-                    let block_col_start = (col / 3) * 3;
-                    for block_row in block_row_start..block_row_start + 3 {
-                        for block_col in block_col_start..block_col_start + 3 {
-                            if block_row == row && block_col == col {
-                                continue;
-                            }
-                            assert_ne!(solution, solved_board[block_row][block_col].solution);
-                        }
-                    }
+/// How many paths `trace --shortest` and `--simplest` follow before settling
+/// for the best so far.
+const PATH_BUDGET: usize = 10_000;
 
-                    // Verify that solution is unique in its row.
-                    for other_col in 0..9 {
-                        if other_col != col {
-                            assert_ne!(solution, solved_board[row][other_col].solution);
-                        }
-                    }
+/// How often `generate` and `analyze` save their progress.
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(10);
 
-                    // Verify that solution is unique in its column.
-                    for other_row in 0..9 {
-                        if other_row != row {
-                            assert_ne!(solution, solved_board[other_row][col].solution);
-                        }
-                    }
-                }
-            }
+/// Reads the checkpoint of `command` at `path`, or returns None if there's
+/// none yet. Exits with an error if the file isn't a checkpoint of the
+/// command.
+fn load_checkpoint(path: &str, command: &str) -> Option<Checkpoint> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return None,
+        Err(err) => fail(&format!("Can't read {}: {}", path, err)),
+    };
+    match Json::parse(&text).as_ref().and_then(Checkpoint::from_json) {
+        Some(checkpoint) if checkpoint.command == command => Some(checkpoint),
+        Some(checkpoint) => fail(&format!("{} is a checkpoint of `{}`.", path, checkpoint.command)),
+        None => fail(&format!("{} isn't a checkpoint.", path)),
+    }
+}
+
+/// Saves a checkpoint to `path`, or exits with an error.
+fn save_checkpoint(path: &str, bytes: &[u8]) {
+    // Write to a temporary file first so a crash never leaves a broken
+    // checkpoint behind.
+    let temp = format!("{}.tmp", path);
+    if let Err(err) = std::fs::write(&temp, bytes).and_then(|_| std::fs::rename(&temp, path)) {
+        fail(&format!("Can't save the checkpoint to {}: {}", path, err));
+    }
+}
+
+/// Prints `--count` new puzzles. With a checkpoint file, the puzzles of an
+/// earlier run that was stopped are printed again and generating carries on
+/// where it left off, so the output is the same as if it hadn't stopped.
+fn run_generate(args: &Args) {
+    let path = args.checkpoint.as_deref();
+    let checkpoint = path.and_then(|path| load_checkpoint(path, "generate"));
+    let mut checkpoint = checkpoint.unwrap_or_else(|| Checkpoint::new("generate"));
+    let mut rng = match (checkpoint.rng.take(), args.seed) {
+        (Some(rng), _) => rng,
+        (None, Some(seed)) => Rng::new(seed),
+        (None, None) => Rng::from_time(),
+    };
+    for puzzle in checkpoint.puzzles.iter() {
+        println!("{}", args.symbols.format(puzzle));
+    }
+    let mut saved = Instant::now();
+    while checkpoint.done < args.count {
+        let puzzle = if args.adversarial {
+            let (puzzle, effort) = generate_adversarial(args.rounds, &mut rng);
+            args.note(&format!("The naive search places {} digits.", effort));
+            puzzle
         } else {
-            assert!(false);
+            generate_with(args.difficulty.unwrap_or(Difficulty::Medium), &args.techniques, &mut rng)
+                .unwrap_or_else(|err| fail(&format!("Can't generate a puzzle: {}.", err)))
+        };
+        println!("{}", args.symbols.format(&puzzle));
+        checkpoint.puzzles.push(puzzle);
+        checkpoint.done += 1;
+        if let Some(path) = path {
+            if saved.elapsed() >= CHECKPOINT_INTERVAL || checkpoint.done == args.count {
+                checkpoint.rng = Some(rng.clone());
+                save_checkpoint(path, checkpoint.to_json().to_string().as_bytes());
+                saved = Instant::now();
+            }
         }
     }
+    if let Some(path) = args.pdf.as_deref() {
+        write_pdf(path, &checkpoint.puzzles, args);
+    }
+    if let Some(puzzle) = checkpoint.puzzles.last() {
+        args.copy_board(puzzle);
+    }
 }
 
-fn solved(n: i8) -> Cell {
-    Cell::solved(n)
+#[cfg(feature = "pdf")]
+fn write_pdf(path: &str, puzzles: &[Board], args: &Args) {
+    let sheet = sudoku::PdfSheet { per_page: args.per_page, solutions: args.solutions };
+    if let Err(err) = std::fs::write(path, sheet.write(puzzles)) {
+        fail(&format!("Can't write {}: {}", path, err));
+    }
+    args.note(&format!("Wrote {} puzzles to {}.", puzzles.len(), path));
 }
 
-fn unsolved() -> Cell {
-    Cell::unsolved()
+#[cfg(not(feature = "pdf"))]
+fn write_pdf(_path: &str, _puzzles: &[Board], _args: &Args) {
+    fail("This build doesn't write PDFs, rebuild with `--features pdf`.");
 }
 
-fn print_board(board: &Board) {
-    let border = {
-        let mut s = String::new();
-        s.push('|');
-        for _ in 0..35 {
-            s.push('=');
-        }
-        s.push('|');
-        s
+fn solve(board: Board, args: &Args) {
+    let options = args.solve_options(args.backend.memory_bytes());
+    let graphics = args.graphics();
+    show_board(&board, None, graphics, &args.symbols);
+    let mut sudoku = match Sudoku::try_new(board) {
+        Ok(sudoku) => sudoku,
+        Err(err) => fail(&err.to_string()),
     };
-    let separator = {
-        let mut s = String::new();
-        s.push('|');
-        for _ in 0..3 {
-            for _ in 0..11 {
-                s.push('-');
+    match sudoku.solve_unique_with_options(options) {
+        Some(solution) => {
+            show_board(&board, Some(solution.board()), graphics, &args.symbols);
+            args.copy_board(solution.board());
+            if let Solution::Multiple { count_hint, .. } = solution {
+                let count = if count_hint < 100 { count_hint.to_string() } else { String::from("at least 100") };
+                args.note(&format!("The puzzle isn't unique, it has {} solutions.", count));
             }
-            s.push('|');
         }
-        s
+        None => println!("{}", args.catalog.message("no_solution", &[])),
+    }
+}
+
+/// Prints the board as an image if the terminal can show one, or as text
+/// otherwise, with `symbols`. The digits of `solution` are drawn in gray in
+/// images, which always show digits.
+fn show_board(puzzle: &Board, solution: Option<&Board>, graphics: Option<Graphics>, symbols: &SymbolSet) {
+    match graphics {
+        Some(graphics) => print!("{}", board_graphics(puzzle, solution, graphics)),
+        None => print_board_with(solution.unwrap_or(puzzle), symbols),
+    }
+}
+
+/// Prints the positions of a file's puzzles where a technique is the only
+/// way forward.
+fn run_mine(args: &Args) {
+    let technique = args.puzzle.as_deref().unwrap_or_else(|| fail(USAGE));
+    let technique = Technique::from_id(technique).unwrap_or_else(|| fail(&format!("Unknown technique: {}", technique)));
+    if !args.techniques.is_enabled(technique) {
+        fail(&format!("{} isn't one of the --techniques.", technique.id()));
+    }
+    let path = args.operands.first().unwrap_or_else(|| fail(USAGE));
+    let input: Box<dyn BufRead> = match path.as_str() {
+        "-" => Box::new(io::stdin().lock()),
+        path => match File::open(path) {
+            Ok(file) => Box::new(BufReader::new(file)),
+            Err(err) => fail(&format!("Can't read {}: {}", path, err)),
+        },
     };
+    let puzzles = input
+        .lines()
+        .map_while(Result::ok)
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_whitespace().next().and_then(parse_board));
+    for example in mine_examples(puzzles, technique, &args.techniques).take(args.limit) {
+        println!("{}", to_hsol(&example));
+    }
+}
 
-    let mut num_lines = 0;
-    for row in board.iter() {
-        if num_lines % 3 == 0 {
-            println!("{}", border);
-        } else {
-            println!("{}", separator);
+/// Times the search's heuristics on a file's puzzles and saves the fastest.
+fn run_tune(args: &Args) {
+    let path = args.puzzle.as_deref().unwrap_or_else(|| fail(USAGE));
+    let text = std::fs::read_to_string(path).unwrap_or_else(|err| fail(&format!("Can't read {}: {}", path, err)));
+    let puzzles: Vec<Board> = text
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_whitespace().next().and_then(parse_board))
+        .collect();
+    if puzzles.is_empty() {
+        fail(&format!("No puzzles in {}.", path));
+    }
+    let report = tune(&puzzles, args.solve_options(args.backend.memory_bytes()));
+    if args.json {
+        println!("{}", report.to_json());
+    } else {
+        println!("{}", report);
+    }
+    if let Some(output) = args.output.as_ref() {
+        std::fs::write(output, format!("{}\n", report.best().profile))
+            .unwrap_or_else(|err| fail(&format!("Can't write {}: {}", output, err)));
+        args.note(&format!("Wrote the profile to {}.", output));
+    }
+}
+
+/// Checks the techniques against the examples of a HoDoKu library.
+fn run_hsol(args: &Args) {
+    let path = args.puzzle.as_deref().unwrap_or_else(|| fail(USAGE));
+    let text = std::fs::read_to_string(path).unwrap_or_else(|err| fail(&format!("Can't read {}: {}", path, err)));
+    let (mut matched, mut failed, mut unsupported, mut invalid) = (0, 0, 0, 0);
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
         }
-        let mut line = String::from("|");
-        for col in row.iter() {
-            match col.solution {
-                Some(solution) => {
-                    line += &format!(" {} |", solution);
-                },
-                None => {
-                    line += &String::from("   |");
-                }
+        let entry = match parse_hsol(line) {
+            Some(entry) => entry,
+            None => {
+                invalid += 1;
+                continue;
+            }
+        };
+        match entry.check() {
+            HsolCheck::Matched => matched += 1,
+            HsolCheck::Different(step) => {
+                failed += 1;
+                println!("Line {}: found {} instead", i + 1, step);
             }
+            HsolCheck::NotFound => {
+                failed += 1;
+                println!("Line {}: no {} found", i + 1, entry.technique.map_or("", Technique::name));
+            }
+            HsolCheck::Unsupported => unsupported += 1,
         }
-        println!("{}", line);
-        num_lines += 1;
-    }
-    println!("{}", border);
-}
-
-fn default_board() -> Board {
-    [
-        [
-            unsolved(), unsolved(), solved(5),
-            unsolved(), unsolved(), solved(8),
-            unsolved(), unsolved(), unsolved(),
-        ],
-        [
-            unsolved(), solved(2), unsolved(),
-            unsolved(), unsolved(), unsolved(),
-            solved(5), unsolved(), unsolved(),
-        ],
-        [
-            solved(7), solved(9), unsolved(),
-            solved(3), solved(4), solved(5),
-            solved(6), solved(2), unsolved(),
-        ],
-
-        [
-            unsolved(), unsolved(), unsolved(),
-            solved(6), unsolved(), solved(4),
-            solved(7), solved(1), unsolved(),
-        ],
-        [
-            unsolved(), solved(4), solved(9),
-            solved(5), unsolved(), solved(7),
-            solved(8), solved(3), unsolved(),
-        ],
-        [
-            unsolved(), solved(1), solved(7),
-            solved(8), unsolved(), solved(2),
-            unsolved(), unsolved(), unsolved(),
-        ],
-
-        [
-            unsolved(), solved(5), solved(4),
-            solved(7), solved(8), solved(3),
-            unsolved(), solved(9), solved(6),
-        ],
-        [
-            unsolved(), unsolved(), solved(6),
-            unsolved(), unsolved(), unsolved(),
-            unsolved(), solved(5), unsolved(),
-        ],
-        [
-            unsolved(), unsolved(), unsolved(),
-            solved(1), unsolved(), unsolved(),
-            solved(4), unsolved(), unsolved(),
-        ],
-    ]
+    }
+    println!(
+        "{} examples matched, {} didn't, {} have techniques we don't, and {} lines aren't examples.",
+        matched, failed, unsupported, invalid
+    );
+}
+
+/// Reads a puzzle in the `.sdk` format, or exits with an error.
+fn read_sdk(path: &str) -> (Board, Metadata) {
+    let text = std::fs::read_to_string(path).unwrap_or_else(|err| fail(&format!("Can't read {}: {}", path, err)));
+    parse_sdk(&text).unwrap_or_else(|| fail(&format!("{} isn't a puzzle in the .sdk format.", path)))
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
 }
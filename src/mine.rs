@@ -0,0 +1,74 @@
+//! Mining puzzle collections for practice positions: the points in a solve
+//! where one technique, and nothing else, gets the solver further. A player
+//! drilling a technique can't get around it there, unlike in a puzzle that
+//! merely needs it somewhere. The positions are exported as examples in
+//! HoDoKu's library format, which keeps the candidates of the position.
+
+use crate::board::Board;
+use crate::grid::Grid;
+use crate::hsol::HsolEntry;
+use crate::logic::{find_technique, solve_with, Technique, TechniqueConfig};
+
+/// Returns whether `technique` is the only one of `config` that finds a
+/// step in `grid`. Guesses always get further, so they don't count.
+pub fn only_way_forward(grid: &Grid, technique: Technique, config: &TechniqueConfig) -> bool {
+    find_technique(grid, technique).is_some()
+        && config
+            .techniques()
+            .iter()
+            .filter(|&&other| other != technique && other != Technique::Guess)
+            .filter(|other| !other.needs_uniqueness() || config.assumes_unique())
+            .all(|&other| find_technique(grid, other).is_none())
+}
+
+/// Solves each of `puzzles` with `config` and yields the positions on the
+/// way where `technique` is the only way forward, as library examples. The
+/// puzzles are only solved as the examples are asked for, so a large
+/// collection can be streamed through and the search stopped early.
+pub fn mine_examples<'a, I>(
+    puzzles: I,
+    technique: Technique,
+    config: &'a TechniqueConfig,
+) -> impl Iterator<Item = HsolEntry> + 'a
+where
+    I: IntoIterator<Item = Board>,
+    I::IntoIter: 'a,
+{
+    puzzles.into_iter().flat_map(move |puzzle| {
+        let trace = solve_with(&puzzle, config);
+        let mut examples = Vec::new();
+        for (i, step) in trace.steps.iter().enumerate() {
+            if step.technique != technique {
+                continue;
+            }
+            let grid = trace.grid_before(i);
+            if only_way_forward(&grid, technique, config) {
+                examples.extend(HsolEntry::from_step(&puzzle, &grid, step));
+            }
+        }
+        examples
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{default_board, parse_board};
+
+    #[test]
+    fn test_mine_examples() {
+        let puzzles = vec![
+            default_board(),
+            parse_board(".......9....5.2.86..1....4....3....7..4.9..1.5....8....9.72....2.6..4..8.7......3").unwrap(),
+        ];
+        let config = TechniqueConfig::default();
+        let examples: Vec<HsolEntry> = mine_examples(puzzles.clone(), Technique::LockedCandidates, &config).collect();
+        assert_eq!(examples.len(), 1);
+        for example in examples.iter() {
+            assert_eq!(example.givens, puzzles[1]);
+            assert!(only_way_forward(&example.grid, Technique::LockedCandidates, &config));
+            assert!(find_technique(&example.grid, Technique::HiddenSingle).is_none());
+        }
+        assert_eq!(mine_examples(puzzles, Technique::Guess, &config).count(), 0);
+    }
+}
@@ -0,0 +1,27 @@
+//! Callbacks for following a solve as it happens, e.g. to animate it or to
+//! log it in a custom way. Rows, columns, and digits are as on the board,
+//! with rows and columns zero-based.
+
+/// Gets told about every change a solver makes to the board. All methods do
+/// nothing by default, so an observer only implements the ones it needs.
+pub trait SolveObserver {
+    /// A digit was placed because it's the only one that fits.
+    fn on_placement(&mut self, _row: usize, _col: usize, _digit: i8) {}
+
+    /// A technique removed a candidate. The candidates a placement removes
+    /// from its peers aren't reported, and only the logical solver removes
+    /// candidates at all; the backtracking one only tracks placed digits.
+    fn on_elimination(&mut self, _row: usize, _col: usize, _digit: i8) {}
+
+    /// A digit was placed as a guess.
+    fn on_guess(&mut self, _row: usize, _col: usize, _digit: i8) {}
+
+    /// The last guess in the cell turned out wrong and was taken back, with
+    /// everything that followed from it.
+    fn on_backtrack(&mut self, _row: usize, _col: usize) {}
+}
+
+/// The observer of solves nobody observes.
+pub(crate) struct NoObserver;
+
+impl SolveObserver for NoObserver {}
@@ -0,0 +1,309 @@
+//! Reading puzzles from photos and scans of printed sudokus, enabled with the
+//! `ocr` feature. The grid is found as the largest connected dark shape of
+//! the image, so it has to be roughly upright and fill a good part of the
+//! picture. Each cell is then matched against digit templates, and how well
+//! it matched is reported so that doubtful cells can be checked and fixed.
+
+use std::fmt;
+use std::path::Path;
+
+pub use image::ImageError;
+
+use crate::board::{board_to_string, Board, Cell};
+use crate::font::{glyph_pixel, GLYPH_HEIGHT, GLYPH_WIDTH};
+use crate::grid::cell_name;
+
+/// The share of a cell's inner area that has to be dark for it to count as
+/// holding a digit.
+const MIN_INK: f64 = 0.02;
+
+/// The part of each side of a cell that is skipped so the grid lines aren't
+/// mistaken for digits.
+const INSET: f64 = 0.15;
+
+/// A puzzle read from an image along with how sure each cell is.
+#[derive(Debug, Clone)]
+pub struct Recognition {
+    pub board: Board,
+    /// How sure the reading of each cell is, from 0 to 1.
+    pub confidence: [[f64; 9]; 9],
+}
+
+impl Recognition {
+    /// Returns the cells read with a confidence below `threshold`, least sure
+    /// first.
+    pub fn doubtful(&self, threshold: f64) -> Vec<(usize, usize)> {
+        let mut cells: Vec<(usize, usize)> = (0..81)
+            .map(|i| (i / 9, i % 9))
+            .filter(|&(row, col)| self.confidence[row][col] < threshold)
+            .collect();
+        cells.sort_by(|a, b| self.confidence[a.0][a.1].total_cmp(&self.confidence[b.0][b.1]));
+        cells
+    }
+
+    /// Corrects the cell to `digit`, or to empty if it's None, and marks it
+    /// as certain.
+    pub fn correct(&mut self, row: usize, col: usize, digit: Option<i8>) {
+        self.board[row][col] = match digit {
+            Some(digit) => Cell::solved(digit),
+            None => Cell::unsolved(),
+        };
+        self.confidence[row][col] = 1.0;
+    }
+}
+
+/// Formats the puzzle followed by the cells that might have been misread.
+impl fmt::Display for Recognition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", board_to_string(&self.board))?;
+        for (row, col) in self.doubtful(0.6) {
+            let read = match self.board[row][col].solution {
+                Some(digit) => digit.to_string(),
+                None => String::from("empty"),
+            };
+            write!(
+                f,
+                "\n{}: {} ({:.0}% sure)",
+                cell_name(row, col),
+                read,
+                self.confidence[row][col] * 100.0
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads the puzzle in the image file at `path`. Returns None in the result
+/// if no grid was found.
+pub fn recognize_file<P: AsRef<Path>>(path: P) -> Result<Option<Recognition>, ImageError> {
+    let image = image::open(path)?.to_luma8();
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    Ok(recognize(width, height, image.as_raw()))
+}
+
+/// Reads the puzzle in a grayscale image, given row by row with one byte per
+/// pixel. Returns None if no grid was found.
+pub fn recognize(width: usize, height: usize, pixels: &[u8]) -> Option<Recognition> {
+    let threshold = otsu_threshold(pixels);
+    let dark: Vec<bool> = pixels.iter().map(|&p| p <= threshold).collect();
+    let (left, top, right, bottom) = largest_shape(width, height, &dark)?;
+    // Anything smaller can't hold readable digits.
+    if right - left < 45 || bottom - top < 45 {
+        return None;
+    }
+
+    let mut recognition = Recognition {
+        board: Default::default(),
+        confidence: [[1.0; 9]; 9],
+    };
+    let cell_width = (right - left) as f64 / 9.0;
+    let cell_height = (bottom - top) as f64 / 9.0;
+    for row in 0..9 {
+        for col in 0..9 {
+            let x0 = left as f64 + (col as f64 + INSET) * cell_width;
+            let y0 = top as f64 + (row as f64 + INSET) * cell_height;
+            let area = Area {
+                left: x0 as usize,
+                top: y0 as usize,
+                right: (x0 + (1.0 - 2.0 * INSET) * cell_width) as usize,
+                bottom: (y0 + (1.0 - 2.0 * INSET) * cell_height) as usize,
+            };
+            let (digit, confidence) = read_cell(width, &dark, area);
+            if let Some(digit) = digit {
+                recognition.board[row][col] = Cell::solved(digit);
+            }
+            recognition.confidence[row][col] = confidence;
+        }
+    }
+    Some(recognition)
+}
+
+/// A rectangle of pixels, excluding its right and bottom edges.
+#[derive(Debug, Clone, Copy)]
+struct Area {
+    left: usize,
+    top: usize,
+    right: usize,
+    bottom: usize,
+}
+
+/// Returns the threshold that best separates the dark and light pixels.
+fn otsu_threshold(pixels: &[u8]) -> u8 {
+    let mut histogram = [0usize; 256];
+    for &p in pixels {
+        histogram[p as usize] += 1;
+    }
+    let total = pixels.len() as f64;
+    let sum: f64 = histogram.iter().enumerate().map(|(i, &n)| i as f64 * n as f64).sum();
+    let (mut best, mut best_variance) = (127, 0.0);
+    let (mut dark_count, mut dark_sum) = (0.0, 0.0);
+    for (t, &n) in histogram.iter().enumerate() {
+        dark_count += n as f64;
+        dark_sum += t as f64 * n as f64;
+        let light_count = total - dark_count;
+        if dark_count == 0.0 || light_count == 0.0 {
+            continue;
+        }
+        let mean_difference = dark_sum / dark_count - (sum - dark_sum) / light_count;
+        let variance = dark_count * light_count * mean_difference * mean_difference;
+        if variance > best_variance {
+            best = t as u8;
+            best_variance = variance;
+        }
+    }
+    best
+}
+
+/// Returns the bounding box of the largest connected dark shape as (left,
+/// top, right, bottom), inclusive.
+fn largest_shape(width: usize, height: usize, dark: &[bool]) -> Option<(usize, usize, usize, usize)> {
+    let mut seen = vec![false; dark.len()];
+    let mut best: Option<(usize, (usize, usize, usize, usize))> = None;
+    let mut stack = Vec::new();
+    for start in 0..dark.len() {
+        if !dark[start] || seen[start] {
+            continue;
+        }
+        seen[start] = true;
+        stack.push(start);
+        let mut size = 0;
+        let mut bounds = (width, height, 0, 0);
+        while let Some(i) = stack.pop() {
+            let (x, y) = (i % width, i / width);
+            size += 1;
+            bounds = (bounds.0.min(x), bounds.1.min(y), bounds.2.max(x), bounds.3.max(y));
+            let mut visit = |j: usize| {
+                if dark[j] && !seen[j] {
+                    seen[j] = true;
+                    stack.push(j);
+                }
+            };
+            if x > 0 {
+                visit(i - 1);
+            }
+            if x + 1 < width {
+                visit(i + 1);
+            }
+            if y > 0 {
+                visit(i - width);
+            }
+            if y + 1 < height {
+                visit(i + width);
+            }
+        }
+        if best.is_none_or(|(best_size, _)| size > best_size) {
+            best = Some((size, bounds));
+        }
+    }
+    best.map(|(_, bounds)| bounds)
+}
+
+/// Reads the digit in `area`, if any, and how sure the reading is.
+fn read_cell(width: usize, dark: &[bool], area: Area) -> (Option<i8>, f64) {
+    // Shrink the area to the bounding box of the ink in it.
+    let mut ink = 0;
+    let mut bounds = Area { left: area.right, top: area.bottom, right: area.left, bottom: area.top };
+    for y in area.top..area.bottom {
+        for x in area.left..area.right {
+            if dark[y * width + x] {
+                ink += 1;
+                bounds.left = bounds.left.min(x);
+                bounds.top = bounds.top.min(y);
+                bounds.right = bounds.right.max(x + 1);
+                bounds.bottom = bounds.bottom.max(y + 1);
+            }
+        }
+    }
+    let share = ink as f64 / ((area.right - area.left) * (area.bottom - area.top)).max(1) as f64;
+    if share < MIN_INK {
+        return (None, 1.0 - share / MIN_INK * 0.5);
+    }
+
+    // Sample the ink into the pixel grid of the glyphs and pick the glyph that
+    // correlates best. A narrow shape is a 1, which is centered in its glyph.
+    let mut samples = [0.0; GLYPH_WIDTH * GLYPH_HEIGHT];
+    let ink_width = bounds.right - bounds.left;
+    let ink_height = bounds.bottom - bounds.top;
+    let narrow = ink_width * 3 < ink_height;
+    for (i, sample) in samples.iter_mut().enumerate() {
+        let (gx, gy) = (i % GLYPH_WIDTH, i / GLYPH_WIDTH);
+        let (x0, x1) = if narrow {
+            if gx != GLYPH_WIDTH / 2 {
+                continue;
+            }
+            (bounds.left, bounds.right)
+        } else {
+            (
+                bounds.left + ink_width * gx / GLYPH_WIDTH,
+                bounds.left + ink_width * (gx + 1) / GLYPH_WIDTH,
+            )
+        };
+        let y0 = bounds.top + ink_height * gy / GLYPH_HEIGHT;
+        let y1 = bounds.top + ink_height * (gy + 1) / GLYPH_HEIGHT;
+        let mut count = 0;
+        for y in y0..y1.max(y0 + 1) {
+            for x in x0..x1.max(x0 + 1) {
+                if dark[y * width + x] {
+                    count += 1;
+                }
+            }
+        }
+        *sample = count as f64 / ((x1.max(x0 + 1) - x0) * (y1.max(y0 + 1) - y0)) as f64;
+    }
+
+    let mut scores: Vec<(f64, i8)> = (1..10)
+        .map(|digit| {
+            let template: Vec<f64> = (0..samples.len())
+                .map(|i| glyph_pixel(digit, i % GLYPH_WIDTH, i / GLYPH_WIDTH) as u8 as f64)
+                .collect();
+            (correlation(&samples, &template), digit)
+        })
+        .collect();
+    scores.sort_by(|a, b| b.0.total_cmp(&a.0));
+    let (best, digit) = scores[0];
+    let margin = best - scores[1].0;
+    // A clear winner is what makes a reading trustworthy, more than a close
+    // match, since every font differs from the templates.
+    let confidence = (best.max(0.0) * 0.5 + (margin * 2.0).min(0.5)).min(1.0);
+    (Some(digit), confidence)
+}
+
+/// Returns the Pearson correlation of `a` and `b`, which have the same
+/// length.
+fn correlation(a: &[f64], b: &[f64]) -> f64 {
+    let n = a.len() as f64;
+    let (mean_a, mean_b) = (a.iter().sum::<f64>() / n, b.iter().sum::<f64>() / n);
+    let (mut cov, mut var_a, mut var_b) = (0.0, 0.0, 0.0);
+    for (x, y) in a.iter().zip(b) {
+        cov += (x - mean_a) * (y - mean_b);
+        var_a += (x - mean_a) * (x - mean_a);
+        var_b += (y - mean_b) * (y - mean_b);
+    }
+    if var_a == 0.0 || var_b == 0.0 {
+        0.0
+    } else {
+        cov / (var_a * var_b).sqrt()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::default_board;
+    use crate::raster::draw_board;
+
+    #[test]
+    fn test_recognize() {
+        let bitmap = draw_board(&default_board(), None);
+        let (size, pixels) = (bitmap.width, bitmap.pixels);
+        let mut recognition = recognize(size, size, &pixels).unwrap();
+        assert_eq!(board_to_string(&recognition.board), board_to_string(&default_board()));
+        assert!(recognition.doubtful(0.6).is_empty());
+
+        recognition.confidence[0][0] = 0.1;
+        assert_eq!(recognition.doubtful(0.6), vec![(0, 0)]);
+        recognition.correct(0, 0, Some(3));
+        assert!(recognition.doubtful(0.6).is_empty());
+        assert!(recognize(size, size, &vec![255; size * size]).is_none());
+    }
+}
@@ -0,0 +1,291 @@
+//! The board representation the backtracking search works on: one byte per
+//! cell and a mask of the digits used in each row, column, and box. It's a
+//! fraction of the size of a `Board`, so the search state stays in cache
+//! even when solving large batches, and checking a digit is a few bit
+//! operations. Boards are only converted to and from it at the API boundary.
+
+use crate::board::{Board, Cell};
+use crate::grid::{digit_mask, Mask, ALL_DIGITS};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) struct Packed {
+    /// The digit of each cell, row by row, or 0 if it's empty.
+    pub(crate) cells: [u8; 81],
+    rows: [Mask; 9],
+    cols: [Mask; 9],
+    boxes: [Mask; 9],
+}
+
+/// Returns the row, column, and box of the cell at `index`.
+fn units(index: usize) -> (usize, usize, usize) {
+    let (row, col) = (index / 9, index % 9);
+    (row, col, row / 3 * 3 + col / 3)
+}
+
+impl Packed {
+    /// Returns an empty board.
+    pub(crate) fn new() -> Packed {
+        Packed { cells: [0; 81], rows: [0; 9], cols: [0; 9], boxes: [0; 9] }
+    }
+
+    /// Packs `board`. Returns None if a digit is given twice in a row,
+    /// column, or box, or a cell holds something other than 1 to 9.
+    pub(crate) fn from_board(board: &Board) -> Option<Packed> {
+        let mut packed = Packed::new();
+        for index in 0..81 {
+            if let Some(digit) = board[index / 9][index % 9].solution {
+                if !(1..=9).contains(&digit) || !packed.allows(index, digit as u8) {
+                    return None;
+                }
+                packed.place(index, digit as u8);
+            }
+        }
+        Some(packed)
+    }
+
+    /// Unpacks into `board`, overwriting all of its cells, with every filled
+    /// cell solved.
+    pub(crate) fn write_to(&self, board: &mut Board) {
+        for (index, &digit) in self.cells.iter().enumerate() {
+            board[index / 9][index % 9] = match digit {
+                0 => Cell::unsolved(),
+                digit => Cell::solved(digit as i8),
+            };
+        }
+    }
+
+    /// Returns the digits used in `row`, `col`, and box `b`.
+    #[cfg(feature = "unstable")]
+    pub(crate) fn used(&self, row: usize, col: usize, b: usize) -> (Mask, Mask, Mask) {
+        (self.rows[row], self.cols[col], self.boxes[b])
+    }
+
+    /// Returns the digits that can go in the empty cell at `index`.
+    pub(crate) fn candidates(&self, index: usize) -> Mask {
+        let (row, col, b) = units(index);
+        ALL_DIGITS & !(self.rows[row] | self.cols[col] | self.boxes[b])
+    }
+
+    /// Returns the position in `cells`, which mustn't be empty, of the empty
+    /// cell with the fewest candidates.
+    #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+    pub(crate) fn fewest_candidates(&self, cells: &[u8]) -> usize {
+        first_fewest(cells, |index| self.candidates(index as usize).count_ones())
+    }
+
+    /// Like the scalar version, but counts the candidates of every cell at
+    /// once with SSE2.
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    pub(crate) fn fewest_candidates(&self, cells: &[u8]) -> usize {
+        let (counts, fewest) = self.candidate_counts();
+        let count = |index: u8| counts[index as usize / 9 * 16 + index as usize % 9] as u32;
+        // When `cells` are all the empty cells, as in the search, the first
+        // with a forced digit or as few candidates as the fewest is the one
+        // the scan would stop at.
+        let fewest = (fewest as u32).max(1);
+        cells.iter().position(|&index| count(index) <= fewest).unwrap_or_else(|| first_fewest(cells, count))
+    }
+
+    /// Returns the candidate counts of the cells, row by row with 16 lanes
+    /// per row, and the fewest of the empty cells. Filled cells and the
+    /// lanes past the ninth column count 16.
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    fn candidate_counts(&self) -> ([u16; 144], u16) {
+        use std::arch::x86_64::*;
+
+        // SAFETY: SSE2 is part of x86_64, and every load and store is within
+        // its array.
+        unsafe {
+            let load = |p: *const u8| _mm_loadu_si128(p as *const __m128i);
+            let zero = _mm_setzero_si128();
+            let full = _mm_set1_epi16(16);
+            let all = _mm_set1_epi16(ALL_DIGITS as i16);
+            let mut cols = [0u16; 16];
+            cols[..9].copy_from_slice(&self.cols);
+            let cols = [load(cols.as_ptr() as *const u8), load(cols[8..].as_ptr() as *const u8)];
+            // Only the first lane of the second half of a row is a cell.
+            let cell_lanes = [_mm_set1_epi16(-1), _mm_setr_epi16(-1, 0, 0, 0, 0, 0, 0, 0)];
+
+            let mut counts = [0u16; 144];
+            let mut fewest = full;
+            for row in 0..9 {
+                let [b0, b1, b2] = [0, 1, 2].map(|i| self.boxes[row / 3 * 3 + i] as i16);
+                let boxes = [_mm_setr_epi16(b0, b0, b0, b1, b1, b1, b2, b2), _mm_setr_epi16(b2, 0, 0, 0, 0, 0, 0, 0)];
+                // The digits of the row's cells as the first of 16 bytes. The
+                // last row is read from further back so as not to overrun.
+                let bytes = match row {
+                    8 => _mm_srli_si128::<7>(load(self.cells.as_ptr().add(65))),
+                    _ => load(self.cells.as_ptr().add(row * 9)),
+                };
+                let digits = [_mm_unpacklo_epi8(bytes, zero), _mm_unpackhi_epi8(bytes, zero)];
+                let row_used = _mm_set1_epi16(self.rows[row] as i16);
+                for half in 0..2 {
+                    let used = _mm_or_si128(_mm_or_si128(row_used, cols[half]), boxes[half]);
+                    let count = popcount(_mm_andnot_si128(used, all));
+                    let empty = _mm_and_si128(_mm_cmpeq_epi16(digits[half], zero), cell_lanes[half]);
+                    let count = _mm_or_si128(_mm_and_si128(empty, count), _mm_andnot_si128(empty, full));
+                    fewest = _mm_min_epi16(fewest, count);
+                    _mm_storeu_si128(counts[row * 16 + half * 8..].as_mut_ptr() as *mut __m128i, count);
+                }
+            }
+            fewest = _mm_min_epi16(fewest, _mm_srli_si128::<8>(fewest));
+            fewest = _mm_min_epi16(fewest, _mm_srli_si128::<4>(fewest));
+            fewest = _mm_min_epi16(fewest, _mm_srli_si128::<2>(fewest));
+            (counts, _mm_cvtsi128_si32(fewest) as u16)
+        }
+    }
+
+    /// Returns how many empty peers of the cell at `index` have `digit` as a
+    /// candidate, i.e. how many candidates placing it there would remove.
+    pub(crate) fn eliminations(&self, index: usize, digit: u8) -> u32 {
+        let (row, col, _) = units(index);
+        let mask = digit_mask(digit as i8);
+        let mut count = 0;
+        let mut count_peer = |peer: usize| {
+            if self.cells[peer] == 0 && self.candidates(peer) & mask != 0 {
+                count += 1;
+            }
+        };
+        for i in 0..9 {
+            if i != col {
+                count_peer(row * 9 + i);
+            }
+            if i != row {
+                count_peer(i * 9 + col);
+            }
+            // The cells of the box outside the row and column of the cell.
+            let (box_row, box_col) = (row / 3 * 3 + i / 3, col / 3 * 3 + i % 3);
+            if box_row != row && box_col != col {
+                count_peer(box_row * 9 + box_col);
+            }
+        }
+        count
+    }
+
+    /// Returns whether `digit` isn't used yet by the peers of the cell at
+    /// `index`.
+    pub(crate) fn allows(&self, index: usize, digit: u8) -> bool {
+        self.candidates(index) & digit_mask(digit as i8) != 0
+    }
+
+    pub(crate) fn place(&mut self, index: usize, digit: u8) {
+        let (row, col, b) = units(index);
+        let mask = digit_mask(digit as i8);
+        self.cells[index] = digit;
+        self.rows[row] |= mask;
+        self.cols[col] |= mask;
+        self.boxes[b] |= mask;
+        invariant!(self.check_invariants());
+    }
+
+    /// Empties the cell at `index`, which holds `digit`.
+    pub(crate) fn unplace(&mut self, index: usize, digit: u8) {
+        let (row, col, b) = units(index);
+        let mask = !digit_mask(digit as i8);
+        self.cells[index] = 0;
+        self.rows[row] &= mask;
+        self.cols[col] &= mask;
+        self.boxes[b] &= mask;
+        invariant!(self.check_invariants());
+    }
+
+    /// Panics unless the unit masks are exactly the digits of the cells and no
+    /// digit is placed twice in a unit.
+    #[cfg(feature = "debug-invariants")]
+    pub(crate) fn check_invariants(&self) {
+        let (mut rows, mut cols, mut boxes) = ([0; 9], [0; 9], [0; 9]);
+        for (index, &digit) in self.cells.iter().enumerate().filter(|(_, &digit)| digit != 0) {
+            let (row, col, b) = units(index);
+            let mask = digit_mask(digit as i8);
+            assert_eq!((rows[row] | cols[col] | boxes[b]) & mask, 0, "{} is placed twice in a unit of cell {}", digit, index);
+            rows[row] |= mask;
+            cols[col] |= mask;
+            boxes[b] |= mask;
+        }
+        assert!(rows == self.rows && cols == self.cols && boxes == self.boxes, "the unit masks don't match the cells");
+    }
+}
+
+/// Returns the position in `cells` of the first cell with the fewest
+/// candidates as counted by `count`, stopping early at one with at most one.
+fn first_fewest(cells: &[u8], count: impl Fn(u8) -> u32) -> usize {
+    let mut fewest = (u32::MAX, 0);
+    for (i, &index) in cells.iter().enumerate() {
+        let count = count(index);
+        if count < fewest.0 {
+            fewest = (count, i);
+            // It can't get better than a dead end or a forced digit.
+            if count <= 1 {
+                break;
+            }
+        }
+    }
+    fewest.1
+}
+
+/// Counts the bits of each 16 bit lane.
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+unsafe fn popcount(x: std::arch::x86_64::__m128i) -> std::arch::x86_64::__m128i {
+    use std::arch::x86_64::*;
+
+    let x = _mm_sub_epi16(x, _mm_and_si128(_mm_srli_epi16::<1>(x), _mm_set1_epi16(0x5555)));
+    let x = _mm_add_epi16(
+        _mm_and_si128(x, _mm_set1_epi16(0x3333)),
+        _mm_and_si128(_mm_srli_epi16::<2>(x), _mm_set1_epi16(0x3333)),
+    );
+    let x = _mm_and_si128(_mm_add_epi16(x, _mm_srli_epi16::<4>(x)), _mm_set1_epi16(0x0f0f));
+    _mm_and_si128(_mm_add_epi16(x, _mm_srli_epi16::<8>(x)), _mm_set1_epi16(0x1f))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{board_to_string, default_board};
+
+    #[test]
+    fn test_packed() {
+        let mut packed = Packed::from_board(&default_board()).unwrap();
+        let mut board = Board::default();
+        packed.write_to(&mut board);
+        assert_eq!(board_to_string(&board), board_to_string(&default_board()));
+        assert_eq!(std::mem::size_of::<Packed>(), 81 + 3 * 9 * 2 + 1);
+
+        // r1c1 sees 2, 5, 7, 8, and 9 in its units.
+        assert_eq!(packed.candidates(0), digit_mask(1) | digit_mask(3) | digit_mask(4) | digit_mask(6));
+        // Only 9 can go in r2c4, the third of these cells.
+        assert_eq!(packed.fewest_candidates(&[0, 1, 12, 4]), 2);
+        // 3 can go in three other cells of r1c1's row, five of its column,
+        // and r2c3 in its box.
+        assert_eq!(packed.eliminations(0, 3), 9);
+        packed.place(0, 3);
+        assert!(!packed.allows(1, 3) && !packed.allows(9, 3) && !packed.allows(20, 3));
+        packed.unplace(0, 3);
+        assert_eq!(packed, Packed::from_board(&default_board()).unwrap());
+
+        let mut board = default_board();
+        board[0][0] = Cell::solved(5);
+        assert!(Packed::from_board(&board).is_none());
+
+        // The vector counts agree with the scalar ones, the last row too.
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        {
+            packed.place(80, packed.candidates(80).trailing_zeros() as u8);
+            let (counts, fewest) = packed.candidate_counts();
+            let empty: Vec<usize> = (0..81).filter(|&index| packed.cells[index] == 0).collect();
+            for index in 0..81 {
+                let expected = if packed.cells[index] == 0 { packed.candidates(index).count_ones() } else { 16 };
+                assert_eq!(counts[index / 9 * 16 + index % 9] as u32, expected, "{}", index);
+            }
+            assert_eq!(Some(fewest as u32), empty.iter().map(|&index| packed.candidates(index).count_ones()).min());
+        }
+    }
+
+    #[cfg(feature = "debug-invariants")]
+    #[test]
+    #[should_panic(expected = "the unit masks don't match the cells")]
+    fn test_invariants() {
+        let mut packed = Packed::from_board(&default_board()).unwrap();
+        packed.rows[0] = 0;
+        packed.place(0, 3);
+    }
+}
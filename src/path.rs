@@ -0,0 +1,148 @@
+//! Searching for the cleanest way to solve a puzzle, for walkthroughs. The
+//! logical solver takes the first step it finds, which can make for a long
+//! detour: an early elimination that doesn't lead anywhere, or a hard
+//! technique where another order of easy ones would have done. Here the
+//! different steps that apply are tried in turn, keeping the best path.
+//!
+//! Singles are always taken as they come, since each of them places a
+//! digit and every path places the same digits. Only the steps that
+//! eliminate candidates are chosen between.
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+use crate::board::Board;
+use crate::grid::Grid;
+use crate::logic::{find_technique, solution_board, Step, Technique, TechniqueConfig, Trace};
+use crate::refute::find_contradiction;
+
+/// What makes one solve path better than another.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum PathGoal {
+    /// The fewest steps, and of those, the easiest hardest technique.
+    FewestSteps,
+    /// The easiest hardest technique, and of those, the fewest steps.
+    SimplestTechniques,
+}
+
+/// The cost of a path so far: its hardest technique and number of steps,
+/// not counting singles, ordered by `goal`. Both only grow as the path does.
+fn cost(goal: PathGoal, hardest: Option<Technique>, steps: usize) -> (usize, usize) {
+    let hardest = hardest.map_or(0, |technique| technique as usize + 1);
+    match goal {
+        PathGoal::FewestSteps => (steps, hardest),
+        PathGoal::SimplestTechniques => (hardest, steps),
+    }
+}
+
+struct PathSearch<'a> {
+    config: &'a TechniqueConfig,
+    goal: PathGoal,
+    /// How many more paths may be followed before the search stops with
+    /// the best it has.
+    budget: usize,
+    /// The cheapest path found to each grid that has been reached.
+    reached: HashMap<Grid, (usize, usize)>,
+    best: Option<((usize, usize), Vec<Step>, Grid)>,
+}
+
+impl<'a> PathSearch<'a> {
+    fn search(&mut self, mut grid: Grid, mut path: Vec<Step>, hardest: Option<Technique>, steps: usize) {
+        let cost = cost(self.goal, hardest, steps);
+        if self.budget == 0 || self.best.as_ref().is_some_and(|(best, _, _)| cost >= *best) {
+            return;
+        }
+        self.budget -= 1;
+        // Singles don't make a path any longer or harder than another, so
+        // they're taken without trying alternatives.
+        let singles = [Technique::NakedSingle, Technique::HiddenSingle];
+        let enabled = |technique: &&Technique| self.config.is_enabled(**technique);
+        while let Some(step) = singles.iter().filter(enabled).find_map(|&technique| find_technique(&grid, technique)) {
+            step.apply(&mut grid);
+            path.push(step);
+        }
+        if find_contradiction(&grid).is_some() {
+            return;
+        }
+        if grid.is_complete() {
+            self.best = Some((cost, path, grid));
+            return;
+        }
+        if self.reached.get(&grid).is_some_and(|&reached| reached <= cost) {
+            return;
+        }
+        self.reached.insert(grid, cost);
+
+        let mut tried: Vec<Vec<(usize, usize, i8)>> = Vec::new();
+        for &technique in self.config.techniques().iter() {
+            if singles.contains(&technique) || (technique.needs_uniqueness() && !self.config.assumes_unique()) {
+                continue;
+            }
+            let step = match find_technique(&grid, technique) {
+                Some(step) => step,
+                None => continue,
+            };
+            let mut eliminations = step.eliminations.clone();
+            eliminations.sort();
+            if tried.contains(&eliminations) {
+                continue;
+            }
+            tried.push(eliminations);
+            let mut next = grid;
+            step.apply(&mut next);
+            let mut next_path = path.clone();
+            next_path.push(step);
+            self.search(next, next_path, hardest.max(Some(technique)), steps + 1);
+        }
+    }
+}
+
+/// Searches for the best path to solve `board` with the techniques of
+/// `config`, as `goal` has it, without guessing. Gives up on paths after
+/// following `budget` of them and returns the best one found, which is
+/// never worse than the path `solve_with` takes if `budget` is enough to
+/// follow that one. Returns None if no path without guesses was found.
+pub fn best_path(board: &Board, config: &TechniqueConfig, goal: PathGoal, budget: usize) -> Option<Trace> {
+    let start = Instant::now();
+    let initial = Grid::from_board(board);
+    let mut search = PathSearch { config, goal, budget, reached: HashMap::new(), best: None };
+    search.search(initial, Vec::new(), None, 0);
+    let (_, steps, grid) = search.best?;
+    Some(Trace {
+        solution: Some(solution_board(board, grid, &steps)),
+        steps,
+        elapsed: start.elapsed(),
+        guesses: 0,
+        guess_depth: 0,
+        initial,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{board_to_string, parse_board};
+    use crate::logic::solve_with;
+
+    #[test]
+    fn test_best_path() {
+        let board =
+            parse_board("5....84.....9..156.6..4..2.842.9.5.....7......31............6...53.....2...43..81").unwrap();
+        let config = TechniqueConfig::default();
+        let greedy = solve_with(&board, &config);
+        assert_eq!(greedy.guesses, 0);
+        let trace = best_path(&board, &config, PathGoal::FewestSteps, 1000).unwrap();
+        // The solver takes 5 more steps than it needs.
+        assert!(trace.steps.len() < greedy.steps.len());
+        assert_eq!(board_to_string(&trace.solution.unwrap()), board_to_string(&greedy.solution.unwrap()));
+
+        let hardest = |trace: &Trace| trace.steps.iter().map(|step| step.technique).max();
+        let simplest = best_path(&board, &config, PathGoal::SimplestTechniques, 1000).unwrap();
+        assert!(hardest(&simplest) <= hardest(&greedy));
+        assert!(hardest(&simplest) <= hardest(&trace));
+
+        let board =
+            parse_board("8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..").unwrap();
+        assert!(best_path(&board, &config, PathGoal::FewestSteps, 1000).is_none());
+    }
+}
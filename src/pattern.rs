@@ -0,0 +1,182 @@
+//! The pattern of a puzzle's givens, regardless of their digits. Setters
+//! care about how it looks: most published puzzles are symmetric, and
+//! givens crowded into a few bands or stacks look lopsided and tend to make
+//! the rest of the grid a slog.
+
+use std::fmt;
+
+use crate::board::Board;
+use crate::json::Json;
+use crate::transform::Symmetry;
+
+/// Where the givens of a puzzle are.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct GivensPattern {
+    /// Whether each cell is a given.
+    pub cells: [[bool; 9]; 9],
+    /// The rotations and reflections that keep the pattern as it is,
+    /// besides the identity.
+    pub symmetries: Vec<Symmetry>,
+    /// The givens in each band of three rows, from the top.
+    pub bands: [usize; 3],
+    /// The givens in each stack of three columns, from the left.
+    pub stacks: [usize; 3],
+}
+
+impl GivensPattern {
+    pub fn clues(&self) -> usize {
+        self.bands.iter().sum()
+    }
+
+    /// Returns the name of the symmetry class of the pattern, from the most
+    /// symmetric, `dihedral`, to `none`.
+    pub fn symmetry_class(&self) -> &'static str {
+        let has = |symmetry| self.symmetries.contains(&symmetry);
+        if self.symmetries.len() == 7 {
+            "dihedral"
+        } else if has(Symmetry::Rotate90) {
+            "90° rotational"
+        } else if has(Symmetry::Transpose) && has(Symmetry::AntiTranspose) {
+            "both diagonals"
+        } else if has(Symmetry::FlipHorizontal) && has(Symmetry::FlipVertical) {
+            "both axes"
+        } else if has(Symmetry::Rotate180) {
+            "180° rotational"
+        } else if has(Symmetry::Transpose) {
+            "diagonal"
+        } else if has(Symmetry::AntiTranspose) {
+            "anti-diagonal"
+        } else if has(Symmetry::FlipHorizontal) {
+            "vertical axis"
+        } else if has(Symmetry::FlipVertical) {
+            "horizontal axis"
+        } else {
+            "none"
+        }
+    }
+
+    /// Returns a line for each band or stack with less than half or more
+    /// than half again its share of the givens, and for each box without
+    /// any.
+    pub fn imbalances(&self) -> Vec<String> {
+        let share = self.clues() as f64 / 3.0;
+        let mut imbalances = Vec::new();
+        for (name, counts) in [("Band", &self.bands), ("Stack", &self.stacks)] {
+            for (i, &count) in counts.iter().enumerate() {
+                if (count as f64) < share / 2.0 {
+                    imbalances.push(format!("{} {} has only {} givens of {}", name, i + 1, count, self.clues()));
+                } else if count as f64 > share * 1.5 {
+                    imbalances.push(format!("{} {} has {} givens of {}", name, i + 1, count, self.clues()));
+                }
+            }
+        }
+        for b in 0..9 {
+            let (row, col) = (b / 3 * 3, b % 3 * 3);
+            if (row..row + 3).all(|r| (col..col + 3).all(|c| !self.cells[r][c])) {
+                imbalances.push(format!("Box {} has no givens", b + 1));
+            }
+        }
+        imbalances
+    }
+
+    pub fn to_json(&self) -> Json {
+        let counts = |counts: &[usize; 3]| Json::from(counts.to_vec());
+        let pattern: String =
+            self.cells.iter().flat_map(|row| row.iter()).map(|&given| if given { 'X' } else { '.' }).collect();
+        Json::object(vec![
+            ("pattern", Json::from(pattern)),
+            ("clues", Json::from(self.clues())),
+            ("symmetry", Json::from(self.symmetry_class())),
+            ("symmetries", Json::from(self.symmetries.iter().map(|symmetry| symmetry.name()).collect::<Vec<_>>())),
+            ("bands", counts(&self.bands)),
+            ("stacks", counts(&self.stacks)),
+            ("imbalances", Json::from(self.imbalances())),
+        ])
+    }
+}
+
+/// Draws the pattern with `X` for givens and `.` for empty cells, with the
+/// boxes spaced apart, followed by its statistics.
+impl fmt::Display for GivensPattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (row, cells) in self.cells.iter().enumerate() {
+            if row > 0 && row % 3 == 0 {
+                writeln!(f)?;
+            }
+            for (col, &given) in cells.iter().enumerate() {
+                if col > 0 && col % 3 == 0 {
+                    write!(f, " ")?;
+                }
+                write!(f, "{}", if given { 'X' } else { '.' })?;
+            }
+            writeln!(f)?;
+        }
+        let counts = |counts: &[usize; 3]| counts.iter().map(usize::to_string).collect::<Vec<_>>().join(" ");
+        writeln!(f, "\nClues: {}", self.clues())?;
+        writeln!(f, "Symmetry: {}", self.symmetry_class())?;
+        writeln!(f, "Bands: {}", counts(&self.bands))?;
+        write!(f, "Stacks: {}", counts(&self.stacks))?;
+        for imbalance in self.imbalances() {
+            write!(f, "\n{}", imbalance)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the pattern of the givens of `board`.
+pub fn givens_pattern(board: &Board) -> GivensPattern {
+    let mut cells = [[false; 9]; 9];
+    let mut bands = [0; 3];
+    let mut stacks = [0; 3];
+    for row in 0..9 {
+        for col in 0..9 {
+            if board[row][col].solution.is_some() {
+                cells[row][col] = true;
+                bands[row / 3] += 1;
+                stacks[col / 3] += 1;
+            }
+        }
+    }
+    let symmetries = Symmetry::ALL[1..]
+        .iter()
+        .copied()
+        .filter(|&symmetry| {
+            (0..81).all(|i| {
+                let (row, col) = (i / 9, i % 9);
+                let (r, c) = symmetry.map(row, col);
+                cells[row][col] == cells[r][c]
+            })
+        })
+        .collect();
+    GivensPattern { cells, symmetries, bands, stacks }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{default_board, parse_board};
+
+    #[test]
+    fn test_givens_pattern() {
+        let pattern = givens_pattern(&default_board());
+        assert_eq!(pattern.clues(), 36);
+        assert!(pattern.to_string().starts_with("..X ..X ...\n"));
+
+        // Unlike most published puzzles, this one has no symmetry.
+        let board =
+            parse_board("8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..").unwrap();
+        let pattern = givens_pattern(&board);
+        assert_eq!(pattern.clues(), 21);
+        assert_eq!(pattern.symmetry_class(), "none");
+
+        let board =
+            parse_board("1...............................................................................1").unwrap();
+        let pattern = givens_pattern(&board);
+        assert_eq!(pattern.symmetries, vec![Symmetry::Rotate180, Symmetry::Transpose, Symmetry::AntiTranspose]);
+        assert_eq!(pattern.symmetry_class(), "both diagonals");
+        assert_eq!((pattern.bands, pattern.stacks), ([1, 0, 1], [1, 0, 1]));
+        assert_eq!(pattern.imbalances().len(), 1 + 1 + 7);
+        assert_eq!(pattern.imbalances()[0], "Band 2 has only 0 givens of 2");
+        assert_eq!(pattern.to_json().get("symmetry").and_then(Json::as_str), Some("both diagonals"));
+    }
+}
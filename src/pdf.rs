@@ -0,0 +1,179 @@
+//! Print-ready PDF sheets of puzzles, enabled with the `pdf` feature. The
+//! writer is minimal: A4 pages of uncompressed drawing commands with the
+//! standard Helvetica font, which every PDF reader has, so nothing needs to
+//! be embedded.
+
+use crate::board::Board;
+use crate::solver::Sudoku;
+
+/// The size of an A4 page in points.
+const PAGE_WIDTH: f64 = 595.0;
+const PAGE_HEIGHT: f64 = 842.0;
+const MARGIN: f64 = 36.0;
+/// The height of the line above each grid with its number.
+const TITLE_HEIGHT: f64 = 20.0;
+/// The space around each grid.
+const PADDING: f64 = 12.0;
+
+/// How puzzles are laid out on a sheet.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct PdfSheet {
+    /// The puzzles on each page, in two columns unless there's only one.
+    pub per_page: usize,
+    /// Whether the solutions follow the puzzles, laid out the same way with
+    /// the givens in black and the rest in gray.
+    pub solutions: bool,
+}
+
+impl Default for PdfSheet {
+    fn default() -> PdfSheet {
+        PdfSheet { per_page: 4, solutions: false }
+    }
+}
+
+impl PdfSheet {
+    /// Writes `puzzles` as a PDF, numbered from 1. Puzzles without a
+    /// solution are left empty on the solution pages.
+    pub fn write(&self, puzzles: &[Board]) -> Vec<u8> {
+        let per_page = self.per_page.max(1);
+        let mut pages: Vec<String> = Vec::new();
+        for (i, chunk) in puzzles.chunks(per_page).enumerate() {
+            let boards: Vec<(Board, Option<Board>)> = chunk.iter().map(|&puzzle| (puzzle, None)).collect();
+            pages.push(page(&boards, i * per_page, per_page, "Puzzle"));
+        }
+        if self.solutions {
+            for (i, chunk) in puzzles.chunks(per_page).enumerate() {
+                let boards: Vec<(Board, Option<Board>)> =
+                    chunk.iter().map(|&puzzle| (puzzle, Sudoku::new(puzzle).solve())).collect();
+                pages.push(page(&boards, i * per_page, per_page, "Solution"));
+            }
+        }
+        document(&pages)
+    }
+}
+
+/// Returns the drawing commands of a page of `boards`, the first of which is
+/// number `first` + 1.
+fn page(boards: &[(Board, Option<Board>)], first: usize, per_page: usize, title: &str) -> String {
+    let columns = if per_page == 1 { 1 } else { 2 };
+    let rows = per_page.div_ceil(columns);
+    let slot_width = (PAGE_WIDTH - 2.0 * MARGIN) / columns as f64;
+    let slot_height = (PAGE_HEIGHT - 2.0 * MARGIN) / rows as f64;
+    let size = (slot_width - 2.0 * PADDING).min(slot_height - TITLE_HEIGHT - 2.0 * PADDING);
+    let mut content = String::new();
+    for (i, (puzzle, solution)) in boards.iter().enumerate() {
+        let left = MARGIN + (i % columns) as f64 * slot_width + (slot_width - size) / 2.0;
+        let top = PAGE_HEIGHT - MARGIN - (i / columns) as f64 * slot_height - PADDING;
+        text(&mut content, left, top - 12.0, 12.0, &format!("{} {}", title, first + i + 1));
+        grid(&mut content, left, top - TITLE_HEIGHT - size, size, puzzle, solution.as_ref());
+    }
+    content
+}
+
+/// Draws the grid with its bottom left corner at `x`, `y`.
+fn grid(content: &mut String, x: f64, y: f64, size: f64, puzzle: &Board, solution: Option<&Board>) {
+    let cell = size / 9.0;
+    // Square line caps close the corners of the thick lines.
+    content.push_str("2 J\n");
+    for i in 0..10 {
+        let width = if i % 3 == 0 { 2.0 } else { 0.5 };
+        let offset = i as f64 * cell;
+        content.push_str(&format!("{} w\n", width));
+        content.push_str(&format!("{:.2} {:.2} m {:.2} {:.2} l S\n", x + offset, y, x + offset, y + size));
+        content.push_str(&format!("{:.2} {:.2} m {:.2} {:.2} l S\n", x, y + offset, x + size, y + offset));
+    }
+    let font_size = cell * 0.6;
+    for row in 0..9 {
+        for col in 0..9 {
+            let (digit, gray) = match (puzzle[row][col].solution, solution) {
+                (Some(digit), _) => (digit, false),
+                (None, Some(solution)) => match solution[row][col].solution {
+                    Some(digit) => (digit, true),
+                    None => continue,
+                },
+                (None, None) => continue,
+            };
+            // Helvetica's digits are 0.556 em wide and 0.7 em high.
+            let center_x = x + (col as f64 + 0.5) * cell;
+            let center_y = y + (8.5 - row as f64) * cell;
+            if gray {
+                content.push_str("0.5 g\n");
+            }
+            let digit = digit.to_string();
+            text(content, center_x - 0.278 * font_size, center_y - 0.35 * font_size, font_size, &digit);
+            if gray {
+                content.push_str("0 g\n");
+            }
+        }
+    }
+}
+
+/// Writes `s`, which mustn't have parentheses or backslashes, at `x`, `y`.
+fn text(content: &mut String, x: f64, y: f64, size: f64, s: &str) {
+    content.push_str(&format!("BT /F1 {:.2} Tf {:.2} {:.2} Td ({}) Tj ET\n", size, x, y, s));
+}
+
+/// Assembles a PDF of pages with the given drawing commands.
+fn document(pages: &[String]) -> Vec<u8> {
+    // The catalog, the page tree, and the font come first, then a page and
+    // its content for each page.
+    let mut objects = vec![
+        String::from("<< /Type /Catalog /Pages 2 0 R >>"),
+        String::new(),
+        String::from("<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>"),
+    ];
+    let mut kids = Vec::new();
+    for content in pages {
+        let id = objects.len() + 1;
+        kids.push(format!("{} 0 R", id));
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R >> >> \
+             /Contents {} 0 R >>",
+            PAGE_WIDTH,
+            PAGE_HEIGHT,
+            id + 1
+        ));
+        objects.push(format!("<< /Length {} >>\nstream\n{}\nendstream", content.len(), content));
+    }
+    objects[1] = format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), pages.len());
+
+    let mut pdf = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::new();
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf += &format!("{} 0 obj\n{}\nendobj\n", i + 1, object);
+    }
+    let xref = pdf.len();
+    pdf += &format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        pdf += &format!("{:010} 00000 n \n", offset);
+    }
+    pdf += &format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref);
+    pdf.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::default_board;
+
+    #[test]
+    fn test_pdf_sheet() {
+        let puzzles = vec![default_board(); 5];
+        let pdf = String::from_utf8(PdfSheet::default().write(&puzzles)).unwrap();
+        assert!(pdf.starts_with("%PDF-1.4\n") && pdf.ends_with("%%EOF\n"));
+        assert!(pdf.contains("/Count 2 >>"));
+        assert!(pdf.contains("(Puzzle 5) Tj") && !pdf.contains("(Solution"));
+
+        // Every object is where the cross-reference table says.
+        let sheet = PdfSheet { per_page: 8, solutions: true };
+        let pdf = String::from_utf8(sheet.write(&puzzles)).unwrap();
+        assert!(pdf.contains("/Count 2 >>") && pdf.contains("(Solution 5) Tj"));
+        let xref = pdf.rsplit("startxref\n").next().unwrap().lines().next().unwrap().parse::<usize>().unwrap();
+        assert!(pdf[xref..].starts_with("xref\n"));
+        for (i, line) in pdf[xref..].lines().skip(3).take_while(|line| line.ends_with(" n ")).enumerate() {
+            let offset: usize = line[..10].parse().unwrap();
+            assert!(pdf[offset..].starts_with(&format!("{} 0 obj", i + 1)));
+        }
+    }
+}
@@ -0,0 +1,216 @@
+//! Puzzles that are known to be proper: their givens don't conflict and they
+//! have exactly one solution. A `Board` can hold anything, a `Puzzle` can
+//! only be made by checking, so code taking one doesn't have to check again.
+//! A puzzle also carries what's known about where it came from, so that
+//! collections keep their attribution when they're converted or imported.
+
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+
+use crate::board::{board_to_string, Board};
+use crate::count::count_solutions;
+use crate::json::Json;
+use crate::logic::TechniqueConfig;
+use crate::rating::{rate, Rating};
+use crate::solver::Sudoku;
+use crate::validate::{validate, InvalidPuzzle};
+
+/// Why a board isn't a proper puzzle.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum PuzzleError {
+    Invalid(InvalidPuzzle),
+    NoSolution,
+    NotUnique,
+}
+
+impl fmt::Display for PuzzleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PuzzleError::Invalid(err) => write!(f, "{}", err),
+            PuzzleError::NoSolution => write!(f, "the puzzle has no solution"),
+            PuzzleError::NotUnique => write!(f, "the puzzle has more than one solution"),
+        }
+    }
+}
+
+impl Error for PuzzleError {}
+
+/// What's known about a puzzle besides its givens. Every field is optional
+/// and free-form, as collections record them in all kinds of ways.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Metadata {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    /// Where the puzzle was published, e.g. a newspaper or a pack.
+    pub source: Option<String>,
+    /// The id the source gave the puzzle, e.g. the hash of a Sudoku
+    /// Exchange Puzzle Bank line.
+    pub id: Option<String>,
+    /// When the puzzle was published, usually as YYYY-MM-DD.
+    pub date: Option<String>,
+    /// The rating the source gave the puzzle, e.g. `SE 7.2`, which isn't
+    /// necessarily comparable to `Rating`.
+    pub rating: Option<String>,
+}
+
+impl Metadata {
+    /// Returns whether no field is set.
+    pub fn is_empty(&self) -> bool {
+        self.fields().iter().all(|(_, value)| value.is_none())
+    }
+
+    fn fields(&self) -> [(&'static str, &Option<String>); 6] {
+        [
+            ("title", &self.title),
+            ("author", &self.author),
+            ("source", &self.source),
+            ("id", &self.id),
+            ("date", &self.date),
+            ("rating", &self.rating),
+        ]
+    }
+
+    /// Returns the fields as a JSON object, with null for the ones not set.
+    pub fn to_json(&self) -> Json {
+        Json::object(self.fields().iter().map(|&(key, value)| (key, Json::from(value.clone()))).collect())
+    }
+
+    /// Reads the fields from the string members of a JSON object with the
+    /// keys of `to_json`. Anything else is ignored.
+    pub fn from_json(json: &Json) -> Metadata {
+        let field = |key: &str| json.get(key).and_then(Json::as_str).map(String::from);
+        Metadata {
+            title: field("title"),
+            author: field("author"),
+            source: field("source"),
+            id: field("id"),
+            date: field("date"),
+            rating: field("rating"),
+        }
+    }
+}
+
+/// Formats the fields that are set as a header, one `Name: value` line each.
+impl fmt::Display for Metadata {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names = ["Title", "Author", "Source", "ID", "Date", "Rating"];
+        for (name, (_, value)) in names.iter().zip(self.fields().iter()) {
+            if let Some(value) = value {
+                writeln!(f, "{}: {}", name, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A board with valid givens and a unique solution, which is kept with it.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Puzzle {
+    givens: Board,
+    solution: Board,
+    metadata: Metadata,
+}
+
+impl Puzzle {
+    /// Checks `board` and solves it.
+    pub fn new(board: Board) -> Result<Puzzle, PuzzleError> {
+        validate(&board).map_err(PuzzleError::Invalid)?;
+        let solution = Sudoku::new(board).solve().ok_or(PuzzleError::NoSolution)?;
+        if count_solutions(&board, 2) > 1 {
+            return Err(PuzzleError::NotUnique);
+        }
+        Ok(Puzzle { givens: board, solution, metadata: Metadata::default() })
+    }
+
+    /// Returns the puzzle with `metadata` instead of what it had.
+    pub fn with_metadata(mut self, metadata: Metadata) -> Puzzle {
+        self.metadata = metadata;
+        self
+    }
+
+    pub fn givens(&self) -> &Board {
+        &self.givens
+    }
+
+    pub fn solution(&self) -> &Board {
+        &self.solution
+    }
+
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    pub fn metadata_mut(&mut self) -> &mut Metadata {
+        &mut self.metadata
+    }
+
+    /// Returns the givens and solution in the 81 character format with the
+    /// fields of the metadata, e.g.
+    /// `{"puzzle": "..5..8...", "solution": "...", "title": null, ...}`.
+    pub fn to_json(&self) -> Json {
+        let mut members = vec![
+            (String::from("puzzle"), Json::from(board_to_string(&self.givens))),
+            (String::from("solution"), Json::from(board_to_string(&self.solution))),
+        ];
+        if let Json::Object(fields) = self.metadata.to_json() {
+            members.extend(fields);
+        }
+        Json::Object(members)
+    }
+
+    /// Rates the puzzle like `rate`. Returns None only if `config` disables
+    /// guessing and its techniques aren't enough.
+    pub fn rate(&self, config: &TechniqueConfig) -> Option<Rating> {
+        rate(&self.givens, config)
+    }
+}
+
+impl TryFrom<Board> for Puzzle {
+    type Error = PuzzleError;
+
+    fn try_from(board: Board) -> Result<Puzzle, PuzzleError> {
+        Puzzle::new(board)
+    }
+}
+
+impl From<Puzzle> for Board {
+    fn from(puzzle: Puzzle) -> Board {
+        puzzle.givens
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{default_board, Cell};
+
+    #[test]
+    fn test_puzzle() {
+        let puzzle = Puzzle::new(default_board()).unwrap();
+        assert_eq!(puzzle.givens(), &default_board());
+        assert_eq!(Some(*puzzle.solution()), Sudoku::new(default_board()).solve());
+        assert!(puzzle.rate(&TechniqueConfig::default()).is_some());
+
+        let mut board = default_board();
+        board[0][0] = Cell::solved(5);
+        assert!(matches!(Puzzle::try_from(board), Err(PuzzleError::Invalid(_))));
+        let mut board = default_board();
+        board[0][2] = Cell::unsolved();
+        board[0][5] = Cell::unsolved();
+        board[1][1] = Cell::unsolved();
+        assert_eq!(Puzzle::new(board), Err(PuzzleError::NotUnique));
+
+        let metadata = Metadata {
+            title: Some(String::from("Example")),
+            date: Some(String::from("2020-01-31")),
+            ..Metadata::default()
+        };
+        let puzzle = Puzzle::new(default_board()).unwrap().with_metadata(metadata.clone());
+        assert_eq!(puzzle.metadata().to_string(), "Title: Example\nDate: 2020-01-31\n");
+        let json = puzzle.to_json();
+        assert_eq!(json.get("author"), Some(&Json::Null));
+        assert_eq!(Metadata::from_json(&json), metadata);
+        assert!(Metadata::default().is_empty());
+    }
+}
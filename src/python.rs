@@ -0,0 +1,191 @@
+//! Python bindings, enabled with the `python` feature and built into the
+//! `sudoku-rs` wheel with maturin. Boards can be created from the 81 character
+//! format or from any 9x9 sequence of digits, such as a nested list or a numpy
+//! array, with 0 for empty cells, and convert back the same way:
+//!
+//! ```python
+//! import numpy, sudoku
+//! board = sudoku.generate("hard", seed=1)
+//! grid = numpy.array(board)            # 9x9 array of digits
+//! solution = sudoku.solve(grid)
+//! ```
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::board::{board_to_string, parse_board, Board, Cell};
+use crate::generate::{Difficulty, Rng};
+use crate::logic::TechniqueConfig;
+use crate::solver::Sudoku;
+
+/// A sudoku board.
+#[pyclass(name = "Board", module = "sudoku")]
+#[derive(Clone)]
+struct PyBoard {
+    board: Board,
+}
+
+#[pymethods]
+impl PyBoard {
+    #[new]
+    fn new(puzzle: &Bound<PyAny>) -> PyResult<PyBoard> {
+        Ok(PyBoard { board: extract_board(puzzle)? })
+    }
+
+    /// Returns the digits row by row, with 0 for empty cells.
+    fn rows(&self) -> Vec<Vec<u32>> {
+        self.board
+            .iter()
+            .map(|row| row.iter().map(|cell| cell.solution.unwrap_or(0) as u32).collect())
+            .collect()
+    }
+
+    /// Returns the board as a 9x9 numpy array, which makes `numpy.array(board)`
+    /// work.
+    #[pyo3(signature = (dtype=None, copy=None))]
+    fn __array__<'py>(
+        &self,
+        py: Python<'py>,
+        dtype: Option<&Bound<'py, PyAny>>,
+        copy: Option<bool>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        // A new array is made every time, which is what `copy=None` asks for.
+        let _ = copy;
+        let numpy = py.import("numpy")?;
+        let dtype = match dtype {
+            Some(dtype) => dtype.clone(),
+            None => numpy.getattr("uint8")?,
+        };
+        numpy.call_method1("array", (self.rows(), dtype))
+    }
+
+    fn __len__(&self) -> usize {
+        9
+    }
+
+    fn __getitem__(&self, index: usize) -> PyResult<Vec<u32>> {
+        let mut rows = self.rows();
+        if index < rows.len() {
+            Ok(rows.swap_remove(index))
+        } else {
+            Err(pyo3::exceptions::PyIndexError::new_err("row index out of range"))
+        }
+    }
+
+    /// Boards are equal if they have the same digits, and they also compare
+    /// equal to the strings and sequences they can be created from.
+    fn __eq__(&self, other: &Bound<PyAny>) -> bool {
+        match extract_board(other) {
+            Ok(board) => board_to_string(&board) == board_to_string(&self.board),
+            Err(_) => false,
+        }
+    }
+
+    fn __str__(&self) -> String {
+        board_to_string(&self.board)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Board('{}')", board_to_string(&self.board))
+    }
+}
+
+/// Converts a `Board`, an 81 character string, or a 9x9 sequence of digits to
+/// a board.
+fn extract_board(puzzle: &Bound<PyAny>) -> PyResult<Board> {
+    if let Ok(board) = puzzle.downcast::<PyBoard>() {
+        return Ok(board.borrow().board);
+    }
+    if let Ok(s) = puzzle.extract::<String>() {
+        return parse_board(&s).ok_or_else(|| PyValueError::new_err("invalid puzzle"));
+    }
+    let rows: Vec<Vec<i64>> = puzzle.extract().map_err(|_| {
+        PyValueError::new_err("a puzzle must be a Board, a string, or a 9x9 sequence of digits")
+    })?;
+    if rows.len() != 9 || rows.iter().any(|row| row.len() != 9) {
+        return Err(PyValueError::new_err("a puzzle must have 9 rows of 9 digits"));
+    }
+    let mut board: Board = Default::default();
+    for (row, digits) in rows.iter().enumerate() {
+        for (col, &digit) in digits.iter().enumerate() {
+            board[row][col] = match digit {
+                0 => Cell::unsolved(),
+                1..=9 => Cell::solved(digit as i8),
+                _ => return Err(PyValueError::new_err("digits must be from 0 to 9")),
+            };
+        }
+    }
+    Ok(board)
+}
+
+/// Returns the solution of `puzzle`, or None if it has none.
+#[pyfunction]
+fn solve(py: Python, puzzle: &Bound<PyAny>) -> PyResult<Option<PyBoard>> {
+    let board = extract_board(puzzle)?;
+    let solution = py.allow_threads(|| Sudoku::new(board).solve());
+    Ok(solution.map(|board| PyBoard { board }))
+}
+
+/// Generates a puzzle of `difficulty` (easy, medium, hard, or expert). The
+/// same seed always generates the same puzzle.
+#[pyfunction]
+#[pyo3(signature = (difficulty="medium", seed=None))]
+fn generate(py: Python, difficulty: &str, seed: Option<u64>) -> PyResult<PyBoard> {
+    let difficulty =
+        Difficulty::from_name(difficulty).ok_or_else(|| PyValueError::new_err("unknown difficulty"))?;
+    let mut rng = seed.map_or_else(Rng::from_time, Rng::new);
+    let board = py.allow_threads(|| crate::generate::generate(difficulty, &mut rng));
+    Ok(PyBoard { board })
+}
+
+/// Rates `puzzle`, returning a dict with the difficulty, the score, the hardest
+/// technique needed, and how much guessing was needed, or None if it has no
+/// solution.
+#[pyfunction]
+fn rate<'py>(py: Python<'py>, puzzle: &Bound<'py, PyAny>) -> PyResult<Option<Bound<'py, PyDict>>> {
+    let board = extract_board(puzzle)?;
+    let rating = match py.allow_threads(|| crate::rating::rate(&board, &TechniqueConfig::default())) {
+        Some(rating) => rating,
+        None => return Ok(None),
+    };
+    let dict = PyDict::new(py);
+    dict.set_item("difficulty", Difficulty::of(&rating).name())?;
+    dict.set_item("score", rating.score())?;
+    dict.set_item("hardest", rating.hardest.map(|technique| technique.id()))?;
+    dict.set_item("guesses_needed", rating.guesses.needed)?;
+    dict.set_item("guesses_total", rating.guesses.total)?;
+    dict.set_item("guess_depth", rating.guesses.depth)?;
+    Ok(Some(dict))
+}
+
+#[pymodule]
+fn sudoku(m: &Bound<PyModule>) -> PyResult<()> {
+    m.add_class::<PyBoard>()?;
+    m.add_function(wrap_pyfunction!(solve, m)?)?;
+    m.add_function(wrap_pyfunction!(generate, m)?)?;
+    m.add_function(wrap_pyfunction!(rate, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::default_board;
+
+    #[test]
+    fn test_extract_board() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let puzzle = board_to_string(&default_board());
+            let from_str = extract_board(&puzzle.clone().into_pyobject(py).unwrap()).unwrap();
+            assert_eq!(from_str, default_board());
+
+            let rows = PyBoard { board: from_str }.rows();
+            let from_rows = extract_board(&rows.into_pyobject(py).unwrap()).unwrap();
+            assert_eq!(from_rows, default_board());
+
+            assert!(extract_board(&vec![vec![1; 9]; 8].into_pyobject(py).unwrap()).is_err());
+        });
+    }
+}
@@ -0,0 +1,94 @@
+//! QR codes of puzzles, enabled with the `qr` feature, for moving puzzles
+//! between devices without typing them. The payload is the puzzle as 81
+//! digits with `0` for empty cells, which fits QR's compact numeric mode and
+//! which `parse_board` reads back as is.
+
+use qrcode::{Color, EcLevel, QrCode};
+
+use crate::board::Board;
+use crate::png;
+
+/// The light border QR readers need around the code, in modules.
+const QUIET_ZONE: usize = 4;
+
+/// Returns the QR payload of `board`.
+pub fn qr_payload(board: &Board) -> String {
+    board
+        .iter()
+        .flat_map(|row| row.iter())
+        .map(|cell| (b'0' + cell.solution.unwrap_or(0) as u8) as char)
+        .collect()
+}
+
+/// Returns whether each module of the QR code of `board` is dark, row by row,
+/// including the quiet zone, along with the width of the code.
+fn modules(board: &Board) -> (usize, Vec<bool>) {
+    // 81 digits always fit, so this can't fail.
+    let code = QrCode::with_error_correction_level(qr_payload(board), EcLevel::M)
+        .expect("a puzzle always fits in a QR code");
+    let colors = code.to_colors();
+    let width = code.width() + 2 * QUIET_ZONE;
+    let mut dark = vec![false; width * width];
+    for (i, color) in colors.iter().enumerate() {
+        let (y, x) = (i / code.width(), i % code.width());
+        dark[(y + QUIET_ZONE) * width + x + QUIET_ZONE] = *color == Color::Dark;
+    }
+    (width, dark)
+}
+
+/// Renders the QR code of `board` for a terminal, two modules per character
+/// using half blocks. Light modules are drawn and dark ones left blank, so
+/// the code scans on the usual light on dark terminal.
+pub fn qr_text(board: &Board) -> String {
+    let (width, dark) = modules(board);
+    let mut text = String::new();
+    for y in (0..width).step_by(2) {
+        for x in 0..width {
+            let top = !dark[y * width + x];
+            let bottom = y + 1 < width && !dark[(y + 1) * width + x];
+            text.push(match (top, bottom) {
+                (true, true) => '█',
+                (true, false) => '▀',
+                (false, true) => '▄',
+                (false, false) => ' ',
+            });
+        }
+        text.push('\n');
+    }
+    text
+}
+
+/// Renders the QR code of `board` as a PNG with `scale` pixels per module.
+pub fn qr_png(board: &Board, scale: usize) -> Vec<u8> {
+    let (width, dark) = modules(board);
+    let size = width * scale;
+    let mut pixels = vec![255; size * size];
+    for y in 0..size {
+        for x in 0..size {
+            if dark[(y / scale) * width + x / scale] {
+                pixels[y * size + x] = 0;
+            }
+        }
+    }
+    png::encode_gray(size, size, &pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{board_to_string, default_board, parse_board};
+
+    #[test]
+    fn test_qr_payload() {
+        let board = default_board();
+        let payload = qr_payload(&board);
+        assert_eq!(payload.len(), 81);
+        assert!(payload.bytes().all(|b| b.is_ascii_digit()));
+        assert_eq!(board_to_string(&parse_board(&payload).unwrap()), board_to_string(&board));
+
+        // 81 numeric characters need a version 3 code, 29 modules wide.
+        let (width, _) = modules(&board);
+        assert_eq!(width, 29 + 2 * QUIET_ZONE);
+        assert_eq!(qr_text(&board).lines().count(), width.div_ceil(2));
+    }
+}
@@ -0,0 +1,208 @@
+//! Head-to-head races over TCP, enabled with the `server` feature. Clients
+//! are paired as they connect, and both players of a pair get the same
+//! generated puzzle. The protocol is JSON lines, like `rpc`: each player
+//! sends the grid as they've filled it in so far, whenever they like,
+//!
+//! ```text
+//! {"board": "3.5..8..."}
+//! ```
+//!
+//! and the server sends both players every player's progress, as the share
+//! of the empty cells filled in, until one sends the solution:
+//!
+//! ```text
+//! {"type": "start", "player": 0, "puzzle": "..5..8..."}
+//! {"type": "progress", "player": 1, "percent": 40}
+//! {"type": "finished", "winner": 0, "reason": "solved"}
+//! ```
+//!
+//! Grids that change a given or aren't a solution once complete are
+//! answered with an `error` to the player who sent them. A player who
+//! disconnects forfeits.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc;
+use std::thread;
+
+use crate::board::{board_to_string, parse_board, Board};
+use crate::generate::{generate, Difficulty, Rng};
+use crate::json::Json;
+use crate::validate::{verify, Discrepancy};
+
+/// Listens on `addr` and runs a race of a puzzle of `difficulty` for every
+/// two clients that connect, each on its own thread, until the listener
+/// fails.
+pub fn serve_races<A: ToSocketAddrs>(addr: A, difficulty: Difficulty) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    let mut waiting = None;
+    for stream in listener.incoming() {
+        let stream = stream?;
+        match waiting.take() {
+            None => waiting = Some(stream),
+            Some(first) => {
+                thread::spawn(move || {
+                    let puzzle = generate(difficulty, &mut Rng::from_time());
+                    // Players hanging up halfway are handled as forfeits.
+                    let _ = race(puzzle, [first, stream]);
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// What a grid sent by a player comes to.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Submission {
+    /// The percentage of the empty cells of the puzzle filled in.
+    Progress(usize),
+    Solved,
+}
+
+/// Checks a line sent by a player against `puzzle`, returning an error
+/// message for the player if it's not a grid of the puzzle or a complete
+/// grid that's wrong.
+fn submit(puzzle: &Board, line: &str) -> Result<Submission, String> {
+    let board = Json::parse(line)
+        .as_ref()
+        .and_then(|json| json.get("board"))
+        .and_then(Json::as_str)
+        .and_then(parse_board)
+        .ok_or("expected {\"board\": \"...\"} with an 81 character grid")?;
+    let verification = verify(puzzle, &board);
+    let discrepancies = &verification.discrepancies;
+    if discrepancies.iter().any(|(_, _, discrepancy)| matches!(discrepancy, Discrepancy::GivenChanged { .. })) {
+        return Err(String::from("the grid changes a given"));
+    }
+    if verification.is_correct() {
+        return Ok(Submission::Solved);
+    }
+    let empty = |board: &Board| board.iter().flatten().filter(|cell| cell.solution.is_none()).count();
+    let empty_cells = empty(puzzle);
+    let left = empty(&board);
+    if left == 0 {
+        return Err(String::from("the grid is complete but not a solution"));
+    }
+    Ok(Submission::Progress((empty_cells - left) * 100 / empty_cells))
+}
+
+/// Runs a race of `puzzle` between two players until one of them solves it
+/// or disconnects.
+fn race(puzzle: Board, players: [TcpStream; 2]) -> io::Result<()> {
+    let (sender, receiver) = mpsc::channel();
+    let mut writers = Vec::with_capacity(2);
+    for (player, stream) in players.iter().enumerate() {
+        writers.push(stream.try_clone()?);
+        let reader = BufReader::new(stream.try_clone()?);
+        let sender = sender.clone();
+        thread::spawn(move || {
+            for line in reader.lines() {
+                match line {
+                    Ok(line) if line.trim().is_empty() => {}
+                    Ok(line) => {
+                        if sender.send((player, Some(line))).is_err() {
+                            return;
+                        }
+                    }
+                    Err(_) => break,
+                }
+            }
+            let _ = sender.send((player, None));
+        });
+    }
+    drop(sender);
+
+    let broadcast = |writers: &mut Vec<TcpStream>, message: &Json| {
+        for writer in writers.iter_mut() {
+            // A player who has hung up finds out by forfeiting.
+            let _ = writeln!(writer, "{}", message);
+        }
+    };
+    for (player, writer) in writers.iter_mut().enumerate() {
+        let start = Json::object(vec![
+            ("type", Json::from("start")),
+            ("player", Json::from(player)),
+            ("puzzle", Json::from(board_to_string(&puzzle))),
+        ]);
+        let _ = writeln!(writer, "{}", start);
+    }
+    let finished = |winner: usize, reason: &str| {
+        Json::object(vec![
+            ("type", Json::from("finished")),
+            ("winner", Json::from(winner)),
+            ("reason", Json::from(reason)),
+        ])
+    };
+    for (player, line) in receiver {
+        let line = match line {
+            Some(line) => line,
+            None => {
+                broadcast(&mut writers, &finished(1 - player, "forfeit"));
+                break;
+            }
+        };
+        match submit(&puzzle, &line) {
+            Ok(Submission::Solved) => {
+                broadcast(&mut writers, &finished(player, "solved"));
+                break;
+            }
+            Ok(Submission::Progress(percent)) => {
+                let progress = Json::object(vec![
+                    ("type", Json::from("progress")),
+                    ("player", Json::from(player)),
+                    ("percent", Json::from(percent)),
+                ]);
+                broadcast(&mut writers, &progress);
+            }
+            Err(message) => {
+                let error = Json::object(vec![("type", Json::from("error")), ("error", Json::from(message))]);
+                let _ = writeln!(writers[player], "{}", error);
+            }
+        }
+    }
+    for stream in players.iter() {
+        let _ = stream.shutdown(Shutdown::Both);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::default_board;
+    use crate::solver::Sudoku;
+
+    #[test]
+    fn test_race() {
+        let puzzle = default_board();
+        let solution = board_to_string(&Sudoku::new(puzzle).solve().unwrap());
+        let line = |board: &str| format!(r#"{{"board": "{}"}}"#, board);
+        assert_eq!(submit(&puzzle, &line(&solution)), Ok(Submission::Solved));
+        assert_eq!(submit(&puzzle, &line(&board_to_string(&puzzle))), Ok(Submission::Progress(0)));
+        assert!(submit(&puzzle, "{}").is_err());
+
+        let mut changed = solution.clone().into_bytes();
+        changed[2] = b'1';
+        let changed = String::from_utf8(changed).unwrap();
+        assert_eq!(submit(&puzzle, &line(&changed)), Err(String::from("the grid changes a given")));
+        // Swapping two filled in digits of a row leaves it complete but
+        // wrong.
+        let mut wrong = solution.into_bytes();
+        wrong.swap(0, 1);
+        assert!(submit(&puzzle, &line(&String::from_utf8(wrong).unwrap())).is_err());
+
+        // A race over real sockets, where the second player hangs up.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let first = TcpStream::connect(addr).unwrap();
+        let second = TcpStream::connect(addr).unwrap();
+        let players = [listener.accept().unwrap().0, listener.accept().unwrap().0];
+        let racing = thread::spawn(move || race(puzzle, players));
+        let mut lines = BufReader::new(first).lines();
+        assert!(lines.next().unwrap().unwrap().starts_with(r#"{"type":"start","player":0,"#));
+        drop(second);
+        assert_eq!(lines.next().unwrap().unwrap(), r#"{"type":"finished","winner":0,"reason":"forfeit"}"#);
+        racing.join().unwrap().unwrap();
+    }
+}
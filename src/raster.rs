@@ -0,0 +1,83 @@
+//! Drawing boards as images.
+
+use crate::board::Board;
+use crate::font::{glyph_pixel, GLYPH_HEIGHT, GLYPH_WIDTH};
+use crate::png;
+
+const CELL_SIZE: usize = 36;
+/// The size of a font pixel in image pixels.
+const GLYPH_SCALE: usize = 3;
+const BACKGROUND: u8 = 255;
+const LINE: u8 = 0;
+const GIVEN: u8 = 0;
+const SOLVED: u8 = 110;
+
+/// A grayscale image with one byte per pixel, row by row.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) struct Bitmap {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+impl Bitmap {
+    fn new(width: usize, height: usize, shade: u8) -> Bitmap {
+        Bitmap { width, height, pixels: vec![shade; width * height] }
+    }
+
+    fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, shade: u8) {
+        for row in y..(y + height).min(self.height) {
+            for col in x..(x + width).min(self.width) {
+                self.pixels[row * self.width + col] = shade;
+            }
+        }
+    }
+
+    pub fn to_png(&self) -> Vec<u8> {
+        png::encode_gray(self.width, self.height, &self.pixels)
+    }
+}
+
+/// Draws `puzzle` with thick lines around the boxes. If `solution` is given,
+/// its digits that aren't given in the puzzle are drawn in gray.
+pub(crate) fn draw_board(puzzle: &Board, solution: Option<&Board>) -> Bitmap {
+    let size = 9 * CELL_SIZE + 3;
+    let mut bitmap = Bitmap::new(size, size, BACKGROUND);
+    for i in 0..10 {
+        let (offset, width) = if i % 3 == 0 { (i * CELL_SIZE, 3) } else { (i * CELL_SIZE + 1, 1) };
+        bitmap.fill_rect(offset, 0, width, size, LINE);
+        bitmap.fill_rect(0, offset, size, width, LINE);
+    }
+
+    let glyph_width = GLYPH_WIDTH * GLYPH_SCALE;
+    let glyph_height = GLYPH_HEIGHT * GLYPH_SCALE;
+    for row in 0..9 {
+        for col in 0..9 {
+            let (digit, shade) = match (puzzle[row][col].solution, solution) {
+                (Some(digit), _) => (digit, GIVEN),
+                (None, Some(solution)) => match solution[row][col].solution {
+                    Some(digit) => (digit, SOLVED),
+                    None => continue,
+                },
+                (None, None) => continue,
+            };
+            let left = col * CELL_SIZE + 2 + (CELL_SIZE - glyph_width) / 2;
+            let top = row * CELL_SIZE + 2 + (CELL_SIZE - glyph_height) / 2;
+            for y in 0..GLYPH_HEIGHT {
+                for x in 0..GLYPH_WIDTH {
+                    if glyph_pixel(digit, x, y) {
+                        let (px, py) = (left + x * GLYPH_SCALE, top + y * GLYPH_SCALE);
+                        bitmap.fill_rect(px, py, GLYPH_SCALE, GLYPH_SCALE, shade);
+                    }
+                }
+            }
+        }
+    }
+    bitmap
+}
+
+/// Renders `puzzle` as a PNG. If `solution` is given, the digits it fills in
+/// are drawn in gray.
+pub fn board_to_png(puzzle: &Board, solution: Option<&Board>) -> Vec<u8> {
+    draw_board(puzzle, solution).to_png()
+}
@@ -0,0 +1,159 @@
+use std::fmt;
+
+use crate::board::Board;
+use crate::generate::Difficulty;
+use crate::json::Json;
+use crate::logic::{solve_variant, Technique, TechniqueConfig, Trace};
+use crate::score::ScoreTable;
+use crate::variant::Variant;
+
+/// How hard a puzzle is for a human, judged by the hardest technique needed
+/// and by how much guessing is left when the enabled techniques run out.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Rating {
+    /// The hardest technique needed, not counting guesses. None if the
+    /// puzzle has nothing left to solve.
+    pub hardest: Option<Technique>,
+    pub guesses: GuessReport,
+}
+
+/// How much the solver had to guess. All zero for puzzles that can be solved
+/// with the enabled techniques alone.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub struct GuessReport {
+    /// The guesses on the path the solver took to the solution. Another
+    /// order of guesses may need fewer.
+    pub needed: usize,
+    /// All guesses made, including the wrong ones that had to be taken back.
+    pub total: usize,
+    /// The deepest nesting of guesses reached.
+    pub depth: usize,
+}
+
+impl Rating {
+    pub fn difficulty(&self) -> Difficulty {
+        Difficulty::of(self)
+    }
+
+    /// Returns the score of the puzzle with the default weights of
+    /// `ScoreTable`, from 0 for puzzles with nothing left to solve to 10 for
+    /// those that need guesses.
+    pub fn score(&self) -> f64 {
+        ScoreTable::default().score(self)
+    }
+
+    pub fn to_json(&self) -> Json {
+        self.to_json_with(&ScoreTable::default())
+    }
+
+    /// Like `to_json`, but with the score of `scores`.
+    pub fn to_json_with(&self, scores: &ScoreTable) -> Json {
+        Json::object(vec![
+            ("difficulty", Json::from(Difficulty::of(self).name())),
+            ("score", Json::from(scores.score(self))),
+            ("hardest", Json::from(self.hardest.map(Technique::id))),
+            (
+                "guesses",
+                Json::object(vec![
+                    ("needed", Json::from(self.guesses.needed)),
+                    ("total", Json::from(self.guesses.total)),
+                    ("depth", Json::from(self.guesses.depth)),
+                ]),
+            ),
+        ])
+    }
+
+    /// Reads a rating written with `to_json`. Returns None if the JSON isn't
+    /// one.
+    pub fn from_json(json: &Json) -> Option<Rating> {
+        let hardest = match json.get("hardest")? {
+            Json::Null => None,
+            id => Some(Technique::from_id(id.as_str()?)?),
+        };
+        let guesses = json.get("guesses")?;
+        let count = |key: &str| guesses.get(key).and_then(Json::as_u64).map(|n| n as usize);
+        let guesses = GuessReport { needed: count("needed")?, total: count("total")?, depth: count("depth")? };
+        Some(Rating { hardest, guesses })
+    }
+}
+
+impl fmt::Display for Rating {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Difficulty: {}", Difficulty::of(self))?;
+        match self.hardest {
+            Some(technique) => writeln!(f, "Hardest technique: {}", technique)?,
+            None => writeln!(f, "Hardest technique: none")?,
+        }
+        write!(
+            f,
+            "Guesses needed: {} ({} made, depth {})",
+            self.guesses.needed, self.guesses.total, self.guesses.depth
+        )
+    }
+}
+
+/// Rates the board using the techniques enabled in `config`. Returns None if
+/// the board has no solution, or if it can't be solved because guessing is
+/// disabled.
+pub fn rate(board: &Board, config: &TechniqueConfig) -> Option<Rating> {
+    rate_variant(board, config, &Variant::default())
+}
+
+/// Like `rate`, but for a puzzle with the extra constraints of `variant`.
+/// Only the classic techniques are known, so variant puzzles usually need
+/// guesses where a solver would use the variant's own logic.
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+pub fn rate_variant(board: &Board, config: &TechniqueConfig, variant: &Variant) -> Option<Rating> {
+    rating_of(&solve_variant(board, config, variant))
+}
+
+/// Returns the rating of the puzzle `trace` is of, or None if the trace has
+/// no solution.
+pub(crate) fn rating_of(trace: &Trace) -> Option<Rating> {
+    trace.solution.as_ref()?;
+    let usage = trace.usage();
+    Some(Rating {
+        hardest: trace
+            .steps
+            .iter()
+            .map(|step| step.technique)
+            .filter(|&technique| technique != Technique::Guess)
+            .max(),
+        guesses: GuessReport {
+            needed: usage.count(Technique::Guess),
+            total: trace.guesses,
+            depth: trace.guess_depth,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{default_board, parse_board};
+
+    #[test]
+    fn test_rate() {
+        let rating = rate(&default_board(), &TechniqueConfig::default()).unwrap();
+        assert_eq!(rating.guesses, GuessReport::default());
+
+        let board = parse_board(
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..",
+        )
+        .unwrap();
+        let rating = rate(&board, &TechniqueConfig::default()).unwrap();
+        assert!(rating.guesses.needed > 0);
+        assert!(rating.guesses.total >= rating.guesses.needed);
+        assert!(rating.guesses.depth >= rating.guesses.needed);
+        assert_eq!((rating.difficulty(), rating.score()), (Difficulty::Expert, 10.0));
+
+        // The thresholds of the scores agree with the difficulties. Guesses
+        // are never the hardest technique.
+        for hardest in Technique::ALL[..Technique::ALL.len() - 1].iter().copied().map(Some).chain([None]) {
+            let rating = Rating { hardest, guesses: GuessReport::default() };
+            assert_eq!(Difficulty::from_score(rating.score()), rating.difficulty(), "{:?}", hardest);
+        }
+        assert_eq!(Difficulty::from_score(Difficulty::Hard.min_score()), Difficulty::Hard);
+        assert_eq!(Difficulty::from_score(-1.0), Difficulty::Easy);
+    }
+}
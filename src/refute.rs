@@ -0,0 +1,264 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::board::Board;
+use crate::grid::{mask_digits, Grid, House};
+use crate::locale::Catalog;
+use crate::logic::{Step, Technique};
+
+/// The longest chain of forced placements we're willing to follow before
+/// giving up. Longer chains still prove the move wrong but they make for a
+/// poor explanation.
+const MAX_CHAIN: usize = 8;
+
+/// The reason a tentative placement can't be part of the solution.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Contradiction {
+    /// The digit is already placed in cell `row:col` of `house`.
+    Duplicate { house: House, row: usize, col: usize },
+    /// The cell at `row:col` is left without any candidates.
+    NoCandidates { row: usize, col: usize },
+    /// `house` is left without any place for `digit`.
+    NoPlace { house: House, digit: i8 },
+}
+
+/// A placement that follows from the tentative move because the cell had a
+/// single candidate left, or because it was the only place for the digit in
+/// `house`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Forced {
+    pub row: usize,
+    pub col: usize,
+    pub digit: i8,
+    pub house: Option<House>,
+}
+
+/// Explains why placing `digit` at `row:col` is wrong.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Refutation {
+    pub row: usize,
+    pub col: usize,
+    pub digit: i8,
+    /// The placements forced by the move, in the order they were made.
+    pub forced: Vec<Forced>,
+    pub contradiction: Contradiction,
+}
+
+/// Tries to find a short logical reason why placing `digit` in the unsolved
+/// cell at `row:col` is wrong. Only the immediate consequences of the move
+/// are followed (naked and hidden singles), so None doesn't mean the move is
+/// correct, just that it can't be refuted simply. None is also returned if
+/// the cell is off the board or filled in, or `digit` isn't from 1 to 9.
+pub fn refute(board: &Board, row: usize, col: usize, digit: i8) -> Option<Refutation> {
+    if row >= 9 || col >= 9 || board[row][col].solution.is_some() || !(1..=9).contains(&digit) {
+        return None;
+    }
+    refute_in(&Grid::from_board(board), row, col, digit)
+}
+
+/// Like `refute`, but in a grid whose candidates may already have been
+/// narrowed down by techniques.
+fn refute_in(before: &Grid, row: usize, col: usize, digit: i8) -> Option<Refutation> {
+    let mut grid = *before;
+    let mut refutation = Refutation {
+        row,
+        col,
+        digit,
+        forced: Vec::new(),
+        contradiction: Contradiction::NoCandidates { row, col },
+    };
+
+    for &house in House::containing(row, col).iter() {
+        for &(r, c) in house.cells().iter() {
+            if grid.value(r, c) == Some(digit) {
+                refutation.contradiction = Contradiction::Duplicate { house, row: r, col: c };
+                return Some(refutation);
+            }
+        }
+    }
+
+    grid.place(row, col, digit);
+    loop {
+        if let Some(contradiction) = find_contradiction(&grid) {
+            refutation.contradiction = contradiction;
+            return Some(refutation);
+        }
+        if refutation.forced.len() == MAX_CHAIN {
+            return None;
+        }
+        let forced = find_single(&grid, before)?;
+        grid.place(forced.row, forced.col, forced.digit);
+        refutation.forced.push(forced);
+    }
+}
+
+/// Finds the candidate of `grid` with the shortest refutation, and
+/// eliminates it as a forcing chain. Only naked and hidden singles are
+/// followed, so the chain always reads as a plain "if ... then ...".
+pub(crate) fn find_forcing_chain(grid: &Grid) -> Option<Step> {
+    let mut best: Option<Refutation> = None;
+    for row in 0..9 {
+        for col in 0..9 {
+            if grid.value(row, col).is_some() {
+                continue;
+            }
+            for digit in mask_digits(grid.candidates(row, col)) {
+                let refutation = match refute_in(grid, row, col, digit) {
+                    Some(refutation) => refutation,
+                    None => continue,
+                };
+                if refutation.forced.len() <= 1 {
+                    return Some(forcing_chain_step(refutation));
+                }
+                if best.as_ref().is_none_or(|best| refutation.forced.len() < best.forced.len()) {
+                    best = Some(refutation);
+                }
+            }
+        }
+    }
+    best.map(forcing_chain_step)
+}
+
+fn forcing_chain_step(refutation: Refutation) -> Step {
+    let mut cells = vec![(refutation.row, refutation.col)];
+    cells.extend(refutation.forced.iter().map(|forced| (forced.row, forced.col)));
+    Step {
+        technique: Technique::ForcingChain,
+        houses: Vec::new(),
+        cells,
+        placements: Vec::new(),
+        eliminations: vec![(refutation.row, refutation.col, refutation.digit)],
+        chain: Vec::new(),
+        refutation: Some(refutation),
+        elapsed: Duration::default(),
+    }
+}
+
+/// Returns the first house left without a place for a digit, or failing that,
+/// the first cell left without candidates.
+pub(crate) fn find_contradiction(grid: &Grid) -> Option<Contradiction> {
+    for house in House::all() {
+        for digit in 1..10 {
+            if !grid.is_placed_in(house, digit) && grid.places_for(house, digit).is_empty() {
+                return Some(Contradiction::NoPlace { house, digit });
+            }
+        }
+    }
+    for row in 0..9 {
+        for col in 0..9 {
+            if grid.value(row, col).is_none() && grid.candidates(row, col) == 0 {
+                return Some(Contradiction::NoCandidates { row, col });
+            }
+        }
+    }
+    None
+}
+
+/// Returns the first naked single, or failing that, the first hidden single
+/// in `grid`. Singles that were already there `before` the move was made are
+/// not its consequence, so they're skipped to keep the explanation relevant.
+fn find_single(grid: &Grid, before: &Grid) -> Option<Forced> {
+    for row in 0..9 {
+        for col in 0..9 {
+            let candidates = grid.candidates(row, col);
+            if candidates.count_ones() == 1 {
+                let digit = mask_digits(candidates).next().unwrap();
+                if !is_single(before, row, col, digit) {
+                    return Some(Forced { row, col, digit, house: None });
+                }
+            }
+        }
+    }
+    for house in House::all() {
+        for digit in 1..10 {
+            let places = grid.places_for(house, digit);
+            if places.len() == 1 {
+                let (row, col) = places[0];
+                if !is_single(before, row, col, digit) {
+                    return Some(Forced { row, col, digit, house: Some(house) });
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Returns whether `digit` is a naked or hidden single at `row:col`.
+fn is_single(grid: &Grid, row: usize, col: usize, digit: i8) -> bool {
+    let candidates = grid.candidates(row, col);
+    if candidates.count_ones() == 1 && mask_digits(candidates).next() == Some(digit) {
+        return true;
+    }
+    House::containing(row, col)
+        .iter()
+        .any(|&house| grid.places_for(house, digit) == [(row, col)])
+}
+
+impl fmt::Display for Contradiction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&Catalog::default().contradiction(self))
+    }
+}
+
+impl fmt::Display for Refutation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&Catalog::default().refutation(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::cell_name;
+    use crate::board::{board_to_string, default_board, parse_board};
+    use crate::logic::{solve_with, TechniqueConfig};
+    use crate::solver::Sudoku;
+
+    #[test]
+    fn test_refute() {
+        let board = default_board();
+
+        // There's already a 5 in the first row.
+        let refutation = refute(&board, 0, 0, 5).unwrap();
+        assert_eq!(refutation.contradiction, Contradiction::Duplicate {
+            house: House::Row(0),
+            row: 0,
+            col: 2,
+        });
+        assert_eq!(refutation.to_string(), "r1c1=5 clashes with r1c3 in row 1");
+
+        // r3c3 can only be 1 or 8 and 1 is the answer, so 8 must be refutable.
+        let refutation = refute(&board, 2, 2, 8).unwrap();
+        assert_eq!(
+            refutation.to_string(),
+            "r3c3=8 forces r3c9=1, r2c3=1, which leaves column 6 without any place for 1"
+        );
+        assert!(refute(&board, 2, 2, 1).is_none());
+
+        // Moves that can't be made aren't refuted, rather than panicking.
+        assert!(refute(&board, 0, 2, 8).is_none());
+        assert!(refute(&board, 2, 2, 0).is_none());
+        assert!(refute(&board, 2, 2, 10).is_none());
+        assert!(refute(&board, 9, 0, 1).is_none());
+    }
+
+    #[test]
+    fn test_find_forcing_chain() {
+        let board =
+            parse_board("..4..5...2..8..6...38.1...4.5.......38..61...6.2........3.9..2....3..4.8...7.29..").unwrap();
+        assert!(solve_with(&board, &TechniqueConfig::default()).usage().count(Technique::Guess) > 0);
+        let solution = Sudoku::new(board).solve().unwrap();
+        let mut config = TechniqueConfig::default();
+        config.enable(Technique::ForcingChain);
+        config.disable(Technique::Guess);
+        let trace = solve_with(&board, &config);
+        let step = trace.steps.iter().find(|step| step.technique == Technique::ForcingChain).unwrap();
+        let refutation = step.refutation.as_ref().unwrap();
+        assert_eq!(step.eliminations, vec![(refutation.row, refutation.col, refutation.digit)]);
+        assert_ne!(solution[refutation.row][refutation.col].solution, Some(refutation.digit));
+        let cell = cell_name(refutation.row, refutation.col);
+        let digit = refutation.digit;
+        assert!(step.to_string().starts_with(&format!("Forcing chain: {}<>{}, since {}={}", cell, digit, cell, digit)));
+        assert_eq!(board_to_string(&trace.solution.unwrap()), board_to_string(&solution));
+    }
+}
@@ -0,0 +1,382 @@
+use crate::annotate::{Annotations, Color};
+use crate::board::{diff_boards, Board};
+use crate::grid::{cell_name, digit_mask, mask_digits, Grid};
+use crate::logic::{Step, Trace};
+
+const RESET: &str = "\x1b[0m";
+const DEFINING: &str = "\x1b[43m";
+const ELIMINATED: &str = "\x1b[31m";
+const PLACED: &str = "\x1b[1;32m";
+
+/// The background of cells tagged with `color`. Yellow is lighter than the
+/// highlight of defining cells, so the two can be told apart.
+fn tag_background(color: Color) -> &'static str {
+    match color {
+        Color::Red => "\x1b[41m",
+        Color::Orange => "\x1b[48;5;208m",
+        Color::Yellow => "\x1b[48;5;229m",
+        Color::Green => "\x1b[42m",
+        Color::Blue => "\x1b[44m",
+        Color::Purple => "\x1b[45m",
+    }
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// How a candidate or digit relates to the step being rendered.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Mark {
+    None,
+    Eliminated,
+    Placed,
+}
+
+fn mark(step: &Step, row: usize, col: usize, digit: i8) -> Mark {
+    if step.placements.contains(&(row, col, digit)) {
+        Mark::Placed
+    } else if step.eliminations.contains(&(row, col, digit)) {
+        Mark::Eliminated
+    } else {
+        Mark::None
+    }
+}
+
+/// Renders the pencil marks right before step `index` of `trace`. Each cell takes three lines
+/// with candidates in their usual keypad positions. With `color`, the cells
+/// defining the step are highlighted and the eliminated and placed candidates
+/// are colored using ANSI escapes.
+pub fn render_step_text(trace: &Trace, index: usize, color: bool) -> String {
+    render_step_text_annotated(trace, index, &Annotations::default(), color)
+}
+
+/// Like `render_step_text`, but with `color` the tagged cells get the
+/// background of their tag, and every annotated cell is listed below the
+/// grid with its tag and note.
+pub fn render_step_text_annotated(trace: &Trace, index: usize, annotations: &Annotations, color: bool) -> String {
+    let grid = trace.grid_before(index);
+    let step = &trace.steps[index];
+    let mut s = String::new();
+    for row in 0..9 {
+        if row > 0 && row % 3 == 0 {
+            s += "------------+-------------+------------\n";
+        }
+        for line in 0..3 {
+            for col in 0..9 {
+                if col > 0 {
+                    s += if col % 3 == 0 { " | " } else { " " };
+                }
+                let background = if !color {
+                    None
+                } else if step.cells.contains(&(row, col)) {
+                    Some(DEFINING)
+                } else {
+                    annotations.get(row, col).color.map(tag_background)
+                };
+                if let Some(background) = background {
+                    s += background;
+                }
+                for i in 0..3 {
+                    let digit = (line * 3 + i + 1) as i8;
+                    let text = match grid.value(row, col) {
+                        Some(value) if line == 1 && i == 1 => (b'0' + value as u8) as char,
+                        Some(_) => ' ',
+                        None if grid.candidates(row, col) & digit_mask(digit) != 0 => {
+                            (b'0' + digit as u8) as char
+                        }
+                        None => '.',
+                    };
+                    let code = match mark(step, row, col, digit) {
+                        _ if !color || grid.value(row, col).is_some() => None,
+                        Mark::Eliminated => Some(ELIMINATED),
+                        Mark::Placed => Some(PLACED),
+                        Mark::None => None,
+                    };
+                    match code {
+                        Some(code) => {
+                            s += code;
+                            s.push(text);
+                            s += RESET;
+                            if let Some(background) = background {
+                                s += background;
+                            }
+                        }
+                        None => s.push(text),
+                    }
+                }
+                if background.is_some() {
+                    s += RESET;
+                }
+            }
+            s.push('\n');
+        }
+        if row % 3 != 2 {
+            s += "            |             |\n";
+        }
+    }
+    for (row, col, annotation) in annotations.iter() {
+        s += &format!("r{}c{}", row + 1, col + 1);
+        if let Some(color) = annotation.color {
+            s += &format!(" [{}]", color.name());
+        }
+        if let Some(note) = annotation.note.as_ref() {
+            s += &format!(": {}", note);
+        }
+        s.push('\n');
+    }
+    s
+}
+
+/// Renders the board right before step `index` of `trace`, which must be the
+/// trace of `board`, as an HTML table.
+/// Nothing is styled inline; instead cells and candidates get classes so the
+/// page can style them: `given` and `solved` for placed digits, `defining`
+/// for the cells the step is based on, and `eliminated` and `placed` for the
+/// candidates the step removes or places.
+pub fn render_step_html(board: &Board, trace: &Trace, index: usize) -> String {
+    render_step_html_annotated(board, trace, index, &Annotations::default())
+}
+
+/// Like `render_step_html`, but tagged cells also get a class like
+/// `tag-blue` and notes are shown as the titles of their cells.
+pub fn render_step_html_annotated(board: &Board, trace: &Trace, index: usize, annotations: &Annotations) -> String {
+    let grid = trace.grid_before(index);
+    let step = &trace.steps[index];
+    let mut s = String::from("<table class=\"sudoku\">\n");
+    for row in 0..9 {
+        s += "  <tr>\n";
+        for col in 0..9 {
+            let mut classes = vec![String::from("cell")];
+            if step.cells.contains(&(row, col)) {
+                classes.push(String::from("defining"));
+            }
+            let annotation = annotations.get(row, col);
+            if let Some(color) = annotation.color {
+                classes.push(format!("tag-{}", color.name()));
+            }
+            let title = match annotation.note.as_ref() {
+                Some(note) => format!(" title=\"{}\"", escape_html(note)),
+                None => String::new(),
+            };
+            match grid.value(row, col) {
+                Some(value) => {
+                    classes.push(String::from(if board[row][col].solution.is_some() { "given" } else { "solved" }));
+                    s += &format!("    <td class=\"{}\"{}>{}</td>\n", classes.join(" "), title, value);
+                }
+                None => {
+                    s += &format!("    <td class=\"{}\"{}>", classes.join(" "), title);
+                    for digit in 1..10 {
+                        if grid.candidates(row, col) & digit_mask(digit) == 0 {
+                            continue;
+                        }
+                        let class = match mark(step, row, col, digit) {
+                            Mark::Eliminated => "candidate eliminated",
+                            Mark::Placed => "candidate placed",
+                            Mark::None => "candidate",
+                        };
+                        s += &format!("<span class=\"{}\">{}</span>", class, digit);
+                    }
+                    s += "</td>\n";
+                }
+            }
+        }
+        s += "  </tr>\n";
+    }
+    s += "</table>\n";
+    s
+}
+
+/// Renders `new` with the cells that differ from `old` marked, followed by a
+/// line for each of them such as `r1c2: . -> 5`. Without `color` the marked
+/// cells are in brackets, with it the digits that are new or changed are
+/// green and the ones removed red.
+pub fn render_diff_text(old: &Board, new: &Board, color: bool) -> String {
+    let diffs = diff_boards(old, new);
+    let digit = |digit: Option<i8>| digit.map_or('.', |digit| (b'0' + digit as u8) as char);
+    let mut s = String::new();
+    for row in 0..9 {
+        if row > 0 && row % 3 == 0 {
+            s += "---------+---------+---------\n";
+        }
+        for col in 0..9 {
+            if col > 0 && col % 3 == 0 {
+                s.push('|');
+            }
+            match diffs.iter().find(|diff| (diff.row, diff.col) == (row, col)) {
+                Some(diff) if color => {
+                    let (code, shown) = match diff.new {
+                        Some(_) => (PLACED, diff.new),
+                        None => (ELIMINATED, diff.old),
+                    };
+                    s += &format!(" {}{}{} ", code, digit(shown), RESET);
+                }
+                Some(diff) => s += &format!("[{}]", digit(diff.new)),
+                None => s += &format!(" {} ", digit(new[row][col].solution)),
+            }
+        }
+        s.push('\n');
+    }
+    for diff in diffs.iter() {
+        s += &format!("r{}c{}: {} -> {}\n", diff.row + 1, diff.col + 1, digit(diff.old), digit(diff.new));
+    }
+    s
+}
+
+/// Renders the pencil marks of `new` with what changed since `old`, e.g. two
+/// grids of the same puzzle before and after some steps, followed by a line
+/// for each changed cell such as `r1c2: eliminated 4, 7` or `r5c5: placed
+/// 3`. Without `color` the eliminated candidates are shown as `x` and the
+/// placed digits in brackets, with it they're red and green.
+pub fn render_candidate_diff_text(old: &Grid, new: &Grid, color: bool) -> String {
+    let placed = |row, col| new.value(row, col).filter(|_| old.value(row, col).is_none());
+    let mut s = String::new();
+    for row in 0..9 {
+        if row > 0 && row % 3 == 0 {
+            s += "------------+-------------+------------\n";
+        }
+        for line in 0..3 {
+            for col in 0..9 {
+                if col > 0 {
+                    s += if col % 3 == 0 { " | " } else { " " };
+                }
+                match (new.value(row, col), placed(row, col)) {
+                    (Some(value), Some(_)) if line == 1 && color => s += &format!(" {}{}{} ", PLACED, value, RESET),
+                    (Some(value), Some(_)) if line == 1 => s += &format!("[{}]", value),
+                    (Some(value), None) if line == 1 => s += &format!(" {} ", value),
+                    (Some(_), _) => s += "   ",
+                    (None, _) => {
+                        for i in 0..3 {
+                            let digit = (line * 3 + i + 1) as i8;
+                            match (old.has_candidate(row, col, digit), new.has_candidate(row, col, digit)) {
+                                (_, true) => s.push((b'0' + digit as u8) as char),
+                                (true, false) if color => s += &format!("{}{}{}", ELIMINATED, digit, RESET),
+                                (true, false) => s.push('x'),
+                                (false, false) => s.push('.'),
+                            }
+                        }
+                    }
+                }
+            }
+            s.push('\n');
+        }
+        if row % 3 != 2 {
+            s += "            |             |\n";
+        }
+    }
+    for row in 0..9 {
+        for col in 0..9 {
+            if let Some(value) = placed(row, col) {
+                s += &format!("{}: placed {}\n", cell_name(row, col), value);
+            } else if new.value(row, col).is_none() {
+                let eliminated: Vec<String> = mask_digits(old.candidates(row, col) & !new.candidates(row, col))
+                    .map(|digit| digit.to_string())
+                    .collect();
+                if !eliminated.is_empty() {
+                    s += &format!("{}: eliminated {}\n", cell_name(row, col), eliminated.join(", "));
+                }
+            }
+        }
+    }
+    s
+}
+
+/// Renders the pencil marks of `new` with what changed since `old` as an
+/// HTML table, classed like `render_step_html`: the cells filled in since
+/// `old` are `placed`, and the candidates gone since then are kept as
+/// `candidate eliminated`.
+pub fn render_candidate_diff_html(old: &Grid, new: &Grid) -> String {
+    let mut s = String::from("<table class=\"sudoku\">\n");
+    for row in 0..9 {
+        s += "  <tr>\n";
+        for col in 0..9 {
+            match new.value(row, col) {
+                Some(value) if old.value(row, col).is_none() => {
+                    s += &format!("    <td class=\"cell placed\">{}</td>\n", value);
+                }
+                Some(value) => s += &format!("    <td class=\"cell solved\">{}</td>\n", value),
+                None => {
+                    s += "    <td class=\"cell\">";
+                    for digit in 1..10 {
+                        let class = match (old.has_candidate(row, col, digit), new.has_candidate(row, col, digit)) {
+                            (_, true) => "candidate",
+                            (true, false) => "candidate eliminated",
+                            (false, false) => continue,
+                        };
+                        s += &format!("<span class=\"{}\">{}</span>", class, digit);
+                    }
+                    s += "</td>\n";
+                }
+            }
+        }
+        s += "  </tr>\n";
+    }
+    s += "</table>\n";
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{default_board, Cell};
+    use crate::logic::solve_logically;
+
+    #[test]
+    fn test_render_step() {
+        let board = default_board();
+        let trace = solve_logically(&board);
+
+        // The first step places 9 in r2c4.
+        let text = render_step_text(&trace, 0, false);
+        assert_eq!(text.lines().count(), 9 * 3 + 2 + 6);
+        let text = render_step_text(&trace, 0, true);
+        assert!(text.contains(&format!("{}..{}9{}", DEFINING, PLACED, RESET)));
+
+        let html = render_step_html(&board, &trace, 0);
+        assert!(html.contains("<td class=\"cell defining\"><span class=\"candidate placed\">9</span></td>"));
+        assert!(html.contains("<td class=\"cell given\">5</td>"));
+
+        let mut annotations = Annotations::default();
+        annotations.get_mut(0, 2).color = Some(Color::Blue);
+        annotations.get_mut(0, 2).note = Some(String::from("a <b>"));
+        let text = render_step_text_annotated(&trace, 0, &annotations, true);
+        assert!(text.contains(&format!("{}   {}", tag_background(Color::Blue), RESET)));
+        assert!(text.ends_with("r1c3 [blue]: a <b>\n"));
+        let html = render_step_html_annotated(&board, &trace, 0, &annotations);
+        assert!(html.contains("<td class=\"cell tag-blue given\" title=\"a &lt;b&gt;\">5</td>"));
+    }
+
+    #[test]
+    fn test_render_diff() {
+        let old = default_board();
+        let mut new = old;
+        new[0][0] = Cell::solved(1);
+        new[0][2] = Cell::unsolved();
+        let text = render_diff_text(&old, &new, false);
+        assert_eq!(text.lines().next(), Some("[1] . [.]| .  .  8 | .  .  . "));
+        assert_eq!(text.lines().skip(11).collect::<Vec<_>>(), vec!["r1c1: . -> 1", "r1c3: 5 -> ."]);
+        let text = render_diff_text(&old, &new, true);
+        assert!(text.starts_with(&format!(" {}1{}  .  {}5{} |", PLACED, RESET, ELIMINATED, RESET)));
+    }
+
+    #[test]
+    fn test_render_candidate_diff() {
+        // The first step of the example places 9 in r2c4, which takes 9 out
+        // of the candidates of its peers.
+        let trace = solve_logically(&default_board());
+        let (old, new) = (trace.grid_before(0), trace.grid_before(1));
+        let text = render_candidate_diff_text(&old, &new, false);
+        assert_eq!(text.lines().nth(5), Some("4.6  2  ... | [9] ..6 ..6 |  5  4.. 4.."));
+        assert_eq!(text.lines().nth(6), Some(".8.     .8. |     7.x ..x |     78. 78x"));
+        assert!(text.contains("\nr1c4: eliminated 9\n"));
+        assert!(text.contains("\nr2c4: placed 9\n"));
+        let text = render_candidate_diff_text(&old, &new, true);
+        assert!(text.contains(&format!(" {}9{} ", PLACED, RESET)));
+        assert!(text.contains(&format!("{}9{}", ELIMINATED, RESET)));
+        assert_eq!(render_candidate_diff_text(&new, &new, false).lines().count(), 9 * 3 + 2 + 6);
+
+        let html = render_candidate_diff_html(&old, &new);
+        assert!(html.contains("<td class=\"cell placed\">9</td>"));
+        assert!(html.contains("<span class=\"candidate eliminated\">9</span>"));
+    }
+}
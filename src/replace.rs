@@ -0,0 +1,91 @@
+//! Moving a given of a puzzle to another cell while keeping the puzzle
+//! uniquely solvable and about as hard, for setters fine-tuning the look of
+//! the clue pattern.
+
+use std::cmp::Reverse;
+
+use crate::board::{Board, Cell};
+use crate::count::count_solutions;
+use crate::generate::Difficulty;
+use crate::logic::TechniqueConfig;
+use crate::rating::{rate, Rating};
+use crate::solver::Sudoku;
+
+/// A puzzle with a given moved.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Replacement {
+    /// The cell the given moved to.
+    pub row: usize,
+    pub col: usize,
+    /// The digit given there, which is the digit of the solution, so the
+    /// solution stays the same.
+    pub digit: i8,
+    pub puzzle: Board,
+    pub rating: Rating,
+}
+
+/// Returns the ways to move the given at `row` and `col` of `puzzle` to
+/// another empty cell so that the puzzle still has a unique solution and
+/// the same difficulty with the techniques of `config`. The closest in
+/// difficulty come first: those needing the same hardest technique, and
+/// then those with the fewest guesses more or less. Returns None if the
+/// cell isn't a given or the puzzle doesn't have a unique solution.
+pub fn replace_clue(puzzle: &Board, row: usize, col: usize, config: &TechniqueConfig) -> Option<Vec<Replacement>> {
+    puzzle[row][col].solution?;
+    if count_solutions(puzzle, 2) != 1 {
+        return None;
+    }
+    let solution = Sudoku::new(*puzzle).solve()?;
+    let original = rate(puzzle, config)?;
+    let mut without = *puzzle;
+    without[row][col] = Cell::unsolved();
+
+    let mut replacements = Vec::new();
+    for r in 0..9 {
+        for c in 0..9 {
+            if (r, c) == (row, col) || puzzle[r][c].solution.is_some() {
+                continue;
+            }
+            let digit = solution[r][c].solution?;
+            let mut moved = without;
+            moved[r][c] = Cell::solved(digit);
+            if count_solutions(&moved, 2) != 1 {
+                continue;
+            }
+            match rate(&moved, config) {
+                Some(rating) if Difficulty::of(&rating) == Difficulty::of(&original) => {
+                    replacements.push(Replacement { row: r, col: c, digit, puzzle: moved, rating });
+                }
+                _ => {}
+            }
+        }
+    }
+    replacements.sort_by_key(|replacement| {
+        let rating = replacement.rating;
+        (Reverse(rating.hardest == original.hardest), rating.guesses.total.abs_diff(original.guesses.total))
+    });
+    Some(replacements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::default_board;
+
+    #[test]
+    fn test_replace_clue() {
+        let puzzle = default_board();
+        let config = TechniqueConfig::default();
+        let original = rate(&puzzle, &config).unwrap();
+        // r1c3 holds a 5.
+        let replacements = replace_clue(&puzzle, 0, 2, &config).unwrap();
+        assert!(!replacements.is_empty());
+        for replacement in replacements.iter() {
+            assert_eq!(replacement.puzzle[0][2].solution, None);
+            assert_eq!(replacement.puzzle[replacement.row][replacement.col].solution, Some(replacement.digit));
+            assert_eq!(count_solutions(&replacement.puzzle, 2), 1);
+            assert_eq!(Difficulty::of(&replacement.rating), Difficulty::of(&original));
+        }
+        assert_eq!(replace_clue(&puzzle, 0, 0, &config), None);
+    }
+}
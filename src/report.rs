@@ -0,0 +1,142 @@
+//! Reports with a row for every puzzle, for looking into a collection with
+//! pandas or a spreadsheet rather than reading summaries. `rate` and
+//! `analyze` write them with `--report csv` or `--report json`.
+
+use std::time::Duration;
+
+use crate::board::{board_to_string, Board};
+use crate::generate::Difficulty;
+use crate::json::Json;
+use crate::logic::{solve_with, Technique, TechniqueConfig};
+use crate::rating::{rating_of, Rating};
+
+/// What a report is written as.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ReportFormat {
+    /// CSV with a header row, and the techniques of a row separated by
+    /// semicolons.
+    Csv,
+    /// A JSON array with an object per row.
+    Json,
+}
+
+impl ReportFormat {
+    pub const ALL: [ReportFormat; 2] = [ReportFormat::Csv, ReportFormat::Json];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ReportFormat::Csv => "csv",
+            ReportFormat::Json => "json",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ReportFormat> {
+        ReportFormat::ALL.iter().copied().find(|format| format.name() == name)
+    }
+
+    /// Writes the rows in this format.
+    pub fn write(self, rows: &[ReportRow]) -> String {
+        match self {
+            ReportFormat::Csv => report_csv(rows),
+            ReportFormat::Json => format!("{}\n", Json::Array(rows.iter().map(ReportRow::to_json).collect())),
+        }
+    }
+}
+
+/// A row of a report: one puzzle and how it was solved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportRow {
+    /// The position of the puzzle in its collection.
+    pub id: usize,
+    pub puzzle: Board,
+    pub clues: usize,
+    /// None if the puzzle has no solution.
+    pub rating: Option<Rating>,
+    /// The techniques the solve needed, easiest first.
+    pub techniques: Vec<Technique>,
+    pub time: Duration,
+    /// The guesses that turned out wrong and were taken back.
+    pub backtracks: usize,
+}
+
+impl ReportRow {
+    /// Solves `puzzle` with the techniques of `config` and reports on it.
+    pub fn new(id: usize, puzzle: &Board, config: &TechniqueConfig) -> ReportRow {
+        let trace = solve_with(puzzle, config);
+        let usage = trace.usage();
+        let rating = rating_of(&trace);
+        ReportRow {
+            id,
+            puzzle: *puzzle,
+            clues: puzzle.iter().flatten().filter(|cell| cell.solution.is_some()).count(),
+            rating,
+            techniques: Technique::ALL.iter().copied().filter(|&technique| usage.count(technique) > 0).collect(),
+            time: trace.elapsed,
+            backtracks: rating.map_or(trace.guesses, |rating| rating.guesses.total - rating.guesses.needed),
+        }
+    }
+
+    pub fn difficulty(&self) -> Option<Difficulty> {
+        self.rating.as_ref().map(Difficulty::of)
+    }
+
+    pub fn to_json(&self) -> Json {
+        Json::object(vec![
+            ("id", Json::from(self.id)),
+            ("puzzle", Json::from(board_to_string(&self.puzzle))),
+            ("clues", Json::from(self.clues)),
+            ("difficulty", Json::from(self.difficulty().map(Difficulty::name))),
+            ("techniques", Json::from(self.techniques.iter().map(|technique| technique.id()).collect::<Vec<_>>())),
+            ("time_ms", Json::from(self.time.as_secs_f64() * 1000.0)),
+            ("backtracks", Json::from(self.backtracks)),
+        ])
+    }
+}
+
+/// Returns the rows as CSV with a header row. The difficulty is empty for
+/// puzzles without a solution.
+pub fn report_csv(rows: &[ReportRow]) -> String {
+    let mut csv = String::from("id,puzzle,clues,difficulty,techniques,time_ms,backtracks\n");
+    for row in rows {
+        let techniques: Vec<&str> = row.techniques.iter().map(|technique| technique.id()).collect();
+        csv += &format!(
+            "{},{},{},{},{},{:.3},{}\n",
+            row.id,
+            board_to_string(&row.puzzle),
+            row.clues,
+            row.difficulty().map_or("", Difficulty::name),
+            techniques.join(";"),
+            row.time.as_secs_f64() * 1000.0,
+            row.backtracks
+        );
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{default_board, Cell};
+
+    #[test]
+    fn test_report() {
+        let mut broken = default_board();
+        broken[0][0] = Cell::solved(5);
+        let config = TechniqueConfig::default();
+        let rows = [ReportRow::new(0, &default_board(), &config), ReportRow::new(1, &broken, &config)];
+        assert_eq!(rows[0].clues, 36);
+        assert_eq!(rows[0].techniques[0], Technique::NakedSingle);
+        assert_eq!(rows[1].difficulty(), None);
+
+        let csv = ReportFormat::Csv.write(&rows);
+        assert_eq!(csv.lines().count(), 3);
+        assert!(csv.starts_with("id,puzzle,clues,difficulty,techniques,time_ms,backtracks\n0,..5..8..."));
+        assert!(csv.lines().nth(2).unwrap().starts_with(&format!("1,{},37,,,", board_to_string(&broken))));
+
+        let json = Json::parse(&ReportFormat::from_name("json").unwrap().write(&rows)).unwrap();
+        let first = &json.as_array().unwrap()[0];
+        assert_eq!(first.get("clues").and_then(Json::as_u64), Some(36));
+        assert_eq!(first.get("techniques").and_then(Json::as_array).unwrap()[0].as_str(), Some("naked_single"));
+        assert_eq!(json.as_array().unwrap()[1].get("difficulty"), Some(&Json::Null));
+    }
+}
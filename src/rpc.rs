@@ -0,0 +1,163 @@
+//! A line oriented JSON protocol for long running integrations such as
+//! editors and bots. Every line of input is a request object and every
+//! request is answered with exactly one line of output, in order:
+//!
+//! ```text
+//! {"id": 1, "method": "solve", "puzzle": "..5..8...."}
+//! {"id": 1, "result": {"solution": "365278149..."}}
+//! ```
+//!
+//! The methods are `solve`, `hint` (with an optional `level` from 1 to 3),
+//! `rate`, and `generate` (with optional `difficulty` and `seed`). All but
+//! `solve` accept a `techniques` array of technique ids. The `id` is copied
+//! to the response as is, and failed requests are answered with an `error`
+//! message instead of a `result`, e.g. a `generate` of a difficulty none of
+//! the techniques is of.
+
+use std::io::{self, BufRead, Write};
+
+use crate::board::{board_to_string, parse_board, Board};
+use crate::generate::{generate_with, Difficulty, Rng};
+use crate::hint::{hint, HintLevel};
+use crate::json::Json;
+use crate::logic::{Technique, TechniqueConfig};
+use crate::rating::rate;
+use crate::solver::Sudoku;
+
+/// Answers each line of `input` on a line of `output` until the input ends.
+/// Blank lines are skipped.
+pub fn serve_lines<R: BufRead, W: Write>(input: R, mut output: W) -> io::Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        writeln!(output, "{}", respond(&line))?;
+        // The client is waiting for the answer before sending more.
+        output.flush()?;
+    }
+    Ok(())
+}
+
+/// Returns the response to a single request line.
+pub fn respond(line: &str) -> Json {
+    let request = match Json::parse(line) {
+        Some(request @ Json::Object(_)) => request,
+        _ => return response(Json::Null, Err("the request must be a JSON object".to_string())),
+    };
+    let id = request.get("id").cloned().unwrap_or(Json::Null);
+    response(id, call(&request))
+}
+
+fn response(id: Json, result: Result<Json, String>) -> Json {
+    match result {
+        Ok(result) => Json::object(vec![("id", id), ("result", result)]),
+        Err(message) => Json::object(vec![("id", id), ("error", Json::from(message))]),
+    }
+}
+
+fn call(request: &Json) -> Result<Json, String> {
+    let method = request.get("method").and_then(Json::as_str).ok_or("missing method")?;
+    let config = techniques(request)?;
+    match method {
+        "solve" => match Sudoku::try_new(puzzle(request)?).map_err(|err| err.to_string())?.solve() {
+            Some(solution) => Ok(Json::object(vec![("solution", Json::from(board_to_string(&solution)))])),
+            None => Err("no solution".to_string()),
+        },
+        "hint" => {
+            let level = match request.get("level") {
+                None => HintLevel::Area,
+                Some(level) => match level.as_u64() {
+                    Some(level @ 1..=3) => HintLevel::ALL[level as usize - 1],
+                    _ => return Err("the hint level must be from 1 to 3".to_string()),
+                },
+            };
+            match hint(&puzzle(request)?, &config) {
+                Some(hint) => Ok(Json::object(vec![
+                    ("hint", Json::from(hint.text(level))),
+                    ("step", hint.step.to_json()),
+                ])),
+                None => Err("the puzzle is solved or has no solution".to_string()),
+            }
+        }
+        "rate" => match rate(&puzzle(request)?, &config) {
+            Some(rating) => Ok(rating.to_json()),
+            None => Err("no solution".to_string()),
+        },
+        "generate" => {
+            let difficulty = match request.get("difficulty") {
+                None => Difficulty::Medium,
+                Some(name) => name
+                    .as_str()
+                    .and_then(Difficulty::from_name)
+                    .ok_or("unknown difficulty")?,
+            };
+            let mut rng = match request.get("seed") {
+                None => Rng::from_time(),
+                Some(seed) => Rng::new(seed.as_u64().ok_or("the seed must be a number")?),
+            };
+            let puzzle = generate_with(difficulty, &config, &mut rng).map_err(|err| err.to_string())?;
+            Ok(Json::object(vec![("puzzle", Json::from(board_to_string(&puzzle)))]))
+        }
+        _ => Err(format!("unknown method `{}`", method)),
+    }
+}
+
+fn puzzle(request: &Json) -> Result<Board, String> {
+    let puzzle = request.get("puzzle").and_then(Json::as_str).ok_or("missing puzzle")?;
+    parse_board(puzzle).ok_or_else(|| "the puzzle must be an 81 character string".to_string())
+}
+
+fn techniques(request: &Json) -> Result<TechniqueConfig, String> {
+    let ids = match request.get("techniques") {
+        None => return Ok(TechniqueConfig::default()),
+        Some(ids) => ids.as_array().ok_or("techniques must be an array")?,
+    };
+    let mut techniques = Vec::new();
+    for id in ids {
+        match id.as_str().and_then(Technique::from_id) {
+            Some(technique) => techniques.push(technique),
+            None => return Err(format!("unknown technique {}", id)),
+        }
+    }
+    Ok(TechniqueConfig::new(techniques))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::default_board;
+
+    #[test]
+    fn test_serve_lines() {
+        let puzzle = board_to_string(&default_board());
+        let input = format!(
+            "{{\"id\": 1, \"method\": \"hint\", \"puzzle\": \"{}\", \"level\": 3}}\n\n\
+             {{\"id\": \"b\", \"method\": \"rate\", \"puzzle\": \"123\"}}\n\
+             {{\"id\": 2, \"method\": \"generate\", \"difficulty\": \"hard\", \"techniques\": [\"naked_single\"]}}\n\
+             not json\n{}\n",
+            puzzle,
+            "[".repeat(200_000)
+        );
+        let mut output = Vec::new();
+        serve_lines(input.as_bytes(), &mut output).unwrap();
+        let lines: Vec<Json> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| Json::parse(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0].get("id"), Some(&Json::from(1usize)));
+        let hint = lines[0].get("result").and_then(|result| result.get("hint"));
+        assert_eq!(hint.and_then(Json::as_str), Some("r2c4 is 9"));
+        assert_eq!(lines[1].get("id").and_then(Json::as_str), Some("b"));
+        assert!(lines[1].get("error").is_some());
+        // No puzzle rated with singles alone is hard, so that's an error
+        // rather than a search that never ends.
+        assert_eq!(lines[2].get("id"), Some(&Json::from(2usize)));
+        assert!(lines[2].get("error").is_some());
+        assert_eq!(lines[3].get("id"), Some(&Json::Null));
+        // Nesting too deep to parse is an error rather than a stack overflow.
+        assert!(lines[4].get("error").is_some());
+    }
+}
@@ -0,0 +1,296 @@
+//! The SAT backend: sudoku as clauses over 729 boolean variables, one for
+//! each digit in each cell, solved with DPLL. Unit propagation keeps two
+//! watched literals per clause, so undoing a decision only has to clear the
+//! assignments, and decisions are made in the clause with the fewest
+//! literals left, like the cell with the fewest candidates of the
+//! backtracking search. There's no clause learning, which sudoku rarely
+//! needs.
+
+use std::mem;
+
+use crate::board::Board;
+use crate::packed::Packed;
+use crate::solver::{write_solution, SolverBackend};
+
+/// A variable, `index * 9 + digit`, shifted left by one, with the lowest bit
+/// set if it's negated.
+type Lit = usize;
+
+fn lit(index: usize, digit: usize, positive: bool) -> Lit {
+    (index * 9 + digit) << 1 | !positive as usize
+}
+
+/// The value of `lit`: 1 if it's true, -1 if it's false, 0 if its variable
+/// isn't assigned.
+fn value(values: &[i8], lit: Lit) -> i8 {
+    let value = values[lit >> 1];
+    if lit & 1 == 0 {
+        value
+    } else {
+        -value
+    }
+}
+
+/// How many clauses there are: for each cell, and each house and digit, that
+/// it has a digit, and that no two of its nine candidates go together.
+const CLAUSES: usize = 324 * (1 + 36);
+
+/// How many literals the clauses have together.
+const LITERALS: usize = 324 * (9 + 36 * 2);
+
+/// About how many bytes of memory a `SatSolver` allocates, taking its watch
+/// lists to grow to twice the watches at most.
+pub(crate) const MEMORY_BYTES: usize = CLAUSES * mem::size_of::<Vec<Lit>>()
+    + LITERALS * mem::size_of::<Lit>()
+    + 729 * 2 * mem::size_of::<Vec<usize>>()
+    + 2 * 2 * CLAUSES * mem::size_of::<usize>()
+    + 324 * mem::size_of::<usize>()
+    + 729 * (mem::size_of::<i8>() + mem::size_of::<Lit>())
+    + 81 * mem::size_of::<(usize, Lit, bool)>();
+
+/// The SAT backend, which keeps the clauses and its scratch state between
+/// puzzles. Only the givens change from one puzzle to the next.
+#[derive(Debug, Clone)]
+pub struct SatSolver {
+    /// The clauses, with their watched literals first.
+    clauses: Vec<Vec<Lit>>,
+    /// The clauses that a cell or a house has some digit, which decisions
+    /// are made in. The others only say two digits don't go together.
+    covering: Vec<usize>,
+    /// The clauses watching each literal, to visit when it becomes false.
+    watches: Vec<Vec<usize>>,
+    values: Vec<i8>,
+    /// The true literals in the order they were assigned.
+    trail: Vec<Lit>,
+    /// How many literals of the trail have been propagated.
+    head: usize,
+    /// The trail length before, the literal, and whether it's the second
+    /// try of each decision.
+    decisions: Vec<(usize, Lit, bool)>,
+}
+
+impl Default for SatSolver {
+    fn default() -> SatSolver {
+        SatSolver::new()
+    }
+}
+
+impl SatSolver {
+    pub fn new() -> SatSolver {
+        let mut clauses = Vec::new();
+        let mut covering = Vec::new();
+        let houses = (0..9).flat_map(|i| {
+            let row: Vec<usize> = (0..9).map(|j| i * 9 + j).collect();
+            let col: Vec<usize> = (0..9).map(|j| j * 9 + i).collect();
+            let b: Vec<usize> = (0..9).map(|j| (i / 3 * 3 + j / 3) * 9 + i % 3 * 3 + j % 3).collect();
+            vec![row, col, b]
+        });
+        for index in 0..81 {
+            covering.push(clauses.len());
+            clauses.push((0..9).map(|digit| lit(index, digit, true)).collect());
+            for a in 0..9 {
+                for b in a + 1..9 {
+                    clauses.push(vec![lit(index, a, false), lit(index, b, false)]);
+                }
+            }
+        }
+        for house in houses {
+            for digit in 0..9 {
+                covering.push(clauses.len());
+                clauses.push(house.iter().map(|&index| lit(index, digit, true)).collect());
+                for (i, &a) in house.iter().enumerate() {
+                    for &b in house[i + 1..].iter() {
+                        clauses.push(vec![lit(a, digit, false), lit(b, digit, false)]);
+                    }
+                }
+            }
+        }
+        let mut watches = vec![Vec::new(); 729 * 2];
+        for (c, clause) in clauses.iter().enumerate() {
+            watches[clause[0]].push(c);
+            watches[clause[1]].push(c);
+        }
+        SatSolver {
+            clauses,
+            covering,
+            watches,
+            values: vec![0; 729],
+            trail: Vec::with_capacity(729),
+            head: 0,
+            decisions: Vec::with_capacity(81),
+        }
+    }
+
+    fn assign(&mut self, lit: Lit) {
+        self.values[lit >> 1] = if lit & 1 == 0 { 1 } else { -1 };
+        self.trail.push(lit);
+    }
+
+    /// Unassigns everything assigned since the trail had `len` literals.
+    fn undo(&mut self, len: usize) {
+        for lit in self.trail.drain(len..) {
+            self.values[lit >> 1] = 0;
+        }
+        self.head = len;
+    }
+
+    /// Assigns the literals that the trail leaves as the only way to satisfy
+    /// a clause. Returns false if a clause can't be satisfied anymore.
+    fn propagate(&mut self) -> bool {
+        while self.head < self.trail.len() {
+            let falsified = self.trail[self.head] ^ 1;
+            self.head += 1;
+            // No clause starts watching `falsified` while it's false, so
+            // nothing is added to its watches in the meantime.
+            let mut watching = mem::take(&mut self.watches[falsified]);
+            let mut i = 0;
+            let mut satisfiable = true;
+            while i < watching.len() {
+                let c = watching[i];
+                let clause = &mut self.clauses[c];
+                if clause[0] == falsified {
+                    clause.swap(0, 1);
+                }
+                let other = clause[0];
+                if value(&self.values, other) == 1 {
+                    i += 1;
+                    continue;
+                }
+                let values = &self.values;
+                if let Some(k) = (2..clause.len()).find(|&k| value(values, clause[k]) != -1) {
+                    clause.swap(1, k);
+                    self.watches[clause[1]].push(c);
+                    watching.swap_remove(i);
+                    continue;
+                }
+                i += 1;
+                match value(&self.values, other) {
+                    -1 => {
+                        satisfiable = false;
+                        break;
+                    }
+                    0 => self.assign(other),
+                    _ => {}
+                }
+            }
+            self.watches[falsified] = watching;
+            if !satisfiable {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Returns a literal to decide on, from the covering clause with the
+    /// fewest unassigned literals that isn't satisfied yet, or None if they
+    /// all are, which solves the puzzle.
+    fn decide(&self) -> Option<Lit> {
+        let mut best = None;
+        let mut fewest = usize::MAX;
+        for &c in self.covering.iter() {
+            let clause = &self.clauses[c];
+            if clause.iter().any(|&lit| value(&self.values, lit) == 1) {
+                continue;
+            }
+            let unassigned = clause.iter().filter(|&&lit| value(&self.values, lit) == 0).count();
+            if unassigned < fewest {
+                fewest = unassigned;
+                best = clause.iter().copied().find(|&lit| value(&self.values, lit) == 0);
+                if fewest <= 2 {
+                    break;
+                }
+            }
+        }
+        best
+    }
+
+    /// Runs DPLL from the givens already on the trail. Returns whether every
+    /// clause could be satisfied.
+    fn search(&mut self) -> bool {
+        loop {
+            if !self.propagate() {
+                // Take back decisions until one can be tried the other way.
+                loop {
+                    match self.decisions.pop() {
+                        None => return false,
+                        Some((len, lit, second)) => {
+                            self.undo(len);
+                            if !second {
+                                self.decisions.push((len, lit ^ 1, true));
+                                self.assign(lit ^ 1);
+                                break;
+                            }
+                        }
+                    }
+                }
+                continue;
+            }
+            match self.decide() {
+                Some(lit) => {
+                    self.decisions.push((self.trail.len(), lit, false));
+                    self.assign(lit);
+                }
+                None => return true,
+            }
+        }
+    }
+}
+
+impl SolverBackend for SatSolver {
+    fn solve_into(&mut self, puzzle: &Board, solution: &mut Board) -> bool {
+        let packed = match Packed::from_board(puzzle) {
+            Some(packed) => packed,
+            None => return false,
+        };
+        self.undo(0);
+        self.decisions.clear();
+        for (index, &digit) in packed.cells.iter().enumerate() {
+            if digit != 0 {
+                self.assign(lit(index, digit as usize - 1, true));
+            }
+        }
+        if !self.search() {
+            return false;
+        }
+        let mut digits = [0; 81];
+        for (index, digit) in digits.iter_mut().enumerate() {
+            *digit = (0..9).find(|&d| self.values[index * 9 + d] == 1).map_or(0, |d| d as u8 + 1);
+        }
+        write_solution(puzzle, &digits, solution);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{board_to_string, default_board, parse_board, Cell};
+    use crate::solver::Sudoku;
+
+    #[test]
+    fn test_sat_solver() {
+        let mut solver = SatSolver::new();
+        let mut solution = Board::default();
+        // Hard for a search without propagation.
+        let hard =
+            parse_board("8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..").unwrap();
+        for puzzle in [default_board(), hard].iter() {
+            assert!(solver.solve_into(puzzle, &mut solution));
+            let expected = Sudoku::new(*puzzle).solve().unwrap();
+            assert_eq!(board_to_string(&solution), board_to_string(&expected));
+        }
+
+        // r1c9 can't hold anything: its row has 1 to 8 and its column a 9.
+        let mut board = Board::default();
+        for col in 0..8 {
+            board[0][col] = Cell::solved(col as i8 + 1);
+        }
+        board[1][8] = Cell::solved(9);
+        assert!(!solver.solve_into(&board, &mut solution));
+
+        let clauses: usize = solver.clauses.iter().map(|clause| clause.capacity() * mem::size_of::<Lit>()).sum();
+        let watches: usize = solver.watches.iter().map(|watches| watches.capacity() * mem::size_of::<usize>()).sum();
+        assert_eq!(solver.clauses.len(), CLAUSES);
+        assert!(clauses + watches < MEMORY_BYTES);
+    }
+}
@@ -0,0 +1,142 @@
+//! Numeric difficulty scores, for publishers whose puzzles are labeled on a
+//! scale of their own rather than with `Difficulty`. Each technique has a
+//! weight, and a puzzle scores the weight of the hardest one it needs, the
+//! way Sudoku Explainer rates puzzles. The weights are read from a file of
+//! `technique = weight` lines:
+//!
+//! ```toml
+//! hidden_single = 1.0
+//! x_wing = 4
+//! guess = 20
+//! ```
+//!
+//! Techniques the file leaves out keep their default weight.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::config::{Config, ConfigError};
+use crate::logic::Technique;
+use crate::rating::Rating;
+
+/// Why a score table couldn't be read.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScoreTableError {
+    /// The file isn't a valid configuration file.
+    Config(ConfigError),
+    /// A key isn't the id of a technique.
+    UnknownTechnique(String),
+    /// The weight of `technique` isn't a number of at least 0.
+    InvalidWeight { technique: Technique, value: String },
+}
+
+impl fmt::Display for ScoreTableError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScoreTableError::Config(err) => write!(f, "{}", err),
+            ScoreTableError::UnknownTechnique(id) => write!(f, "unknown technique `{}`", id),
+            ScoreTableError::InvalidWeight { technique, value } => {
+                write!(f, "the weight of {} should be a number of at least 0, not `{}`", technique.id(), value)
+            }
+        }
+    }
+}
+
+impl Error for ScoreTableError {}
+
+/// The weight of every technique.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScoreTable {
+    weights: [f64; Technique::ALL.len()],
+}
+
+impl ScoreTable {
+    /// Reads a table of weights, on top of the default ones.
+    pub fn parse(text: &str) -> Result<ScoreTable, ScoreTableError> {
+        let config = Config::parse(text).map_err(ScoreTableError::Config)?;
+        let mut table = ScoreTable::default();
+        for (key, value) in config.iter() {
+            let technique = Technique::from_id(key).ok_or_else(|| ScoreTableError::UnknownTechnique(key.into()))?;
+            match value.parse::<f64>() {
+                Ok(weight) if weight >= 0.0 && weight.is_finite() => table.set_weight(technique, weight),
+                _ => return Err(ScoreTableError::InvalidWeight { technique, value: value.into() }),
+            }
+        }
+        Ok(table)
+    }
+
+    pub fn weight(&self, technique: Technique) -> f64 {
+        self.weights[technique as usize]
+    }
+
+    pub fn set_weight(&mut self, technique: Technique, weight: f64) {
+        self.weights[technique as usize] = weight;
+    }
+
+    /// Returns the score of a puzzle with `rating`: the weight of its hardest
+    /// technique, or of guessing if it needs guesses and that weighs more.
+    /// Puzzles with nothing left to solve score 0.
+    pub fn score(&self, rating: &Rating) -> f64 {
+        let hardest = rating.hardest.map_or(0.0, |technique| self.weight(technique));
+        if rating.guesses.needed > 0 {
+            hardest.max(self.weight(Technique::Guess))
+        } else {
+            hardest
+        }
+    }
+}
+
+/// Weights close to Sudoku Explainer's ratings, from 1.5 for hidden singles
+/// to 10 for guesses.
+impl Default for ScoreTable {
+    fn default() -> ScoreTable {
+        let mut table = ScoreTable { weights: [0.0; Technique::ALL.len()] };
+        for &technique in Technique::ALL.iter() {
+            let weight = match technique {
+                Technique::NakedSingle => 2.3,
+                Technique::HiddenSingle => 1.5,
+                Technique::LockedCandidates => 2.6,
+                Technique::NakedPair => 3.0,
+                Technique::HiddenPair => 3.4,
+                Technique::XWing => 3.2,
+                Technique::BugPlusOne => 5.6,
+                Technique::XChain => 6.6,
+                Technique::XYChain => 7.0,
+                Technique::ForcingChain => 8.3,
+                Technique::Guess => 10.0,
+            };
+            table.set_weight(technique, weight);
+        }
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{default_board, parse_board};
+    use crate::logic::TechniqueConfig;
+    use crate::rating::rate;
+
+    #[test]
+    fn test_score_table() {
+        let table = ScoreTable::default();
+        let rating = rate(&default_board(), &TechniqueConfig::default()).unwrap();
+        assert_eq!(table.score(&rating), table.weight(rating.hardest.unwrap()));
+
+        let board = parse_board(
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..",
+        )
+        .unwrap();
+        let rating = rate(&board, &TechniqueConfig::default()).unwrap();
+        assert_eq!(table.score(&rating), 10.0);
+
+        let table = ScoreTable::parse("# Our own scale\nguess = 100\nx_wing = 4\n").unwrap();
+        assert_eq!(table.score(&rating), 100.0);
+        assert_eq!(table.weight(Technique::XWing), 4.0);
+        assert_eq!(table.weight(Technique::HiddenSingle), 1.5);
+        assert_eq!(ScoreTable::parse("x_wing = 2.5\n").unwrap().weight(Technique::XWing), 2.5);
+        assert_eq!(ScoreTable::parse("swordfish = 4\n"), Err(ScoreTableError::UnknownTechnique("swordfish".into())));
+        assert!(matches!(ScoreTable::parse("guess = -1\n"), Err(ScoreTableError::InvalidWeight { .. })));
+    }
+}
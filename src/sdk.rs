@@ -0,0 +1,76 @@
+//! The `.sdk` format of SadMan Software Sudoku, which many collections are
+//! shared in: lines of metadata starting with `#` and a letter, followed by
+//! the grid as nine lines of nine digits with `.` for empty cells.
+
+use crate::board::{board_to_string, parse_board, Board};
+use crate::puzzle::Metadata;
+
+/// Parses a puzzle in the `.sdk` format. The description is taken as the
+/// title and the level as the rating; comments, URLs, and unknown fields are
+/// ignored. Returns None if the grid isn't 81 cells.
+pub fn parse_sdk(s: &str) -> Option<(Board, Metadata)> {
+    let mut metadata = Metadata::default();
+    let mut grid = String::with_capacity(81);
+    for line in s.lines().map(str::trim) {
+        if let Some(field) = line.strip_prefix('#') {
+            let mut chars = field.chars();
+            let tag = chars.next();
+            let value = Some(String::from(chars.as_str().trim())).filter(|value| !value.is_empty());
+            match tag {
+                Some('D') => metadata.title = value,
+                Some('A') => metadata.author = value,
+                Some('S') => metadata.source = value,
+                Some('B') => metadata.date = value,
+                Some('L') => metadata.rating = value,
+                _ => {}
+            }
+        } else {
+            grid += line;
+        }
+    }
+    parse_board(&grid).map(|board| (board, metadata))
+}
+
+/// Formats `board` in the `.sdk` format with the fields of `metadata` that
+/// are set.
+pub fn to_sdk(board: &Board, metadata: &Metadata) -> String {
+    let mut s = String::new();
+    let fields = [
+        ('D', &metadata.title),
+        ('A', &metadata.author),
+        ('S', &metadata.source),
+        ('B', &metadata.date),
+        ('L', &metadata.rating),
+    ];
+    for (tag, value) in fields.iter() {
+        if let Some(value) = value {
+            s += &format!("#{}{}\n", tag, value);
+        }
+    }
+    let cells = board_to_string(board);
+    for row in 0..9 {
+        s += &cells[row * 9..row * 9 + 9];
+        s.push('\n');
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::default_board;
+
+    #[test]
+    fn test_sdk() {
+        let sdk = "#AJohn Doe\n#DThe example\n#CA comment\n#B2020-01-31\n\
+                   ..5..8...\n.2....5..\n79.34562.\n...6.471.\n.495.783.\n.178.2...\n.54783.96\n..6....5.\n...1..4..\n";
+        let (board, metadata) = parse_sdk(sdk).unwrap();
+        assert_eq!(board, default_board());
+        assert_eq!(metadata.author.as_deref(), Some("John Doe"));
+        assert_eq!(metadata.title.as_deref(), Some("The example"));
+        assert_eq!(metadata.date.as_deref(), Some("2020-01-31"));
+        assert_eq!(metadata.source, None);
+        assert_eq!(parse_sdk(&to_sdk(&board, &metadata)), Some((board, metadata)));
+        assert_eq!(parse_sdk("#AJohn Doe\n..5..8...\n"), None);
+    }
+}
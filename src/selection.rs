@@ -0,0 +1,198 @@
+//! Arbitrary groups of cells, for tools that work on part of a board: a
+//! player's multi-cell selection, a region to clear, or the cells whose
+//! candidates an analysis looks at. Selections are built from cells,
+//! houses, and rectangles and combined with the usual set operations.
+
+use std::fmt;
+use std::ops::{BitAnd, BitOr, Not, Sub};
+
+use crate::annotate::{Annotations, Color};
+use crate::board::{Board, Cell};
+use crate::grid::{cell_name, mask_digits, Grid, House};
+
+/// The cells of all 81 set.
+const ALL_CELLS: u128 = (1 << 81) - 1;
+
+/// A set of cells, with bit `row * 9 + col` set for each cell in it.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub struct Selection {
+    bits: u128,
+}
+
+impl Selection {
+    pub fn empty() -> Selection {
+        Selection::default()
+    }
+
+    pub fn all() -> Selection {
+        Selection { bits: ALL_CELLS }
+    }
+
+    pub fn cell(row: usize, col: usize) -> Selection {
+        assert!(row < 9 && col < 9);
+        Selection { bits: 1 << (row * 9 + col) }
+    }
+
+    pub fn house(house: House) -> Selection {
+        house.cells().iter().map(|&(row, col)| Selection::cell(row, col)).fold(Selection::empty(), BitOr::bitor)
+    }
+
+    /// Returns the cells from `top_left` to `bottom_right`, both included,
+    /// as (row, column) pairs. It's empty if the corners are the wrong way
+    /// round.
+    pub fn rect(top_left: (usize, usize), bottom_right: (usize, usize)) -> Selection {
+        let mut selection = Selection::empty();
+        for row in top_left.0..=bottom_right.0 {
+            for col in top_left.1..=bottom_right.1 {
+                selection.insert(row, col);
+            }
+        }
+        selection
+    }
+
+    /// Returns the cells of `board` that satisfy `predicate`, e.g. the empty
+    /// ones.
+    pub fn matching(board: &Board, predicate: impl Fn(&Cell) -> bool) -> Selection {
+        let mut selection = Selection::empty();
+        for row in 0..9 {
+            for col in 0..9 {
+                if predicate(&board[row][col]) {
+                    selection.insert(row, col);
+                }
+            }
+        }
+        selection
+    }
+
+    pub fn contains(&self, row: usize, col: usize) -> bool {
+        *self & Selection::cell(row, col) != Selection::empty()
+    }
+
+    pub fn insert(&mut self, row: usize, col: usize) {
+        *self = *self | Selection::cell(row, col);
+    }
+
+    pub fn remove(&mut self, row: usize, col: usize) {
+        *self = *self - Selection::cell(row, col);
+    }
+
+    pub fn len(&self) -> usize {
+        self.bits.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits == 0
+    }
+
+    /// Returns the cells, row by row.
+    pub fn cells(self) -> impl Iterator<Item = (usize, usize)> {
+        (0..81).filter(move |i| self.bits & (1 << i) != 0).map(|i| (i / 9, i % 9))
+    }
+
+    /// Empties the selected cells of `board`.
+    pub fn clear(&self, board: &mut Board) {
+        for (row, col) in self.cells() {
+            board[row][col] = Cell::unsolved();
+        }
+    }
+
+    /// Returns how many candidates the selected empty cells of `grid` have
+    /// between them.
+    pub fn count_candidates(&self, grid: &Grid) -> usize {
+        self.cells().map(|(row, col)| grid.candidates(row, col).count_ones() as usize).sum()
+    }
+
+    /// Returns the digits that are a candidate of any selected cell of
+    /// `grid`.
+    pub fn candidates(&self, grid: &Grid) -> Vec<i8> {
+        mask_digits(self.cells().fold(0, |mask, (row, col)| mask | grid.candidates(row, col))).collect()
+    }
+
+    /// Tags the selected cells with `color`, which renderers show as their
+    /// background.
+    pub fn highlight(&self, annotations: &mut Annotations, color: Color) {
+        for (row, col) in self.cells() {
+            annotations.get_mut(row, col).color = Some(color);
+        }
+    }
+}
+
+impl BitOr for Selection {
+    type Output = Selection;
+
+    /// Returns the union of the selections.
+    fn bitor(self, other: Selection) -> Selection {
+        Selection { bits: self.bits | other.bits }
+    }
+}
+
+impl BitAnd for Selection {
+    type Output = Selection;
+
+    /// Returns the intersection of the selections.
+    fn bitand(self, other: Selection) -> Selection {
+        Selection { bits: self.bits & other.bits }
+    }
+}
+
+impl Sub for Selection {
+    type Output = Selection;
+
+    /// Returns the cells of `self` that aren't in `other`.
+    fn sub(self, other: Selection) -> Selection {
+        Selection { bits: self.bits & !other.bits }
+    }
+}
+
+impl Not for Selection {
+    type Output = Selection;
+
+    /// Returns the cells that aren't selected.
+    fn not(self) -> Selection {
+        Selection { bits: !self.bits & ALL_CELLS }
+    }
+}
+
+/// Formats the selection as a comma separated list of cell names, e.g.
+/// `r1c1, r1c2`.
+impl fmt::Display for Selection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names: Vec<String> = self.cells().map(|(row, col)| cell_name(row, col)).collect();
+        write!(f, "{}", names.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::default_board;
+
+    #[test]
+    fn test_selection() {
+        let row = Selection::house(House::Row(0));
+        let first_box = Selection::house(House::Box(0));
+        assert_eq!(row.len(), 9);
+        assert_eq!(row & first_box, Selection::rect((0, 0), (0, 2)));
+        assert_eq!((row | first_box).len(), 15);
+        assert_eq!((first_box - row).to_string(), "r2c1, r2c2, r2c3, r3c1, r3c2, r3c3");
+        assert_eq!((!row).len(), 72);
+        assert_eq!(!Selection::all(), Selection::empty());
+        assert!(Selection::rect((2, 2), (1, 1)).is_empty());
+
+        let mut board = default_board();
+        let empty = Selection::matching(&board, |cell| cell.solution.is_none());
+        assert_eq!(empty.len(), 45);
+        let grid = Grid::from_board(&board);
+        // r3c3 has 1 and 8 left, and r3c9 the same.
+        let third_row = Selection::house(House::Row(2)) & empty;
+        assert_eq!(third_row.count_candidates(&grid), 4);
+        assert_eq!(third_row.candidates(&grid), vec![1, 8]);
+
+        first_box.clear(&mut board);
+        assert!(first_box.cells().all(|(row, col)| board[row][col].solution.is_none()));
+        let mut annotations = Annotations::default();
+        third_row.highlight(&mut annotations, Color::Blue);
+        assert_eq!(annotations.iter().count(), 2);
+        assert_eq!(annotations.get(2, 8).color, Some(Color::Blue));
+    }
+}
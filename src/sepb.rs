@@ -0,0 +1,56 @@
+//! The line format of the Sudoku Exchange Puzzle Bank, a large public
+//! collection that's mirrored in many places: the puzzle's id, its 81 cells
+//! with `0` for empty ones, and its rating, separated by spaces.
+//!
+//! ```text
+//! 0000183b305c  050703060007000800000816000000030000005000100730040086906000204840572093000409000  1.2
+//! ```
+
+use crate::board::{board_to_string, parse_board, Board};
+use crate::puzzle::Metadata;
+
+/// Parses a line of the format. The id and the rating are kept in the
+/// metadata, unless they're `-`. Returns None if the line doesn't have the
+/// three fields or the grid isn't 81 cells.
+pub fn parse_sepb(line: &str) -> Option<(Board, Metadata)> {
+    let mut fields = line.split_whitespace();
+    let (id, puzzle, rating) = (fields.next()?, fields.next()?, fields.next()?);
+    if fields.next().is_some() {
+        return None;
+    }
+    let field = |value: &str| Some(String::from(value)).filter(|value| value != "-");
+    let metadata = Metadata { id: field(id), rating: field(rating), ..Metadata::default() };
+    parse_board(puzzle).map(|board| (board, metadata))
+}
+
+/// Formats `board` as a line of the format, without a line break. The id or
+/// the rating are written as `-` if `metadata` doesn't have them.
+pub fn to_sepb(board: &Board, metadata: &Metadata) -> String {
+    let field = |value: &Option<String>| value.clone().unwrap_or_else(|| String::from("-"));
+    format!("{}  {}  {}", field(&metadata.id), board_to_string(board).replace('.', "0"), field(&metadata.rating))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::default_board;
+
+    #[test]
+    fn test_sepb() {
+        let line = "0000183b305c  050703060007000800000816000000030000005000100730040086906000204840572093000409000  1.2";
+        let (board, metadata) = parse_sepb(line).unwrap();
+        assert_eq!(
+            board_to_string(&board),
+            ".5.7.3.6...7...8.....816.......3......5...1..73..4..869.6...2.484.572.93...4.9..."
+        );
+        assert_eq!(metadata.id.as_deref(), Some("0000183b305c"));
+        assert_eq!(metadata.rating.as_deref(), Some("1.2"));
+        assert_eq!(to_sepb(&board, &metadata), line);
+
+        let line = to_sepb(&default_board(), &Metadata::default());
+        assert!(line.starts_with("-  005008000") && line.ends_with("  -"));
+        assert_eq!(parse_sepb(&line), Some((default_board(), Metadata::default())));
+        assert_eq!(parse_sepb("0000183b305c  0507030600  1.2"), None);
+        assert_eq!(parse_sepb(&board_to_string(&board)), None);
+    }
+}
@@ -0,0 +1,394 @@
+//! A small HTTP server, enabled with the `server` feature, so that web pages
+//! and bots can use the solver without spawning a process per puzzle. It
+//! speaks just enough HTTP/1.1 for one request per connection and answers
+//! with JSON:
+//!
+//! - `POST /solve` with the puzzle as the body returns `{"solution": ...}`
+//! - `POST /rate` returns the rating, as `sudoku rate --json` prints it
+//! - `POST /hint?level=N` returns `{"hint": ...}`
+//! - `GET /generate?difficulty=hard&seed=N` returns `{"puzzle": ...}`
+//!
+//! Errors are returned as `{"error": ...}` with a 4xx status. Request
+//! counts, durations, and generated difficulties are exposed for Prometheus
+//! at `GET /metrics`. With the UI turned on, `GET /` returns a page that
+//! plays sudoku with the endpoints above.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::board::{board_to_string, parse_board, Board};
+use crate::generate::{generate, Difficulty, Rng};
+use crate::hint::{hint, HintLevel};
+use crate::json::Json;
+use crate::logic::TechniqueConfig;
+use crate::rating::rate;
+use crate::solver::Sudoku;
+
+/// Bodies are puzzles, so anything much larger than 81 characters is a
+/// mistake.
+const MAX_BODY_LEN: usize = 4096;
+
+/// The most bytes the request line and headers may take together, and the
+/// most headers a request may have, so that a client can't use up memory
+/// with an endless line.
+const MAX_HEAD_LEN: u64 = 8192;
+const MAX_HEADERS: usize = 64;
+
+/// How long a client may take to send its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The upper bounds of the request duration histogram buckets, in seconds.
+const DURATION_BUCKETS: [f64; 8] = [0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0];
+
+/// The playable page served at `/` with the UI turned on.
+const UI: &str = include_str!("../ui/index.html");
+
+/// Listens on `addr` and serves requests, each connection on its own thread,
+/// until the listener fails.
+pub fn serve<A: ToSocketAddrs>(addr: A) -> io::Result<()> {
+    serve_with(addr, false)
+}
+
+/// Like `serve`, but also serves the playable page at `/` if `ui` is true.
+pub fn serve_with<A: ToSocketAddrs>(addr: A, ui: bool) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    let metrics = Arc::new(Mutex::new(Metrics::default()));
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let metrics = metrics.clone();
+        thread::spawn(move || {
+            // A client hanging up halfway is not our problem.
+            let _ = handle_connection(stream, &metrics, ui);
+        });
+    }
+    Ok(())
+}
+
+/// A request's method, target, and body.
+#[derive(Debug)]
+struct Request {
+    method: String,
+    target: String,
+    body: Vec<u8>,
+}
+
+/// Reads a request, or returns the status and error to reply with if it's
+/// too large.
+fn read_request<R: Read>(stream: R) -> io::Result<Result<Request, (u16, &'static str)>> {
+    const HEADERS_TOO_LARGE: (u16, &str) = (431, "the headers are too large");
+    let mut reader = BufReader::new(stream);
+    let mut head = reader.by_ref().take(MAX_HEAD_LEN);
+    let mut request_line = String::new();
+    head.read_line(&mut request_line)?;
+    if head.limit() == 0 {
+        return Ok(Err(HEADERS_TOO_LARGE));
+    }
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let target = parts.next().unwrap_or_default().to_string();
+
+    let mut content_length = 0;
+    let mut headers = 0;
+    loop {
+        let mut header = String::new();
+        if head.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        headers += 1;
+        if head.limit() == 0 || headers > MAX_HEADERS {
+            return Ok(Err(HEADERS_TOO_LARGE));
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+    if content_length > MAX_BODY_LEN {
+        return Ok(Err((413, "the body is too large")));
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    Ok(Ok(Request { method, target, body }))
+}
+
+fn handle_connection(stream: TcpStream, metrics: &Mutex<Metrics>, ui: bool) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let request = match read_request(&stream) {
+        Ok(Ok(request)) => request,
+        Ok(Err((status, message))) => {
+            return write_response(stream, status, "application/json", &error(message).to_string());
+        }
+        Err(err) if err.kind() == ErrorKind::WouldBlock || err.kind() == ErrorKind::TimedOut => {
+            metrics.lock().unwrap().timeouts += 1;
+            let body = error("timed out reading the request").to_string();
+            return write_response(stream, 408, "application/json", &body);
+        }
+        Err(err) => return Err(err),
+    };
+
+    let path = request.target.split('?').next().unwrap_or_default();
+    if path == "/metrics" {
+        let text = metrics.lock().unwrap().to_string();
+        return write_response(stream, 200, "text/plain; version=0.0.4", &text);
+    }
+    if ui && request.method == "GET" && path == "/" {
+        return write_response(stream, 200, "text/html; charset=utf-8", UI);
+    }
+    let start = Instant::now();
+    let (status, json) = handle(&request.method, &request.target, &String::from_utf8_lossy(&request.body));
+    metrics.lock().unwrap().record(&request.target, status, start.elapsed());
+    write_response(stream, status, "application/json", &json.to_string())
+}
+
+fn write_response(mut stream: TcpStream, status: u16, content_type: &str, body: &str) -> io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        413 => "Payload Too Large",
+        431 => "Request Header Fields Too Large",
+        _ => "Unprocessable Entity",
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+/// Request statistics, exposed at `/metrics` in the Prometheus text format.
+#[derive(Debug, Default)]
+struct Metrics {
+    /// Requests by endpoint and status.
+    requests: BTreeMap<(&'static str, u16), u64>,
+    /// Durations by endpoint, as counts per bucket of `DURATION_BUCKETS`
+    /// followed by the overflow, and the sum of all durations in seconds.
+    durations: BTreeMap<&'static str, ([u64; DURATION_BUCKETS.len() + 1], f64)>,
+    /// Generated puzzles by difficulty.
+    generated: BTreeMap<&'static str, u64>,
+    /// Connections that took too long to send their request.
+    timeouts: u64,
+}
+
+impl Metrics {
+    fn record(&mut self, target: &str, status: u16, elapsed: Duration) {
+        // Only known endpoints get their own label so that clients can't
+        // blow up the number of series.
+        let endpoint = match target.split('?').next().unwrap_or_default() {
+            "/solve" => "/solve",
+            "/rate" => "/rate",
+            "/hint" => "/hint",
+            "/generate" => "/generate",
+            _ => "other",
+        };
+        *self.requests.entry((endpoint, status)).or_default() += 1;
+
+        let seconds = elapsed.as_secs_f64();
+        let (buckets, sum) = self.durations.entry(endpoint).or_default();
+        let bucket = DURATION_BUCKETS.iter().position(|&bound| seconds <= bound);
+        buckets[bucket.unwrap_or(DURATION_BUCKETS.len())] += 1;
+        *sum += seconds;
+
+        if endpoint == "/generate" && status == 200 {
+            let name = query_param(target, "difficulty").unwrap_or("medium");
+            if let Some(difficulty) = Difficulty::from_name(name) {
+                *self.generated.entry(difficulty.name()).or_default() += 1;
+            }
+        }
+    }
+}
+
+impl fmt::Display for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "# HELP sudoku_requests_total Requests by endpoint and status.")?;
+        writeln!(f, "# TYPE sudoku_requests_total counter")?;
+        for ((endpoint, status), count) in self.requests.iter() {
+            writeln!(f, "sudoku_requests_total{{endpoint=\"{}\",status=\"{}\"}} {}", endpoint, status, count)?;
+        }
+
+        writeln!(f, "# HELP sudoku_request_duration_seconds Time taken to answer requests.")?;
+        writeln!(f, "# TYPE sudoku_request_duration_seconds histogram")?;
+        for (endpoint, (buckets, sum)) in self.durations.iter() {
+            let mut cumulative = 0;
+            for (i, count) in buckets.iter().enumerate() {
+                cumulative += count;
+                let bound = DURATION_BUCKETS.get(i).map_or(String::from("+Inf"), f64::to_string);
+                writeln!(
+                    f,
+                    "sudoku_request_duration_seconds_bucket{{endpoint=\"{}\",le=\"{}\"}} {}",
+                    endpoint, bound, cumulative
+                )?;
+            }
+            writeln!(f, "sudoku_request_duration_seconds_sum{{endpoint=\"{}\"}} {}", endpoint, sum)?;
+            writeln!(f, "sudoku_request_duration_seconds_count{{endpoint=\"{}\"}} {}", endpoint, cumulative)?;
+        }
+
+        writeln!(f, "# HELP sudoku_generated_puzzles_total Generated puzzles by difficulty.")?;
+        writeln!(f, "# TYPE sudoku_generated_puzzles_total counter")?;
+        for (difficulty, count) in self.generated.iter() {
+            writeln!(f, "sudoku_generated_puzzles_total{{difficulty=\"{}\"}} {}", difficulty, count)?;
+        }
+
+        writeln!(f, "# HELP sudoku_request_timeouts_total Clients that took too long to send a request.")?;
+        writeln!(f, "# TYPE sudoku_request_timeouts_total counter")?;
+        writeln!(f, "sudoku_request_timeouts_total {}", self.timeouts)
+    }
+}
+
+fn error(message: &str) -> Json {
+    Json::object(vec![("error", Json::from(message))])
+}
+
+/// Returns the value of `name` in the query string of `target`, if any.
+fn query_param<'a>(target: &'a str, name: &str) -> Option<&'a str> {
+    let query = target.split_once('?')?.1;
+    query.split('&').find_map(|pair| match pair.split_once('=') {
+        Some((key, value)) if key == name => Some(value),
+        _ => None,
+    })
+}
+
+/// Answers a request with a status code and a JSON body.
+fn handle(method: &str, target: &str, body: &str) -> (u16, Json) {
+    let path = target.split('?').next().unwrap_or_default();
+    let expected_method = match path {
+        "/solve" | "/rate" | "/hint" => "POST",
+        "/generate" => "GET",
+        _ => return (404, error("not found")),
+    };
+    if method != expected_method {
+        return (405, error(&format!("expected {} {}", expected_method, path)));
+    }
+    if path == "/generate" {
+        return handle_generate(target);
+    }
+
+    let board: Board = match parse_board(body) {
+        Some(board) => board,
+        None => return (400, error("the body must be an 81 character puzzle")),
+    };
+    let config = TechniqueConfig::default();
+    match path {
+        "/solve" => match Sudoku::try_new(board).map(|mut sudoku| sudoku.solve()) {
+            Ok(Some(solution)) => {
+                (200, Json::object(vec![("solution", Json::from(board_to_string(&solution)))]))
+            }
+            Ok(None) => (422, error("no solution")),
+            Err(err) => (422, error(&err.to_string())),
+        },
+        "/rate" => match rate(&board, &config) {
+            Some(rating) => (200, rating.to_json()),
+            None => (422, error("no solution")),
+        },
+        _ => {
+            let level = match query_param(target, "level").unwrap_or("1").parse::<usize>() {
+                Ok(level) if (1..=HintLevel::ALL.len()).contains(&level) => HintLevel::ALL[level - 1],
+                _ => return (400, error("the hint level must be from 1 to 3")),
+            };
+            if let Err(err) = Sudoku::try_new(board) {
+                return (422, error(&err.to_string()));
+            }
+            match hint(&board, &config) {
+                Some(hint) => (200, Json::object(vec![("hint", Json::from(hint.text(level)))])),
+                None => (422, error("the puzzle is solved or has no solution")),
+            }
+        }
+    }
+}
+
+fn handle_generate(target: &str) -> (u16, Json) {
+    let difficulty = match Difficulty::from_name(query_param(target, "difficulty").unwrap_or("medium")) {
+        Some(difficulty) => difficulty,
+        None => return (400, error("unknown difficulty")),
+    };
+    let mut rng = match query_param(target, "seed").map(str::parse) {
+        Some(Ok(seed)) => Rng::new(seed),
+        Some(Err(_)) => return (400, error("the seed must be a number")),
+        None => Rng::from_time(),
+    };
+    let puzzle = generate(difficulty, &mut rng);
+    (200, Json::object(vec![("puzzle", Json::from(board_to_string(&puzzle)))]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::default_board;
+
+    #[test]
+    fn test_handle() {
+        let puzzle = board_to_string(&default_board());
+        let (status, json) = handle("POST", "/solve", &puzzle);
+        assert_eq!(status, 200);
+        assert!(json.to_string().starts_with(r#"{"solution":"365278149"#));
+
+        let (status, json) = handle("POST", "/hint?level=3", &puzzle);
+        assert_eq!((status, json.to_string()), (200, r#"{"hint":"r2c4 is 9"}"#.to_string()));
+        let (status, json) = handle("POST", "/hint", &format!("55{}", ".".repeat(79)));
+        assert_eq!(status, 422);
+        assert!(json.to_string().contains("the puzzle is invalid"));
+
+        assert_eq!(handle("GET", "/generate?difficulty=easy&seed=1", "").0, 200);
+        assert_eq!(handle("GET", "/generate?difficulty=silly", "").0, 400);
+        assert_eq!(handle("POST", "/rate", "123").0, 400);
+        assert_eq!(handle("GET", "/solve", &puzzle).0, 405);
+        assert_eq!(handle("GET", "/", "").0, 404);
+    }
+
+    #[test]
+    fn test_read_request() {
+        let request = read_request(&b"POST /solve HTTP/1.1\r\nContent-Length: 3\r\n\r\n123"[..]).unwrap().unwrap();
+        assert_eq!((request.method.as_str(), request.target.as_str()), ("POST", "/solve"));
+        assert_eq!(request.body, b"123");
+
+        // A line that never ends, too many headers, and too large a body are
+        // turned away without reading them in full.
+        let status = |request: &[u8]| read_request(request).unwrap().unwrap_err().0;
+        let endless = format!("GET / HTTP/1.1\r\nX: {}", "a".repeat(100_000));
+        assert_eq!(status(endless.as_bytes()), 431);
+        assert_eq!(status(format!("GET /{}", "a".repeat(100_000)).as_bytes()), 431);
+        let many = format!("GET / HTTP/1.1\r\n{}\r\n", "X: 1\r\n".repeat(MAX_HEADERS + 1));
+        assert_eq!(status(many.as_bytes()), 431);
+        assert_eq!(status(b"POST /solve HTTP/1.1\r\nContent-Length: 5000\r\n\r\n"), 413);
+    }
+
+    #[test]
+    fn test_ui() {
+        // The page only uses endpoints that exist.
+        for endpoint in ["`/generate?", "`/hint?", "\"/solve\""].iter() {
+            assert!(UI.contains(endpoint), "{}", endpoint);
+        }
+    }
+
+    #[test]
+    fn test_metrics() {
+        let mut metrics = Metrics::default();
+        metrics.record("/generate?difficulty=hard", 200, Duration::from_millis(20));
+        metrics.record("/solve", 400, Duration::from_micros(10));
+        metrics.record("/../etc", 404, Duration::from_secs(10));
+        let text = metrics.to_string();
+        assert!(text.contains("sudoku_requests_total{endpoint=\"/solve\",status=\"400\"} 1\n"));
+        assert!(text.contains("sudoku_requests_total{endpoint=\"other\",status=\"404\"} 1\n"));
+        assert!(text.contains(
+            "sudoku_request_duration_seconds_bucket{endpoint=\"/generate\",le=\"0.01\"} 0\n\
+             sudoku_request_duration_seconds_bucket{endpoint=\"/generate\",le=\"0.05\"} 1\n"
+        ));
+        assert!(text.contains("sudoku_request_duration_seconds_bucket{endpoint=\"other\",le=\"+Inf\"} 1\n"));
+        assert!(text.contains("sudoku_generated_puzzles_total{difficulty=\"hard\"} 1\n"));
+        assert!(text.ends_with("sudoku_request_timeouts_total 0\n"));
+    }
+}
@@ -0,0 +1,1016 @@
+use std::error::Error;
+use std::fmt;
+use std::mem;
+
+use crate::board::{Board, Cell, Origin};
+use crate::count::count_solutions;
+use crate::dlx::{self, DlxSolver};
+use crate::generate::Rng;
+use crate::grid::{digit_mask, mask_digits, Mask};
+use crate::observe::{NoObserver, SolveObserver};
+use crate::packed::Packed;
+use crate::sat::{self, SatSolver};
+use crate::validate::{validate, InvalidPuzzle};
+
+/// A puzzle to solve. Solving never changes the puzzle, so it can be solved
+/// again, e.g. with other options after a solve was cancelled.
+pub struct Sudoku {
+    board: Board,
+}
+
+/// How many solutions `Sudoku::solve_unique` counts at most.
+const COUNT_HINT_LIMIT: usize = 100;
+
+/// The solution of a puzzle, telling whether it's the only one.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Solution {
+    Unique(Board),
+    Multiple {
+        /// The solution `solve` would have returned.
+        first: Board,
+        /// How many solutions there are, up to 100. There may be many more.
+        count_hint: usize,
+    },
+}
+
+impl Solution {
+    /// Returns the solution, or the first of them.
+    pub fn board(&self) -> &Board {
+        match self {
+            Solution::Unique(board) | Solution::Multiple { first: board, .. } => board,
+        }
+    }
+
+    pub fn is_unique(&self) -> bool {
+        matches!(self, Solution::Unique(_))
+    }
+}
+
+impl Sudoku {
+    /// Takes `board` as is. Boards with conflicting givens have no solution,
+    /// use `try_new` to find out what the conflicts are.
+    pub fn new(board: Board) -> Sudoku {
+        Sudoku { board }
+    }
+
+    /// Checks the givens of `board` with `validate` before taking it.
+    pub fn try_new(board: Board) -> Result<Sudoku, InvalidPuzzle> {
+        validate(&board)?;
+        Ok(Sudoku { board })
+    }
+
+    /// If the board passed to the constructor is solvable, it returns a copy of
+    /// the solved board. If it's unsolvable, None is returned.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    pub fn solve(&mut self) -> Option<Board> {
+        self.solve_with_options(SolveOptions::default())
+    }
+
+    /// Like `solve`, but searches as set in `options`.
+    pub fn solve_with_options(&mut self, options: SolveOptions) -> Option<Board> {
+        self.solve_until(options, &|| false)
+    }
+
+    /// Like `solve_with_options`, but tells a search that would need more
+    /// memory than `options.max_memory_bytes` from one that found no
+    /// solution. `solve_with_options` returns None for either.
+    pub fn try_solve_with_options(&mut self, options: SolveOptions) -> Result<Option<Board>, MemoryLimitExceeded> {
+        options.check_memory(options.backend.memory_bytes())?;
+        Ok(self.solve_until(options, &|| false))
+    }
+
+    /// Like `solve`, but also finds out whether the solution is the only one,
+    /// which takes another search. Most puzzles are meant to have one, so
+    /// several usually mean a mistake in the puzzle.
+    pub fn solve_unique(&mut self) -> Option<Solution> {
+        self.solve_unique_with_options(SolveOptions::default())
+    }
+
+    /// Like `solve_unique`, but searches as set in `options`. The solutions
+    /// are counted the same way whatever the backend.
+    pub fn solve_unique_with_options(&mut self, options: SolveOptions) -> Option<Solution> {
+        let first = self.solve_with_options(options)?;
+        Some(match count_solutions(&self.board, COUNT_HINT_LIMIT) {
+            1 => Solution::Unique(first),
+            count_hint => Solution::Multiple { first, count_hint },
+        })
+    }
+
+    /// Like `solve`, but gives up and returns None once `cancelled` returns
+    /// true, which is checked between guesses.
+    /// Only the backtracking backend checks in between, the others only
+    /// before they start.
+    pub(crate) fn solve_until(&mut self, options: SolveOptions, cancelled: &dyn Fn() -> bool) -> Option<Board> {
+        options.check_memory(options.backend.memory_bytes()).ok()?;
+        let mut solution = Board::default();
+        let solved = match options.backend {
+            Backend::Backtracking => Solver::with_options(options).solve_until(&self.board, &mut solution, cancelled),
+            backend => !cancelled() && backend.solver(options).solve_into(&self.board, &mut solution),
+        };
+        solved.then_some(solution)
+    }
+}
+
+/// The order in which the search tries the candidates of a cell.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ValueOrder {
+    /// From the smallest digit to the largest.
+    Ascending,
+    /// The digit that's a candidate of the fewest peers first, so a guess
+    /// rules out as little as possible of the rest of the board.
+    LeastConstraining,
+}
+
+impl ValueOrder {
+    pub const ALL: [ValueOrder; 2] = [ValueOrder::Ascending, ValueOrder::LeastConstraining];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ValueOrder::Ascending => "ascending",
+            ValueOrder::LeastConstraining => "least_constraining",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ValueOrder> {
+        ValueOrder::ALL.iter().copied().find(|order| order.name() == name)
+    }
+}
+
+/// The order in which the search picks the cells to guess in.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum CellOrder {
+    /// The cell with the fewest candidates left, so dead ends are found
+    /// early and forced digits are placed without a choice.
+    FewestCandidates,
+    /// The first empty cell, row by row, which saves counting candidates
+    /// but branches much more on hard puzzles.
+    RowByRow,
+}
+
+impl CellOrder {
+    pub const ALL: [CellOrder; 2] = [CellOrder::FewestCandidates, CellOrder::RowByRow];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CellOrder::FewestCandidates => "fewest_candidates",
+            CellOrder::RowByRow => "row_by_row",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<CellOrder> {
+        CellOrder::ALL.iter().copied().find(|order| order.name() == name)
+    }
+}
+
+/// A search that solves puzzles, which can be reused for many of them.
+pub trait SolverBackend {
+    /// Solves `puzzle` and writes the solution into `solution`. Returns false,
+    /// leaving `solution` as it was, if the puzzle has no solution.
+    fn solve_into(&mut self, puzzle: &Board, solution: &mut Board) -> bool;
+}
+
+/// The searches puzzles can be solved with. They find the same solution of
+/// puzzles that have one, but differ in speed, memory, and what else they
+/// can do.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Backend {
+    /// `Solver`, the fastest on easy puzzles and the one that uses the least
+    /// memory. It's the only one that can be cancelled while it runs and
+    /// that tells the cells it deduced from the ones it guessed.
+    Backtracking,
+    /// `DlxSolver`, dancing links over the exact cover matrix, which
+    /// rebuilds a matrix of a few thousand nodes for every puzzle.
+    Dlx,
+    /// `SatSolver`, DPLL over the puzzle as boolean clauses, which keeps
+    /// about twelve thousand clauses around but is the fastest on hard
+    /// puzzles.
+    Sat,
+}
+
+impl Backend {
+    pub const ALL: [Backend; 3] = [Backend::Backtracking, Backend::Dlx, Backend::Sat];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Backtracking => "backtracking",
+            Backend::Dlx => "dlx",
+            Backend::Sat => "sat",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Backend> {
+        Backend::ALL.iter().copied().find(|backend| backend.name() == name)
+    }
+
+    /// Returns about how many bytes of memory a solve with this backend
+    /// needs at most, counting the solver itself and what it allocates.
+    pub fn memory_bytes(self) -> usize {
+        match self {
+            Backend::Backtracking => mem::size_of::<Solver>(),
+            Backend::Dlx => dlx::MEMORY_BYTES,
+            Backend::Sat => mem::size_of::<SatSolver>() + sat::MEMORY_BYTES,
+        }
+    }
+
+    /// Returns a new solver of this backend. Only backtracking uses the rest
+    /// of `options`.
+    pub fn solver(self, options: SolveOptions) -> Box<dyn SolverBackend + Send> {
+        match self {
+            Backend::Backtracking => Box::new(Solver::with_options(options)),
+            Backend::Dlx => Box::new(DlxSolver::new()),
+            Backend::Sat => Box::new(SatSolver::new()),
+        }
+    }
+}
+
+/// How puzzles are solved. The options only change how fast a solution is
+/// found, and for puzzles with several, which one.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct SolveOptions {
+    pub cell_order: CellOrder,
+    pub value_order: ValueOrder,
+    /// Restart the search every so many guesses, times the Luby sequence 1,
+    /// 1, 2, 1, 1, 2, 4, ..., with the ties of the cell and value order
+    /// broken at random, or None to never restart. A few pathological
+    /// puzzles take a search far longer in one order than in most others,
+    /// which restarts cut short.
+    pub restarts: Option<u64>,
+    /// The backend `Sudoku` solves with. `Solver` always backtracks.
+    pub backend: Backend,
+    /// The most memory a search may need, in bytes, or None for no limit.
+    /// Searches check the most they could need before they start, rather
+    /// than run out halfway.
+    pub max_memory_bytes: Option<usize>,
+}
+
+impl Default for SolveOptions {
+    fn default() -> SolveOptions {
+        SolveOptions {
+            cell_order: CellOrder::FewestCandidates,
+            value_order: ValueOrder::Ascending,
+            restarts: None,
+            backend: Backend::Backtracking,
+            max_memory_bytes: None,
+        }
+    }
+}
+
+impl SolveOptions {
+    pub fn cell_order(self, cell_order: CellOrder) -> SolveOptions {
+        SolveOptions { cell_order, ..self }
+    }
+
+    pub fn value_order(self, value_order: ValueOrder) -> SolveOptions {
+        SolveOptions { value_order, ..self }
+    }
+
+    /// Restarts the search after `unit` guesses, then `unit` again, then
+    /// twice as many, and so on.
+    pub fn restarts(self, unit: u64) -> SolveOptions {
+        SolveOptions { restarts: Some(unit.max(1)), ..self }
+    }
+
+    pub fn backend(self, backend: Backend) -> SolveOptions {
+        SolveOptions { backend, ..self }
+    }
+
+    pub fn max_memory_bytes(self, bytes: usize) -> SolveOptions {
+        SolveOptions { max_memory_bytes: Some(bytes), ..self }
+    }
+
+    /// Returns an error if a search that needs up to `needed` bytes would go
+    /// over the memory limit.
+    pub fn check_memory(&self, needed: usize) -> Result<(), MemoryLimitExceeded> {
+        match self.max_memory_bytes {
+            Some(limit) if needed > limit => Err(MemoryLimitExceeded { needed, limit }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The error of a search that would need more memory than
+/// `SolveOptions::max_memory_bytes` allows.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct MemoryLimitExceeded {
+    /// About how many bytes the search could need.
+    pub needed: usize,
+    pub limit: usize,
+}
+
+impl fmt::Display for MemoryLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the search may need {} bytes of memory, more than the limit of {}", self.needed, self.limit)
+    }
+}
+
+impl Error for MemoryLimitExceeded {}
+
+/// Writes the digits a backend other than backtracking found into
+/// `solution`. The givens of `puzzle` are kept and the rest marked as
+/// guessed, as those backends don't tell deductions from guesses.
+pub(crate) fn write_solution(puzzle: &Board, digits: &[u8; 81], solution: &mut Board) {
+    for (index, &digit) in digits.iter().enumerate() {
+        let (row, col) = (index / 9, index % 9);
+        solution[row][col] = match puzzle[row][col].solution {
+            Some(_) => puzzle[row][col],
+            None => Cell::with_origin(digit as i8, Origin::Guessed),
+        };
+    }
+}
+
+/// What the last solve of a `Solver` took, counting the runs it restarted.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct SolveStats {
+    pub guesses: u64,
+    pub backtracks: u64,
+    pub restarts: u64,
+}
+
+/// A solver that keeps its scratch state between puzzles, for solving
+/// batches. Unlike `Sudoku`, it doesn't take ownership of the puzzles and
+/// writes the solutions into boards the caller owns, so a batch loop
+/// doesn't allocate or copy more than it has to.
+#[derive(Debug, Clone)]
+pub struct Solver {
+    packed: Packed,
+    /// The indices of the cells the search guesses in, in its first
+    /// `num_unsolved` entries.
+    unsolved_cells: [u8; 81],
+    num_unsolved: usize,
+    /// The candidates not guessed yet in each cell guessed in so far.
+    untried: [Mask; 81],
+    /// How many candidates each cell guessed in so far had to begin with.
+    branches: [u8; 81],
+    /// The order of the digits after a restart, which shuffles it.
+    digit_order: [u8; 9],
+    /// The position of the search in `unsolved_cells`, kept between steps.
+    cell_idx: usize,
+    /// Whether the search just moved on to `cell_idx` rather than
+    /// backtracked to it, in which case the cell to guess in is picked.
+    advanced: bool,
+    /// The board as it was before the first guess in each cell, which
+    /// taking the guesses back has to restore.
+    #[cfg(feature = "debug-invariants")]
+    trail: Vec<Packed>,
+    stepping: Stepping,
+    options: SolveOptions,
+    stats: SolveStats,
+}
+
+/// How many guesses `Solver::step` makes at most.
+pub const STEP_GUESSES: u64 = 1000;
+
+/// Where a solve run with `Solver::step` is.
+#[derive(Debug, Clone, Copy)]
+enum Stepping {
+    Idle,
+    Searching(Board),
+    Finished(Option<Board>),
+}
+
+/// What a call of `Solver::step` came to.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum StepOutcome {
+    /// The search isn't finished yet.
+    Pending,
+    Solved(Box<Board>),
+    NoSolution,
+}
+
+impl Default for Solver {
+    fn default() -> Solver {
+        Solver::new()
+    }
+}
+
+impl Solver {
+    pub fn new() -> Solver {
+        Solver::with_options(SolveOptions::default())
+    }
+
+    pub fn with_options(options: SolveOptions) -> Solver {
+        Solver {
+            packed: Packed::new(),
+            unsolved_cells: [0; 81],
+            num_unsolved: 0,
+            untried: [0; 81],
+            branches: [0; 81],
+            digit_order: [1, 2, 3, 4, 5, 6, 7, 8, 9],
+            cell_idx: 0,
+            advanced: true,
+            #[cfg(feature = "debug-invariants")]
+            trail: Vec::new(),
+            stepping: Stepping::Idle,
+            options,
+            stats: SolveStats::default(),
+        }
+    }
+
+    /// Returns what the last solve took. A solve run with `step` doesn't
+    /// restart.
+    pub fn stats(&self) -> SolveStats {
+        self.stats
+    }
+
+    /// Solves `puzzle` and writes the solution into `solution`. Returns false,
+    /// leaving `solution` as it was, if the puzzle has no solution.
+    pub fn solve_into(&mut self, puzzle: &Board, solution: &mut Board) -> bool {
+        self.solve_until(puzzle, solution, &|| false)
+    }
+
+    /// Like `solve_into`, but tells `observer` about every placement, guess,
+    /// and backtrack as it happens.
+    pub fn solve_observed<O: SolveObserver + ?Sized>(
+        &mut self,
+        puzzle: &Board,
+        solution: &mut Board,
+        observer: &mut O,
+    ) -> bool {
+        self.search(puzzle, solution, &|| false, observer, &mut Progress::none())
+    }
+
+    /// Like `solve_into`, but calls `report` every `every` guesses, or never if
+    /// `every` is 0, with an estimate of the fraction of the search done, from
+    /// 0 to 1, e.g. to show a progress bar for very hard puzzles. The
+    /// estimate assumes that the guesses left in a cell lead to as much
+    /// search as the ones tried, so it's rough and may jump ahead once the
+    /// right guess is found.
+    pub fn solve_with_progress(
+        &mut self,
+        puzzle: &Board,
+        solution: &mut Board,
+        every: u64,
+        report: &mut dyn FnMut(f64),
+    ) -> bool {
+        let mut progress = Progress { every, left: every, report: Some(report).filter(|_| every > 0) };
+        self.search(puzzle, solution, &|| false, &mut NoObserver, &mut progress)
+    }
+
+    fn solve_until(&mut self, puzzle: &Board, solution: &mut Board, cancelled: &dyn Fn() -> bool) -> bool {
+        self.search(puzzle, solution, cancelled, &mut NoObserver, &mut Progress::none())
+    }
+
+    /// Starts solving `puzzle` a bit at a time with `step`, dropping any solve
+    /// that was in progress.
+    pub fn start(&mut self, puzzle: &Board) {
+        self.stats = SolveStats::default();
+        self.stepping = if self.begin(puzzle, &mut NoObserver) {
+            Stepping::Searching(*puzzle)
+        } else {
+            Stepping::Finished(None)
+        };
+    }
+
+    /// Goes on with the solve begun with `start` for at most `STEP_GUESSES`
+    /// guesses, which takes about a millisecond at most, so that single
+    /// threaded environments like the browser can solve hard puzzles between
+    /// other work. Once the solve is finished, it keeps returning its outcome.
+    /// Without a solve begun there's nothing to solve, which is reported as
+    /// `StepOutcome::NoSolution`.
+    pub fn step(&mut self) -> StepOutcome {
+        self.step_by(STEP_GUESSES)
+    }
+
+    /// Like `step`, but for at most `guesses` guesses.
+    pub fn step_by(&mut self, guesses: u64) -> StepOutcome {
+        if let Stepping::Searching(puzzle) = self.stepping {
+            let mut budget = guesses;
+            match self.resume(&mut budget, &|| false, &mut NoObserver, &mut Progress::none()) {
+                None => return StepOutcome::Pending,
+                Some(true) => {
+                    let mut solution = Board::default();
+                    self.finish(&puzzle, &mut solution);
+                    self.stepping = Stepping::Finished(Some(solution));
+                }
+                Some(false) => self.stepping = Stepping::Finished(None),
+            }
+        }
+        match self.stepping {
+            Stepping::Finished(Some(solution)) => StepOutcome::Solved(Box::new(solution)),
+            _ => StepOutcome::NoSolution,
+        }
+    }
+
+    fn search<O: SolveObserver + ?Sized>(
+        &mut self,
+        puzzle: &Board,
+        solution: &mut Board,
+        cancelled: &dyn Fn() -> bool,
+        observer: &mut O,
+        progress: &mut Progress,
+    ) -> bool {
+        self.stepping = Stepping::Idle;
+        self.stats = SolveStats::default();
+        let solved = match self.options.restarts {
+            None => {
+                let mut budget = u64::MAX;
+                self.begin(puzzle, observer) && self.resume(&mut budget, cancelled, observer, progress) == Some(true)
+            }
+            Some(unit) => loop {
+                if !self.begin(puzzle, observer) {
+                    break false;
+                }
+                if self.stats.restarts > 0 {
+                    // Picking the cell with the fewest candidates takes the
+                    // first of those tied, so shuffling the cells breaks ties
+                    // at random. Row by row there are no ties to break.
+                    let mut rng = Rng::new(self.stats.restarts);
+                    if self.options.cell_order == CellOrder::FewestCandidates {
+                        rng.shuffle(&mut self.unsolved_cells[..self.num_unsolved]);
+                    }
+                    rng.shuffle(&mut self.digit_order);
+                }
+                let mut budget = unit.saturating_mul(luby(self.stats.restarts + 1));
+                match self.resume(&mut budget, cancelled, observer, progress) {
+                    Some(solved) => break solved,
+                    None => {
+                        event!(DEBUG, restarts = self.stats.restarts + 1, "restart");
+                        self.stats.restarts += 1;
+                    }
+                }
+            },
+        };
+        if !solved {
+            return false;
+        }
+        self.finish(puzzle, solution);
+        true
+    }
+
+    /// Sets up the search of `puzzle`. Returns false if its givens conflict.
+    fn begin<O: SolveObserver + ?Sized>(&mut self, puzzle: &Board, observer: &mut O) -> bool {
+        // The search works on the packed board and only the solution is
+        // turned back into a `Board`.
+        self.packed = match Packed::from_board(puzzle) {
+            Some(packed) => packed,
+            None => {
+                event!(DEBUG, "no solution");
+                return false;
+            }
+        };
+        place_single_candidates(&mut self.packed, observer);
+        self.num_unsolved = 0;
+        for index in 0..81 {
+            if self.packed.cells[index] == 0 {
+                self.unsolved_cells[self.num_unsolved] = index as u8;
+                self.num_unsolved += 1;
+            }
+        }
+        self.cell_idx = 0;
+        self.advanced = true;
+        invariant!(self.trail.clear());
+        true
+    }
+
+    /// Writes the solution the search found into `solution`.
+    fn finish(&self, puzzle: &Board, solution: &mut Board) {
+        self.packed.write_to(solution);
+        // The givens keep their origin, and the cells the search didn't
+        // guess in were single candidates.
+        for index in 0..81 {
+            let (row, col) = (index / 9, index % 9);
+            if puzzle[row][col].solution.is_some() {
+                solution[row][col] = puzzle[row][col];
+            } else {
+                solution[row][col].origin = Origin::Deduced;
+            }
+        }
+        for &index in self.unsolved_cells[..self.num_unsolved].iter() {
+            solution[index as usize / 9][index as usize % 9].origin = Origin::Guessed;
+        }
+    }
+
+    /// A brute-force, backtracking algorithm that guesses digits for the
+    /// empty cells one at a time, going on with the cell the cell order picks,
+    /// and backtracks once a cell has no candidates left. It goes
+    /// on from where it was left until `budget` guesses are made, and returns
+    /// None if that's before it's finished, or else whether the board was
+    /// solved.
+    fn resume<O: SolveObserver + ?Sized>(
+        &mut self,
+        budget: &mut u64,
+        cancelled: &dyn Fn() -> bool,
+        observer: &mut O,
+        progress: &mut Progress,
+    ) -> Option<bool> {
+        let packed = &mut self.packed;
+        let cells = &mut self.unsolved_cells[..self.num_unsolved];
+        let mut cell_idx = self.cell_idx;
+        let mut advanced = self.advanced;
+        while cell_idx < cells.len() {
+            if cancelled() {
+                return Some(false);
+            }
+            if *budget == 0 {
+                self.cell_idx = cell_idx;
+                self.advanced = advanced;
+                return None;
+            }
+            if advanced {
+                // The cells guessed in so far stay in order before `cell_idx`,
+                // so backtracking finds them again.
+                if self.options.cell_order == CellOrder::FewestCandidates {
+                    let fewest = cell_idx + packed.fewest_candidates(&cells[cell_idx..]);
+                    cells.swap(cell_idx, fewest);
+                }
+                self.untried[cell_idx] = packed.candidates(cells[cell_idx] as usize);
+                self.branches[cell_idx] = self.untried[cell_idx].count_ones() as u8;
+                invariant!(self.trail.truncate(cell_idx));
+                invariant!(self.trail.push(*packed));
+            }
+            let index = cells[cell_idx] as usize;
+            // Take back the digit guessed here before, if we've backtracked,
+            // and go on with the next one.
+            let previous = packed.cells[index];
+            if previous != 0 {
+                packed.unplace(index, previous);
+                self.stats.backtracks += 1;
+                observer.on_backtrack(index / 9, index % 9);
+                invariant!(assert_eq!(*packed, self.trail[cell_idx], "backtracking didn't restore the board"));
+            }
+            let untried = self.untried[cell_idx];
+            let next = if self.stats.restarts == 0 {
+                match self.options.value_order {
+                    ValueOrder::Ascending => mask_digits(untried).next(),
+                    ValueOrder::LeastConstraining => {
+                        mask_digits(untried).min_by_key(|&digit| packed.eliminations(index, digit as u8))
+                    }
+                }
+            } else {
+                // The same, but going by the shuffled order of the digits.
+                let mut digits =
+                    self.digit_order.iter().map(|&digit| digit as i8).filter(|&digit| untried & digit_mask(digit) != 0);
+                match self.options.value_order {
+                    ValueOrder::Ascending => digits.next(),
+                    ValueOrder::LeastConstraining => {
+                        digits.min_by_key(|&digit| packed.eliminations(index, digit as u8))
+                    }
+                }
+            };
+            match next {
+                Some(digit) => {
+                    self.untried[cell_idx] &= !digit_mask(digit);
+                    event!(DEBUG, row = index / 9, col = index % 9, digit, "guess");
+                    packed.place(index, digit as u8);
+                    observer.on_guess(index / 9, index % 9, digit);
+                    cell_idx += 1;
+                    advanced = true;
+                    *budget -= 1;
+                    self.stats.guesses += 1;
+                    if let Some(report) = progress.report.as_mut() {
+                        progress.left -= 1;
+                        if progress.left == 0 {
+                            progress.left = progress.every;
+                            report(explored(&self.untried[..cell_idx], &self.branches[..cell_idx]));
+                        }
+                    }
+                }
+                None => {
+                    // If we're back at the first cell after not finding any
+                    // candidates, it means there is no solution.
+                    if cell_idx == 0 {
+                        event!(DEBUG, "no solution");
+                        return Some(false);
+                    }
+                    event!(TRACE, row = index / 9, col = index % 9, "backtrack");
+                    cell_idx -= 1;
+                    advanced = false;
+                }
+            }
+        }
+        Some(true)
+    }
+}
+
+/// Where a search reports its progress, and how often.
+struct Progress<'a> {
+    /// Every how many guesses to report.
+    every: u64,
+    /// The guesses left until the next report.
+    left: u64,
+    /// None if progress isn't reported.
+    report: Option<&'a mut dyn FnMut(f64)>,
+}
+
+impl Progress<'_> {
+    fn none() -> Progress<'static> {
+        Progress { every: 0, left: 0, report: None }
+    }
+}
+
+/// Estimates the fraction of a search done from how many of the candidates
+/// of each cell guessed in have been tried, taking the branches of a cell to
+/// be equally large. The digit in each cell is being tried, so it's not done.
+fn explored(untried: &[Mask], branches: &[u8]) -> f64 {
+    let mut fraction = 0.0;
+    let mut share = 1.0;
+    for (untried, &branches) in untried.iter().zip(branches.iter()) {
+        let branches = branches as f64;
+        let done = branches - untried.count_ones() as f64 - 1.0;
+        fraction += share * done / branches;
+        share /= branches;
+    }
+    fraction
+}
+
+impl SolverBackend for Solver {
+    fn solve_into(&mut self, puzzle: &Board, solution: &mut Board) -> bool {
+        Solver::solve_into(self, puzzle, solution)
+    }
+}
+
+/// Returns the `i`th number of the Luby sequence, from 1: 1, 1, 2, 1, 1, 2,
+/// 4, 1, 1, 2, ..., the restart schedule that wastes the least on puzzles
+/// nothing is known about.
+fn luby(mut i: u64) -> u64 {
+    loop {
+        // The sequence up to the `2^k - 1`th number ends with `2^(k - 1)`
+        // and repeats the one before it twice.
+        let mut k = 1;
+        while (1 << k) - 1 < i {
+            k += 1;
+        }
+        if i == (1 << k) - 1 {
+            return 1 << (k - 1);
+        }
+        i -= (1 << (k - 1)) - 1;
+    }
+}
+
+/// Narrows down the search-space by placing the digits of cells that only
+/// have a single candidate, which in turn removes that candidate from the
+/// cells that come after them.
+fn place_single_candidates<O: SolveObserver + ?Sized>(packed: &mut Packed, observer: &mut O) {
+    for index in 0..81 {
+        if packed.cells[index] != 0 {
+            continue;
+        }
+        let candidates = packed.candidates(index);
+        if candidates.count_ones() == 1 {
+            let solution = mask_digits(candidates).next().unwrap();
+            event!(TRACE, row = index / 9, col = index % 9, solution, "single candidate");
+            packed.place(index, solution as u8);
+            observer.on_placement(index / 9, index % 9, solution);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{board_to_string, default_board, parse_board, Cell};
+    use crate::logic::{solve_observed, TechniqueConfig};
+    use crate::variant::Variant;
+
+    #[test]
+    fn test_solve_into() {
+        let mut solver = Solver::new();
+        let mut solution = Board::default();
+        let mut broken = default_board();
+        broken[0][0] = Cell::solved(5);
+        assert!(Sudoku::try_new(broken).is_err());
+        assert!(!solver.solve_into(&broken, &mut solution));
+        let mut out_of_range = default_board();
+        out_of_range[0][0] = Cell::solved(0);
+        assert!(!solver.solve_into(&out_of_range, &mut solution));
+        assert_eq!(solution, Board::default());
+
+        assert!(solver.solve_into(&default_board(), &mut solution));
+        let expected = Sudoku::new(default_board()).solve().unwrap();
+        assert_eq!(board_to_string(&solution), board_to_string(&expected));
+
+        let solution = Sudoku::new(default_board()).solve_unique().unwrap();
+        assert_eq!(solution, Solution::Unique(expected));
+        let mut puzzle = default_board();
+        puzzle[0][2] = Cell::unsolved();
+        puzzle[0][5] = Cell::unsolved();
+        puzzle[1][1] = Cell::unsolved();
+        let solution = Sudoku::new(puzzle).solve_unique().unwrap();
+        assert!(!solution.is_unique());
+        assert_eq!(Some(*solution.board()), Sudoku::new(puzzle).solve());
+
+        let options = SolveOptions::default().value_order(ValueOrder::LeastConstraining);
+        let mut solution = Board::default();
+        assert!(Solver::with_options(options).solve_into(&default_board(), &mut solution));
+        assert_eq!(board_to_string(&solution), board_to_string(&expected));
+    }
+
+    #[test]
+    fn test_failed_solves_are_side_effect_free() {
+        // r1c9 can't hold anything: its row has 1 to 8 and its column a 9.
+        let mut board = Board::default();
+        for col in 0..8 {
+            board[0][col] = Cell::solved(col as i8 + 1);
+        }
+        board[1][8] = Cell::solved(9);
+        let mut sudoku = Sudoku::new(board);
+        assert_eq!(sudoku.solve(), None);
+        assert_eq!(sudoku.board, board);
+
+        // A cancelled solve can be retried, with other options too.
+        let mut sudoku = Sudoku::new(default_board());
+        let expected = sudoku.solve().unwrap();
+        assert_eq!(sudoku.solve_until(SolveOptions::default(), &|| true), None);
+        assert_eq!(sudoku.board, default_board());
+        let options = SolveOptions::default().value_order(ValueOrder::LeastConstraining);
+        assert_eq!(sudoku.solve_with_options(options), Some(expected));
+
+        let mut solver = Solver::new();
+        let mut solution = expected;
+        assert!(!solver.solve_until(&board, &mut solution, &|| false));
+        assert!(!solver.solve_until(&default_board(), &mut solution, &|| true));
+        assert_eq!(solution, expected);
+        let mut solution = Board::default();
+        assert!(solver.solve_into(&default_board(), &mut solution));
+        assert_eq!(solution, expected);
+    }
+
+    #[test]
+    fn test_origins() {
+        let mut puzzle = default_board();
+        puzzle[0][2] = Cell::with_origin(5, Origin::UserEntered);
+        let solution = Sudoku::new(puzzle).solve().unwrap();
+        assert_eq!(solution[0][2].origin(), Some(Origin::UserEntered));
+        assert_eq!(solution[0][5].origin(), Some(Origin::Given));
+        // 9 is the only candidate of r2c4 from the start.
+        assert_eq!(solution[1][3].origin(), Some(Origin::Deduced));
+        let guessed = solution.iter().flatten().filter(|cell| cell.origin() == Some(Origin::Guessed)).count();
+        assert!(guessed > 0);
+    }
+
+    /// Counts the events of a solve.
+    #[derive(Default)]
+    struct Counter {
+        placements: usize,
+        guesses: usize,
+        backtracks: usize,
+    }
+
+    impl SolveObserver for Counter {
+        fn on_placement(&mut self, _: usize, _: usize, _: i8) {
+            self.placements += 1;
+        }
+
+        fn on_guess(&mut self, _: usize, _: usize, _: i8) {
+            self.guesses += 1;
+        }
+
+        fn on_backtrack(&mut self, _: usize, _: usize) {
+            self.backtracks += 1;
+        }
+    }
+
+    #[test]
+    fn test_solve_observed() {
+        let mut counter = Counter::default();
+        let mut solution = Board::default();
+        assert!(Solver::new().solve_observed(&default_board(), &mut solution, &mut counter));
+        let empty = default_board().iter().flatten().filter(|cell| cell.solution.is_none()).count();
+        // Every empty cell ends up with a digit, and every wrong guess is
+        // taken back.
+        assert_eq!(counter.placements + counter.guesses - counter.backtracks, empty);
+        assert!(counter.guesses > 0);
+
+        let mut counter = Counter::default();
+        let trace = solve_observed(&default_board(), &TechniqueConfig::default(), &Variant::default(), &mut counter);
+        assert!(trace.solution.is_some());
+        assert_eq!(counter.guesses, trace.guesses);
+    }
+
+    #[test]
+    fn test_solve_with_progress() {
+        let hard =
+            parse_board("8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..").unwrap();
+        let mut reports = Vec::new();
+        let mut solution = Board::default();
+        assert!(Solver::new().solve_with_progress(&hard, &mut solution, 10, &mut |fraction| reports.push(fraction)));
+        assert!(!reports.is_empty());
+        assert!(reports.iter().all(|&fraction| (0.0..1.0).contains(&fraction)));
+        assert!(reports.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(explored(&[0b11, 0], &[4, 2]), 0.25 + 0.25 * 0.5);
+    }
+
+    #[test]
+    fn test_step() {
+        let hard =
+            parse_board("8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..").unwrap();
+        let expected = Sudoku::new(hard).solve().unwrap();
+        let mut solver = Solver::new();
+        assert_eq!(solver.step(), StepOutcome::NoSolution);
+        solver.start(&hard);
+        let mut steps = 1;
+        let mut outcome = solver.step_by(10);
+        while outcome == StepOutcome::Pending {
+            outcome = solver.step_by(10);
+            steps += 1;
+        }
+        assert!(steps > 1);
+        assert_eq!(outcome, StepOutcome::Solved(Box::new(expected)));
+        assert_eq!(solver.step(), outcome);
+
+        let mut broken = default_board();
+        broken[0][0] = Cell::solved(5);
+        solver.start(&broken);
+        assert_eq!(solver.step(), StepOutcome::NoSolution);
+    }
+
+    #[test]
+    fn test_backends() {
+        let expected = Sudoku::new(default_board()).solve().unwrap();
+        let mut broken = default_board();
+        broken[0][0] = Cell::solved(5);
+        for &backend in Backend::ALL.iter() {
+            assert_eq!(Backend::from_name(backend.name()), Some(backend));
+            let options = SolveOptions::default().backend(backend);
+            let solution = Sudoku::new(default_board()).solve_with_options(options).unwrap();
+            assert_eq!(board_to_string(&solution), board_to_string(&expected));
+            assert_eq!(solution[0][2].origin(), Some(Origin::Given));
+            assert_eq!(Sudoku::new(broken).solve_with_options(options), None);
+        }
+        let solution = Sudoku::new(default_board()).solve_with_options(SolveOptions::default().backend(Backend::Dlx));
+        assert_eq!(solution.unwrap()[1][3].origin(), Some(Origin::Guessed));
+
+        for &backend in Backend::ALL.iter() {
+            let needed = backend.memory_bytes();
+            let options = SolveOptions::default().backend(backend).max_memory_bytes(needed);
+            let solution = Sudoku::new(default_board()).try_solve_with_options(options).unwrap().unwrap();
+            assert_eq!(board_to_string(&solution), board_to_string(&expected));
+            let options = options.max_memory_bytes(needed - 1);
+            let err = Sudoku::new(default_board()).try_solve_with_options(options).unwrap_err();
+            assert_eq!(err, MemoryLimitExceeded { needed, limit: needed - 1 });
+            assert_eq!(Sudoku::new(default_board()).solve_with_options(options), None);
+        }
+        assert!(Backend::Dlx.memory_bytes() > Backend::Backtracking.memory_bytes());
+    }
+
+    #[test]
+    fn test_restarts() {
+        assert_eq!((1..=15).map(luby).collect::<Vec<_>>(), vec![1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8]);
+
+        let puzzle =
+            parse_board("8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..").unwrap();
+        let mut expected = Board::default();
+        let mut solver = Solver::new();
+        assert!(solver.solve_into(&puzzle, &mut expected));
+        assert_eq!(solver.stats().restarts, 0);
+        for value_order in ValueOrder::ALL.iter() {
+            let options = SolveOptions::default().value_order(*value_order).restarts(10);
+            let mut solver = Solver::with_options(options);
+            let mut solution = Board::default();
+            assert!(solver.solve_into(&puzzle, &mut solution));
+            assert_eq!(board_to_string(&solution), board_to_string(&expected));
+            let stats = solver.stats();
+            assert!(stats.restarts > 0);
+            assert!(stats.guesses >= 10 * stats.restarts);
+        }
+
+        // A puzzle without a solution is still found out: the solution has
+        // a 1 in r1c2, so a 2 there leaves none.
+        assert_eq!(expected[0][1].solution, Some(1));
+        let mut board = puzzle;
+        board[0][1] = Cell::solved(2);
+        let mut solver = Solver::with_options(SolveOptions::default().restarts(1));
+        assert!(!solver.solve_into(&board, &mut Board::default()));
+    }
+
+    #[test]
+    fn test_solver() {
+        let board = default_board();
+        let mut sudoku = Sudoku::new(board);
+        if let Some(solved_board) = sudoku.solve() {
+            for row in 0..9 {
+                for col in 0..9 {
+                    let solution = solved_board[row][col].solution;
+
+                    // Check that this cell's solution is unique in its block.
+                    let block_row_start = (row / 3) * 3;
+                    let block_col_start = (col / 3) * 3;
+                    for block_row in block_row_start..block_row_start + 3 {
+                        for block_col in block_col_start..block_col_start + 3 {
+                            if block_row == row && block_col == col {
+                                continue;
+                            }
+                            assert_ne!(solution, solved_board[block_row][block_col].solution);
+                        }
+                    }
+
+                    // Verify that solution is unique in its row.
+                    for other_col in 0..9 {
+                        if other_col != col {
+                            assert_ne!(solution, solved_board[row][other_col].solution);
+                        }
+                    }
+
+                    // Verify that solution is unique in its column.
+                    for other_row in 0..9 {
+                        if other_row != row {
+                            assert_ne!(solution, solved_board[other_row][col].solution);
+                        }
+                    }
+                }
+            }
+        } else {
+            panic!("no solution found");
+        }
+    }
+}
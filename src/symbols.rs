@@ -0,0 +1,145 @@
+//! Writing the digits as other symbols, for puzzle styles that use letters
+//! or pictures instead of numbers. The solver only ever sees the digits 1 to
+//! 9, so a symbol set is just a mapping applied when reading and printing.
+
+use crate::board::{Board, Cell, ParseError};
+
+/// The symbols for the digits 1 to 9 and for empty cells. Symbols may be any
+/// strings without whitespace, e.g. emoji made of several code points, as
+/// long as none is the start of another, so that a puzzle reads only one
+/// way.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SymbolSet {
+    symbols: Vec<String>,
+    empty: String,
+}
+
+impl Default for SymbolSet {
+    fn default() -> SymbolSet {
+        SymbolSet::digits()
+    }
+}
+
+impl SymbolSet {
+    /// The names of the built-in sets, for `from_name`.
+    pub const NAMES: [&'static str; 3] = ["digits", "letters", "emoji"];
+
+    /// Returns the set of `symbols` for the digits 1 to 9 and `empty` for
+    /// empty cells, or None if they can't be told apart when reading.
+    pub fn new(symbols: [&str; 9], empty: &str) -> Option<SymbolSet> {
+        let all: Vec<&str> = symbols.iter().copied().chain(Some(empty)).collect();
+        for (i, a) in all.iter().enumerate() {
+            if a.is_empty() || a.contains(char::is_whitespace) {
+                return None;
+            }
+            if all[i + 1..].iter().any(|b| a.starts_with(b) || b.starts_with(a)) {
+                return None;
+            }
+        }
+        Some(SymbolSet { symbols: symbols.iter().map(|s| s.to_string()).collect(), empty: empty.to_string() })
+    }
+
+    /// The usual digits, with `.` for empty cells.
+    pub fn digits() -> SymbolSet {
+        SymbolSet::new(["1", "2", "3", "4", "5", "6", "7", "8", "9"], ".").unwrap()
+    }
+
+    /// The letters A to I, as in some newspapers' "wordoku".
+    pub fn letters() -> SymbolSet {
+        SymbolSet::new(["A", "B", "C", "D", "E", "F", "G", "H", "I"], ".").unwrap()
+    }
+
+    /// Fruit, for children's puzzles.
+    pub fn emoji() -> SymbolSet {
+        SymbolSet::new(["🍎", "🍊", "🍋", "🍐", "🍇", "🍓", "🍒", "🍑", "🥝"], "⬜").unwrap()
+    }
+
+    /// Returns the built-in set called `name`, one of `NAMES`.
+    pub fn from_name(name: &str) -> Option<SymbolSet> {
+        match name {
+            "digits" => Some(SymbolSet::digits()),
+            "letters" => Some(SymbolSet::letters()),
+            "emoji" => Some(SymbolSet::emoji()),
+            _ => None,
+        }
+    }
+
+    /// Returns the symbol of `digit`, from 1 to 9.
+    pub fn symbol(&self, digit: i8) -> &str {
+        &self.symbols[digit as usize - 1]
+    }
+
+    pub fn empty(&self) -> &str {
+        &self.empty
+    }
+
+    /// Returns the symbol of a cell, or of an empty one.
+    pub fn cell(&self, cell: &Cell) -> &str {
+        cell.solution.map_or(self.empty(), |digit| self.symbol(digit))
+    }
+
+    /// Parses a board written with these symbols, cell by cell, row by row,
+    /// like `parse_board` parses digits. Whitespace is ignored.
+    pub fn parse(&self, s: &str) -> Result<Board, ParseError> {
+        let mut board = Board::default();
+        let mut num_cells = 0;
+        let mut offset = 0;
+        while offset < s.len() {
+            let rest = &s[offset..];
+            let c = rest.chars().next().unwrap();
+            if c.is_whitespace() {
+                offset += c.len_utf8();
+                continue;
+            }
+            let digit = if rest.starts_with(self.empty.as_str()) {
+                None
+            } else {
+                match self.symbols.iter().position(|symbol| rest.starts_with(symbol.as_str())) {
+                    Some(i) => Some(i as i8 + 1),
+                    None => return Err(ParseError { offset, message: format!("unexpected {:?}", c) }),
+                }
+            };
+            if num_cells == 81 {
+                return Err(ParseError { offset, message: String::from("more than 81 cells") });
+            }
+            if let Some(digit) = digit {
+                board[num_cells / 9][num_cells % 9] = Cell::solved(digit);
+            }
+            num_cells += 1;
+            offset += digit.map_or(self.empty.len(), |digit| self.symbol(digit).len());
+        }
+        if num_cells < 81 {
+            return Err(ParseError { offset: s.len(), message: format!("only {} of 81 cells", num_cells) });
+        }
+        Ok(board)
+    }
+
+    /// Formats the board with these symbols on one line, which `parse` reads
+    /// back.
+    pub fn format(&self, board: &Board) -> String {
+        board.iter().flatten().map(|cell| self.cell(cell)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{board_to_string, default_board};
+
+    #[test]
+    fn test_symbol_set() {
+        let board = default_board();
+        for name in SymbolSet::NAMES.iter() {
+            let symbols = SymbolSet::from_name(name).unwrap();
+            assert_eq!(symbols.parse(&symbols.format(&board)), Ok(board));
+        }
+        assert_eq!(SymbolSet::digits().format(&board), board_to_string(&board));
+        let letters = SymbolSet::letters().format(&board);
+        assert!(letters.starts_with("..E..H..."));
+        let err = SymbolSet::letters().parse(&letters.replace('E', "J")).unwrap_err();
+        assert_eq!(err.to_string(), "unexpected 'J' at byte 2");
+
+        assert!(SymbolSet::new(["a", "b", "c", "d", "e", "f", "g", "h", "ab"], ".").is_none());
+        assert!(SymbolSet::new(["a", "b", "c", "d", "e", "f", "g", "h", "i"], "a").is_none());
+    }
+}
@@ -0,0 +1,138 @@
+//! Async wrappers for services on tokio, enabled with the `async` feature.
+//! The work runs on tokio's blocking pool so it doesn't hold up the async
+//! workers, and it's cancelled once its task is dropped or `cancel` is
+//! called, e.g. when the client of a request goes away. The wrappers have to
+//! be called from within a tokio runtime.
+
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use tokio::task::JoinHandle;
+
+use crate::board::Board;
+use crate::generate::{generate_until, Difficulty, GenerateError, Rng};
+use crate::logic::TechniqueConfig;
+use crate::solver::{SolveOptions, Sudoku};
+
+/// The error of a task that was cancelled before it finished.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the task was cancelled")
+    }
+}
+
+impl Error for Cancelled {}
+
+/// Work running on the blocking pool, which resolves to its result. The work
+/// stops at the next chance it gets once the task is cancelled or dropped.
+pub struct Task<T> {
+    handle: JoinHandle<Option<T>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl<T: Send + 'static> Task<T> {
+    fn spawn<F>(work: F) -> Task<T>
+    where
+        F: FnOnce(&dyn Fn() -> bool) -> Option<T> + Send + 'static,
+    {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&cancelled);
+        let handle = tokio::task::spawn_blocking(move || work(&|| flag.load(Ordering::Relaxed)));
+        Task { handle, cancelled }
+    }
+}
+
+impl<T> Task<T> {
+    /// Stops the work, after which the task resolves to `Cancelled`.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+impl<T> Future for Task<T> {
+    type Output = Result<T, Cancelled>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let result = match Pin::new(&mut self.handle).poll(cx) {
+            Poll::Ready(result) => result,
+            Poll::Pending => return Poll::Pending,
+        };
+        Poll::Ready(match result {
+            _ if self.cancelled.load(Ordering::Relaxed) => Err(Cancelled),
+            Ok(Some(value)) => Ok(value),
+            Ok(None) => Err(Cancelled),
+            Err(err) if err.is_panic() => std::panic::resume_unwind(err.into_panic()),
+            Err(_) => Err(Cancelled),
+        })
+    }
+}
+
+impl<T> Drop for Task<T> {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// Solves `board` on the blocking pool. The task resolves to the solution,
+/// or to None if there's none.
+pub fn solve_async(board: Board) -> Task<Option<Board>> {
+    Task::spawn(move |cancelled| {
+        let solution = Sudoku::new(board).solve_until(SolveOptions::default(), cancelled);
+        if cancelled() {
+            None
+        } else {
+            Some(solution)
+        }
+    })
+}
+
+/// Generates a puzzle of `difficulty` on the blocking pool, like
+/// `generate_with`.
+pub fn generate_async(
+    difficulty: Difficulty,
+    config: TechniqueConfig,
+    mut rng: Rng,
+) -> Task<Result<Board, GenerateError>> {
+    Task::spawn(move |cancelled| generate_until(difficulty, &config, &mut rng, cancelled))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{board_to_string, default_board};
+    use crate::generate::generate;
+    use crate::logic::Technique;
+
+    #[test]
+    fn test_tasks() {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        runtime.block_on(async {
+            let solution = solve_async(default_board()).await.unwrap().unwrap();
+            let expected = Sudoku::new(default_board()).solve().unwrap();
+            assert_eq!(board_to_string(&solution), board_to_string(&expected));
+
+            let puzzle = generate_async(Difficulty::Easy, TechniqueConfig::default(), Rng::new(1)).await;
+            assert_eq!(puzzle, Ok(Ok(generate(Difficulty::Easy, &mut Rng::new(1)))));
+
+            let mut config = TechniqueConfig::default();
+            config.disable(Technique::Guess);
+            config.disable(Technique::ForcingChain);
+            let puzzle = generate_async(Difficulty::Expert, config, Rng::new(1)).await;
+            assert_eq!(puzzle, Ok(Err(GenerateError::Unreachable(Difficulty::Expert))));
+
+            // Expert puzzles take many tries, so this is cancelled long before
+            // one is found.
+            let task = generate_async(Difficulty::Expert, TechniqueConfig::default(), Rng::new(1));
+            task.cancel();
+            assert_eq!(task.await, Err(Cancelled));
+        });
+    }
+}
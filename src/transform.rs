@@ -0,0 +1,135 @@
+//! Transformations that keep a puzzle the same puzzle in a different guise:
+//! the eight rotations and reflections of the grid and relabelings of the
+//! digits. Publishers use them to reuse a puzzle across editions without
+//! readers noticing, which `rotation_pack` does in one go.
+
+use crate::board::Board;
+use crate::generate::Rng;
+
+/// The rotations and reflections of the grid.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Symmetry {
+    Identity,
+    /// A quarter turn clockwise.
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    /// Mirrored left to right.
+    FlipHorizontal,
+    /// Mirrored top to bottom.
+    FlipVertical,
+    /// Mirrored along the main diagonal, from r1c1 to r9c9.
+    Transpose,
+    /// Mirrored along the other diagonal, from r1c9 to r9c1.
+    AntiTranspose,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::Identity,
+        Symmetry::Rotate90,
+        Symmetry::Rotate180,
+        Symmetry::Rotate270,
+        Symmetry::FlipHorizontal,
+        Symmetry::FlipVertical,
+        Symmetry::Transpose,
+        Symmetry::AntiTranspose,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Symmetry::Identity => "identity",
+            Symmetry::Rotate90 => "rotate90",
+            Symmetry::Rotate180 => "rotate180",
+            Symmetry::Rotate270 => "rotate270",
+            Symmetry::FlipHorizontal => "flip_horizontal",
+            Symmetry::FlipVertical => "flip_vertical",
+            Symmetry::Transpose => "transpose",
+            Symmetry::AntiTranspose => "anti_transpose",
+        }
+    }
+
+    /// Returns where the cell at `row` and `col` ends up.
+    pub fn map(self, row: usize, col: usize) -> (usize, usize) {
+        match self {
+            Symmetry::Identity => (row, col),
+            Symmetry::Rotate90 => (col, 8 - row),
+            Symmetry::Rotate180 => (8 - row, 8 - col),
+            Symmetry::Rotate270 => (8 - col, row),
+            Symmetry::FlipHorizontal => (row, 8 - col),
+            Symmetry::FlipVertical => (8 - row, col),
+            Symmetry::Transpose => (col, row),
+            Symmetry::AntiTranspose => (8 - col, 8 - row),
+        }
+    }
+
+    /// Returns the board with every cell moved to where `map` puts it.
+    pub fn apply(self, board: &Board) -> Board {
+        let mut moved = Board::default();
+        for row in 0..9 {
+            for col in 0..9 {
+                let (r, c) = self.map(row, col);
+                moved[r][c] = board[row][col];
+            }
+        }
+        moved
+    }
+}
+
+/// Returns the board with every digit `d` replaced by `labels[d - 1]`, which
+/// must hold the digits 1 to 9 in some order.
+pub fn relabel(board: &Board, labels: [i8; 9]) -> Board {
+    let mut relabeled = *board;
+    for cell in relabeled.iter_mut().flatten() {
+        cell.solution = cell.solution.map(|digit| labels[digit as usize - 1]);
+    }
+    relabeled
+}
+
+/// Returns the eight rotations and reflections of `puzzle`, and after each
+/// `relabelings` more copies of it with the digits shuffled by `rng`, named
+/// like `rotate90` or `rotate90-2`. The first is the puzzle itself.
+pub fn rotation_pack(puzzle: &Board, relabelings: usize, rng: &mut Rng) -> Vec<(String, Board)> {
+    let mut pack = Vec::new();
+    for &symmetry in Symmetry::ALL.iter() {
+        let board = symmetry.apply(puzzle);
+        pack.push((String::from(symmetry.name()), board));
+        for i in 0..relabelings {
+            let mut labels = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+            rng.shuffle(&mut labels);
+            pack.push((format!("{}-{}", symmetry.name(), i + 1), relabel(&board, labels)));
+        }
+    }
+    pack
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{board_to_string, default_board};
+    use crate::canonical::canonical_form;
+    use crate::count::count_solutions;
+
+    #[test]
+    fn test_rotation_pack() {
+        let puzzle = default_board();
+        let rotated = Symmetry::Rotate90.apply(&puzzle);
+        // r1c3 holds a 5, which a quarter turn moves to r3c9.
+        assert_eq!(rotated[2][8].solution, Some(5));
+        assert_eq!(Symmetry::Rotate90.apply(&Symmetry::Rotate270.apply(&puzzle)), puzzle);
+        assert_eq!(Symmetry::Transpose.apply(&Symmetry::Transpose.apply(&puzzle)), puzzle);
+
+        let pack = rotation_pack(&puzzle, 2, &mut Rng::new(1));
+        assert_eq!(pack.len(), 8 * 3);
+        assert_eq!(pack[0], (String::from("identity"), puzzle));
+        assert_eq!(pack[4].0, "rotate90-1");
+        let mut distinct: Vec<String> = pack.iter().map(|(_, board)| board_to_string(board)).collect();
+        distinct.sort();
+        distinct.dedup();
+        assert_eq!(distinct.len(), pack.len());
+        for (_, board) in pack.iter() {
+            assert_eq!(canonical_form(board), canonical_form(&puzzle));
+            assert_eq!(count_solutions(board, 2), 1);
+        }
+    }
+}
@@ -0,0 +1,226 @@
+//! Tuning the heuristics of the backtracking search to a collection of
+//! puzzles. Which cell and digit to guess first, and when to start over,
+//! matters most on the puzzles a program actually solves, so rather than
+//! guess, every combination is timed on a benchmark set and the fastest is
+//! kept as a profile:
+//!
+//! ```toml
+//! cell_order = "fewest_candidates"
+//! value_order = "least_constraining"
+//! restarts = 0
+//! ```
+//!
+//! A profile is read back with `TuningProfile::parse` and applied to the
+//! options of later solves.
+
+use std::error::Error;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::board::Board;
+use crate::config::{Config, ConfigError};
+use crate::json::Json;
+use crate::solver::{CellOrder, SolveOptions, Solver, ValueOrder};
+
+/// Why a profile couldn't be read.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TuningProfileError {
+    /// The file isn't a valid configuration file.
+    Config(ConfigError),
+    /// A key isn't one of the parameters.
+    UnknownKey(String),
+    /// The value of `key` isn't one of its settings.
+    InvalidValue { key: String, value: String },
+}
+
+impl fmt::Display for TuningProfileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TuningProfileError::Config(err) => write!(f, "{}", err),
+            TuningProfileError::UnknownKey(key) => write!(f, "unknown parameter `{}`", key),
+            TuningProfileError::InvalidValue { key, value } => write!(f, "`{}` isn't a setting of {}", value, key),
+        }
+    }
+}
+
+impl Error for TuningProfileError {}
+
+/// The heuristics of the search, as tuned.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct TuningProfile {
+    pub cell_order: CellOrder,
+    pub value_order: ValueOrder,
+    /// The guesses until the first restart, or None to never restart.
+    pub restarts: Option<u64>,
+}
+
+/// The restart settings tried: never, and after about as many guesses as
+/// hard puzzles usually take.
+const RESTARTS: [Option<u64>; 2] = [None, Some(256)];
+
+impl TuningProfile {
+    /// Reads a profile. Parameters it leaves out keep their default.
+    pub fn parse(text: &str) -> Result<TuningProfile, TuningProfileError> {
+        let config = Config::parse(text).map_err(TuningProfileError::Config)?;
+        let mut profile = TuningProfile::default();
+        for (key, value) in config.iter() {
+            let invalid = || TuningProfileError::InvalidValue { key: key.into(), value: value.into() };
+            match key {
+                "cell_order" => profile.cell_order = CellOrder::from_name(value).ok_or_else(invalid)?,
+                "value_order" => profile.value_order = ValueOrder::from_name(value).ok_or_else(invalid)?,
+                // 0 is never.
+                "restarts" => profile.restarts = Some(value.parse().map_err(|_| invalid())?).filter(|&unit| unit > 0),
+                _ => return Err(TuningProfileError::UnknownKey(key.into())),
+            }
+        }
+        Ok(profile)
+    }
+
+    /// Returns `options` with the heuristics of the profile.
+    pub fn apply(&self, options: SolveOptions) -> SolveOptions {
+        let options = options.cell_order(self.cell_order).value_order(self.value_order);
+        SolveOptions { restarts: self.restarts, ..options }
+    }
+}
+
+impl Default for TuningProfile {
+    fn default() -> TuningProfile {
+        let options = SolveOptions::default();
+        TuningProfile { cell_order: options.cell_order, value_order: options.value_order, restarts: options.restarts }
+    }
+}
+
+/// Writes the profile as it's read by `TuningProfile::parse`.
+impl fmt::Display for TuningProfile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "cell_order = \"{}\"", self.cell_order.name())?;
+        writeln!(f, "value_order = \"{}\"", self.value_order.name())?;
+        write!(f, "restarts = {}", self.restarts.unwrap_or(0))
+    }
+}
+
+/// How a profile did on the benchmark set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TuningResult {
+    pub profile: TuningProfile,
+    /// The time solving all the puzzles took.
+    pub elapsed: Duration,
+    /// The guesses solving all the puzzles took, which unlike the time is
+    /// the same on every machine.
+    pub guesses: u64,
+    /// The puzzles that have a solution.
+    pub solved: usize,
+}
+
+impl TuningResult {
+    pub fn to_json(&self) -> Json {
+        Json::object(vec![
+            ("cell_order", Json::from(self.profile.cell_order.name())),
+            ("value_order", Json::from(self.profile.value_order.name())),
+            ("restarts", Json::from(self.profile.restarts.map(|unit| unit as usize))),
+            ("seconds", Json::from(self.elapsed.as_secs_f64())),
+            ("guesses", Json::from(self.guesses as usize)),
+            ("solved", Json::from(self.solved)),
+        ])
+    }
+}
+
+/// The results of every profile, fastest first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TuningReport {
+    pub results: Vec<TuningResult>,
+}
+
+impl TuningReport {
+    pub fn best(&self) -> &TuningResult {
+        &self.results[0]
+    }
+
+    pub fn to_json(&self) -> Json {
+        Json::from(self.results.iter().map(TuningResult::to_json).collect::<Vec<_>>())
+    }
+}
+
+/// Lists the results, one per line, with the best marked.
+impl fmt::Display for TuningReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:<18} {:<19} {:>8} {:>10} {:>10}", "Cell order", "Value order", "Restarts", "Time (ms)", "Guesses")?;
+        for (i, result) in self.results.iter().enumerate() {
+            let mark = if i == 0 { " best" } else { "" };
+            write!(
+                f,
+                "\n{:<18} {:<19} {:>8} {:>10.1} {:>10}{}",
+                result.profile.cell_order.name(),
+                result.profile.value_order.name(),
+                result.profile.restarts.map_or(String::from("never"), |unit| unit.to_string()),
+                result.elapsed.as_secs_f64() * 1000.0,
+                result.guesses,
+                mark
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Solves `puzzles` with every combination of heuristics, each starting from
+/// `options`, and ranks them by the time they took, or by their guesses if
+/// two took the same time.
+pub fn tune(puzzles: &[Board], options: SolveOptions) -> TuningReport {
+    let mut results = Vec::new();
+    for &cell_order in CellOrder::ALL.iter() {
+        for &value_order in ValueOrder::ALL.iter() {
+            for &restarts in RESTARTS.iter() {
+                let profile = TuningProfile { cell_order, value_order, restarts };
+                let mut solver = Solver::with_options(profile.apply(options));
+                let mut solution = Board::default();
+                let (mut solved, mut guesses) = (0, 0);
+                let start = Instant::now();
+                for puzzle in puzzles {
+                    if solver.solve_into(puzzle, &mut solution) {
+                        solved += 1;
+                    }
+                    guesses += solver.stats().guesses;
+                }
+                results.push(TuningResult { profile, elapsed: start.elapsed(), guesses, solved });
+            }
+        }
+    }
+    results.sort_by_key(|result| (result.elapsed, result.guesses));
+    TuningReport { results }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{default_board, parse_board};
+
+    #[test]
+    fn test_tune() {
+        let puzzles = vec![
+            default_board(),
+            parse_board("..5..8....2....5..79.34562....6.471..495.783..178.2....54783.96..6....5....1..4..").unwrap(),
+        ];
+        let report = tune(&puzzles, SolveOptions::default());
+        assert_eq!(report.results.len(), 8);
+        assert!(report.results.iter().all(|result| result.solved == 2));
+        assert!(report.results.windows(2).all(|pair| pair[0].elapsed <= pair[1].elapsed));
+        let guesses = |cell_order| {
+            let results = report.results.iter().filter(move |result| result.profile.cell_order == cell_order);
+            results.map(|result| result.guesses)
+        };
+        // Picking the most constrained cell makes far fewer guesses.
+        assert!(guesses(CellOrder::FewestCandidates).max() < guesses(CellOrder::RowByRow).min());
+
+        let profile = report.best().profile;
+        assert_eq!(TuningProfile::parse(&profile.to_string()), Ok(profile));
+        let profile = TuningProfile::parse("value_order = \"least_constraining\"").unwrap();
+        assert_eq!(profile.cell_order, CellOrder::FewestCandidates);
+        assert_eq!(profile.apply(SolveOptions::default()).value_order, ValueOrder::LeastConstraining);
+        assert_eq!(TuningProfile::parse("restarts = 100").unwrap().apply(SolveOptions::default()).restarts, Some(100));
+        assert!(matches!(TuningProfile::parse("seed = 5"), Err(TuningProfileError::UnknownKey(_))));
+        assert!(matches!(
+            TuningProfile::parse("cell_order = \"random\""),
+            Err(TuningProfileError::InvalidValue { .. })
+        ));
+    }
+}
@@ -0,0 +1,72 @@
+//! Techniques that rely on the puzzle having a single solution: they rule
+//! out patterns that would leave the puzzle with several. They're only used
+//! when a `TechniqueConfig` assumes uniqueness, since on a puzzle with more
+//! than one solution they can lead the solver astray.
+
+use crate::grid::{mask_digits, Grid, House};
+use crate::logic::{Step, Technique};
+
+/// Finds a BUG+1 (bivalue universal grave plus one): every empty cell but
+/// one has two candidates, and every candidate appears twice in each of its
+/// houses except for one digit of the cell with three, which appears three
+/// times. Such a grid without that digit in the cell would have two
+/// solutions, so the digit goes there.
+pub(crate) fn find_bug_plus_one(grid: &Grid) -> Option<Step> {
+    let mut extra = None;
+    for row in 0..9 {
+        for col in 0..9 {
+            if grid.value(row, col).is_some() {
+                continue;
+            }
+            match grid.candidates(row, col).count_ones() {
+                2 => {}
+                3 if extra.is_none() => extra = Some((row, col)),
+                _ => return None,
+            }
+        }
+    }
+    let (row, col) = extra?;
+    let houses = House::containing(row, col);
+    let digit = mask_digits(grid.candidates(row, col))
+        .find(|&digit| houses.iter().all(|&house| grid.places_for(house, digit).len() == 3))?;
+    for house in House::all() {
+        for d in 1..10 {
+            if grid.is_placed_in(house, d) {
+                continue;
+            }
+            let expected = if d == digit && houses.contains(&house) { 3 } else { 2 };
+            if grid.places_for(house, d).len() != expected {
+                return None;
+            }
+        }
+    }
+    Some(Step::placement(Technique::BugPlusOne, houses.to_vec(), row, col, digit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::parse_board;
+    use crate::logic::{solve_with, TechniqueConfig};
+    use crate::solver::Sudoku;
+
+    #[test]
+    fn test_find_bug_plus_one() {
+        let board =
+            parse_board("..3......59.........6..4....35...2...1.7...93..4..9..5....4.8.77...35.1.2.1....5.").unwrap();
+        let solution = Sudoku::new(board).solve().unwrap();
+        // Chains would find the digit first.
+        let mut config = TechniqueConfig::default();
+        config.disable(Technique::XChain);
+        config.disable(Technique::XYChain);
+        let trace = solve_with(&board, &config);
+        assert!(trace.steps.iter().all(|step| step.technique != Technique::BugPlusOne));
+
+        config.assume_unique(true);
+        let trace = solve_with(&board, &config);
+        let step = trace.steps.iter().find(|step| step.technique == Technique::BugPlusOne).unwrap();
+        assert_eq!(step.placements, vec![(2, 3, 9)]);
+        assert_eq!(solution[2][3].solution, Some(9));
+        assert_eq!(step.to_string(), "BUG+1 in row 3, column 4, box 2: r3c4=9");
+    }
+}
@@ -0,0 +1,299 @@
+//! Checking the givens of a puzzle before solving it, so that a typo in a
+//! puzzle is reported as such rather than as a puzzle without a solution,
+//! and checking the grids players fill in.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::board::Board;
+use crate::count::count_solutions;
+use crate::grid::House;
+
+/// A problem with the givens of a puzzle.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Conflict {
+    /// The cell holds something other than a digit from 1 to 9.
+    OutOfRange { row: usize, col: usize, digit: i8 },
+    /// `digit` is given more than once in `house`, in `cells`.
+    Duplicate { house: House, digit: i8, cells: Vec<(usize, usize)> },
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Conflict::OutOfRange { row, col, digit } => {
+                write!(f, "r{}c{} holds {}, which isn't a digit from 1 to 9", row + 1, col + 1, digit)
+            }
+            Conflict::Duplicate { house, digit, cells } => {
+                let cells: Vec<String> = cells.iter().map(|(row, col)| format!("r{}c{}", row + 1, col + 1)).collect();
+                write!(f, "{} is given more than once in {}, in {}", digit, house, cells.join(", "))
+            }
+        }
+    }
+}
+
+/// The error of a puzzle whose givens break the rules.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct InvalidPuzzle {
+    pub conflicts: Vec<Conflict>,
+}
+
+impl fmt::Display for InvalidPuzzle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the puzzle is invalid: ")?;
+        for (i, conflict) in self.conflicts.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}", conflict)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for InvalidPuzzle {}
+
+/// Checks that every given of `board` is a digit from 1 to 9 and that no
+/// digit is given twice in a row, column, or box. All conflicts are
+/// reported, cells out of range first and then duplicates house by house.
+pub fn validate(board: &Board) -> Result<(), InvalidPuzzle> {
+    let mut conflicts = Vec::new();
+    for row in 0..9 {
+        for col in 0..9 {
+            match board[row][col].solution {
+                Some(digit) if !(1..=9).contains(&digit) => conflicts.push(Conflict::OutOfRange { row, col, digit }),
+                _ => {}
+            }
+        }
+    }
+    for house in House::all() {
+        for digit in 1..=9 {
+            let cells: Vec<(usize, usize)> = house
+                .cells()
+                .iter()
+                .copied()
+                .filter(|&(row, col)| board[row][col].solution == Some(digit))
+                .collect();
+            if cells.len() > 1 {
+                conflicts.push(Conflict::Duplicate { house, digit, cells });
+            }
+        }
+    }
+    if conflicts.is_empty() {
+        Ok(())
+    } else {
+        Err(InvalidPuzzle { conflicts })
+    }
+}
+
+/// A sign that a puzzle can't have a unique solution, which is cheap to
+/// find compared to counting the solutions.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Warning {
+    /// The puzzle has this many givens, fewer than the 17 any puzzle with
+    /// a unique solution has.
+    TooFewClues(usize),
+    /// These digits aren't given anywhere. If two are missing, they can be
+    /// swapped in any solution to get another one.
+    MissingDigits(Vec<i8>),
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Warning::TooFewClues(clues) => write!(f, "only {} digits are given, fewer than 17", clues),
+            Warning::MissingDigits(digits) => {
+                let digits: Vec<String> = digits.iter().map(i8::to_string).collect();
+                let (last, rest) = digits.split_last().unwrap();
+                write!(f, "{} and {} aren't given anywhere", rest.join(", "), last)
+            }
+        }
+    }
+}
+
+/// Returns the reasons `board` can't have a unique solution that can be seen
+/// without solving it. None of them doesn't mean the solution is unique.
+pub fn preflight(board: &Board) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    let givens: Vec<i8> = board.iter().flatten().filter_map(|cell| cell.solution).collect();
+    if givens.len() < 17 {
+        warnings.push(Warning::TooFewClues(givens.len()));
+    }
+    let missing: Vec<i8> = (1..=9).filter(|digit| !givens.contains(digit)).collect();
+    if missing.len() >= 2 {
+        warnings.push(Warning::MissingDigits(missing));
+    }
+    warnings
+}
+
+/// What's wrong with a cell of a filled in grid.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Discrepancy {
+    /// The cell wasn't filled in.
+    Empty,
+    /// The cell holds something other than a digit from 1 to 9.
+    OutOfRange(i8),
+    /// The cell doesn't hold the digit the puzzle gives for it.
+    GivenChanged { given: i8 },
+    /// The digit of the cell is in another cell of the house too.
+    Repeated(House),
+}
+
+/// What `check_puzzle` finds wrong with a puzzle.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum PuzzleProblem {
+    Invalid(InvalidPuzzle),
+    /// The puzzle can't have a unique solution, for these reasons.
+    NotUnique(Vec<Warning>),
+    NoSolution,
+    SeveralSolutions,
+}
+
+impl fmt::Display for PuzzleProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PuzzleProblem::Invalid(err) => write!(f, "{}", err),
+            PuzzleProblem::NotUnique(warnings) => {
+                write!(f, "the puzzle can't have a unique solution: ")?;
+                for (i, warning) in warnings.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "; ")?;
+                    }
+                    write!(f, "{}", warning)?;
+                }
+                Ok(())
+            }
+            PuzzleProblem::NoSolution => write!(f, "the puzzle has no solution"),
+            PuzzleProblem::SeveralSolutions => write!(f, "the puzzle has several solutions"),
+        }
+    }
+}
+
+impl Error for PuzzleProblem {}
+
+/// Checks a puzzle without solving it, for dry runs: that its givens don't
+/// conflict, and with `unique` that it has exactly one solution, which
+/// takes a search but stops at the second solution.
+pub fn check_puzzle(board: &Board, unique: bool) -> Result<(), PuzzleProblem> {
+    validate(board).map_err(PuzzleProblem::Invalid)?;
+    if !unique {
+        return Ok(());
+    }
+    let warnings = preflight(board);
+    if !warnings.is_empty() {
+        return Err(PuzzleProblem::NotUnique(warnings));
+    }
+    match count_solutions(board, 2) {
+        0 => Err(PuzzleProblem::NoSolution),
+        1 => Ok(()),
+        _ => Err(PuzzleProblem::SeveralSolutions),
+    }
+}
+
+/// The result of checking a filled in grid against its puzzle.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Verification {
+    /// The cells that are wrong, by row and column, and what's wrong with
+    /// them. A cell may be wrong in several ways.
+    pub discrepancies: Vec<(usize, usize, Discrepancy)>,
+}
+
+impl Verification {
+    /// Returns whether the grid is a solution of the puzzle.
+    pub fn is_correct(&self) -> bool {
+        self.discrepancies.is_empty()
+    }
+}
+
+/// Checks that `filled` is complete, breaks none of the rules, and keeps the
+/// givens of `puzzle`. A correct grid doesn't need to be the solution the
+/// solver finds, which matters for puzzles with several.
+pub fn verify(puzzle: &Board, filled: &Board) -> Verification {
+    let mut discrepancies = Vec::new();
+    for row in 0..9 {
+        for col in 0..9 {
+            let digit = filled[row][col].solution;
+            match digit {
+                None => discrepancies.push((row, col, Discrepancy::Empty)),
+                Some(digit) if !(1..=9).contains(&digit) => {
+                    discrepancies.push((row, col, Discrepancy::OutOfRange(digit)))
+                }
+                _ => {}
+            }
+            match puzzle[row][col].solution {
+                Some(given) if digit != Some(given) => discrepancies.push((row, col, Discrepancy::GivenChanged { given })),
+                _ => {}
+            }
+            if digit.is_none() {
+                continue;
+            }
+            for &house in House::containing(row, col).iter() {
+                let repeated =
+                    house.cells().iter().any(|&(r, c)| (r, c) != (row, col) && filled[r][c].solution == digit);
+                if repeated {
+                    discrepancies.push((row, col, Discrepancy::Repeated(house)));
+                }
+            }
+        }
+    }
+    Verification { discrepancies }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{default_board, Cell};
+    use crate::solver::Sudoku;
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(validate(&default_board()), Ok(()));
+
+        // r1c3 already holds a 5.
+        let mut board = default_board();
+        board[0][0] = Cell::solved(5);
+        board[8][8] = Cell::solved(12);
+        let err = validate(&board).unwrap_err();
+        assert_eq!(err.conflicts.len(), 3);
+        assert_eq!(err.conflicts[0], Conflict::OutOfRange { row: 8, col: 8, digit: 12 });
+        assert_eq!(
+            err.conflicts[1],
+            Conflict::Duplicate { house: House::Row(0), digit: 5, cells: vec![(0, 0), (0, 2)] }
+        );
+        assert!(matches!(err.conflicts[2], Conflict::Duplicate { house: House::Box(0), digit: 5, .. }));
+        assert_eq!(err.conflicts[1].to_string(), "5 is given more than once in row 1, in r1c1, r1c3");
+
+        assert_eq!(preflight(&default_board()), vec![]);
+        let mut board: Board = Default::default();
+        for i in 0..7 {
+            board[i][i] = Cell::solved(i as i8 + 1);
+        }
+        assert_eq!(preflight(&board), vec![Warning::TooFewClues(7), Warning::MissingDigits(vec![8, 9])]);
+        assert_eq!(preflight(&board)[1].to_string(), "8 and 9 aren't given anywhere");
+
+        assert_eq!(check_puzzle(&default_board(), true), Ok(()));
+        assert_eq!(check_puzzle(&board, false), Ok(()));
+        let err = check_puzzle(&board, true).unwrap_err();
+        assert!(err.to_string().starts_with("the puzzle can't have a unique solution: only 7 digits are given"));
+        // Without the first two rows of a solution, they can be swapped.
+        let mut several = Sudoku::new(default_board()).solve().unwrap();
+        several[0] = [Cell::unsolved(); 9];
+        several[1] = [Cell::unsolved(); 9];
+        assert_eq!(check_puzzle(&several, true), Err(PuzzleProblem::SeveralSolutions));
+
+        let puzzle = default_board();
+        let mut filled = Sudoku::new(puzzle).solve().unwrap();
+        assert!(verify(&puzzle, &filled).is_correct());
+        // Swapping two digits of a row keeps the row right but not the
+        // columns, and r1c3 is a given.
+        let (a, b) = (filled[0][1], filled[0][2]);
+        filled[0][1] = b;
+        filled[0][2] = a;
+        filled[8][8] = Cell::unsolved();
+        let discrepancies = verify(&puzzle, &filled).discrepancies;
+        assert!(discrepancies.contains(&(0, 2, Discrepancy::GivenChanged { given: 5 })));
+        assert!(discrepancies.contains(&(0, 1, Discrepancy::Repeated(House::Col(1)))));
+        assert!(!discrepancies.contains(&(0, 1, Discrepancy::Repeated(House::Row(0)))));
+        assert!(discrepancies.contains(&(8, 8, Discrepancy::Empty)));
+    }
+}
@@ -0,0 +1,161 @@
+//! Variant constraints on top of the classic rules: diagonals, killer cages,
+//! thermometers, and arrows. The logical techniques only know the classic
+//! rules, which still hold in every variant, so variants are solved with them
+//! and the extra constraints are only checked against the placed digits to
+//! reject wrong guesses.
+
+use crate::grid::Grid;
+
+/// Cells that must hold distinct digits, optionally adding up to `sum`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Cage {
+    pub cells: Vec<(usize, usize)>,
+    pub sum: Option<u32>,
+}
+
+/// The digits on `line` must add up to the number in the `circle`, whose
+/// digits are read in order when it spans several cells.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Arrow {
+    pub circle: Vec<(usize, usize)>,
+    pub line: Vec<(usize, usize)>,
+}
+
+/// The constraints of a variant puzzle. The default has none, which is a
+/// classic sudoku.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct Variant {
+    /// Whether the digits from r1c1 to r9c9 must differ.
+    pub main_diagonal: bool,
+    /// Whether the digits from r9c1 to r1c9 must differ.
+    pub anti_diagonal: bool,
+    pub cages: Vec<Cage>,
+    /// Cells whose digits must increase from the bulb, the first cell, on.
+    pub thermos: Vec<Vec<(usize, usize)>>,
+    pub arrows: Vec<Arrow>,
+}
+
+impl Variant {
+    /// Returns whether there are no constraints besides the classic ones.
+    pub fn is_classic(&self) -> bool {
+        *self == Variant::default()
+    }
+
+    /// Returns whether the digits placed in `grid` can still satisfy every
+    /// constraint. Once the grid is complete, this is whether they do.
+    pub fn allows(&self, grid: &Grid) -> bool {
+        let main: Vec<_> = (0..9).map(|i| (i, i)).collect();
+        let anti: Vec<_> = (0..9).map(|i| (8 - i, i)).collect();
+        (!self.main_diagonal || distinct(grid, &main))
+            && (!self.anti_diagonal || distinct(grid, &anti))
+            && self.cages.iter().all(|cage| cage_allows(grid, cage))
+            && self.thermos.iter().all(|thermo| thermo_allows(grid, thermo))
+            && self.arrows.iter().all(|arrow| arrow_allows(grid, arrow))
+    }
+}
+
+/// Returns whether the digits placed in `cells` are all different.
+fn distinct(grid: &Grid, cells: &[(usize, usize)]) -> bool {
+    let mut seen = 0u16;
+    for &(row, col) in cells {
+        if let Some(digit) = grid.value(row, col) {
+            if seen & (1 << digit) != 0 {
+                return false;
+            }
+            seen |= 1 << digit;
+        }
+    }
+    true
+}
+
+fn cage_allows(grid: &Grid, cage: &Cage) -> bool {
+    if !distinct(grid, &cage.cells) {
+        return false;
+    }
+    let sum = match cage.sum {
+        Some(sum) => sum,
+        None => return true,
+    };
+    let placed: Vec<u32> = cage.cells.iter().filter_map(|&(r, c)| grid.value(r, c)).map(|d| d as u32).collect();
+    let empty = cage.cells.len() - placed.len();
+    // The empty cells need distinct unused digits, which bounds their sum.
+    let unused: Vec<u32> = (1..10).filter(|d| !placed.contains(d)).collect();
+    if empty > unused.len() {
+        return false;
+    }
+    let placed_sum: u32 = placed.iter().sum();
+    let least: u32 = unused[..empty].iter().sum();
+    let most: u32 = unused[unused.len() - empty..].iter().sum();
+    placed_sum + least <= sum && sum <= placed_sum + most
+}
+
+fn thermo_allows(grid: &Grid, thermo: &[(usize, usize)]) -> bool {
+    let len = thermo.len();
+    let mut last: Option<(usize, i8)> = None;
+    for (i, &(row, col)) in thermo.iter().enumerate() {
+        let digit = match grid.value(row, col) {
+            Some(digit) => digit,
+            None => continue,
+        };
+        // There have to be enough smaller digits for the cells before and
+        // larger ones for the cells after.
+        if (digit as usize) < i + 1 || digit as usize > 9 - (len - 1 - i) {
+            return false;
+        }
+        if let Some((j, before)) = last {
+            if ((digit - before) as isize) < (i - j) as isize {
+                return false;
+            }
+        }
+        last = Some((i, digit));
+    }
+    true
+}
+
+fn arrow_allows(grid: &Grid, arrow: &Arrow) -> bool {
+    // The largest the circle can be, which is its number once it's filled.
+    let circle = arrow.circle.iter().fold(0u32, |number, &(r, c)| {
+        number * 10 + grid.value(r, c).unwrap_or(9) as u32
+    });
+    let circle_filled = arrow.circle.iter().all(|&(r, c)| grid.value(r, c).is_some());
+    let placed: u32 = arrow.line.iter().filter_map(|&(r, c)| grid.value(r, c)).map(|d| d as u32).sum();
+    let empty = arrow.line.iter().filter(|&&(r, c)| grid.value(r, c).is_none()).count() as u32;
+    if placed + empty > circle {
+        return false;
+    }
+    !circle_filled || circle <= placed + 9 * empty
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{Board, Cell};
+
+    #[test]
+    fn test_allows() {
+        let mut board: Board = Default::default();
+        board[0][0] = Cell::solved(5);
+        board[4][4] = Cell::solved(5);
+        let grid = Grid::from_board(&board);
+        let mut variant = Variant::default();
+        assert!(variant.is_classic() && variant.allows(&grid));
+        variant.main_diagonal = true;
+        assert!(!variant.allows(&grid));
+
+        let thermo = |cells: Vec<(usize, usize)>| Variant { thermos: vec![cells], ..Variant::default() };
+        assert!(thermo(vec![(0, 0), (0, 1), (0, 2)]).allows(&grid));
+        assert!(!thermo(vec![(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2)]).allows(&grid));
+        assert!(!thermo(vec![(0, 0), (1, 1), (2, 2), (3, 3), (4, 4)]).allows(&grid));
+
+        let cage = |sum| Variant { cages: vec![Cage { cells: vec![(0, 0), (0, 1)], sum: Some(sum) }], ..Variant::default() };
+        assert!(cage(6).allows(&grid) && cage(14).allows(&grid));
+        assert!(!cage(5).allows(&grid) && !cage(15).allows(&grid));
+
+        let arrow = |line: Vec<(usize, usize)>| Variant {
+            arrows: vec![Arrow { circle: vec![(4, 4)], line }],
+            ..Variant::default()
+        };
+        assert!(arrow(vec![(3, 3), (3, 4), (3, 5)]).allows(&grid));
+        assert!(!arrow((0..6).map(|col| (5, col)).collect()).allows(&grid));
+    }
+}
@@ -0,0 +1,101 @@
+//! JavaScript bindings, enabled with the `wasm` feature. Puzzles are passed
+//! around in the 81 character format and reports as JSON strings, so they're
+//! easy to use from JavaScript without any glue.
+
+use wasm_bindgen::prelude::*;
+
+use crate::board::{board_to_string, parse_board, Board};
+use crate::daily::{daily_puzzle, Date};
+use crate::generate::{Difficulty, Rng};
+use crate::hint::HintLevel;
+use crate::json::Json;
+use crate::logic::TechniqueConfig;
+use crate::solver::{Solver, StepOutcome, Sudoku};
+
+fn parse(puzzle: &str) -> Result<Board, JsError> {
+    parse_board(puzzle).ok_or_else(|| JsError::new("invalid puzzle"))
+}
+
+/// Returns the solution of `puzzle` as an 81 character string.
+#[wasm_bindgen]
+pub fn solve(puzzle: &str) -> Result<String, JsError> {
+    let mut sudoku = Sudoku::new(parse(puzzle)?);
+    match sudoku.solve() {
+        Some(solution) => Ok(board_to_string(&solution)),
+        None => Err(JsError::new("no solution")),
+    }
+}
+
+/// Solves a puzzle a bit at a time, so that hard puzzles don't block the
+/// event loop: each call of `step` does at most about a millisecond of work.
+#[wasm_bindgen]
+pub struct Stepper {
+    solver: Solver,
+}
+
+#[wasm_bindgen]
+impl Stepper {
+    #[wasm_bindgen(constructor)]
+    pub fn new(puzzle: &str) -> Result<Stepper, JsError> {
+        let mut solver = Solver::new();
+        solver.start(&parse(puzzle)?);
+        Ok(Stepper { solver })
+    }
+
+    /// Goes on with the solve. Returns the solution as an 81 character
+    /// string once it's found, or undefined if it isn't yet.
+    pub fn step(&mut self) -> Result<Option<String>, JsError> {
+        match self.solver.step() {
+            StepOutcome::Pending => Ok(None),
+            StepOutcome::Solved(solution) => Ok(Some(board_to_string(&solution))),
+            StepOutcome::NoSolution => Err(JsError::new("no solution")),
+        }
+    }
+}
+
+/// Generates a puzzle of `difficulty` (easy, medium, hard, or expert). The
+/// same seed always generates the same puzzle.
+#[wasm_bindgen]
+pub fn generate(difficulty: &str, seed: u32) -> Result<String, JsError> {
+    let difficulty =
+        Difficulty::from_name(difficulty).ok_or_else(|| JsError::new("unknown difficulty"))?;
+    let puzzle = crate::generate::generate(difficulty, &mut Rng::new(seed as u64));
+    Ok(board_to_string(&puzzle))
+}
+
+/// Returns the puzzle of `difficulty` for `date`, written as YYYY-MM-DD. It's
+/// the same puzzle wherever it's generated.
+#[wasm_bindgen]
+pub fn daily(date: &str, difficulty: &str) -> Result<String, JsError> {
+    let date = Date::parse(date).ok_or_else(|| JsError::new("expected a date like 2024-03-01"))?;
+    let difficulty =
+        Difficulty::from_name(difficulty).ok_or_else(|| JsError::new("unknown difficulty"))?;
+    Ok(board_to_string(daily_puzzle(date, difficulty).givens()))
+}
+
+/// Returns the rating of `puzzle` as JSON.
+#[wasm_bindgen]
+pub fn rate(puzzle: &str) -> Result<String, JsError> {
+    match crate::rating::rate(&parse(puzzle)?, &TechniqueConfig::default()) {
+        Some(rating) => Ok(rating.to_json().to_string()),
+        None => Err(JsError::new("no solution")),
+    }
+}
+
+/// Returns a hint for the next step of `puzzle` as JSON, with its text
+/// revealing more the higher `level` is, from 1 to 3, and the step itself.
+#[wasm_bindgen]
+pub fn hint(puzzle: &str, level: u8) -> Result<String, JsError> {
+    let level = match level {
+        1..=3 => HintLevel::ALL[level as usize - 1],
+        _ => return Err(JsError::new("hint level must be from 1 to 3")),
+    };
+    match crate::hint::hint(&parse(puzzle)?, &TechniqueConfig::default()) {
+        Some(hint) => Ok(Json::object(vec![
+            ("hint", Json::from(hint.text(level))),
+            ("step", hint.step.to_json()),
+        ])
+        .to_string()),
+        None => Err(JsError::new("the puzzle is solved or has no solution")),
+    }
+}
@@ -0,0 +1,112 @@
+//! Properties that must hold for any puzzle, checked on random ones.
+
+use proptest::prelude::*;
+use sudoku::{
+    count_solutions, generate, hint, solve_logically, Board, Cell, Difficulty, Rng, Sudoku, TechniqueConfig,
+};
+
+/// Returns whether `board` is completely filled without breaking any rule.
+fn is_valid_solution(board: &Board) -> bool {
+    let digit = |row: usize, col: usize| board[row][col].solution().unwrap_or(0);
+    (0..9).all(|i| {
+        let mut row = [false; 10];
+        let mut col = [false; 10];
+        let mut block = [false; 10];
+        (0..9).all(|j| {
+            let (r, c) = (i / 3 * 3 + j / 3, i % 3 * 3 + j % 3);
+            let seen = [
+                std::mem::replace(&mut row[digit(i, j) as usize], true),
+                std::mem::replace(&mut col[digit(j, i) as usize], true),
+                std::mem::replace(&mut block[digit(r, c) as usize], true),
+            ];
+            digit(i, j) != 0 && !seen.iter().any(|&s| s)
+        })
+    })
+}
+
+/// Returns whether `solution` keeps every given of `puzzle`.
+fn extends(solution: &Board, puzzle: &Board) -> bool {
+    (0..81).all(|i| {
+        let given = puzzle[i / 9][i % 9].solution();
+        given.is_none() || given == solution[i / 9][i % 9].solution()
+    })
+}
+
+/// A random valid solution. It starts from a patterned one, where each row
+/// is the one above shifted, and shuffles the digits, the bands, and the
+/// rows within bands, which all keep it valid.
+fn solution() -> impl Strategy<Value = Board> {
+    let digits = Just((1..10).collect::<Vec<i8>>()).prop_shuffle();
+    let bands = Just(vec![0, 1, 2]).prop_shuffle();
+    let rows = prop::collection::vec(Just(vec![0, 1, 2]).prop_shuffle(), 3);
+    (digits, bands, rows, any::<bool>()).prop_map(|(digits, bands, rows, transpose)| {
+        let mut board: Board = Default::default();
+        for row in 0..9 {
+            let from = bands[row / 3] * 3 + rows[row / 3][row % 3];
+            for col in 0..9 {
+                let (r, c) = if transpose { (col, row) } else { (row, col) };
+                board[r][c] = Cell::solved(digits[(from % 3 * 3 + from / 3 + col) % 9]);
+            }
+        }
+        board
+    })
+}
+
+/// A random solution with a random subset of its digits given, so it may
+/// have any number of solutions.
+fn sparse_puzzle() -> impl Strategy<Value = Board> {
+    (solution(), prop::collection::vec(any::<bool>(), 81)).prop_map(|(solution, keep)| {
+        let mut board = solution;
+        for i in 0..81 {
+            if !keep[i] {
+                board[i / 9][i % 9] = Cell::unsolved();
+            }
+        }
+        board
+    })
+}
+
+/// A generated puzzle. Only easy ones are generated since harder ones take
+/// many tries, which is too slow for unoptimized test builds.
+fn generated_puzzle() -> impl Strategy<Value = Board> {
+    any::<u64>().prop_map(|seed| generate(Difficulty::Easy, &mut Rng::new(seed)))
+}
+
+proptest! {
+    #[test]
+    fn random_solutions_are_valid(solution in solution()) {
+        prop_assert!(is_valid_solution(&solution));
+    }
+
+    #[test]
+    fn solutions_are_valid_and_keep_the_givens(puzzle in sparse_puzzle()) {
+        let solution = Sudoku::new(puzzle).solve().unwrap();
+        prop_assert!(is_valid_solution(&solution));
+        prop_assert!(extends(&solution, &puzzle));
+
+        let logical = solve_logically(&puzzle).solution.unwrap();
+        prop_assert!(is_valid_solution(&logical));
+        prop_assert!(extends(&logical, &puzzle));
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(8))]
+
+    #[test]
+    fn generated_puzzles_are_unique(puzzle in generated_puzzle()) {
+        prop_assert_eq!(count_solutions(&puzzle, 2), 1);
+    }
+
+    #[test]
+    fn hints_agree_with_the_solution(puzzle in generated_puzzle()) {
+        let solution = Sudoku::new(puzzle).solve().unwrap();
+        let hint = hint(&puzzle, &TechniqueConfig::default()).unwrap();
+        for &(row, col, digit) in hint.step.placements.iter() {
+            prop_assert_eq!(solution[row][col].solution(), Some(digit));
+        }
+        for &(row, col, digit) in hint.step.eliminations.iter() {
+            prop_assert_ne!(solution[row][col].solution(), Some(digit));
+        }
+    }
+}